@@ -0,0 +1,19 @@
+-- Parâmetros de depreciação por bem (nulos usam o padrão: linear, 120 meses, residual 0)
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS depreciation_method VARCHAR;
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS useful_life_months INTEGER;
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS residual_value DECIMAL(10, 2);
+
+-- Lançamentos mensais de depreciação (conciliação contábil)
+CREATE TABLE IF NOT EXISTS depreciation_entries (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    period DATE NOT NULL,                         -- primeiro dia do mês de competência
+    method VARCHAR NOT NULL,
+    depreciation_amount DECIMAL(12, 2) NOT NULL,
+    accumulated_depreciation DECIMAL(12, 2) NOT NULL,
+    book_value DECIMAL(12, 2) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (patrimony_id, period)
+);
+
+CREATE INDEX IF NOT EXISTS idx_depreciation_entries_period ON depreciation_entries(period);
@@ -0,0 +1,14 @@
+-- Baixas patrimoniais (o bem permanece no histórico com status 'written_off')
+CREATE TABLE IF NOT EXISTS write_offs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL UNIQUE REFERENCES patrimonies(id),
+    reason VARCHAR NOT NULL CHECK (reason IN ('obsolescence', 'loss', 'theft', 'donation', 'auction')),
+    act_number VARCHAR NOT NULL,                  -- Nº do ato administrativo
+    write_off_date DATE NOT NULL,
+    notes TEXT,
+    document_file VARCHAR,                        -- PDF do ato/laudo
+    book_value DECIMAL(12, 2) NOT NULL DEFAULT 0, -- valor contábil na data da baixa
+    previous_status VARCHAR NOT NULL,
+    approved_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
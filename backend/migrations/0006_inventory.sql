@@ -0,0 +1,39 @@
+-- Campanhas de inventário físico (uma campanha aberta por departamento)
+CREATE TABLE IF NOT EXISTS inventory_campaigns (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    department VARCHAR NOT NULL,
+    year INTEGER NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed')),
+    opened_by UUID REFERENCES users(id),
+    opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    closed_by UUID REFERENCES users(id),
+    closed_at TIMESTAMP WITH TIME ZONE,
+    report JSONB
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_inventory_campaigns_open_department ON inventory_campaigns(department) WHERE status = 'open';
+
+-- Sessões de leitura dentro de uma campanha
+CREATE TABLE IF NOT EXISTS inventory_sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    campaign_id UUID NOT NULL REFERENCES inventory_campaigns(id) ON DELETE CASCADE,
+    description VARCHAR,
+    started_by UUID REFERENCES users(id),
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    closed_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Placas lidas; cada placa conta uma única vez por campanha
+CREATE TABLE IF NOT EXISTS inventory_scans (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    campaign_id UUID NOT NULL REFERENCES inventory_campaigns(id) ON DELETE CASCADE,
+    session_id UUID NOT NULL REFERENCES inventory_sessions(id) ON DELETE CASCADE,
+    plate VARCHAR NOT NULL,
+    patrimony_id UUID REFERENCES patrimonies(id) ON DELETE SET NULL,
+    scanned_by UUID REFERENCES users(id),
+    scanned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    UNIQUE (campaign_id, plate)
+);
+
+CREATE INDEX IF NOT EXISTS idx_inventory_scans_session ON inventory_scans(session_id);
@@ -0,0 +1,32 @@
+-- Sequências de numeração de placas por departamento ou categoria
+-- Formato: PREFIXO[ANO]CONTADOR (ex.: EDU001, EDU-2025-0001)
+CREATE TABLE IF NOT EXISTS plate_sequences (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    scope_type VARCHAR NOT NULL CHECK (scope_type IN ('department', 'category')),
+    scope_value VARCHAR NOT NULL,
+    prefix VARCHAR NOT NULL,
+    padding INTEGER NOT NULL DEFAULT 3 CHECK (padding BETWEEN 1 AND 12),
+    include_year BOOLEAN NOT NULL DEFAULT FALSE,
+    separator VARCHAR NOT NULL DEFAULT '',
+    next_value BIGINT NOT NULL DEFAULT 1 CHECK (next_value >= 1),
+    current_year INTEGER,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    UNIQUE (scope_type, scope_value)
+);
+
+-- Sequências iniciais seguindo o padrão das placas existentes (EDU001, SAU002...)
+INSERT INTO plate_sequences (scope_type, scope_value, prefix, padding, next_value)
+SELECT 'department', s.department, s.prefix, 3,
+       COALESCE((SELECT MAX(SUBSTRING(p.plate FROM LENGTH(s.prefix) + 1)::bigint)
+                 FROM patrimonies p
+                 WHERE p.plate ~ ('^' || s.prefix || '[0-9]+$')), 0) + 1
+FROM (VALUES
+    ('education', 'EDU'),
+    ('health', 'SAU'),
+    ('administration', 'ADM'),
+    ('urbanism', 'URB'),
+    ('culture', 'CUL'),
+    ('sports', 'ESP')
+) AS s(department, prefix)
+ON CONFLICT (scope_type, scope_value) DO NOTHING;
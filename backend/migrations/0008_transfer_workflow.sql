@@ -0,0 +1,21 @@
+-- Fluxo de aprovação de transferências: pending -> approved -> received (ou rejected)
+-- transferred_by/transferred_at passam a registrar a solicitação
+-- Transferências já existentes foram efetivadas imediatamente, então ficam como 'received'
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS status VARCHAR NOT NULL DEFAULT 'received'
+    CHECK (status IN ('pending', 'approved', 'rejected', 'received'));
+ALTER TABLE transfers ALTER COLUMN status SET DEFAULT 'pending';
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS approved_by UUID REFERENCES users(id);
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS approved_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS rejected_by UUID REFERENCES users(id);
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS rejected_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS rejection_reason TEXT;
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS received_by UUID REFERENCES users(id);
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS received_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE transfers SET received_by = transferred_by, received_at = transferred_at
+WHERE status = 'received' AND received_at IS NULL;
+
+-- Apenas uma transferência em aberto por bem
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transfers_open_patrimony
+    ON transfers(patrimony_id) WHERE status IN ('pending', 'approved');
+CREATE INDEX IF NOT EXISTS idx_transfers_status ON transfers(status);
@@ -0,0 +1,3 @@
+-- Transferências de vários bens solicitadas juntas (termo combinado)
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS batch_id UUID;
+CREATE INDEX IF NOT EXISTS idx_transfers_batch ON transfers(batch_id) WHERE batch_id IS NOT NULL;
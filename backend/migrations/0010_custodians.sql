@@ -0,0 +1,28 @@
+-- Servidores responsáveis pela guarda dos bens
+CREATE TABLE IF NOT EXISTS custodians (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    registration_number VARCHAR NOT NULL UNIQUE,
+    position VARCHAR,
+    department VARCHAR NOT NULL,
+    email VARCHAR,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Histórico de guarda; released_at nulo indica a guarda vigente (uma por bem)
+CREATE TABLE IF NOT EXISTS custody_assignments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    custodian_id UUID NOT NULL REFERENCES custodians(id),
+    notes TEXT,
+    assigned_by UUID REFERENCES users(id),
+    assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    released_by UUID REFERENCES users(id),
+    released_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_custody_active_patrimony ON custody_assignments(patrimony_id) WHERE released_at IS NULL;
+CREATE INDEX IF NOT EXISTS idx_custody_custodian ON custody_assignments(custodian_id);
@@ -0,0 +1,31 @@
+-- Árvore de localização física (prédio > andar > sala)
+CREATE TABLE IF NOT EXISTS locations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    kind VARCHAR NOT NULL CHECK (kind IN ('building', 'floor', 'room')),
+    code VARCHAR UNIQUE,
+    parent_id UUID REFERENCES locations(id),
+    description TEXT,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_locations_parent ON locations(parent_id);
+
+-- Local atual do bem (independente do departamento proprietário)
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS location_id UUID REFERENCES locations(id);
+CREATE INDEX IF NOT EXISTS idx_patrimonies_location ON patrimonies(location_id);
+
+-- Histórico de movimentações físicas (separado das transferências entre departamentos)
+CREATE TABLE IF NOT EXISTS location_moves (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    from_location_id UUID REFERENCES locations(id),
+    to_location_id UUID NOT NULL REFERENCES locations(id),
+    notes TEXT,
+    moved_by UUID REFERENCES users(id),
+    moved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_location_moves_patrimony ON location_moves(patrimony_id);
@@ -0,0 +1,87 @@
+-- Cadastro de departamentos (antes derivados do texto livre em patrimonies.department)
+CREATE TABLE IF NOT EXISTS departments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    parent_id UUID REFERENCES departments(id),   -- Secretaria à qual o departamento pertence
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_departments_parent ON departments(parent_id);
+
+-- Gestores do departamento (aprovam transferências e atribuem responsáveis)
+CREATE TABLE IF NOT EXISTS department_managers (
+    department_id UUID NOT NULL REFERENCES departments(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    assigned_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    PRIMARY KEY (department_id, user_id)
+);
+
+-- Departamentos usados pelo sistema até aqui
+INSERT INTO departments (code, name) VALUES
+    ('education', 'Educação'),
+    ('health', 'Saúde'),
+    ('administration', 'Administração'),
+    ('urbanism', 'Urbanismo'),
+    ('culture', 'Cultura'),
+    ('sports', 'Esportes'),
+    ('transportation', 'Transporte'),
+    ('finance', 'Finanças'),
+    ('tourism', 'Turismo'),
+    ('environment', 'Meio Ambiente')
+ON CONFLICT (code) DO NOTHING;
+
+-- Valores em texto livre que correspondem ao nome de exibição ou ao código
+-- com outra grafia (ex.: 'Administração', ' Health') passam a usar o código
+UPDATE users t SET department = d.code FROM departments d
+WHERE t.department <> d.code AND (LOWER(TRIM(t.department)) = LOWER(d.name) OR LOWER(TRIM(t.department)) = d.code);
+
+UPDATE patrimonies t SET department = d.code FROM departments d
+WHERE t.department <> d.code AND (LOWER(TRIM(t.department)) = LOWER(d.name) OR LOWER(TRIM(t.department)) = d.code);
+
+UPDATE transfers t SET from_department = d.code FROM departments d
+WHERE t.from_department <> d.code AND (LOWER(TRIM(t.from_department)) = LOWER(d.name) OR LOWER(TRIM(t.from_department)) = d.code);
+
+UPDATE transfers t SET to_department = d.code FROM departments d
+WHERE t.to_department <> d.code AND (LOWER(TRIM(t.to_department)) = LOWER(d.name) OR LOWER(TRIM(t.to_department)) = d.code);
+
+UPDATE custodians t SET department = d.code FROM departments d
+WHERE t.department <> d.code AND (LOWER(TRIM(t.department)) = LOWER(d.name) OR LOWER(TRIM(t.department)) = d.code);
+
+UPDATE inventory_campaigns t SET department = d.code FROM departments d
+WHERE t.department <> d.code AND (LOWER(TRIM(t.department)) = LOWER(d.name) OR LOWER(TRIM(t.department)) = d.code);
+
+-- Demais valores viram departamentos próprios, para revisão pelo administrador
+INSERT INTO departments (code, name)
+SELECT DISTINCT value, value FROM (
+    SELECT department AS value FROM users
+    UNION SELECT department FROM patrimonies
+    UNION SELECT from_department FROM transfers
+    UNION SELECT to_department FROM transfers
+    UNION SELECT department FROM custodians
+    UNION SELECT department FROM inventory_campaigns
+) AS legacy
+ON CONFLICT (code) DO NOTHING;
+
+-- Chaves estrangeiras para o código do departamento
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_users_department') THEN
+        ALTER TABLE users ADD CONSTRAINT fk_users_department
+            FOREIGN KEY (department) REFERENCES departments(code) ON UPDATE CASCADE;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_patrimonies_department') THEN
+        ALTER TABLE patrimonies ADD CONSTRAINT fk_patrimonies_department
+            FOREIGN KEY (department) REFERENCES departments(code) ON UPDATE CASCADE;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_transfers_from_department') THEN
+        ALTER TABLE transfers ADD CONSTRAINT fk_transfers_from_department
+            FOREIGN KEY (from_department) REFERENCES departments(code) ON UPDATE CASCADE;
+    END IF;
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_transfers_to_department') THEN
+        ALTER TABLE transfers ADD CONSTRAINT fk_transfers_to_department
+            FOREIGN KEY (to_department) REFERENCES departments(code) ON UPDATE CASCADE;
+    END IF;
+END $$;
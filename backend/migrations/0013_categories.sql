@@ -0,0 +1,41 @@
+-- Categorias de bens vinculadas às contas do PCASP (Plano de Contas Aplicado ao Setor Público)
+CREATE TABLE IF NOT EXISTS categories (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    parent_id UUID REFERENCES categories(id),
+    accounting_code VARCHAR NOT NULL,                        -- Conta contábil PCASP (ex.: 1.2.3.1.1.03.00)
+    default_useful_life_months INTEGER NOT NULL CHECK (default_useful_life_months > 0),
+    default_residual_percent DECIMAL(5, 2) NOT NULL DEFAULT 0
+        CHECK (default_residual_percent BETWEEN 0 AND 100),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id);
+
+-- Grupos iniciais de bens móveis e imóveis (vida útil e valor residual de referência)
+INSERT INTO categories (code, name, accounting_code, default_useful_life_months, default_residual_percent) VALUES
+    ('equipment', 'Máquinas, aparelhos e equipamentos', '1.2.3.1.1.01.00', 120, 10),
+    ('it', 'Bens de informática', '1.2.3.1.1.02.00', 60, 10),
+    ('furniture', 'Móveis e utensílios', '1.2.3.1.1.03.00', 120, 10),
+    ('vehicles', 'Veículos', '1.2.3.1.1.05.00', 60, 10),
+    ('real-estate', 'Bens imóveis', '1.2.3.2.1.00.00', 300, 10)
+ON CONFLICT (code) DO NOTHING;
+
+INSERT INTO categories (code, name, parent_id, accounting_code, default_useful_life_months, default_residual_percent)
+SELECT c.code, c.name, p.id, c.accounting_code, c.months, c.percent
+FROM (VALUES
+    ('it-computers', 'Equipamentos de processamento de dados', 'it', '1.2.3.1.1.02.01', 60, 10),
+    ('it-peripherals', 'Periféricos e acessórios de informática', 'it', '1.2.3.1.1.02.02', 60, 10),
+    ('furniture-office', 'Mobiliário em geral', 'furniture', '1.2.3.1.1.03.01', 120, 10),
+    ('vehicles-road', 'Veículos em geral', 'vehicles', '1.2.3.1.1.05.01', 60, 10),
+    ('real-estate-special', 'Bens de uso especial', 'real-estate', '1.2.3.2.1.01.00', 300, 10)
+) AS c(code, name, parent_code, accounting_code, months, percent)
+JOIN categories p ON p.code = c.parent_code
+ON CONFLICT (code) DO NOTHING;
+
+-- Categoria do bem
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS category_id UUID REFERENCES categories(id);
+CREATE INDEX IF NOT EXISTS idx_patrimonies_category ON patrimonies(category_id);
@@ -0,0 +1,34 @@
+-- Ordens de serviço de manutenção: enquanto aberta, o bem fica com status 'maintenance'
+CREATE TABLE IF NOT EXISTS maintenance_orders (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed', 'cancelled')),
+    problem_description TEXT NOT NULL,
+    vendor VARCHAR,                          -- Empresa ou setor responsável pelo reparo
+    previous_status VARCHAR NOT NULL,        -- Status do bem restaurado no fechamento
+    opened_by UUID REFERENCES users(id),
+    opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    closed_by UUID REFERENCES users(id),
+    closed_at TIMESTAMP WITH TIME ZONE,
+    resolution TEXT,
+    cost DECIMAL(12, 2) CHECK (cost >= 0),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- No máximo uma ordem aberta por bem
+CREATE UNIQUE INDEX IF NOT EXISTS idx_maintenance_orders_open_patrimony ON maintenance_orders(patrimony_id) WHERE status = 'open';
+CREATE INDEX IF NOT EXISTS idx_maintenance_orders_patrimony ON maintenance_orders(patrimony_id);
+
+-- Orçamentos e notas fiscais anexados à ordem
+CREATE TABLE IF NOT EXISTS maintenance_attachments (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    order_id UUID NOT NULL REFERENCES maintenance_orders(id) ON DELETE CASCADE,
+    kind VARCHAR NOT NULL CHECK (kind IN ('quote', 'invoice', 'other')),
+    file_url VARCHAR NOT NULL,
+    original_name VARCHAR,
+    uploaded_by UUID REFERENCES users(id),
+    uploaded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_attachments_order ON maintenance_attachments(order_id);
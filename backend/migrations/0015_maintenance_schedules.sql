@@ -0,0 +1,71 @@
+-- Planos de manutenção preventiva: por bem ou por categoria, a cada N dias ou N unidades do medidor
+CREATE TABLE IF NOT EXISTS maintenance_schedules (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR NOT NULL,
+    description TEXT,
+    patrimony_id UUID REFERENCES patrimonies(id) ON DELETE CASCADE,
+    category_id UUID REFERENCES categories(id),
+    interval_days INTEGER CHECK (interval_days > 0),
+    interval_meter DECIMAL(14, 2) CHECK (interval_meter > 0),
+    meter_unit VARCHAR,                                      -- Ex.: km, horas
+    lead_days INTEGER NOT NULL DEFAULT 7 CHECK (lead_days >= 0),
+    lead_meter DECIMAL(14, 2) NOT NULL DEFAULT 0 CHECK (lead_meter >= 0),
+    start_date DATE NOT NULL DEFAULT CURRENT_DATE,          -- Base do primeiro ciclo
+    start_meter DECIMAL(14, 2) CHECK (start_meter >= 0),   -- Leitura base do primeiro ciclo (planos por medidor)
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CHECK ((patrimony_id IS NULL) <> (category_id IS NULL)),
+    CHECK ((interval_days IS NULL) <> (interval_meter IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_maintenance_schedules_patrimony ON maintenance_schedules(patrimony_id);
+CREATE INDEX IF NOT EXISTS idx_maintenance_schedules_category ON maintenance_schedules(category_id);
+
+-- Leituras do medidor de uso (hodômetro, horímetro)
+CREATE TABLE IF NOT EXISTS patrimony_meter_readings (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    reading DECIMAL(14, 2) NOT NULL CHECK (reading >= 0),
+    notes TEXT,
+    recorded_by UUID REFERENCES users(id),
+    recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_patrimony_meter_readings_patrimony ON patrimony_meter_readings(patrimony_id, recorded_at);
+
+-- Itens gerados pela rotina de agendamento (a vencer, vencidos, concluídos ou dispensados)
+CREATE TABLE IF NOT EXISTS maintenance_due_items (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    schedule_id UUID NOT NULL REFERENCES maintenance_schedules(id),
+    patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+    status VARCHAR NOT NULL CHECK (status IN ('due', 'overdue', 'done', 'skipped')),
+    due_date DATE,
+    due_meter DECIMAL(14, 2),
+    order_id UUID REFERENCES maintenance_orders(id) ON DELETE SET NULL,
+    generated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    completed_by UUID REFERENCES users(id),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    completed_meter DECIMAL(14, 2),
+    notes TEXT,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- No máximo um item pendente por plano e bem
+CREATE UNIQUE INDEX IF NOT EXISTS idx_maintenance_due_items_pending
+    ON maintenance_due_items(schedule_id, patrimony_id) WHERE status IN ('due', 'overdue');
+CREATE INDEX IF NOT EXISTS idx_maintenance_due_items_patrimony ON maintenance_due_items(patrimony_id);
+
+-- Planos iniciais: revisão semestral da maca hospitalar e revisão dos veículos a cada 10.000 km
+INSERT INTO maintenance_schedules (name, description, patrimony_id, interval_days, lead_days)
+SELECT 'Revisão preventiva semestral', 'Inspeção de rodízios, freios e estrutura da maca', p.id, 180, 15
+FROM patrimonies p
+WHERE p.plate = 'SAU001'
+  AND NOT EXISTS (SELECT 1 FROM maintenance_schedules s WHERE s.patrimony_id = p.id);
+
+INSERT INTO maintenance_schedules (name, description, category_id, interval_meter, meter_unit, lead_meter)
+SELECT 'Revisão a cada 10.000 km', 'Troca de óleo, filtros e inspeção geral', c.id, 10000, 'km', 500
+FROM categories c
+WHERE c.code = 'vehicles'
+  AND NOT EXISTS (SELECT 1 FROM maintenance_schedules s WHERE s.category_id = c.id);
@@ -0,0 +1,27 @@
+-- Encadeamento da trilha de auditoria: cada entrada guarda o hash da anterior
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS prev_hash VARCHAR(64);
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS entry_hash VARCHAR(64);   -- NULL apenas em entradas anteriores ao encadeamento
+
+-- Ponta da cadeia (linha única): o FOR UPDATE serializa quem grava na trilha
+CREATE TABLE IF NOT EXISTS audit_chain_head (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+    last_entry_id BIGINT,
+    last_hash VARCHAR(64),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+INSERT INTO audit_chain_head (id) VALUES (TRUE) ON CONFLICT (id) DO NOTHING;
+
+-- Pontos de controle assinados (HMAC) exportados periodicamente para fora do banco
+CREATE TABLE IF NOT EXISTS audit_checkpoints (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    last_entry_id BIGINT NOT NULL,
+    last_hash VARCHAR(64) NOT NULL,
+    entry_count BIGINT NOT NULL,
+    signature VARCHAR(64) NOT NULL,
+    file_path VARCHAR,
+    created_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_checkpoints_entry ON audit_checkpoints(last_entry_id);
@@ -0,0 +1,5 @@
+-- Exclusão lógica de bens: o registro vai para a lixeira e o histórico de transferências é preservado
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id);
+
+CREATE INDEX IF NOT EXISTS idx_patrimonies_deleted_at ON patrimonies(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,46 @@
+-- Entidades municipais (prefeitura, fundações, autarquia) atendidas pela mesma instalação
+CREATE TABLE IF NOT EXISTS tenants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    code VARCHAR NOT NULL UNIQUE,
+    name VARCHAR NOT NULL,
+    document_number VARCHAR,            -- CNPJ da entidade
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+INSERT INTO tenants (code, name) VALUES ('default', 'Prefeitura Municipal') ON CONFLICT (code) DO NOTHING;
+
+-- Registros sem entidade explícita (dados legados e cargas iniciais) pertencem à entidade padrão
+CREATE OR REPLACE FUNCTION default_tenant_id() RETURNS UUID AS $$
+    SELECT id FROM tenants WHERE code = 'default'
+$$ LANGUAGE sql STABLE;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE users SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE users ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE users ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_users_tenant ON users(tenant_id);
+
+ALTER TABLE departments ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE departments SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE departments ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE departments ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_departments_tenant ON departments(tenant_id);
+
+ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE patrimonies SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE patrimonies ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE patrimonies ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_patrimonies_tenant ON patrimonies(tenant_id);
+
+ALTER TABLE transfers ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE transfers SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE transfers ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE transfers ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_transfers_tenant ON transfers(tenant_id);
+
+-- Superadministrador: gerencia as entidades; o administrador padrão assume o papel se ninguém o tiver
+ALTER TABLE users ADD COLUMN IF NOT EXISTS is_super_admin BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE users SET is_super_admin = TRUE
+WHERE username = 'admin' AND NOT EXISTS (SELECT 1 FROM users WHERE is_super_admin);
@@ -0,0 +1,110 @@
+-- Papéis de acesso da aplicação (os privilégios do banco ficam em 0002_permissions.sql)
+CREATE TABLE IF NOT EXISTS roles (
+    code VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    description TEXT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS permissions (
+    code VARCHAR PRIMARY KEY,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS role_permissions (
+    role VARCHAR NOT NULL REFERENCES roles(code) ON DELETE CASCADE,
+    permission VARCHAR NOT NULL REFERENCES permissions(code) ON DELETE CASCADE,
+    PRIMARY KEY (role, permission)
+);
+
+INSERT INTO roles (code, name, description) VALUES
+    ('admin', 'Administrador', 'Acesso completo à entidade'),
+    ('patrimony_manager', 'Gestor de patrimônio', 'Cadastro, transferência, inventário e manutenção de bens'),
+    ('department_manager', 'Gestor de departamento', 'Bens e transferências do próprio departamento'),
+    ('auditor', 'Auditor', 'Consulta de bens, transferências e trilha de auditoria'),
+    ('read_only', 'Somente leitura', 'Consulta de bens e transferências')
+ON CONFLICT (code) DO NOTHING;
+
+-- Concessões padrão só para permissões novas: ajustes feitos pelo administrador não são desfeitos
+WITH new_permissions AS (
+    INSERT INTO permissions (code, description) VALUES
+        ('patrimony.read', 'Consultar bens, estatísticas, etiquetas e histórico'),
+        ('patrimony.write', 'Cadastrar e editar bens, imagens, documentos e localização'),
+        ('patrimony.delete', 'Enviar bens para a lixeira'),
+        ('trash.manage', 'Restaurar e remover definitivamente bens da lixeira'),
+        ('transfer.read', 'Consultar transferências e termos'),
+        ('transfer.request', 'Solicitar transferências'),
+        ('transfer.approve', 'Aprovar e rejeitar transferências do departamento'),
+        ('transfer.receive', 'Confirmar o recebimento de transferências'),
+        ('write_off.manage', 'Baixar bens e anexar documentos de baixa'),
+        ('depreciation.run', 'Executar o cálculo mensal de depreciação'),
+        ('inventory.manage', 'Abrir e encerrar campanhas de inventário'),
+        ('inventory.count', 'Abrir sessões e registrar leituras de inventário'),
+        ('custody.manage', 'Atribuir e liberar responsáveis por bens'),
+        ('custodian.manage', 'Cadastrar responsáveis'),
+        ('location.manage', 'Cadastrar locais'),
+        ('department.manage', 'Cadastrar departamentos e gestores'),
+        ('department.all', 'Atuar em bens e transferências de qualquer departamento'),
+        ('category.manage', 'Cadastrar categorias'),
+        ('plate_sequence.manage', 'Configurar sequências de placas'),
+        ('maintenance.read', 'Consultar ordens de serviço e planos de manutenção'),
+        ('maintenance.manage', 'Abrir e encerrar ordens de serviço e registrar leituras'),
+        ('maintenance.plan', 'Planos de manutenção por categoria e execução da rotina'),
+        ('audit.read', 'Consultar e verificar a trilha de auditoria'),
+        ('audit.manage', 'Gerar pontos de controle da auditoria'),
+        ('user.read', 'Listar usuários')
+    ON CONFLICT (code) DO NOTHING
+    RETURNING code
+)
+INSERT INTO role_permissions (role, permission)
+SELECT 'admin', code FROM new_permissions
+UNION ALL
+SELECT grants.role, grants.permission
+FROM (VALUES
+    ('patrimony_manager', 'patrimony.read'),
+    ('patrimony_manager', 'patrimony.write'),
+    ('patrimony_manager', 'patrimony.delete'),
+    ('patrimony_manager', 'transfer.read'),
+    ('patrimony_manager', 'transfer.request'),
+    ('patrimony_manager', 'transfer.approve'),
+    ('patrimony_manager', 'transfer.receive'),
+    ('patrimony_manager', 'inventory.manage'),
+    ('patrimony_manager', 'inventory.count'),
+    ('patrimony_manager', 'custody.manage'),
+    ('patrimony_manager', 'maintenance.read'),
+    ('patrimony_manager', 'maintenance.manage'),
+    ('department_manager', 'patrimony.read'),
+    ('department_manager', 'patrimony.write'),
+    ('department_manager', 'transfer.read'),
+    ('department_manager', 'transfer.request'),
+    ('department_manager', 'transfer.approve'),
+    ('department_manager', 'transfer.receive'),
+    ('department_manager', 'inventory.count'),
+    ('department_manager', 'custody.manage'),
+    ('department_manager', 'maintenance.read'),
+    ('department_manager', 'maintenance.manage'),
+    ('auditor', 'patrimony.read'),
+    ('auditor', 'transfer.read'),
+    ('auditor', 'maintenance.read'),
+    ('auditor', 'audit.read'),
+    ('auditor', 'user.read'),
+    ('read_only', 'patrimony.read'),
+    ('read_only', 'transfer.read'),
+    ('read_only', 'maintenance.read')
+) AS grants(role, permission)
+JOIN new_permissions n ON n.code = grants.permission
+ON CONFLICT DO NOTHING;
+
+-- Papéis legados: "user" fazia o trabalho do gestor de patrimônio e "manager" geria o próprio departamento
+UPDATE users SET role = 'patrimony_manager' WHERE role = 'user';
+UPDATE users SET role = 'department_manager' WHERE role = 'manager';
+UPDATE users SET role = 'read_only' WHERE role NOT IN (SELECT code FROM roles);
+ALTER TABLE users ALTER COLUMN role SET DEFAULT 'read_only';
+
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_users_role') THEN
+        ALTER TABLE users ADD CONSTRAINT fk_users_role
+            FOREIGN KEY (role) REFERENCES roles(code) ON UPDATE CASCADE;
+    END IF;
+END $$;
@@ -0,0 +1,29 @@
+-- Consulta de bens e transferências de todos os departamentos (auditoria)
+WITH new_permissions AS (
+    INSERT INTO permissions (code, description) VALUES
+        ('department.read_all', 'Consultar bens e transferências de todos os departamentos')
+    ON CONFLICT (code) DO NOTHING
+    RETURNING code
+)
+INSERT INTO role_permissions (role, permission)
+SELECT r.code, n.code FROM new_permissions n CROSS JOIN (VALUES ('admin'), ('auditor')) AS r(code)
+ON CONFLICT DO NOTHING;
+
+-- Acesso de leitura a outros departamentos (e às suas subunidades), concedido a um usuário
+-- ou a todos os lotados em um departamento (ex.: setor central de patrimônio).
+-- Sem department_id, o acesso vale para todos os departamentos da entidade.
+CREATE TABLE IF NOT EXISTS department_access_grants (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    grantee_department VARCHAR REFERENCES departments(code) ON UPDATE CASCADE ON DELETE CASCADE,
+    department_id UUID REFERENCES departments(id) ON DELETE CASCADE,
+    granted_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    CONSTRAINT chk_department_grant_grantee CHECK ((user_id IS NULL) <> (grantee_department IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_department_grants_user ON department_access_grants(user_id);
+CREATE INDEX IF NOT EXISTS idx_department_grants_grantee_department ON department_access_grants(grantee_department);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_department_grants_unique
+    ON department_access_grants(tenant_id, COALESCE(user_id::text, grantee_department), COALESCE(department_id::text, '*'));
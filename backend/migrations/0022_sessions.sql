@@ -0,0 +1,36 @@
+-- Sessões de login: o token de acesso é curto e carrega o id da sessão; revogar a sessão corta o acesso
+CREATE TABLE IF NOT EXISTS sessions (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ip_address VARCHAR,
+    user_agent VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    last_used_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    revoked_reason VARCHAR                  -- logout, admin, reuse
+);
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id);
+
+-- Tokens de renovação (só o hash SHA-256 é gravado); cada uso gera um novo token
+-- e reapresentar um token já usado revoga a sessão inteira
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    token_hash VARCHAR PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session ON refresh_tokens(session_id);
+
+WITH new_permissions AS (
+    INSERT INTO permissions (code, description) VALUES
+        ('user.manage', 'Gerenciar usuários e encerrar suas sessões')
+    ON CONFLICT (code) DO NOTHING
+    RETURNING code
+)
+INSERT INTO role_permissions (role, permission)
+SELECT 'admin', code FROM new_permissions
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,22 @@
+-- Usuários desativados não entram nem usam sessões antigas; a senha temporária precisa ser trocada
+ALTER TABLE users ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS deactivated_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS must_change_password BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Cadastro só por convite: papel, departamento e entidade vêm do convite (só o hash do token é gravado)
+CREATE TABLE IF NOT EXISTS user_invitations (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    tenant_id UUID NOT NULL REFERENCES tenants(id),
+    token_hash VARCHAR NOT NULL UNIQUE,
+    email VARCHAR,
+    department VARCHAR NOT NULL REFERENCES departments(code) ON UPDATE CASCADE,
+    role VARCHAR NOT NULL REFERENCES roles(code) ON UPDATE CASCADE,
+    invited_by UUID REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    accepted_at TIMESTAMP WITH TIME ZONE,
+    accepted_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_invitations_tenant ON user_invitations(tenant_id);
@@ -0,0 +1,11 @@
+-- Tokens de redefinição de senha enviados por e-mail: uso único, com validade (só o hash é gravado)
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    token_hash VARCHAR PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    ip_address VARCHAR,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user ON password_reset_tokens(user_id);
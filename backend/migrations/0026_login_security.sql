@@ -0,0 +1,21 @@
+-- Proteção contra força bruta no login: falhas seguidas por usuário e bloqueio temporário
+ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_failed_login_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP WITH TIME ZONE;
+
+-- Todas as tentativas de login (com e sem sucesso), para o atraso por IP e para revisão de segurança
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    username VARCHAR NOT NULL,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    tenant_id UUID REFERENCES tenants(id),
+    ip_address VARCHAR,
+    user_agent VARCHAR,
+    success BOOLEAN NOT NULL,
+    failure_reason VARCHAR,                 -- unknown_user, invalid_password, locked, throttled, deactivated, tenant_inactive
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_attempts_ip ON login_attempts(ip_address, created_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_tenant ON login_attempts(tenant_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_username ON login_attempts(LOWER(username), created_at);
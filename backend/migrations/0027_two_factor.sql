@@ -0,0 +1,35 @@
+-- Papéis com 2FA obrigatório; o valor inicial só é definido uma vez (ajustes posteriores são mantidos)
+ALTER TABLE roles ADD COLUMN IF NOT EXISTS requires_two_factor BOOLEAN;
+UPDATE roles SET requires_two_factor = code IN ('admin', 'patrimony_manager') WHERE requires_two_factor IS NULL;
+ALTER TABLE roles ALTER COLUMN requires_two_factor SET DEFAULT FALSE;
+ALTER TABLE roles ALTER COLUMN requires_two_factor SET NOT NULL;
+
+-- TOTP (RFC 6238): segredo em base32, ativado só depois de confirmar um código;
+-- o último passo aceito impede reutilizar o mesmo código
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret VARCHAR;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_step BIGINT;
+
+-- Códigos de recuperação de uso único (só o hash é gravado)
+CREATE TABLE IF NOT EXISTS two_factor_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_two_factor_recovery_codes_user ON two_factor_recovery_codes(user_id);
+
+-- Segunda etapa do login: a senha foi aceita e falta o código
+CREATE TABLE IF NOT EXISTS two_factor_challenges (
+    token_hash VARCHAR PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    used_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_two_factor_challenges_user ON two_factor_challenges(user_id);
@@ -0,0 +1,22 @@
+-- Entidade dona de cada entrada da trilha: a consulta só mostra as da entidade do usuário
+-- (sem FK, como actor_id, para o registro sobreviver a qualquer remoção)
+ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS tenant_id UUID;
+
+-- Entradas anteriores: a entidade vem do retrato gravado ou, na falta dele, do registro atual.
+-- A trilha é somente inserção, então a trava é suspensa só durante o preenchimento
+ALTER TABLE audit_log DISABLE TRIGGER audit_log_no_change;
+UPDATE audit_log a SET tenant_id = COALESCE(
+    (a.after_data->>'tenant_id')::uuid,
+    (a.before_data->>'tenant_id')::uuid,
+    CASE a.entity_type
+        WHEN 'tenant' THEN a.entity_id
+        WHEN 'patrimony' THEN (SELECT tenant_id FROM patrimonies WHERE id = a.entity_id)
+        WHEN 'transfer' THEN (SELECT tenant_id FROM transfers WHERE id = a.entity_id)
+        WHEN 'user' THEN (SELECT tenant_id FROM users WHERE id = a.entity_id)
+    END,
+    default_tenant_id()
+)
+WHERE a.tenant_id IS NULL;
+ALTER TABLE audit_log ENABLE TRIGGER audit_log_no_change;
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_tenant ON audit_log(tenant_id);
@@ -0,0 +1,48 @@
+-- Cadastros de apoio também pertencem a uma entidade: cada uma mantém suas campanhas,
+-- sequências de placa, locais e categorias, com códigos repetíveis entre entidades
+ALTER TABLE inventory_campaigns ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE inventory_campaigns c SET tenant_id = COALESCE(
+    (SELECT d.tenant_id FROM departments d WHERE d.code = c.department),
+    default_tenant_id()
+)
+WHERE c.tenant_id IS NULL;
+ALTER TABLE inventory_campaigns ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE inventory_campaigns ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_inventory_campaigns_tenant ON inventory_campaigns(tenant_id);
+
+DROP INDEX IF EXISTS idx_inventory_campaigns_open_department;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_inventory_campaigns_open_tenant_department
+    ON inventory_campaigns(tenant_id, department) WHERE status = 'open';
+
+ALTER TABLE plate_sequences ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE plate_sequences s SET tenant_id = COALESCE(
+    CASE WHEN s.scope_type = 'department'
+        THEN (SELECT d.tenant_id FROM departments d WHERE d.code = s.scope_value)
+    END,
+    default_tenant_id()
+)
+WHERE s.tenant_id IS NULL;
+ALTER TABLE plate_sequences ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE plate_sequences ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_plate_sequences_tenant ON plate_sequences(tenant_id);
+
+ALTER TABLE plate_sequences DROP CONSTRAINT IF EXISTS plate_sequences_scope_type_scope_value_key;
+ALTER TABLE plate_sequences ADD CONSTRAINT plate_sequences_tenant_scope_key UNIQUE (tenant_id, scope_type, scope_value);
+
+ALTER TABLE locations ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE locations SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE locations ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE locations ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_locations_tenant ON locations(tenant_id);
+
+ALTER TABLE locations DROP CONSTRAINT IF EXISTS locations_code_key;
+ALTER TABLE locations ADD CONSTRAINT locations_tenant_code_key UNIQUE (tenant_id, code);
+
+ALTER TABLE categories ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE categories SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL;
+ALTER TABLE categories ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE categories ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_categories_tenant ON categories(tenant_id);
+
+ALTER TABLE categories DROP CONSTRAINT IF EXISTS categories_code_key;
+ALTER TABLE categories ADD CONSTRAINT categories_tenant_code_key UNIQUE (tenant_id, code);
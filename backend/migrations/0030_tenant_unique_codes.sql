@@ -0,0 +1,28 @@
+-- Código de departamento e placa são únicos dentro da entidade: duas entidades podem ter
+-- o departamento 'education' ou a placa 'ADM001'. As referências por código passam a
+-- incluir a entidade (mesmos nomes de restrição, agora compostas)
+ALTER TABLE users DROP CONSTRAINT IF EXISTS fk_users_department;
+ALTER TABLE patrimonies DROP CONSTRAINT IF EXISTS fk_patrimonies_department;
+ALTER TABLE transfers DROP CONSTRAINT IF EXISTS fk_transfers_from_department;
+ALTER TABLE transfers DROP CONSTRAINT IF EXISTS fk_transfers_to_department;
+ALTER TABLE department_access_grants DROP CONSTRAINT IF EXISTS department_access_grants_grantee_department_fkey;
+ALTER TABLE user_invitations DROP CONSTRAINT IF EXISTS user_invitations_department_fkey;
+
+ALTER TABLE departments DROP CONSTRAINT IF EXISTS departments_code_key;
+ALTER TABLE departments ADD CONSTRAINT departments_tenant_code_key UNIQUE (tenant_id, code);
+
+ALTER TABLE users ADD CONSTRAINT fk_users_department
+    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+ALTER TABLE patrimonies ADD CONSTRAINT fk_patrimonies_department
+    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+ALTER TABLE transfers ADD CONSTRAINT fk_transfers_from_department
+    FOREIGN KEY (tenant_id, from_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+ALTER TABLE transfers ADD CONSTRAINT fk_transfers_to_department
+    FOREIGN KEY (tenant_id, to_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+ALTER TABLE department_access_grants ADD CONSTRAINT department_access_grants_grantee_department_fkey
+    FOREIGN KEY (tenant_id, grantee_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE ON DELETE CASCADE;
+ALTER TABLE user_invitations ADD CONSTRAINT user_invitations_department_fkey
+    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+
+ALTER TABLE patrimonies DROP CONSTRAINT IF EXISTS patrimonies_plate_key;
+ALTER TABLE patrimonies ADD CONSTRAINT patrimonies_tenant_plate_key UNIQUE (tenant_id, plate);
@@ -0,0 +1,17 @@
+-- Responsáveis também pertencem a uma entidade; a matrícula é única dentro dela.
+-- Quem tem usuário vinculado herda a entidade dele; os demais, a do departamento
+-- (a padrão, quando o código existe em mais de uma)
+ALTER TABLE custodians ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id);
+UPDATE custodians c SET tenant_id = COALESCE(
+    (SELECT u.tenant_id FROM users u WHERE u.id = c.user_id),
+    (SELECT d.tenant_id FROM departments d WHERE d.code = c.department
+     ORDER BY d.tenant_id = default_tenant_id() DESC LIMIT 1),
+    default_tenant_id()
+)
+WHERE c.tenant_id IS NULL;
+ALTER TABLE custodians ALTER COLUMN tenant_id SET DEFAULT default_tenant_id();
+ALTER TABLE custodians ALTER COLUMN tenant_id SET NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_custodians_tenant ON custodians(tenant_id);
+
+ALTER TABLE custodians DROP CONSTRAINT IF EXISTS custodians_registration_number_key;
+ALTER TABLE custodians ADD CONSTRAINT custodians_tenant_registration_key UNIQUE (tenant_id, registration_number);
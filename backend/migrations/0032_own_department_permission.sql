@@ -0,0 +1,10 @@
+-- Gestão do próprio departamento de lotação vira permissão, em vez de depender do código do papel
+WITH new_permissions AS (
+    INSERT INTO permissions (code, description) VALUES
+        ('department.own', 'Atuar em bens e transferências do próprio departamento de lotação')
+    ON CONFLICT (code) DO NOTHING
+    RETURNING code
+)
+INSERT INTO role_permissions (role, permission)
+SELECT 'department_manager', code FROM new_permissions
+ON CONFLICT DO NOTHING;
@@ -132,7 +132,7 @@ async fn execute_migrations(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
             INSERT INTO users (company_name, department, username, password_hash, email, role)
-            VALUES ('Prefeitura Municipal', 'Administração', 'admin', $1, 'admin@prefeitura.gov.br', 'admin')
+            VALUES ('Prefeitura Municipal', 'administration', 'admin', $1, 'admin@prefeitura.gov.br', 'admin')
             "#
         )
         .bind(password_hash)
@@ -169,7 +169,7 @@ async fn execute_migrations(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
                 ('EDU001', 'Cadeira Escolar', 'Cadeira para sala de aula', '2023-01-15', 150.00, 'education', 'active', 'NF20230115001', 'EMP20230115001', 'DENF20230115001', $1),
                 ('SAU001', 'Maca Hospitalar', 'Maca para atendimento', '2023-02-20', 1200.00, 'health', 'active', 'NF20230220001', 'EMP20230220001', 'DENF20230220001', $1),
                 ('ADM001', 'Computador', 'Computador para administração', '2023-03-10', 2500.00, 'administration', 'active', 'NF20230310001', 'EMP20230310001', 'DENF20230310001', $1)
-                ON CONFLICT (plate) DO NOTHING
+                ON CONFLICT DO NOTHING
                 "#
             )
             .bind(admin_id)
@@ -180,5 +180,1571 @@ async fn execute_migrations(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
         }
     }
     
+    // Parâmetros de depreciação por bem
+    sqlx::query(
+        r#"
+        ALTER TABLE patrimonies
+            ADD COLUMN IF NOT EXISTS depreciation_method VARCHAR,
+            ADD COLUMN IF NOT EXISTS useful_life_months INTEGER,
+            ADD COLUMN IF NOT EXISTS residual_value DECIMAL(10, 2)
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // Criar tabela de lançamentos de depreciação
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS depreciation_entries (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            period DATE NOT NULL,
+            method VARCHAR NOT NULL,
+            depreciation_amount DECIMAL(12, 2) NOT NULL,
+            accumulated_depreciation DECIMAL(12, 2) NOT NULL,
+            book_value DECIMAL(12, 2) NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            UNIQUE (patrimony_id, period)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_depreciation_entries_period ON depreciation_entries(period)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'depreciation_entries' criada/verificada");
+    
+    // Criar tabela de baixas patrimoniais
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS write_offs (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL UNIQUE REFERENCES patrimonies(id),
+            reason VARCHAR NOT NULL CHECK (reason IN ('obsolescence', 'loss', 'theft', 'donation', 'auction')),
+            act_number VARCHAR NOT NULL,
+            write_off_date DATE NOT NULL,
+            notes TEXT,
+            document_file VARCHAR,
+            book_value DECIMAL(12, 2) NOT NULL DEFAULT 0,
+            previous_status VARCHAR NOT NULL,
+            approved_by UUID NOT NULL REFERENCES users(id),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'write_offs' criada/verificada");
+    
+    // Criar tabelas de inventário físico
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS inventory_campaigns (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name VARCHAR NOT NULL,
+            department VARCHAR NOT NULL,
+            year INTEGER NOT NULL,
+            status VARCHAR NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed')),
+            opened_by UUID REFERENCES users(id),
+            opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            closed_by UUID REFERENCES users(id),
+            closed_at TIMESTAMP WITH TIME ZONE,
+            report JSONB
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // O índice de campanha aberta por departamento é criado junto com a coluna da entidade
+    
+    println!("✅ Tabela 'inventory_campaigns' criada/verificada");
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS inventory_sessions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            campaign_id UUID NOT NULL REFERENCES inventory_campaigns(id) ON DELETE CASCADE,
+            description VARCHAR,
+            started_by UUID REFERENCES users(id),
+            started_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            closed_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'inventory_sessions' criada/verificada");
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS inventory_scans (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            campaign_id UUID NOT NULL REFERENCES inventory_campaigns(id) ON DELETE CASCADE,
+            session_id UUID NOT NULL REFERENCES inventory_sessions(id) ON DELETE CASCADE,
+            plate VARCHAR NOT NULL,
+            patrimony_id UUID REFERENCES patrimonies(id) ON DELETE SET NULL,
+            scanned_by UUID REFERENCES users(id),
+            scanned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            UNIQUE (campaign_id, plate)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_inventory_scans_session ON inventory_scans(session_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'inventory_scans' criada/verificada");
+    
+    // Criar tabela de sequências de placas
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS plate_sequences (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            scope_type VARCHAR NOT NULL CHECK (scope_type IN ('department', 'category')),
+            scope_value VARCHAR NOT NULL,
+            prefix VARCHAR NOT NULL,
+            padding INTEGER NOT NULL DEFAULT 3 CHECK (padding BETWEEN 1 AND 12),
+            include_year BOOLEAN NOT NULL DEFAULT FALSE,
+            separator VARCHAR NOT NULL DEFAULT '',
+            next_value BIGINT NOT NULL DEFAULT 1 CHECK (next_value >= 1),
+            current_year INTEGER,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            UNIQUE (scope_type, scope_value)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'plate_sequences' criada/verificada");
+    
+    // Fluxo de aprovação de transferências (registros antigos ficam como 'received')
+    sqlx::query(
+        r#"
+        ALTER TABLE transfers
+            ADD COLUMN IF NOT EXISTS status VARCHAR NOT NULL DEFAULT 'received'
+                CHECK (status IN ('pending', 'approved', 'rejected', 'received')),
+            ADD COLUMN IF NOT EXISTS approved_by UUID REFERENCES users(id),
+            ADD COLUMN IF NOT EXISTS approved_at TIMESTAMP WITH TIME ZONE,
+            ADD COLUMN IF NOT EXISTS rejected_by UUID REFERENCES users(id),
+            ADD COLUMN IF NOT EXISTS rejected_at TIMESTAMP WITH TIME ZONE,
+            ADD COLUMN IF NOT EXISTS rejection_reason TEXT,
+            ADD COLUMN IF NOT EXISTS received_by UUID REFERENCES users(id),
+            ADD COLUMN IF NOT EXISTS received_at TIMESTAMP WITH TIME ZONE
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("ALTER TABLE transfers ALTER COLUMN status SET DEFAULT 'pending'")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        "UPDATE transfers SET received_by = transferred_by, received_at = transferred_at WHERE status = 'received' AND received_at IS NULL"
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_transfers_open_patrimony ON transfers(patrimony_id) WHERE status IN ('pending', 'approved')"
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transfers_status ON transfers(status)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Fluxo de aprovação de transferências verificado");
+    
+    // Lotes de transferência (termo combinado)
+    sqlx::query("ALTER TABLE transfers ADD COLUMN IF NOT EXISTS batch_id UUID")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_transfers_batch ON transfers(batch_id) WHERE batch_id IS NOT NULL")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Lotes de transferência verificados");
+    
+    // Criar tabelas de responsáveis e histórico de guarda
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS custodians (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name VARCHAR NOT NULL,
+            registration_number VARCHAR NOT NULL UNIQUE,
+            position VARCHAR,
+            department VARCHAR NOT NULL,
+            email VARCHAR,
+            user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'custodians' criada/verificada");
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS custody_assignments (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            custodian_id UUID NOT NULL REFERENCES custodians(id),
+            notes TEXT,
+            assigned_by UUID REFERENCES users(id),
+            assigned_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            released_by UUID REFERENCES users(id),
+            released_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_custody_active_patrimony ON custody_assignments(patrimony_id) WHERE released_at IS NULL")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_custody_custodian ON custody_assignments(custodian_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'custody_assignments' criada/verificada");
+    
+    // Criar árvore de localizações e histórico de movimentações
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS locations (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name VARCHAR NOT NULL,
+            kind VARCHAR NOT NULL CHECK (kind IN ('building', 'floor', 'room')),
+            code VARCHAR UNIQUE,
+            parent_id UUID REFERENCES locations(id),
+            description TEXT,
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_locations_parent ON locations(parent_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'locations' criada/verificada");
+    
+    sqlx::query("ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS location_id UUID REFERENCES locations(id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_patrimonies_location ON patrimonies(location_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS location_moves (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            from_location_id UUID REFERENCES locations(id),
+            to_location_id UUID NOT NULL REFERENCES locations(id),
+            notes TEXT,
+            moved_by UUID REFERENCES users(id),
+            moved_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_location_moves_patrimony ON location_moves(patrimony_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'location_moves' criada/verificada");
+    
+    // Cadastro de departamentos com hierarquia (secretaria) e gestores
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS departments (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            code VARCHAR NOT NULL UNIQUE,
+            name VARCHAR NOT NULL,
+            parent_id UUID REFERENCES departments(id),
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_departments_parent ON departments(parent_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS department_managers (
+            department_id UUID NOT NULL REFERENCES departments(id) ON DELETE CASCADE,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            assigned_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            PRIMARY KEY (department_id, user_id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO departments (code, name) VALUES
+            ('education', 'Educação'),
+            ('health', 'Saúde'),
+            ('administration', 'Administração'),
+            ('urbanism', 'Urbanismo'),
+            ('culture', 'Cultura'),
+            ('sports', 'Esportes'),
+            ('transportation', 'Transporte'),
+            ('finance', 'Finanças'),
+            ('tourism', 'Turismo'),
+            ('environment', 'Meio Ambiente')
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // Texto livre legado: nomes de exibição ('Administração') e outras grafias passam a usar o código.
+    // Só roda antes das entidades: depois delas o mesmo código pode existir em mais de uma
+    let (has_tenants,): (bool,) = sqlx::query_as(
+        "SELECT EXISTS(SELECT 1 FROM information_schema.columns WHERE table_name = 'departments' AND column_name = 'tenant_id')"
+    )
+    .fetch_one(pool)
+    .await?;
+    
+    let department_columns = [
+        ("users", "department"),
+        ("patrimonies", "department"),
+        ("transfers", "from_department"),
+        ("transfers", "to_department"),
+        ("custodians", "department"),
+        ("inventory_campaigns", "department"),
+    ];
+    
+    for (table, column) in department_columns.into_iter().filter(|_| !has_tenants) {
+        sqlx::query(&format!(
+            "UPDATE {table} t SET {column} = d.code FROM departments d
+             WHERE t.{column} <> d.code AND (LOWER(TRIM(t.{column})) = LOWER(d.name) OR LOWER(TRIM(t.{column})) = d.code)"
+        ))
+        .execute(pool)
+        .await?;
+        
+        // Valores sem correspondência viram departamentos próprios, para revisão pelo administrador
+        sqlx::query(&format!(
+            "INSERT INTO departments (code, name) SELECT DISTINCT {column}, {column} FROM {table} ON CONFLICT (code) DO NOTHING"
+        ))
+        .execute(pool)
+        .await?;
+    }
+    
+    sqlx::query(
+        r#"
+        DO $$
+        BEGIN
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_users_department') THEN
+                ALTER TABLE users ADD CONSTRAINT fk_users_department
+                    FOREIGN KEY (department) REFERENCES departments(code) ON UPDATE CASCADE;
+            END IF;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_patrimonies_department') THEN
+                ALTER TABLE patrimonies ADD CONSTRAINT fk_patrimonies_department
+                    FOREIGN KEY (department) REFERENCES departments(code) ON UPDATE CASCADE;
+            END IF;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_transfers_from_department') THEN
+                ALTER TABLE transfers ADD CONSTRAINT fk_transfers_from_department
+                    FOREIGN KEY (from_department) REFERENCES departments(code) ON UPDATE CASCADE;
+            END IF;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_transfers_to_department') THEN
+                ALTER TABLE transfers ADD CONSTRAINT fk_transfers_to_department
+                    FOREIGN KEY (to_department) REFERENCES departments(code) ON UPDATE CASCADE;
+            END IF;
+        END $$
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'departments' criada/verificada");
+    
+    // Categorias de bens vinculadas às contas do PCASP
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS categories (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            code VARCHAR NOT NULL UNIQUE,
+            name VARCHAR NOT NULL,
+            parent_id UUID REFERENCES categories(id),
+            accounting_code VARCHAR NOT NULL,
+            default_useful_life_months INTEGER NOT NULL CHECK (default_useful_life_months > 0),
+            default_residual_percent DECIMAL(5, 2) NOT NULL DEFAULT 0
+                CHECK (default_residual_percent BETWEEN 0 AND 100),
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_categories_parent ON categories(parent_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO categories (code, name, accounting_code, default_useful_life_months, default_residual_percent) VALUES
+            ('equipment', 'Máquinas, aparelhos e equipamentos', '1.2.3.1.1.01.00', 120, 10),
+            ('it', 'Bens de informática', '1.2.3.1.1.02.00', 60, 10),
+            ('furniture', 'Móveis e utensílios', '1.2.3.1.1.03.00', 120, 10),
+            ('vehicles', 'Veículos', '1.2.3.1.1.05.00', 60, 10),
+            ('real-estate', 'Bens imóveis', '1.2.3.2.1.00.00', 300, 10)
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO categories (code, name, parent_id, accounting_code, default_useful_life_months, default_residual_percent)
+        SELECT c.code, c.name, p.id, c.accounting_code, c.months, c.percent
+        FROM (VALUES
+            ('it-computers', 'Equipamentos de processamento de dados', 'it', '1.2.3.1.1.02.01', 60, 10),
+            ('it-peripherals', 'Periféricos e acessórios de informática', 'it', '1.2.3.1.1.02.02', 60, 10),
+            ('furniture-office', 'Mobiliário em geral', 'furniture', '1.2.3.1.1.03.01', 120, 10),
+            ('vehicles-road', 'Veículos em geral', 'vehicles', '1.2.3.1.1.05.01', 60, 10),
+            ('real-estate-special', 'Bens de uso especial', 'real-estate', '1.2.3.2.1.01.00', 300, 10)
+        ) AS c(code, name, parent_code, accounting_code, months, percent)
+        JOIN categories p ON p.id = (SELECT id FROM categories WHERE code = c.parent_code ORDER BY created_at LIMIT 1)
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS category_id UUID REFERENCES categories(id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_patrimonies_category ON patrimonies(category_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'categories' criada/verificada");
+    
+    // Ordens de serviço de manutenção e anexos (orçamentos, notas fiscais)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_orders (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            status VARCHAR NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'closed', 'cancelled')),
+            problem_description TEXT NOT NULL,
+            vendor VARCHAR,
+            previous_status VARCHAR NOT NULL,
+            opened_by UUID REFERENCES users(id),
+            opened_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            closed_by UUID REFERENCES users(id),
+            closed_at TIMESTAMP WITH TIME ZONE,
+            resolution TEXT,
+            cost DECIMAL(12, 2) CHECK (cost >= 0),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_maintenance_orders_open_patrimony ON maintenance_orders(patrimony_id) WHERE status = 'open'"
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_maintenance_orders_patrimony ON maintenance_orders(patrimony_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_attachments (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            order_id UUID NOT NULL REFERENCES maintenance_orders(id) ON DELETE CASCADE,
+            kind VARCHAR NOT NULL CHECK (kind IN ('quote', 'invoice', 'other')),
+            file_url VARCHAR NOT NULL,
+            original_name VARCHAR,
+            uploaded_by UUID REFERENCES users(id),
+            uploaded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_maintenance_attachments_order ON maintenance_attachments(order_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'maintenance_orders' criada/verificada");
+    
+    // Planos de manutenção preventiva, leituras de medidor e itens a vencer
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_schedules (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            name VARCHAR NOT NULL,
+            description TEXT,
+            patrimony_id UUID REFERENCES patrimonies(id) ON DELETE CASCADE,
+            category_id UUID REFERENCES categories(id),
+            interval_days INTEGER CHECK (interval_days > 0),
+            interval_meter DECIMAL(14, 2) CHECK (interval_meter > 0),
+            meter_unit VARCHAR,
+            lead_days INTEGER NOT NULL DEFAULT 7 CHECK (lead_days >= 0),
+            lead_meter DECIMAL(14, 2) NOT NULL DEFAULT 0 CHECK (lead_meter >= 0),
+            start_date DATE NOT NULL DEFAULT CURRENT_DATE,
+            start_meter DECIMAL(14, 2) CHECK (start_meter >= 0),
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_by UUID REFERENCES users(id),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            CHECK ((patrimony_id IS NULL) <> (category_id IS NULL)),
+            CHECK ((interval_days IS NULL) <> (interval_meter IS NULL))
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_maintenance_schedules_patrimony ON maintenance_schedules(patrimony_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_maintenance_schedules_category ON maintenance_schedules(category_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS patrimony_meter_readings (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            reading DECIMAL(14, 2) NOT NULL CHECK (reading >= 0),
+            notes TEXT,
+            recorded_by UUID REFERENCES users(id),
+            recorded_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_patrimony_meter_readings_patrimony ON patrimony_meter_readings(patrimony_id, recorded_at)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_due_items (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            schedule_id UUID NOT NULL REFERENCES maintenance_schedules(id),
+            patrimony_id UUID NOT NULL REFERENCES patrimonies(id) ON DELETE CASCADE,
+            status VARCHAR NOT NULL CHECK (status IN ('due', 'overdue', 'done', 'skipped')),
+            due_date DATE,
+            due_meter DECIMAL(14, 2),
+            order_id UUID REFERENCES maintenance_orders(id) ON DELETE SET NULL,
+            generated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            completed_by UUID REFERENCES users(id),
+            completed_at TIMESTAMP WITH TIME ZONE,
+            completed_meter DECIMAL(14, 2),
+            notes TEXT,
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_maintenance_due_items_pending ON maintenance_due_items(schedule_id, patrimony_id) WHERE status IN ('due', 'overdue')"
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_maintenance_due_items_patrimony ON maintenance_due_items(patrimony_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO maintenance_schedules (name, description, patrimony_id, interval_days, lead_days)
+        SELECT 'Revisão preventiva semestral', 'Inspeção de rodízios, freios e estrutura da maca', p.id, 180, 15
+        FROM patrimonies p
+        WHERE p.id = (SELECT id FROM patrimonies WHERE plate = 'SAU001' ORDER BY created_at LIMIT 1)
+          AND NOT EXISTS (SELECT 1 FROM maintenance_schedules s WHERE s.patrimony_id = p.id)
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO maintenance_schedules (name, description, category_id, interval_meter, meter_unit, lead_meter)
+        SELECT 'Revisão a cada 10.000 km', 'Troca de óleo, filtros e inspeção geral', c.id, 10000, 'km', 500
+        FROM categories c
+        WHERE c.id = (SELECT id FROM categories WHERE code = 'vehicles' ORDER BY created_at LIMIT 1)
+          AND NOT EXISTS (SELECT 1 FROM maintenance_schedules s WHERE s.category_id = c.id)
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'maintenance_schedules' criada/verificada");
+    
+    // Trilha de auditoria (somente inserção)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id BIGSERIAL PRIMARY KEY,
+            occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+            actor_id UUID,
+            actor_username VARCHAR,
+            ip_address VARCHAR,
+            entity_type VARCHAR NOT NULL,
+            entity_id UUID NOT NULL,
+            action VARCHAR NOT NULL,
+            before_data JSONB,
+            after_data JSONB,
+            changes JSONB NOT NULL DEFAULT '{}'::jsonb
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_entity ON audit_log(entity_type, entity_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_occurred_at ON audit_log(occurred_at)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+        BEGIN
+            RAISE EXCEPTION 'audit_log is append-only';
+        END;
+        $$ LANGUAGE plpgsql
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("DROP TRIGGER IF EXISTS audit_log_no_change ON audit_log")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TRIGGER audit_log_no_change BEFORE UPDATE OR DELETE ON audit_log
+            FOR EACH ROW EXECUTE FUNCTION audit_log_append_only()
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("DROP TRIGGER IF EXISTS audit_log_no_truncate ON audit_log")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON audit_log
+            FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only()
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'audit_log' criada/verificada");
+    
+    // Encadeamento da trilha: hash da entrada anterior e hash da própria entrada
+    sqlx::query("ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS prev_hash VARCHAR(64)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS entry_hash VARCHAR(64)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_chain_head (
+            id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+            last_entry_id BIGINT,
+            last_hash VARCHAR(64),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("INSERT INTO audit_chain_head (id) VALUES (TRUE) ON CONFLICT (id) DO NOTHING")
+        .execute(pool)
+        .await?;
+    
+    // Pontos de controle assinados da trilha
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS audit_checkpoints (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            last_entry_id BIGINT NOT NULL,
+            last_hash VARCHAR(64) NOT NULL,
+            entry_count BIGINT NOT NULL,
+            signature VARCHAR(64) NOT NULL,
+            file_path VARCHAR,
+            created_by UUID REFERENCES users(id),
+            created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_checkpoints_entry ON audit_checkpoints(last_entry_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'audit_checkpoints' criada/verificada");
+    
+    // Exclusão lógica de bens (lixeira)
+    sqlx::query("ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE patrimonies ADD COLUMN IF NOT EXISTS deleted_by UUID REFERENCES users(id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_patrimonies_deleted_at ON patrimonies(deleted_at) WHERE deleted_at IS NOT NULL")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Lixeira de patrimônios verificada");
+    
+    // Entidades municipais (multi-tenant)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tenants (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            code VARCHAR NOT NULL UNIQUE,
+            name VARCHAR NOT NULL,
+            document_number VARCHAR,
+            active BOOLEAN NOT NULL DEFAULT TRUE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("INSERT INTO tenants (code, name) VALUES ('default', 'Prefeitura Municipal') ON CONFLICT (code) DO NOTHING")
+        .execute(pool)
+        .await?;
+    
+    // Registros sem entidade explícita (dados legados e cargas iniciais) pertencem à entidade padrão
+    sqlx::query(
+        r#"
+        CREATE OR REPLACE FUNCTION default_tenant_id() RETURNS UUID AS $$
+            SELECT id FROM tenants WHERE code = 'default'
+        $$ LANGUAGE sql STABLE
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    for table in ["users", "departments", "patrimonies", "transfers"] {
+        sqlx::query(&format!("ALTER TABLE {table} ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id)"))
+            .execute(pool)
+            .await?;
+        
+        sqlx::query(&format!("UPDATE {table} SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL"))
+            .execute(pool)
+            .await?;
+        
+        sqlx::query(&format!("ALTER TABLE {table} ALTER COLUMN tenant_id SET DEFAULT default_tenant_id()"))
+            .execute(pool)
+            .await?;
+        
+        sqlx::query(&format!("ALTER TABLE {table} ALTER COLUMN tenant_id SET NOT NULL"))
+            .execute(pool)
+            .await?;
+        
+        sqlx::query(&format!("CREATE INDEX IF NOT EXISTS idx_{table}_tenant ON {table}(tenant_id)"))
+            .execute(pool)
+            .await?;
+    }
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS is_super_admin BOOLEAN NOT NULL DEFAULT FALSE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        "UPDATE users SET is_super_admin = TRUE
+         WHERE username = 'admin' AND NOT EXISTS (SELECT 1 FROM users WHERE is_super_admin)"
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'tenants' criada/verificada");
+    
+    // Papéis e permissões da aplicação
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS roles (
+            code VARCHAR PRIMARY KEY,
+            name VARCHAR NOT NULL,
+            description TEXT,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS permissions (
+            code VARCHAR PRIMARY KEY,
+            description TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS role_permissions (
+            role VARCHAR NOT NULL REFERENCES roles(code) ON DELETE CASCADE,
+            permission VARCHAR NOT NULL REFERENCES permissions(code) ON DELETE CASCADE,
+            PRIMARY KEY (role, permission)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query(
+        r#"
+        INSERT INTO roles (code, name, description) VALUES
+            ('admin', 'Administrador', 'Acesso completo à entidade'),
+            ('patrimony_manager', 'Gestor de patrimônio', 'Cadastro, transferência, inventário e manutenção de bens'),
+            ('department_manager', 'Gestor de departamento', 'Bens e transferências do próprio departamento'),
+            ('auditor', 'Auditor', 'Consulta de bens, transferências e trilha de auditoria'),
+            ('read_only', 'Somente leitura', 'Consulta de bens e transferências')
+        ON CONFLICT (code) DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // Concessões padrão só para permissões novas: ajustes feitos pelo administrador não são desfeitos
+    sqlx::query(
+        r#"
+        WITH new_permissions AS (
+            INSERT INTO permissions (code, description) VALUES
+                ('patrimony.read', 'Consultar bens, estatísticas, etiquetas e histórico'),
+                ('patrimony.write', 'Cadastrar e editar bens, imagens, documentos e localização'),
+                ('patrimony.delete', 'Enviar bens para a lixeira'),
+                ('trash.manage', 'Restaurar e remover definitivamente bens da lixeira'),
+                ('transfer.read', 'Consultar transferências e termos'),
+                ('transfer.request', 'Solicitar transferências'),
+                ('transfer.approve', 'Aprovar e rejeitar transferências do departamento'),
+                ('transfer.receive', 'Confirmar o recebimento de transferências'),
+                ('write_off.manage', 'Baixar bens e anexar documentos de baixa'),
+                ('depreciation.run', 'Executar o cálculo mensal de depreciação'),
+                ('inventory.manage', 'Abrir e encerrar campanhas de inventário'),
+                ('inventory.count', 'Abrir sessões e registrar leituras de inventário'),
+                ('custody.manage', 'Atribuir e liberar responsáveis por bens'),
+                ('custodian.manage', 'Cadastrar responsáveis'),
+                ('location.manage', 'Cadastrar locais'),
+                ('department.manage', 'Cadastrar departamentos e gestores'),
+                ('department.all', 'Atuar em bens e transferências de qualquer departamento'),
+                ('category.manage', 'Cadastrar categorias'),
+                ('plate_sequence.manage', 'Configurar sequências de placas'),
+                ('maintenance.read', 'Consultar ordens de serviço e planos de manutenção'),
+                ('maintenance.manage', 'Abrir e encerrar ordens de serviço e registrar leituras'),
+                ('maintenance.plan', 'Planos de manutenção por categoria e execução da rotina'),
+                ('audit.read', 'Consultar e verificar a trilha de auditoria'),
+                ('audit.manage', 'Gerar pontos de controle da auditoria'),
+                ('user.read', 'Listar usuários')
+            ON CONFLICT (code) DO NOTHING
+            RETURNING code
+        )
+        INSERT INTO role_permissions (role, permission)
+        SELECT 'admin', code FROM new_permissions
+        UNION ALL
+        SELECT grants.role, grants.permission
+        FROM (VALUES
+            ('patrimony_manager', 'patrimony.read'),
+            ('patrimony_manager', 'patrimony.write'),
+            ('patrimony_manager', 'patrimony.delete'),
+            ('patrimony_manager', 'transfer.read'),
+            ('patrimony_manager', 'transfer.request'),
+            ('patrimony_manager', 'transfer.approve'),
+            ('patrimony_manager', 'transfer.receive'),
+            ('patrimony_manager', 'inventory.manage'),
+            ('patrimony_manager', 'inventory.count'),
+            ('patrimony_manager', 'custody.manage'),
+            ('patrimony_manager', 'maintenance.read'),
+            ('patrimony_manager', 'maintenance.manage'),
+            ('department_manager', 'patrimony.read'),
+            ('department_manager', 'patrimony.write'),
+            ('department_manager', 'transfer.read'),
+            ('department_manager', 'transfer.request'),
+            ('department_manager', 'transfer.approve'),
+            ('department_manager', 'transfer.receive'),
+            ('department_manager', 'inventory.count'),
+            ('department_manager', 'custody.manage'),
+            ('department_manager', 'maintenance.read'),
+            ('department_manager', 'maintenance.manage'),
+            ('auditor', 'patrimony.read'),
+            ('auditor', 'transfer.read'),
+            ('auditor', 'maintenance.read'),
+            ('auditor', 'audit.read'),
+            ('auditor', 'user.read'),
+            ('read_only', 'patrimony.read'),
+            ('read_only', 'transfer.read'),
+            ('read_only', 'maintenance.read')
+        ) AS grants(role, permission)
+        JOIN new_permissions n ON n.code = grants.permission
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // Papéis legados: "user" fazia o trabalho do gestor de patrimônio e "manager" geria o próprio departamento
+    sqlx::query("UPDATE users SET role = 'patrimony_manager' WHERE role = 'user'")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("UPDATE users SET role = 'department_manager' WHERE role = 'manager'")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("UPDATE users SET role = 'read_only' WHERE role NOT IN (SELECT code FROM roles)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ALTER COLUMN role SET DEFAULT 'read_only'")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        DO $$
+        BEGIN
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'fk_users_role') THEN
+                ALTER TABLE users ADD CONSTRAINT fk_users_role
+                    FOREIGN KEY (role) REFERENCES roles(code) ON UPDATE CASCADE;
+            END IF;
+        END $$
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabelas 'roles' e 'permissions' criadas/verificadas");
+    
+    // Consulta de bens e transferências de todos os departamentos (auditoria)
+    sqlx::query(
+        r#"
+        WITH new_permissions AS (
+            INSERT INTO permissions (code, description) VALUES
+                ('department.read_all', 'Consultar bens e transferências de todos os departamentos')
+            ON CONFLICT (code) DO NOTHING
+            RETURNING code
+        )
+        INSERT INTO role_permissions (role, permission)
+        SELECT r.code, n.code FROM new_permissions n CROSS JOIN (VALUES ('admin'), ('auditor')) AS r(code)
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    // Acesso de leitura a outros departamentos, por usuário ou por departamento de lotação
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS department_access_grants (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            tenant_id UUID NOT NULL REFERENCES tenants(id),
+            user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+            grantee_department VARCHAR REFERENCES departments(code) ON UPDATE CASCADE ON DELETE CASCADE,
+            department_id UUID REFERENCES departments(id) ON DELETE CASCADE,
+            granted_by UUID REFERENCES users(id),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            CONSTRAINT chk_department_grant_grantee CHECK ((user_id IS NULL) <> (grantee_department IS NULL))
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_department_grants_user ON department_access_grants(user_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_department_grants_grantee_department ON department_access_grants(grantee_department)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_department_grants_unique
+         ON department_access_grants(tenant_id, COALESCE(user_id::text, grantee_department), COALESCE(department_id::text, '*'))"
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabela 'department_access_grants' criada/verificada");
+    
+    // Sessões de login e tokens de renovação (só o hash é gravado)
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS sessions (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            ip_address VARCHAR,
+            user_agent VARCHAR,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            last_used_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+            revoked_at TIMESTAMP WITH TIME ZONE,
+            revoked_reason VARCHAR
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions(user_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS refresh_tokens (
+            token_hash VARCHAR PRIMARY KEY,
+            session_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+            used_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_refresh_tokens_session ON refresh_tokens(session_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        WITH new_permissions AS (
+            INSERT INTO permissions (code, description) VALUES
+                ('user.manage', 'Gerenciar usuários e encerrar suas sessões')
+            ON CONFLICT (code) DO NOTHING
+            RETURNING code
+        )
+        INSERT INTO role_permissions (role, permission)
+        SELECT 'admin', code FROM new_permissions
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    println!("✅ Tabelas 'sessions' e 'refresh_tokens' criadas/verificadas");
+    
+    // Situação do usuário e troca obrigatória de senha
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS active BOOLEAN NOT NULL DEFAULT TRUE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS deactivated_at TIMESTAMP WITH TIME ZONE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS must_change_password BOOLEAN NOT NULL DEFAULT FALSE")
+        .execute(pool)
+        .await?;
+    
+    // Convites de cadastro
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS user_invitations (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            tenant_id UUID NOT NULL REFERENCES tenants(id),
+            token_hash VARCHAR NOT NULL UNIQUE,
+            email VARCHAR,
+            department VARCHAR NOT NULL REFERENCES departments(code) ON UPDATE CASCADE,
+            role VARCHAR NOT NULL REFERENCES roles(code) ON UPDATE CASCADE,
+            invited_by UUID REFERENCES users(id),
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+            accepted_at TIMESTAMP WITH TIME ZONE,
+            accepted_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            revoked_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_user_invitations_tenant ON user_invitations(tenant_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'user_invitations' criada/verificada");
+    
+    // Histórico de senhas para a política de reutilização
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS password_history (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            password_hash VARCHAR NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_history_user ON password_history(user_id, created_at DESC)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'password_history' criada/verificada");
+    
+    // Tokens de redefinição de senha por e-mail
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS password_reset_tokens (
+            token_hash VARCHAR PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            ip_address VARCHAR,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+            used_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user ON password_reset_tokens(user_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'password_reset_tokens' criada/verificada");
+    
+    // Proteção contra força bruta no login
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS failed_login_count INTEGER NOT NULL DEFAULT 0")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS last_failed_login_at TIMESTAMP WITH TIME ZONE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS locked_until TIMESTAMP WITH TIME ZONE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS login_attempts (
+            id BIGSERIAL PRIMARY KEY,
+            username VARCHAR NOT NULL,
+            user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+            tenant_id UUID REFERENCES tenants(id),
+            ip_address VARCHAR,
+            user_agent VARCHAR,
+            success BOOLEAN NOT NULL,
+            failure_reason VARCHAR,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_login_attempts_ip ON login_attempts(ip_address, created_at)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_login_attempts_tenant ON login_attempts(tenant_id, created_at)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_login_attempts_username ON login_attempts(LOWER(username), created_at)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Tabela 'login_attempts' criada/verificada");
+    
+    // Autenticação em dois fatores (TOTP)
+    sqlx::query("ALTER TABLE roles ADD COLUMN IF NOT EXISTS requires_two_factor BOOLEAN")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("UPDATE roles SET requires_two_factor = code IN ('admin', 'patrimony_manager') WHERE requires_two_factor IS NULL")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE roles ALTER COLUMN requires_two_factor SET DEFAULT FALSE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE roles ALTER COLUMN requires_two_factor SET NOT NULL")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret VARCHAR")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled BOOLEAN NOT NULL DEFAULT FALSE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMP WITH TIME ZONE")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query("ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_step BIGINT")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS two_factor_recovery_codes (
+            id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_hash VARCHAR NOT NULL,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            used_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_two_factor_recovery_codes_user ON two_factor_recovery_codes(user_id)")
+        .execute(pool)
+        .await?;
+    
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS two_factor_challenges (
+            token_hash VARCHAR PRIMARY KEY,
+            user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+            expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            used_at TIMESTAMP WITH TIME ZONE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_two_factor_challenges_user ON two_factor_challenges(user_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Autenticação em dois fatores criada/verificada");
+    
+    // Entidade dona de cada entrada da trilha de auditoria
+    sqlx::query("ALTER TABLE audit_log ADD COLUMN IF NOT EXISTS tenant_id UUID")
+        .execute(pool)
+        .await?;
+    
+    let untagged: (bool,) = sqlx::query_as("SELECT EXISTS(SELECT 1 FROM audit_log WHERE tenant_id IS NULL)")
+        .fetch_one(pool)
+        .await?;
+    
+    // Entradas anteriores: a trava de somente inserção é suspensa só durante o preenchimento
+    if untagged.0 {
+        let mut transaction = pool.begin().await?;
+        
+        sqlx::query("ALTER TABLE audit_log DISABLE TRIGGER audit_log_no_change")
+            .execute(&mut *transaction)
+            .await?;
+        
+        sqlx::query(
+            r#"
+            UPDATE audit_log a SET tenant_id = COALESCE(
+                (a.after_data->>'tenant_id')::uuid,
+                (a.before_data->>'tenant_id')::uuid,
+                CASE a.entity_type
+                    WHEN 'tenant' THEN a.entity_id
+                    WHEN 'patrimony' THEN (SELECT tenant_id FROM patrimonies WHERE id = a.entity_id)
+                    WHEN 'transfer' THEN (SELECT tenant_id FROM transfers WHERE id = a.entity_id)
+                    WHEN 'user' THEN (SELECT tenant_id FROM users WHERE id = a.entity_id)
+                END,
+                default_tenant_id()
+            )
+            WHERE a.tenant_id IS NULL
+            "#
+        )
+        .execute(&mut *transaction)
+        .await?;
+        
+        sqlx::query("ALTER TABLE audit_log ENABLE TRIGGER audit_log_no_change")
+            .execute(&mut *transaction)
+            .await?;
+        
+        transaction.commit().await?;
+    }
+    
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_audit_log_tenant ON audit_log(tenant_id)")
+        .execute(pool)
+        .await?;
+    
+    println!("✅ Trilha de auditoria separada por entidade");
+
+    // Campanhas, sequências de placa, locais e categorias pertencem a uma entidade
+    for table in ["inventory_campaigns", "plate_sequences", "locations", "categories"] {
+        sqlx::query(&format!("ALTER TABLE {table} ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id)"))
+            .execute(pool)
+            .await?;
+    }
+
+    // Registros ligados a um departamento herdam a entidade dele; o restante fica com a padrão
+    sqlx::query(
+        r#"
+        UPDATE inventory_campaigns c SET tenant_id = (SELECT d.tenant_id FROM departments d WHERE d.code = c.department)
+        WHERE c.tenant_id IS NULL
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE plate_sequences s SET tenant_id = (SELECT d.tenant_id FROM departments d WHERE d.code = s.scope_value)
+        WHERE s.tenant_id IS NULL AND s.scope_type = 'department'
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    for table in ["inventory_campaigns", "plate_sequences", "locations", "categories"] {
+        sqlx::query(&format!("UPDATE {table} SET tenant_id = default_tenant_id() WHERE tenant_id IS NULL"))
+            .execute(pool)
+            .await?;
+
+        sqlx::query(&format!("ALTER TABLE {table} ALTER COLUMN tenant_id SET DEFAULT default_tenant_id()"))
+            .execute(pool)
+            .await?;
+
+        sqlx::query(&format!("ALTER TABLE {table} ALTER COLUMN tenant_id SET NOT NULL"))
+            .execute(pool)
+            .await?;
+
+        sqlx::query(&format!("CREATE INDEX IF NOT EXISTS idx_{table}_tenant ON {table}(tenant_id)"))
+            .execute(pool)
+            .await?;
+    }
+
+    // Códigos únicos dentro da entidade, não mais na instalação inteira
+    sqlx::query("DROP INDEX IF EXISTS idx_inventory_campaigns_open_department")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_inventory_campaigns_open_tenant_department
+         ON inventory_campaigns(tenant_id, department) WHERE status = 'open'"
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        DO $$
+        BEGIN
+            ALTER TABLE plate_sequences DROP CONSTRAINT IF EXISTS plate_sequences_scope_type_scope_value_key;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'plate_sequences_tenant_scope_key') THEN
+                ALTER TABLE plate_sequences ADD CONSTRAINT plate_sequences_tenant_scope_key UNIQUE (tenant_id, scope_type, scope_value);
+            END IF;
+            ALTER TABLE locations DROP CONSTRAINT IF EXISTS locations_code_key;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'locations_tenant_code_key') THEN
+                ALTER TABLE locations ADD CONSTRAINT locations_tenant_code_key UNIQUE (tenant_id, code);
+            END IF;
+            ALTER TABLE categories DROP CONSTRAINT IF EXISTS categories_code_key;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'categories_tenant_code_key') THEN
+                ALTER TABLE categories ADD CONSTRAINT categories_tenant_code_key UNIQUE (tenant_id, code);
+            END IF;
+        END $$;
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    println!("✅ Cadastros de apoio separados por entidade");
+
+    // Código de departamento e placa únicos dentro da entidade; as referências por código
+    // passam a incluir a entidade (mesmos nomes, para o bloco de FKs acima não recriá-las)
+    sqlx::query(
+        r#"
+        DO $$
+        BEGIN
+            IF EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'departments_code_key') THEN
+                ALTER TABLE users DROP CONSTRAINT IF EXISTS fk_users_department;
+                ALTER TABLE patrimonies DROP CONSTRAINT IF EXISTS fk_patrimonies_department;
+                ALTER TABLE transfers DROP CONSTRAINT IF EXISTS fk_transfers_from_department;
+                ALTER TABLE transfers DROP CONSTRAINT IF EXISTS fk_transfers_to_department;
+                ALTER TABLE department_access_grants DROP CONSTRAINT IF EXISTS department_access_grants_grantee_department_fkey;
+                ALTER TABLE user_invitations DROP CONSTRAINT IF EXISTS user_invitations_department_fkey;
+
+                ALTER TABLE departments DROP CONSTRAINT departments_code_key;
+                ALTER TABLE departments ADD CONSTRAINT departments_tenant_code_key UNIQUE (tenant_id, code);
+
+                ALTER TABLE users ADD CONSTRAINT fk_users_department
+                    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+                ALTER TABLE patrimonies ADD CONSTRAINT fk_patrimonies_department
+                    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+                ALTER TABLE transfers ADD CONSTRAINT fk_transfers_from_department
+                    FOREIGN KEY (tenant_id, from_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+                ALTER TABLE transfers ADD CONSTRAINT fk_transfers_to_department
+                    FOREIGN KEY (tenant_id, to_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+                ALTER TABLE department_access_grants ADD CONSTRAINT department_access_grants_grantee_department_fkey
+                    FOREIGN KEY (tenant_id, grantee_department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE ON DELETE CASCADE;
+                ALTER TABLE user_invitations ADD CONSTRAINT user_invitations_department_fkey
+                    FOREIGN KEY (tenant_id, department) REFERENCES departments(tenant_id, code) ON UPDATE CASCADE;
+            END IF;
+            IF EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'patrimonies_plate_key') THEN
+                ALTER TABLE patrimonies DROP CONSTRAINT patrimonies_plate_key;
+                ALTER TABLE patrimonies ADD CONSTRAINT patrimonies_tenant_plate_key UNIQUE (tenant_id, plate);
+            END IF;
+        END $$;
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    println!("✅ Departamentos e placas únicos por entidade");
+
+    // Responsáveis pertencem a uma entidade: a do usuário vinculado ou a do departamento
+    sqlx::query("ALTER TABLE custodians ADD COLUMN IF NOT EXISTS tenant_id UUID REFERENCES tenants(id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE custodians c SET tenant_id = COALESCE(
+            (SELECT u.tenant_id FROM users u WHERE u.id = c.user_id),
+            (SELECT d.tenant_id FROM departments d WHERE d.code = c.department
+             ORDER BY d.tenant_id = default_tenant_id() DESC LIMIT 1),
+            default_tenant_id()
+        )
+        WHERE c.tenant_id IS NULL
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("ALTER TABLE custodians ALTER COLUMN tenant_id SET DEFAULT default_tenant_id()")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("ALTER TABLE custodians ALTER COLUMN tenant_id SET NOT NULL")
+        .execute(pool)
+        .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_custodians_tenant ON custodians(tenant_id)")
+        .execute(pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        DO $$
+        BEGIN
+            ALTER TABLE custodians DROP CONSTRAINT IF EXISTS custodians_registration_number_key;
+            IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conname = 'custodians_tenant_registration_key') THEN
+                ALTER TABLE custodians ADD CONSTRAINT custodians_tenant_registration_key UNIQUE (tenant_id, registration_number);
+            END IF;
+        END $$;
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    println!("✅ Responsáveis separados por entidade");
+
+    // Gestão do próprio departamento de lotação como permissão do papel
+    sqlx::query(
+        r#"
+        WITH new_permissions AS (
+            INSERT INTO permissions (code, description) VALUES
+                ('department.own', 'Atuar em bens e transferências do próprio departamento de lotação')
+            ON CONFLICT (code) DO NOTHING
+            RETURNING code
+        )
+        INSERT INTO role_permissions (role, permission)
+        SELECT 'department_manager', code FROM new_permissions
+        ON CONFLICT DO NOTHING
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    println!("✅ Permissão de gestão do próprio departamento verificada");
+
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,106 @@
+// Codificador Code128 (conjunto B) para as etiquetas patrimoniais
+
+// Larguras de barra/espaço de cada símbolo (0-105) e do símbolo de parada
+const PATTERNS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+
+const START_B: usize = 104;
+const STOP: usize = 106;
+
+/// Converte o texto em módulos Code128 (true = barra), incluindo dígito verificador.
+/// Retorna None se houver caracteres fora do conjunto B (ASCII 32-126).
+pub fn code128_modules(text: &str) -> Option<Vec<bool>> {
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut values = vec![START_B];
+    for c in text.chars() {
+        let code = c as u32;
+        if !(32..=126).contains(&code) {
+            return None;
+        }
+        values.push((code - 32) as usize);
+    }
+
+    let checksum = values
+        .iter()
+        .enumerate()
+        .fold(0, |acc, (position, value)| acc + value * position.max(1))
+        % 103;
+    values.push(checksum);
+    values.push(STOP);
+
+    let mut modules = Vec::new();
+    for value in values {
+        for (index, width) in PATTERNS[value].chars().enumerate() {
+            let width = width.to_digit(10).unwrap_or(1);
+            for _ in 0..width {
+                modules.push(index % 2 == 0);
+            }
+        }
+    }
+
+    Some(modules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bits(modules: &[bool]) -> String {
+        modules.iter().map(|bar| if *bar { '1' } else { '0' }).collect()
+    }
+
+    #[test]
+    fn every_symbol_is_eleven_modules_wide() {
+        for (value, pattern) in PATTERNS.iter().enumerate() {
+            let width: u32 = pattern.chars().filter_map(|c| c.to_digit(10)).sum();
+            assert_eq!(width, if value == STOP { 13 } else { 11 }, "symbol {}", value);
+        }
+    }
+
+    #[test]
+    fn encodes_plate_with_checksum() {
+        // Start B, E, D, U, 0, 0, 1, verificador (104 + 37 + 2*36 + 3*53 + 4*16 + 5*16 + 6*17 = 618, 618 % 103 = 0), Stop
+        let expected = [
+            "11010010000",
+            "10001101000",
+            "10110001000",
+            "11011101110",
+            "10011101100",
+            "10011101100",
+            "10011100110",
+            "11011001100",
+            "1100011101011",
+        ]
+        .concat();
+
+        assert_eq!(bits(&code128_modules("EDU001").unwrap()), expected);
+    }
+
+    #[test]
+    fn checksum_weights_each_position() {
+        // Start B + "A" (33): (104 + 33) % 103 = 34
+        let modules = code128_modules("A").unwrap();
+        assert_eq!(&bits(&modules)[22..33], "10001011000");
+    }
+
+    #[test]
+    fn rejects_text_outside_code_set_b() {
+        assert!(code128_modules("").is_none());
+        assert!(code128_modules("PATRIMÔNIO").is_none());
+        assert!(code128_modules("EDU\t001").is_none());
+    }
+}
@@ -0,0 +1,261 @@
+// Folhas de etiquetas patrimoniais em PDF (Code128 + QR Code)
+use printpdf::{BuiltinFont, IndirectFontRef, Mm, PdfDocument, PdfLayerReference, Rect};
+use qrcode::{Color, EcLevel, QrCode};
+
+use super::barcode::code128_modules;
+
+/// Geometria de uma folha de etiquetas adesivas (medidas em mm)
+pub struct LabelLayout {
+    pub code: &'static str,
+    pub page_width: f32,
+    pub page_height: f32,
+    pub columns: usize,
+    pub rows: usize,
+    pub label_width: f32,
+    pub label_height: f32,
+    pub margin_left: f32,
+    pub margin_top: f32,
+    pub gap_x: f32,
+    pub gap_y: f32,
+}
+
+pub const DEFAULT_LAYOUT: &str = "a4-3x7";
+
+// Formatos comuns de folhas adesivas (A4 Pimaco/Avery e Carta)
+pub const LAYOUTS: [LabelLayout; 4] = [
+    LabelLayout {
+        code: "a4-3x7",
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 3,
+        rows: 7,
+        label_width: 63.5,
+        label_height: 38.1,
+        margin_left: 7.2,
+        margin_top: 15.15,
+        gap_x: 2.5,
+        gap_y: 0.0,
+    },
+    LabelLayout {
+        code: "a4-2x7",
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 2,
+        rows: 7,
+        label_width: 99.1,
+        label_height: 38.1,
+        margin_left: 4.65,
+        margin_top: 15.15,
+        gap_x: 2.5,
+        gap_y: 0.0,
+    },
+    LabelLayout {
+        code: "a4-2x5",
+        page_width: 210.0,
+        page_height: 297.0,
+        columns: 2,
+        rows: 5,
+        label_width: 99.1,
+        label_height: 57.0,
+        margin_left: 4.65,
+        margin_top: 6.0,
+        gap_x: 2.5,
+        gap_y: 0.0,
+    },
+    LabelLayout {
+        code: "letter-3x10",
+        page_width: 215.9,
+        page_height: 279.4,
+        columns: 3,
+        rows: 10,
+        label_width: 66.7,
+        label_height: 25.4,
+        margin_left: 4.8,
+        margin_top: 12.7,
+        gap_x: 3.2,
+        gap_y: 0.0,
+    },
+];
+
+pub fn find_layout(code: &str) -> Option<&'static LabelLayout> {
+    LAYOUTS.iter().find(|layout| layout.code == code)
+}
+
+/// Conteúdo de uma etiqueta
+pub struct LabelData {
+    pub plate: String,
+    pub name: String,
+    pub department: String,
+    pub url: String,
+}
+
+const PADDING: f32 = 2.0;
+const PT_TO_MM: f32 = 0.3528;
+
+pub fn render_labels(layout: &LabelLayout, labels: &[LabelData]) -> Result<Vec<u8>, String> {
+    let (doc, first_page, first_layer) = PdfDocument::new(
+        "Etiquetas patrimoniais",
+        Mm(layout.page_width),
+        Mm(layout.page_height),
+        "Etiquetas",
+    );
+
+    let font = doc
+        .add_builtin_font(BuiltinFont::Helvetica)
+        .map_err(|e| e.to_string())?;
+    let bold = doc
+        .add_builtin_font(BuiltinFont::HelveticaBold)
+        .map_err(|e| e.to_string())?;
+
+    let per_page = layout.columns * layout.rows;
+    let mut layer = doc.get_page(first_page).get_layer(first_layer);
+
+    for (index, label) in labels.iter().enumerate() {
+        if index > 0 && index % per_page == 0 {
+            let (page, page_layer) = doc.add_page(
+                Mm(layout.page_width),
+                Mm(layout.page_height),
+                "Etiquetas",
+            );
+            layer = doc.get_page(page).get_layer(page_layer);
+        }
+
+        let slot = index % per_page;
+        let column = slot % layout.columns;
+        let row = slot / layout.columns;
+
+        // Origem do PDF fica no canto inferior esquerdo
+        let x = layout.margin_left + column as f32 * (layout.label_width + layout.gap_x);
+        let top = layout.page_height - layout.margin_top - row as f32 * (layout.label_height + layout.gap_y);
+        let y = top - layout.label_height;
+
+        draw_label(&layer, &font, &bold, x, y, layout.label_width, layout.label_height, label)?;
+    }
+
+    doc.save_to_bytes().map_err(|e| e.to_string())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_label(
+    layer: &PdfLayerReference,
+    font: &IndirectFontRef,
+    bold: &IndirectFontRef,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    label: &LabelData,
+) -> Result<(), String> {
+    // QR Code à direita, ocupando a altura útil da etiqueta
+    let qr_size = (height - 2.0 * PADDING).min(width * 0.4);
+    let qr_x = x + width - PADDING - qr_size;
+    let qr_y = y + (height - qr_size) / 2.0;
+    draw_qr(layer, &label.url, qr_x, qr_y, qr_size)?;
+
+    // Textos e código de barras à esquerda
+    let text_x = x + PADDING;
+    let text_width = qr_x - PADDING - text_x;
+    let name_size = (height * 0.22).clamp(6.0, 9.0);
+    let department_size = name_size - 1.0;
+    let plate_size = name_size;
+
+    let name_y = y + height - PADDING - name_size * PT_TO_MM;
+    layer.use_text(fit_text(&label.name, text_width, name_size), name_size, Mm(text_x), Mm(name_y), bold);
+
+    let department_y = name_y - department_size * PT_TO_MM - 1.0;
+    layer.use_text(
+        fit_text(&label.department, text_width, department_size),
+        department_size,
+        Mm(text_x),
+        Mm(department_y),
+        font,
+    );
+
+    let plate_y = y + PADDING;
+    let barcode_y = plate_y + plate_size * PT_TO_MM + 0.8;
+    let barcode_height = (department_y - 1.5 - barcode_y).max(4.0);
+    draw_code128(layer, &label.plate, text_x, barcode_y, text_width, barcode_height)?;
+
+    layer.use_text(fit_text(&label.plate, text_width, plate_size), plate_size, Mm(text_x), Mm(plate_y), bold);
+
+    Ok(())
+}
+
+fn draw_code128(
+    layer: &PdfLayerReference,
+    text: &str,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+) -> Result<(), String> {
+    let modules = code128_modules(text)
+        .ok_or_else(|| format!("Plate '{}' cannot be encoded as Code128", text))?;
+    let module_width = width / modules.len() as f32;
+
+    let mut start = 0;
+    while start < modules.len() {
+        if !modules[start] {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < modules.len() && modules[end] {
+            end += 1;
+        }
+        layer.add_rect(Rect::new(
+            Mm(x + start as f32 * module_width),
+            Mm(y),
+            Mm(x + end as f32 * module_width),
+            Mm(y + height),
+        ));
+        start = end;
+    }
+
+    Ok(())
+}
+
+fn draw_qr(layer: &PdfLayerReference, data: &str, x: f32, y: f32, size: f32) -> Result<(), String> {
+    let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| e.to_string())?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let module_size = size / modules as f32;
+
+    // Módulos escuros consecutivos da mesma linha viram um único retângulo
+    for row in 0..modules {
+        let top = y + size - row as f32 * module_size;
+        let mut column = 0;
+        while column < modules {
+            if colors[row * modules + column] != Color::Dark {
+                column += 1;
+                continue;
+            }
+            let start = column;
+            while column < modules && colors[row * modules + column] == Color::Dark {
+                column += 1;
+            }
+            layer.add_rect(Rect::new(
+                Mm(x + start as f32 * module_size),
+                Mm(top - module_size),
+                Mm(x + column as f32 * module_size),
+                Mm(top),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Trunca o texto para caber na largura disponível (estimativa para Helvetica)
+fn fit_text(text: &str, width: f32, font_size: f32) -> String {
+    let char_width = font_size * PT_TO_MM * 0.55;
+    let max_chars = (width / char_width).floor().max(1.0) as usize;
+
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        let truncated: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+        format!("{}...", truncated)
+    }
+}
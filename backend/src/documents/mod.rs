@@ -0,0 +1,6 @@
+// documents/mod.rs
+// Geração de documentos PDF (etiquetas, termos)
+pub mod barcode;
+pub mod labels;
+pub mod terms;
+pub mod writer;
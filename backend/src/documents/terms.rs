@@ -0,0 +1,150 @@
+// Termos oficiais em PDF (transferência, responsabilidade)
+use chrono::{DateTime, Utc};
+
+use super::writer::{Column, DocumentWriter};
+
+pub const DEFAULT_ORGANIZATION: &str = "Prefeitura Municipal";
+
+pub struct TermItem {
+    pub plate: String,
+    pub name: String,
+    pub description: String,
+    pub value: f64,
+}
+
+pub struct TransferTermData {
+    pub organization: String,
+    pub number: String,
+    pub requested_at: DateTime<Utc>,
+    pub status: String,
+    pub from_department: String,
+    pub to_department: String,
+    pub reason: String,
+    pub requested_by: Option<String>,
+    pub approved_by: Option<String>,
+    pub received_by: Option<String>,
+    pub items: Vec<TermItem>,
+}
+
+pub struct ResponsibilityTermData {
+    pub organization: String,
+    pub custodian_name: String,
+    pub registration_number: String,
+    pub position: Option<String>,
+    pub department: String,
+    pub issued_at: DateTime<Utc>,
+    pub items: Vec<TermItem>,
+}
+
+/// Valor monetário no formato brasileiro (R$ 1.234,56)
+pub fn format_brl(value: f64) -> String {
+    let cents = (value * 100.0).round() as i64;
+    let negative = cents < 0;
+    let cents = cents.abs();
+    let integer = (cents / 100).to_string();
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index).is_multiple_of(3) {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+
+    format!("{}R$ {},{:02}", if negative { "-" } else { "" }, grouped, cents % 100)
+}
+
+fn items_table(writer: &mut DocumentWriter, items: &[TermItem]) {
+    let columns = [
+        Column { title: "Placa", width: 25.0 },
+        Column { title: "Bem", width: 50.0 },
+        Column { title: "Descrição", width: 65.0 },
+        Column { title: "Valor", width: DocumentWriter::content_width() - 140.0 },
+    ];
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| vec![item.plate.clone(), item.name.clone(), item.description.clone(), format_brl(item.value)])
+        .collect();
+    writer.table(&columns, &rows);
+
+    let total: f64 = items.iter().map(|item| item.value).sum();
+    writer.field("Quantidade de bens", &items.len().to_string());
+    writer.field("Valor total", &format_brl(total));
+}
+
+pub fn render_transfer_term(term: &TransferTermData) -> Result<Vec<u8>, String> {
+    let mut writer = DocumentWriter::new("Termo de Transferência")?;
+
+    let subtitle = format!("Nº {}", term.number);
+    writer.header(&term.organization, "TERMO DE TRANSFERÊNCIA DE BENS PATRIMONIAIS", Some(&subtitle));
+
+    writer.field("Data da solicitação", &term.requested_at.format("%d/%m/%Y").to_string());
+    writer.field("Situação", &term.status);
+    writer.field("Departamento de origem", &term.from_department);
+    writer.field("Departamento de destino", &term.to_department);
+    writer.field("Motivo", if term.reason.trim().is_empty() { "-" } else { &term.reason });
+    writer.space(4.0);
+
+    writer.heading("Bens transferidos");
+    items_table(&mut writer, &term.items);
+    writer.space(4.0);
+
+    writer.paragraph(&format!(
+        "Pelo presente termo, o departamento {} transfere ao departamento {} a guarda e a responsabilidade \
+         dos bens patrimoniais relacionados acima, que passam a integrar o acervo do departamento de destino \
+         a partir da confirmação de recebimento.",
+        term.from_department, term.to_department
+    ));
+
+    writer.signatures(&[
+        (
+            format!("Responsável pela entrega - {}", term.from_department),
+            term.requested_by.clone().unwrap_or_default(),
+        ),
+        (
+            format!("Responsável pelo recebimento - {}", term.to_department),
+            term.received_by.clone().unwrap_or_default(),
+        ),
+        (
+            format!("Gestor do destino - {}", term.to_department),
+            term.approved_by.clone().unwrap_or_default(),
+        ),
+    ]);
+
+    writer.finish()
+}
+
+pub fn render_responsibility_term(term: &ResponsibilityTermData) -> Result<Vec<u8>, String> {
+    let mut writer = DocumentWriter::new("Termo de Responsabilidade")?;
+
+    let subtitle = format!("Emitido em {}", term.issued_at.format("%d/%m/%Y"));
+    writer.header(&term.organization, "TERMO DE RESPONSABILIDADE PATRIMONIAL", Some(&subtitle));
+
+    writer.field("Responsável", &term.custodian_name);
+    writer.field("Matrícula", &term.registration_number);
+    writer.field("Cargo", term.position.as_deref().unwrap_or("-"));
+    writer.field("Departamento", &term.department);
+    writer.space(4.0);
+
+    writer.heading("Bens sob guarda");
+    if term.items.is_empty() {
+        writer.paragraph("Nenhum bem sob a guarda do servidor nesta data.");
+    } else {
+        items_table(&mut writer, &term.items);
+    }
+    writer.space(4.0);
+
+    writer.paragraph(&format!(
+        "Declaro ter recebido os bens patrimoniais relacionados acima, comprometendo-me a zelar por sua guarda, \
+         conservação e uso exclusivo em serviço, bem como a comunicar imediatamente ao setor de patrimônio \
+         qualquer extravio, dano ou movimentação. Este termo substitui os anteriormente assinados por {}.",
+        term.custodian_name
+    ));
+
+    writer.signatures(&[
+        (format!("Responsável - Matrícula {}", term.registration_number), term.custodian_name.clone()),
+        ("Setor de Patrimônio".to_string(), String::new()),
+    ]);
+
+    writer.finish()
+}
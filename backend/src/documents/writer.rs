@@ -0,0 +1,233 @@
+// Escritor de documentos A4 em fluxo (termos oficiais): cabeçalho, textos,
+// tabelas e blocos de assinatura, com quebra de página automática
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point,
+};
+
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+const MARGIN: f32 = 20.0;
+const PT_TO_MM: f32 = 0.3528;
+// Largura média de um caractere em Helvetica, relativa ao tamanho da fonte
+const CHAR_WIDTH_FACTOR: f32 = 0.5;
+
+pub const BODY_SIZE: f32 = 10.0;
+
+pub struct DocumentWriter {
+    doc: PdfDocumentReference,
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    bold: IndirectFontRef,
+    y: f32,
+}
+
+/// Coluna de tabela: título e largura em mm
+pub struct Column<'a> {
+    pub title: &'a str,
+    pub width: f32,
+}
+
+impl DocumentWriter {
+    pub fn new(title: &str) -> Result<Self, String> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Conteúdo");
+        let font = doc
+            .add_builtin_font(BuiltinFont::Helvetica)
+            .map_err(|e| e.to_string())?;
+        let bold = doc
+            .add_builtin_font(BuiltinFont::HelveticaBold)
+            .map_err(|e| e.to_string())?;
+        let layer = doc.get_page(page).get_layer(layer);
+
+        Ok(Self {
+            doc,
+            layer,
+            font,
+            bold,
+            y: PAGE_HEIGHT - MARGIN,
+        })
+    }
+
+    pub fn content_width() -> f32 {
+        PAGE_WIDTH - 2.0 * MARGIN
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_WIDTH), Mm(PAGE_HEIGHT), "Conteúdo");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn ensure_space(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    fn line_height(size: f32) -> f32 {
+        size * PT_TO_MM * 1.4
+    }
+
+    fn text_width(text: &str, size: f32) -> f32 {
+        text.chars().count() as f32 * size * PT_TO_MM * CHAR_WIDTH_FACTOR
+    }
+
+    fn wrap(text: &str, width: f32, size: f32) -> Vec<String> {
+        let max_chars = (width / (size * PT_TO_MM * CHAR_WIDTH_FACTOR)).floor().max(1.0) as usize;
+        let mut lines = Vec::new();
+        let mut current = String::new();
+
+        for word in text.split_whitespace() {
+            let candidate_len = current.chars().count() + word.chars().count() + usize::from(!current.is_empty());
+            if candidate_len > max_chars && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+            }
+            if !current.is_empty() {
+                current.push(' ');
+            }
+            current.push_str(word);
+        }
+        if !current.is_empty() {
+            lines.push(current);
+        }
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines
+    }
+
+    fn horizontal_rule(&self, x1: f32, x2: f32, y: f32) {
+        self.layer.add_line(Line {
+            points: vec![(Point::new(Mm(x1), Mm(y)), false), (Point::new(Mm(x2), Mm(y)), false)],
+            is_closed: false,
+        });
+    }
+
+    /// Cabeçalho com o nome do órgão e o título do documento
+    pub fn header(&mut self, organization: &str, title: &str, subtitle: Option<&str>) {
+        let size = 13.0;
+        self.y -= Self::line_height(size);
+        let x = (PAGE_WIDTH - Self::text_width(organization, size)) / 2.0;
+        self.layer.use_text(organization, size, Mm(x.max(MARGIN)), Mm(self.y), &self.bold);
+
+        self.y -= Self::line_height(size) + 2.0;
+        let x = (PAGE_WIDTH - Self::text_width(title, size)) / 2.0;
+        self.layer.use_text(title, size, Mm(x.max(MARGIN)), Mm(self.y), &self.bold);
+
+        if let Some(subtitle) = subtitle {
+            self.y -= Self::line_height(BODY_SIZE);
+            let x = (PAGE_WIDTH - Self::text_width(subtitle, BODY_SIZE)) / 2.0;
+            self.layer.use_text(subtitle, BODY_SIZE, Mm(x.max(MARGIN)), Mm(self.y), &self.font);
+        }
+
+        self.y -= 3.0;
+        self.horizontal_rule(MARGIN, PAGE_WIDTH - MARGIN, self.y);
+        self.y -= 6.0;
+    }
+
+    pub fn heading(&mut self, text: &str) {
+        let size = 11.0;
+        self.ensure_space(Self::line_height(size) * 2.0);
+        self.y -= Self::line_height(size);
+        self.layer.use_text(text, size, Mm(MARGIN), Mm(self.y), &self.bold);
+        self.y -= 2.0;
+    }
+
+    pub fn paragraph(&mut self, text: &str) {
+        for line in Self::wrap(text, Self::content_width(), BODY_SIZE) {
+            self.ensure_space(Self::line_height(BODY_SIZE));
+            self.y -= Self::line_height(BODY_SIZE);
+            self.layer.use_text(line, BODY_SIZE, Mm(MARGIN), Mm(self.y), &self.font);
+        }
+        self.y -= 2.0;
+    }
+
+    /// Linha "Rótulo: valor"
+    pub fn field(&mut self, label: &str, value: &str) {
+        let label = format!("{}:", label);
+        let label_width = Self::text_width(&label, BODY_SIZE) + 2.0;
+        let lines = Self::wrap(value, Self::content_width() - label_width, BODY_SIZE);
+
+        for (index, line) in lines.into_iter().enumerate() {
+            self.ensure_space(Self::line_height(BODY_SIZE));
+            self.y -= Self::line_height(BODY_SIZE);
+            if index == 0 {
+                self.layer.use_text(label.as_str(), BODY_SIZE, Mm(MARGIN), Mm(self.y), &self.bold);
+            }
+            self.layer.use_text(line, BODY_SIZE, Mm(MARGIN + label_width), Mm(self.y), &self.font);
+        }
+    }
+
+    pub fn space(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    /// Tabela simples; textos longos são quebrados dentro da coluna
+    pub fn table(&mut self, columns: &[Column], rows: &[Vec<String>]) {
+        let size = 9.0;
+        let line_height = Self::line_height(size);
+
+        let draw_header = |writer: &mut Self| {
+            writer.y -= line_height;
+            let mut x = MARGIN;
+            for column in columns {
+                writer.layer.use_text(column.title, size, Mm(x + 1.0), Mm(writer.y), &writer.bold);
+                x += column.width;
+            }
+            writer.y -= 1.5;
+            writer.horizontal_rule(MARGIN, x, writer.y);
+        };
+
+        self.ensure_space(line_height * 3.0);
+        draw_header(self);
+
+        for row in rows {
+            let cells: Vec<Vec<String>> = columns
+                .iter()
+                .zip(row.iter())
+                .map(|(column, value)| Self::wrap(value, column.width - 2.0, size))
+                .collect();
+            let height = cells.iter().map(|lines| lines.len()).max().unwrap_or(1) as f32 * line_height + 1.5;
+
+            if self.y - height < MARGIN {
+                self.new_page();
+                draw_header(self);
+            }
+
+            let top = self.y;
+            let mut x = MARGIN;
+            for (column, lines) in columns.iter().zip(cells.iter()) {
+                let mut y = top;
+                for line in lines {
+                    y -= line_height;
+                    self.layer.use_text(line.as_str(), size, Mm(x + 1.0), Mm(y), &self.font);
+                }
+                x += column.width;
+            }
+            self.y = top - height;
+            self.horizontal_rule(MARGIN, x, self.y);
+        }
+        self.y -= 4.0;
+    }
+
+    /// Blocos de assinatura lado a lado (até dois por linha): (função, nome)
+    pub fn signatures(&mut self, blocks: &[(String, String)]) {
+        let block_width = (Self::content_width() - 10.0) / 2.0;
+        let block_height = 28.0;
+
+        for pair in blocks.chunks(2) {
+            self.ensure_space(block_height);
+            self.y -= 18.0;
+            for (index, (role, name)) in pair.iter().enumerate() {
+                let x = MARGIN + index as f32 * (block_width + 10.0);
+                self.horizontal_rule(x, x + block_width, self.y);
+                self.layer.use_text(name.as_str(), BODY_SIZE, Mm(x), Mm(self.y - 4.5), &self.bold);
+                self.layer.use_text(role.as_str(), 9.0, Mm(x), Mm(self.y - 8.5), &self.font);
+            }
+            self.y -= block_height - 18.0;
+        }
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>, String> {
+        self.doc.save_to_bytes().map_err(|e| e.to_string())
+    }
+}
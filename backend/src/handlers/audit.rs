@@ -0,0 +1,250 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgExecutor, PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, User};
+use super::audit_chain;
+use super::login_security::client_ip;
+
+// Entidades auditadas
+pub const PATRIMONY: &str = "patrimony";
+pub const TRANSFER: &str = "transfer";
+pub const USER: &str = "user";
+pub const TENANT: &str = "tenant";
+
+// Ações registradas
+pub const CREATE: &str = "create";
+pub const UPDATE: &str = "update";
+pub const DELETE: &str = "delete";
+pub const UPLOAD: &str = "upload";
+pub const APPROVE: &str = "approve";
+pub const REJECT: &str = "reject";
+pub const RECEIVE: &str = "receive";
+pub const WRITE_OFF: &str = "write_off";
+pub const MOVE: &str = "move";
+pub const MAINTENANCE: &str = "maintenance";
+pub const RESTORE: &str = "restore";
+pub const PURGE: &str = "purge";
+pub const DEACTIVATE: &str = "deactivate";
+pub const REACTIVATE: &str = "reactivate";
+pub const RESET_PASSWORD: &str = "reset_password";
+pub const CHANGE_PASSWORD: &str = "change_password";
+pub const UNLOCK: &str = "unlock";
+pub const ENABLE_TWO_FACTOR: &str = "enable_two_factor";
+pub const DISABLE_TWO_FACTOR: &str = "disable_two_factor";
+pub const RESET_TWO_FACTOR: &str = "reset_two_factor";
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 500;
+
+// Campos que mudam a cada gravação e não interessam ao diff
+const IGNORED_FIELDS: [&str; 1] = ["updated_at"];
+
+#[derive(Deserialize)]
+pub struct AuditQuery {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<Uuid>,
+    pub user_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Quem executou a ação e de onde
+pub struct Actor {
+    pub id: Option<Uuid>,
+    pub username: Option<String>,
+    pub ip_address: Option<String>,
+}
+
+impl Actor {
+    pub fn new(user: &User, req: &HttpRequest) -> Self {
+        Actor {
+            id: Some(user.id),
+            username: Some(user.username.clone()),
+            ip_address: client_ip(req),
+        }
+    }
+}
+
+// Tabela e colunas omitidas de cada entidade (segredos nunca entram na trilha)
+fn snapshot_sql(entity_type: &str) -> Option<&'static str> {
+    match entity_type {
+        PATRIMONY => Some("SELECT to_jsonb(t) as data FROM patrimonies t WHERE id = $1"),
+        TRANSFER => Some("SELECT to_jsonb(t) as data FROM transfers t WHERE id = $1"),
+        USER => Some("SELECT to_jsonb(t) - 'password_hash' - 'totp_secret' as data FROM users t WHERE id = $1"),
+        TENANT => Some("SELECT to_jsonb(t) as data FROM tenants t WHERE id = $1"),
+        _ => None,
+    }
+}
+
+/// Estado atual da entidade em JSON (None se não existe)
+pub async fn snapshot<'e, E: PgExecutor<'e>>(
+    executor: E,
+    entity_type: &str,
+    entity_id: Uuid,
+) -> Result<Option<serde_json::Value>, sqlx::Error> {
+    let Some(sql) = snapshot_sql(entity_type) else {
+        return Ok(None);
+    };
+    sqlx::query(sql)
+        .bind(entity_id)
+        .map(|row: PgRow| row.get::<serde_json::Value, _>("data"))
+        .fetch_optional(executor)
+        .await
+}
+
+/// Entidade municipal dona do registro auditado: a própria, para entidades, ou a gravada no retrato
+pub fn owner_tenant(
+    entity_type: &str,
+    entity_id: Uuid,
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> Option<Uuid> {
+    if entity_type == TENANT {
+        return Some(entity_id);
+    }
+    [after, before]
+        .into_iter()
+        .flatten()
+        .find_map(|data| data.get("tenant_id")?.as_str()?.parse().ok())
+}
+
+/// Campos alterados no formato { campo: { before, after } }
+pub fn diff(before: Option<&serde_json::Value>, after: Option<&serde_json::Value>) -> serde_json::Value {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(|value| value.as_object()).unwrap_or(&empty);
+    let after = after.and_then(|value| value.as_object()).unwrap_or(&empty);
+
+    let mut changes = serde_json::Map::new();
+    for key in before.keys().chain(after.keys()) {
+        if IGNORED_FIELDS.contains(&key.as_str()) || changes.contains_key(key) {
+            continue;
+        }
+        let old = before.get(key).unwrap_or(&serde_json::Value::Null);
+        let new = after.get(key).unwrap_or(&serde_json::Value::Null);
+        if old != new {
+            changes.insert(key.clone(), serde_json::json!({ "before": old, "after": new }));
+        }
+    }
+    serde_json::Value::Object(changes)
+}
+
+/// Registra a ação comparando o estado anterior com o estado atual na mesma transação
+pub async fn record_change(
+    transaction: &mut Transaction<'_, Postgres>,
+    actor: &Actor,
+    entity_type: &str,
+    entity_id: Uuid,
+    action: &str,
+    before: Option<serde_json::Value>,
+) -> Result<(), sqlx::Error> {
+    let after = snapshot(&mut **transaction, entity_type, entity_id).await?;
+    audit_chain::append(transaction, actor, entity_type, entity_id, action, before, after).await
+}
+
+fn entry_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<i64, _>("id"),
+        "occurred_at": row.get::<chrono::DateTime<Utc>, _>("occurred_at"),
+        "actor_id": row.get::<Option<Uuid>, _>("actor_id"),
+        "actor_username": row.get::<Option<String>, _>("actor_username"),
+        "ip_address": row.get::<Option<String>, _>("ip_address"),
+        "entity_type": row.get::<String, _>("entity_type"),
+        "entity_id": row.get::<Uuid, _>("entity_id"),
+        "action": row.get::<String, _>("action"),
+        "before": row.get::<Option<serde_json::Value>, _>("before_data"),
+        "after": row.get::<Option<serde_json::Value>, _>("after_data"),
+        "changes": row.get::<serde_json::Value, _>("changes"),
+        "prev_hash": row.get::<Option<String>, _>("prev_hash"),
+        "entry_hash": row.get::<Option<String>, _>("entry_hash")
+    })
+}
+
+pub async fn get_audit_log(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    // Somente as entradas da entidade do usuário; o superadministrador vê todas
+    let tenant_id = if user.is_super_admin { None } else { Some(user.tenant_id) };
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    let filter =
+        "($1::varchar IS NULL OR entity_type = $1)
+         AND ($2::uuid IS NULL OR entity_id = $2)
+         AND ($3::uuid IS NULL OR actor_id = $3)
+         AND ($4::varchar IS NULL OR action = $4)
+         AND ($5::date IS NULL OR occurred_at::date >= $5)
+         AND ($6::date IS NULL OR occurred_at::date <= $6)
+         AND ($7::uuid IS NULL OR tenant_id = $7)";
+
+    let total = sqlx::query(&format!("SELECT COUNT(*) as total FROM audit_log WHERE {}", filter))
+        .bind(&query.entity_type)
+        .bind(query.entity_id)
+        .bind(query.user_id)
+        .bind(&query.action)
+        .bind(query.from)
+        .bind(query.to)
+        .bind(tenant_id)
+        .map(|row: PgRow| row.get::<i64, _>("total"))
+        .fetch_one(pool.get_ref())
+        .await;
+
+    let total = match total {
+        Ok(total) => total,
+        Err(e) => {
+            eprintln!("Error counting audit entries: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching audit log");
+        }
+    };
+
+    let entries = sqlx::query(&format!(
+        "SELECT id, occurred_at, actor_id, actor_username, ip_address, entity_type, entity_id, action,
+                before_data, after_data, changes, prev_hash, entry_hash
+         FROM audit_log
+         WHERE {}
+         ORDER BY id DESC
+         LIMIT $8 OFFSET $9",
+        filter
+    ))
+    .bind(&query.entity_type)
+    .bind(query.entity_id)
+    .bind(query.user_id)
+    .bind(&query.action)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(tenant_id)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .map(|row: PgRow| entry_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match entries {
+        Ok(entries) => HttpResponse::Ok().json(serde_json::json!({
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+            "entries": entries
+        })),
+        Err(e) => {
+            eprintln!("Error fetching audit log: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching audit log")
+        }
+    }
+}
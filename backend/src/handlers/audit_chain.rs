@@ -0,0 +1,572 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{DateTime, DurationRound, Utc};
+use futures::TryStreamExt;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use uuid::Uuid;
+
+use super::patrimony::auth_middleware;
+use super::audit::{diff, owner_tenant, Actor};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// "Hash anterior" da primeira entrada encadeada
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+const DEFAULT_CHECKPOINT_INTERVAL_SECS: u64 = 86400;
+const DEFAULT_CHECKPOINT_DIR: &str = "./audit_checkpoints";
+
+const ENTRY_COLUMNS: &str =
+    "id, occurred_at, actor_id, actor_username, ip_address, entity_type, entity_id, action,
+     before_data, after_data, changes, prev_hash, entry_hash";
+
+const CHECKPOINT_SELECT: &str =
+    "SELECT c.id, c.last_entry_id, c.last_hash, c.entry_count, c.signature, c.file_path,
+            c.created_by, u.username as created_by_name, c.created_at
+     FROM audit_checkpoints c
+     LEFT JOIN users u ON c.created_by = u.id";
+
+/// Conteúdo de uma entrada coberto pelo hash
+struct ChainEntry {
+    id: i64,
+    occurred_at: DateTime<Utc>,
+    actor_id: Option<Uuid>,
+    actor_username: Option<String>,
+    ip_address: Option<String>,
+    entity_type: String,
+    entity_id: Uuid,
+    action: String,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+    changes: serde_json::Value,
+}
+
+impl ChainEntry {
+    fn from_row(row: &PgRow) -> Self {
+        ChainEntry {
+            id: row.get("id"),
+            occurred_at: row.get("occurred_at"),
+            actor_id: row.get("actor_id"),
+            actor_username: row.get("actor_username"),
+            ip_address: row.get("ip_address"),
+            entity_type: row.get("entity_type"),
+            entity_id: row.get("entity_id"),
+            action: row.get("action"),
+            before: row.get("before_data"),
+            after: row.get("after_data"),
+            changes: row.get("changes"),
+        }
+    }
+
+    /// SHA-256 do JSON da entrada junto com o hash anterior.
+    /// O serde_json ordena as chaves dos objetos, então a serialização é canônica.
+    fn hash(&self, prev_hash: &str) -> String {
+        let material = serde_json::json!({
+            "prev_hash": prev_hash,
+            "id": self.id,
+            "occurred_at": self.occurred_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true),
+            "actor_id": self.actor_id,
+            "actor_username": self.actor_username,
+            "ip_address": self.ip_address,
+            "entity_type": self.entity_type,
+            "entity_id": self.entity_id,
+            "action": self.action,
+            "before": self.before,
+            "after": self.after,
+            "changes": self.changes
+        });
+        hex::encode(Sha256::digest(material.to_string().as_bytes()))
+    }
+}
+
+/// Grava a entrada na trilha encadeando-a à anterior
+pub async fn append(
+    transaction: &mut Transaction<'_, Postgres>,
+    actor: &Actor,
+    entity_type: &str,
+    entity_id: Uuid,
+    action: &str,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+) -> Result<(), sqlx::Error> {
+    let changes = diff(before.as_ref(), after.as_ref());
+    // Fora do hash: só separa a consulta por entidade (e as entradas antigas continuam verificáveis)
+    let tenant_id = owner_tenant(entity_type, entity_id, before.as_ref(), after.as_ref());
+
+    // Trava a ponta da cadeia até o commit: o id só é reservado depois da trava,
+    // então a ordem dos ids é a ordem da cadeia
+    let prev_hash = sqlx::query("SELECT last_hash FROM audit_chain_head WHERE id FOR UPDATE")
+        .map(|row: PgRow| row.get::<Option<String>, _>("last_hash"))
+        .fetch_one(&mut **transaction)
+        .await?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    // Os JSONs passam pelo jsonb para que o hash use exatamente o que será lido na verificação
+    let row = sqlx::query(
+        "SELECT nextval(pg_get_serial_sequence('audit_log', 'id')) as id, NOW() as occurred_at,
+                $1::jsonb as before_data, $2::jsonb as after_data, $3::jsonb as changes"
+    )
+    .bind(before)
+    .bind(after)
+    .bind(changes)
+    .fetch_one(&mut **transaction)
+    .await?;
+
+    let entry = ChainEntry {
+        id: row.get("id"),
+        occurred_at: row.get("occurred_at"),
+        actor_id: actor.id,
+        actor_username: actor.username.clone(),
+        ip_address: actor.ip_address.clone(),
+        entity_type: entity_type.to_string(),
+        entity_id,
+        action: action.to_string(),
+        before: row.get("before_data"),
+        after: row.get("after_data"),
+        changes: row.get("changes"),
+    };
+    let entry_hash = entry.hash(&prev_hash);
+
+    sqlx::query(
+        "INSERT INTO audit_log (id, occurred_at, actor_id, actor_username, ip_address, entity_type, entity_id, action,
+                                before_data, after_data, changes, prev_hash, entry_hash, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)"
+    )
+    .bind(entry.id)
+    .bind(entry.occurred_at)
+    .bind(entry.actor_id)
+    .bind(&entry.actor_username)
+    .bind(&entry.ip_address)
+    .bind(&entry.entity_type)
+    .bind(entry.entity_id)
+    .bind(&entry.action)
+    .bind(&entry.before)
+    .bind(&entry.after)
+    .bind(&entry.changes)
+    .bind(&prev_hash)
+    .bind(&entry_hash)
+    .bind(tenant_id)
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query("UPDATE audit_chain_head SET last_entry_id = $1, last_hash = $2, updated_at = NOW() WHERE id")
+        .bind(entry.id)
+        .bind(&entry_hash)
+        .execute(&mut **transaction)
+        .await?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+pub struct BrokenLink {
+    pub entry_id: Option<i64>,
+    pub reason: &'static str,
+    pub expected: Option<String>,
+    pub found: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct CheckpointFailure {
+    pub checkpoint_id: Uuid,
+    pub last_entry_id: i64,
+    pub reason: &'static str,
+}
+
+#[derive(Serialize, Default)]
+pub struct VerificationReport {
+    pub valid: bool,
+    pub entries_checked: i64,
+    pub unsealed_entries: i64,          // Entradas anteriores ao encadeamento
+    pub last_entry_id: Option<i64>,
+    pub last_hash: Option<String>,
+    pub broken_link: Option<BrokenLink>,
+    pub checkpoints_checked: usize,
+    pub signatures_verified: bool,     // false quando AUDIT_CHECKPOINT_KEY não está configurada
+    pub checkpoint_failure: Option<CheckpointFailure>,
+}
+
+struct StoredCheckpoint {
+    id: Uuid,
+    last_entry_id: i64,
+    last_hash: String,
+    entry_count: i64,
+    signature: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Chave HMAC dos pontos de controle (mantida fora do banco)
+pub fn checkpoint_key() -> Option<Vec<u8>> {
+    env::var("AUDIT_CHECKPOINT_KEY")
+        .ok()
+        .filter(|key| !key.is_empty())
+        .map(String::into_bytes)
+}
+
+fn checkpoint_payload(id: Uuid, last_entry_id: i64, last_hash: &str, entry_count: i64, created_at: DateTime<Utc>) -> serde_json::Value {
+    serde_json::json!({
+        "id": id,
+        "last_entry_id": last_entry_id,
+        "last_hash": last_hash,
+        "entry_count": entry_count,
+        "created_at": created_at.to_rfc3339_opts(chrono::SecondsFormat::Micros, true)
+    })
+}
+
+fn checkpoint_mac(key: &[u8], payload: &serde_json::Value) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(payload.to_string().as_bytes());
+    mac
+}
+
+fn signature_matches(key: &[u8], checkpoint: &StoredCheckpoint) -> bool {
+    let payload = checkpoint_payload(
+        checkpoint.id,
+        checkpoint.last_entry_id,
+        &checkpoint.last_hash,
+        checkpoint.entry_count,
+        checkpoint.created_at,
+    );
+    match hex::decode(&checkpoint.signature) {
+        Ok(signature) => checkpoint_mac(key, &payload).verify_slice(&signature).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Percorre a trilha em ordem e informa o primeiro elo quebrado
+pub async fn verify_chain(pool: &PgPool) -> Result<VerificationReport, sqlx::Error> {
+    let key = checkpoint_key();
+    let mut report = VerificationReport { signatures_verified: key.is_some(), ..Default::default() };
+
+    let checkpoints = sqlx::query(
+        "SELECT id, last_entry_id, last_hash, entry_count, signature, created_at FROM audit_checkpoints ORDER BY last_entry_id"
+    )
+    .map(|row: PgRow| StoredCheckpoint {
+        id: row.get("id"),
+        last_entry_id: row.get("last_entry_id"),
+        last_hash: row.get("last_hash"),
+        entry_count: row.get("entry_count"),
+        signature: row.get("signature"),
+        created_at: row.get("created_at"),
+    })
+    .fetch_all(pool)
+    .await?;
+
+    // Assinatura primeiro: um ponto de controle forjado não serve de referência
+    if let Some(key) = &key {
+        if let Some(forged) = checkpoints.iter().find(|checkpoint| !signature_matches(key, checkpoint)) {
+            report.checkpoint_failure = Some(CheckpointFailure {
+                checkpoint_id: forged.id,
+                last_entry_id: forged.last_entry_id,
+                reason: "invalid_signature",
+            });
+            return Ok(report);
+        }
+    }
+
+    let mut pending: HashMap<i64, Vec<&StoredCheckpoint>> = HashMap::new();
+    for checkpoint in &checkpoints {
+        pending.entry(checkpoint.last_entry_id).or_default().push(checkpoint);
+    }
+
+    let sql = format!("SELECT {} FROM audit_log ORDER BY id", ENTRY_COLUMNS);
+    let mut rows = sqlx::query(&sql).fetch(pool);
+    let mut chain_started = false;
+
+    while let Some(row) = rows.try_next().await? {
+        let entry = ChainEntry::from_row(&row);
+        let prev_hash: Option<String> = row.get("prev_hash");
+        let entry_hash: Option<String> = row.get("entry_hash");
+        report.entries_checked += 1;
+
+        match entry_hash {
+            None if !chain_started => report.unsealed_entries += 1,
+            None => {
+                report.broken_link = Some(BrokenLink { entry_id: Some(entry.id), reason: "unsealed_entry", expected: None, found: None });
+                break;
+            }
+            Some(entry_hash) => {
+                let expected_prev = report.last_hash.clone().unwrap_or_else(|| GENESIS_HASH.to_string());
+                if prev_hash.as_deref() != Some(expected_prev.as_str()) {
+                    report.broken_link = Some(BrokenLink {
+                        entry_id: Some(entry.id),
+                        reason: "prev_hash_mismatch",
+                        expected: Some(expected_prev),
+                        found: prev_hash,
+                    });
+                    break;
+                }
+
+                let computed = entry.hash(&expected_prev);
+                if computed != entry_hash {
+                    report.broken_link = Some(BrokenLink {
+                        entry_id: Some(entry.id),
+                        reason: "entry_hash_mismatch",
+                        expected: Some(computed),
+                        found: Some(entry_hash),
+                    });
+                    break;
+                }
+
+                chain_started = true;
+                report.last_entry_id = Some(entry.id);
+                report.last_hash = Some(entry_hash);
+            }
+        }
+
+        if let Some(covering) = pending.remove(&entry.id) {
+            for checkpoint in covering {
+                report.checkpoints_checked += 1;
+                let reason = if report.last_hash.as_deref() != Some(checkpoint.last_hash.as_str()) {
+                    Some("hash_mismatch")
+                } else if report.entries_checked != checkpoint.entry_count {
+                    Some("entry_count_mismatch")
+                } else {
+                    None
+                };
+                if let Some(reason) = reason {
+                    report.checkpoint_failure = Some(CheckpointFailure {
+                        checkpoint_id: checkpoint.id,
+                        last_entry_id: checkpoint.last_entry_id,
+                        reason,
+                    });
+                    return Ok(report);
+                }
+            }
+        }
+    }
+    drop(rows);
+
+    if report.broken_link.is_some() {
+        return Ok(report);
+    }
+
+    // Entrada coberta por um ponto de controle que sumiu da trilha
+    if let Some(missing) = pending.values().flatten().min_by_key(|checkpoint| checkpoint.last_entry_id) {
+        report.checkpoints_checked += 1;
+        report.checkpoint_failure = Some(CheckpointFailure {
+            checkpoint_id: missing.id,
+            last_entry_id: missing.last_entry_id,
+            reason: "entry_missing",
+        });
+        return Ok(report);
+    }
+
+    // A ponta registrada deve ser a última entrada (detecta remoções no fim da trilha)
+    let head = sqlx::query("SELECT last_entry_id, last_hash FROM audit_chain_head WHERE id")
+        .fetch_optional(pool)
+        .await?;
+    let (head_id, head_hash) = match head {
+        Some(row) => (row.get::<Option<i64>, _>("last_entry_id"), row.get::<Option<String>, _>("last_hash")),
+        None => (None, None),
+    };
+    if head_id != report.last_entry_id || head_hash != report.last_hash {
+        report.broken_link = Some(BrokenLink {
+            entry_id: head_id,
+            reason: "chain_head_mismatch",
+            expected: head_hash,
+            found: report.last_hash.clone(),
+        });
+        return Ok(report);
+    }
+
+    report.valid = true;
+    Ok(report)
+}
+
+pub enum CheckpointOutcome {
+    Created(serde_json::Value),
+    NothingNew,
+    ChainBroken(VerificationReport),
+}
+
+fn checkpoint_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "last_entry_id": row.get::<i64, _>("last_entry_id"),
+        "last_hash": row.get::<String, _>("last_hash"),
+        "entry_count": row.get::<i64, _>("entry_count"),
+        "signature": row.get::<String, _>("signature"),
+        "file_path": row.get::<Option<String>, _>("file_path"),
+        "created_by": row.get::<Option<Uuid>, _>("created_by"),
+        "created_by_name": row.get::<Option<String>, _>("created_by_name"),
+        "created_at": row.get::<DateTime<Utc>, _>("created_at")
+    })
+}
+
+/// Verifica a cadeia, assina a ponta atual e exporta o ponto de controle para arquivo
+pub async fn create_checkpoint(
+    pool: &PgPool,
+    key: &[u8],
+    created_by: Option<Uuid>,
+) -> Result<CheckpointOutcome, sqlx::Error> {
+    let report = verify_chain(pool).await?;
+    if !report.valid {
+        return Ok(CheckpointOutcome::ChainBroken(report));
+    }
+
+    let (Some(last_entry_id), Some(last_hash)) = (report.last_entry_id, report.last_hash.clone()) else {
+        return Ok(CheckpointOutcome::NothingNew);
+    };
+
+    let already_covered = sqlx::query("SELECT 1 FROM audit_checkpoints WHERE last_entry_id >= $1")
+        .bind(last_entry_id)
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+    if already_covered {
+        return Ok(CheckpointOutcome::NothingNew);
+    }
+
+    let id = Uuid::new_v4();
+    let created_at = Utc::now()
+        .duration_trunc(chrono::Duration::microseconds(1))
+        .unwrap_or_else(|_| Utc::now());
+    let payload = checkpoint_payload(id, last_entry_id, &last_hash, report.entries_checked, created_at);
+    let signature = hex::encode(checkpoint_mac(key, &payload).finalize().into_bytes());
+
+    // Cópia fora do banco: é ela que sustenta a verificação se o banco for adulterado
+    let dir = env::var("AUDIT_CHECKPOINT_DIR").unwrap_or_else(|_| DEFAULT_CHECKPOINT_DIR.to_string());
+    let file_path = format!("{}/checkpoint_{}_{}.json", dir, created_at.format("%Y%m%dT%H%M%S"), last_entry_id);
+    let mut export = payload.clone();
+    export["algorithm"] = serde_json::json!("HMAC-SHA256");
+    export["signature"] = serde_json::json!(signature);
+    let contents = serde_json::to_string_pretty(&export).unwrap_or_default();
+
+    tokio::fs::create_dir_all(&dir).await.map_err(sqlx::Error::Io)?;
+    tokio::fs::write(&file_path, contents).await.map_err(sqlx::Error::Io)?;
+
+    sqlx::query(
+        "INSERT INTO audit_checkpoints (id, last_entry_id, last_hash, entry_count, signature, file_path, created_by, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+    )
+    .bind(id)
+    .bind(last_entry_id)
+    .bind(&last_hash)
+    .bind(report.entries_checked)
+    .bind(&signature)
+    .bind(&file_path)
+    .bind(created_by)
+    .bind(created_at)
+    .execute(pool)
+    .await?;
+
+    let row = sqlx::query(&format!("{} WHERE c.id = $1", CHECKPOINT_SELECT))
+        .bind(id)
+        .fetch_one(pool)
+        .await?;
+
+    Ok(CheckpointOutcome::Created(checkpoint_json(&row)))
+}
+
+/// Rotina periódica de pontos de controle assinados
+pub async fn run_checkpoints(pool: PgPool) {
+    let Some(key) = checkpoint_key() else {
+        println!("⚠️ AUDIT_CHECKPOINT_KEY não configurada: pontos de controle da auditoria desativados");
+        return;
+    };
+
+    let seconds = env::var("AUDIT_CHECKPOINT_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL_SECS);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+        match create_checkpoint(&pool, &key, None).await {
+            Ok(CheckpointOutcome::Created(checkpoint)) => {
+                println!("🔏 Ponto de controle da auditoria gravado: {}", checkpoint["file_path"]);
+            }
+            Ok(CheckpointOutcome::NothingNew) => {}
+            Ok(CheckpointOutcome::ChainBroken(report)) => {
+                eprintln!(
+                    "Audit chain verification failed, checkpoint not created: {}",
+                    serde_json::to_string(&report).unwrap_or_default()
+                );
+            }
+            Err(e) => eprintln!("Error creating audit checkpoint: {}", e),
+        }
+    }
+}
+
+pub async fn verify_audit_chain(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match verify_chain(pool.get_ref()).await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            eprintln!("Error verifying audit chain: {}", e);
+            HttpResponse::InternalServerError().json("Error verifying audit chain")
+        }
+    }
+}
+
+pub async fn create_audit_checkpoint(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let Some(key) = checkpoint_key() else {
+        return HttpResponse::ServiceUnavailable().json("AUDIT_CHECKPOINT_KEY is not configured");
+    };
+
+    match create_checkpoint(pool.get_ref(), &key, Some(user.id)).await {
+        Ok(CheckpointOutcome::Created(checkpoint)) => HttpResponse::Created().json(checkpoint),
+        Ok(CheckpointOutcome::NothingNew) => {
+            HttpResponse::Conflict().json("No new audit entries since the last checkpoint")
+        }
+        Ok(CheckpointOutcome::ChainBroken(report)) => HttpResponse::Conflict().json(serde_json::json!({
+            "message": "Audit chain verification failed; checkpoint not created",
+            "verification": report
+        })),
+        Err(e) => {
+            eprintln!("Error creating audit checkpoint: {}", e);
+            HttpResponse::InternalServerError().json("Error creating audit checkpoint")
+        }
+    }
+}
+
+pub async fn get_audit_checkpoints(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(&format!("{} ORDER BY c.last_entry_id DESC", CHECKPOINT_SELECT))
+        .map(|row: PgRow| checkpoint_json(&row))
+        .fetch_all(pool.get_ref())
+        .await
+    {
+        Ok(checkpoints) => HttpResponse::Ok().json(checkpoints),
+        Err(e) => {
+            eprintln!("Error fetching audit checkpoints: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching audit checkpoints")
+        }
+    }
+}
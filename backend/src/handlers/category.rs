@@ -0,0 +1,459 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::auth_middleware;
+use super::depreciation::{self, DEFAULT_USEFUL_LIFE_MONTHS};
+
+#[derive(Deserialize)]
+pub struct CreateCategory {
+    pub code: String,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    pub accounting_code: Option<String>,
+    pub default_useful_life_months: Option<i32>,
+    pub default_residual_percent: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCategory {
+    pub name: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub accounting_code: Option<String>,
+    pub default_useful_life_months: Option<i32>,
+    pub default_residual_percent: Option<f64>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct CategoryQuery {
+    pub active: Option<bool>,
+}
+
+/// Valores padrão da categoria aplicados ao cadastro do bem
+pub struct CategoryDefaults {
+    pub code: String,
+    pub active: bool,
+    pub useful_life_months: i32,
+    pub residual_percent: f64,
+}
+
+impl CategoryDefaults {
+    pub fn residual_value(&self, value: f64) -> f64 {
+        depreciation::round_cents(value * self.residual_percent / 100.0)
+    }
+}
+
+/// Subconsulta com a categoria informada e todas as suas subcategorias
+pub fn subtree_sql(param: usize) -> String {
+    format!(
+        "(WITH RECURSIVE category_subtree AS (
+            SELECT id FROM categories WHERE id = ${}::uuid
+            UNION ALL
+            SELECT c.id FROM categories c JOIN category_subtree s ON c.parent_id = s.id
+        ) SELECT id FROM category_subtree)",
+        param
+    )
+}
+
+// Caminho completo (Grupo / Subgrupo) calculado a partir das raízes
+const CATEGORY_SELECT: &str =
+    "WITH RECURSIVE tree AS (
+        SELECT id, name::text as path, 0 as depth FROM categories WHERE parent_id IS NULL
+        UNION ALL
+        SELECT c.id, t.path || ' / ' || c.name, t.depth + 1 FROM categories c JOIN tree t ON c.parent_id = t.id
+     )
+     SELECT c.id, c.code, c.name, c.parent_id, c.accounting_code, c.default_useful_life_months,
+            c.default_residual_percent, c.active, c.created_at, c.updated_at,
+            tree.path, tree.depth,
+            (SELECT COUNT(*) FROM patrimonies p WHERE p.category_id = c.id AND p.deleted_at IS NULL) as asset_count
+     FROM categories c
+     JOIN tree ON tree.id = c.id";
+
+fn category_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "code": row.get::<String, _>("code"),
+        "name": row.get::<String, _>("name"),
+        "parent_id": row.get::<Option<Uuid>, _>("parent_id"),
+        "accounting_code": row.get::<String, _>("accounting_code"),
+        "default_useful_life_months": row.get::<i32, _>("default_useful_life_months"),
+        "default_residual_percent": depreciation::optional_decimal(row, "default_residual_percent").unwrap_or(0.0),
+        "active": row.get::<bool, _>("active"),
+        "path": row.get::<String, _>("path"),
+        "depth": row.get::<i32, _>("depth"),
+        "asset_count": row.get::<i64, _>("asset_count"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+async fn fetch_category(pool: &PgPool, tenant_id: Uuid, category_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query(&format!("{} WHERE c.id = $1 AND c.tenant_id = $2", CATEGORY_SELECT))
+        .bind(category_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| category_json(&row))
+        .fetch_one(pool)
+        .await
+}
+
+/// Busca os padrões da categoria dentro da transação de cadastro do bem
+pub async fn load_defaults(
+    transaction: &mut Transaction<'_, Postgres>,
+    tenant_id: Uuid,
+    category_id: Uuid,
+) -> Result<Option<CategoryDefaults>, sqlx::Error> {
+    sqlx::query(
+        "SELECT code, active, default_useful_life_months, default_residual_percent FROM categories WHERE id = $1 AND tenant_id = $2"
+    )
+    .bind(category_id)
+    .bind(tenant_id)
+    .map(|row: PgRow| CategoryDefaults {
+        code: row.get("code"),
+        active: row.get("active"),
+        useful_life_months: row.get("default_useful_life_months"),
+        residual_percent: depreciation::optional_decimal(&row, "default_residual_percent").unwrap_or(0.0),
+    })
+    .fetch_optional(&mut **transaction)
+    .await
+}
+
+// Conta PCASP: dígitos separados por pontos (ex.: 1.2.3.1.1.03.00)
+fn is_valid_accounting_code(code: &str) -> bool {
+    !code.is_empty() && code.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+fn validate_defaults(
+    accounting_code: Option<&str>,
+    useful_life_months: Option<i32>,
+    residual_percent: Option<f64>,
+) -> Result<(), &'static str> {
+    if let Some(code) = accounting_code {
+        if !is_valid_accounting_code(code.trim()) {
+            return Err("Accounting code must be digits separated by dots (e.g. 1.2.3.1.1.03.00)");
+        }
+    }
+    if let Some(months) = useful_life_months {
+        if months <= 0 {
+            return Err("Default useful life must be greater than zero months");
+        }
+    }
+    if let Some(percent) = residual_percent {
+        if !(0.0..=100.0).contains(&percent) {
+            return Err("Default residual percent must be between 0 and 100");
+        }
+    }
+    Ok(())
+}
+
+// A categoria pai precisa existir e não pode estar abaixo da própria categoria
+async fn validate_parent(pool: &PgPool, tenant_id: Uuid, category_id: Option<Uuid>, parent_id: Uuid) -> Result<PgRow, HttpResponse> {
+    let result = sqlx::query(&format!(
+        "SELECT accounting_code, default_useful_life_months, default_residual_percent, $2::uuid IN {} as cycle
+         FROM categories WHERE id = $2 AND tenant_id = $3",
+        subtree_sql(1)
+    ))
+    .bind(category_id)
+    .bind(parent_id)
+    .bind(tenant_id)
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(row)) if row.get::<bool, _>("cycle") => {
+            Err(HttpResponse::BadRequest().json("A category cannot be placed inside itself"))
+        }
+        Ok(Some(row)) => Ok(row),
+        Ok(None) => Err(HttpResponse::BadRequest().json("Parent category not found")),
+        Err(e) => {
+            eprintln!("Error checking category hierarchy: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating parent category"))
+        }
+    }
+}
+
+pub async fn get_categories(
+    pool: web::Data<PgPool>,
+    query: web::Query<CategoryQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE c.tenant_id = $2 AND ($1::boolean IS NULL OR c.active = $1) ORDER BY tree.path",
+        CATEGORY_SELECT
+    ))
+    .bind(query.active)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| category_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(categories) => HttpResponse::Ok().json(categories),
+        Err(e) => {
+            eprintln!("Error fetching categories: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching categories")
+        }
+    }
+}
+
+pub async fn get_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let category_id = id.into_inner();
+
+    let category = match fetch_category(pool.get_ref(), user.tenant_id, category_id).await {
+        Ok(category) => category,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().json("Category not found"),
+        Err(e) => {
+            eprintln!("Error fetching category: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching category");
+        }
+    };
+
+    let children = sqlx::query(&format!("{} WHERE c.parent_id = $1 ORDER BY c.name", CATEGORY_SELECT))
+        .bind(category_id)
+        .map(|row: PgRow| category_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match children {
+        Ok(children) => HttpResponse::Ok().json(serde_json::json!({
+            "category": category,
+            "children": children
+        })),
+        Err(e) => {
+            eprintln!("Error fetching child categories: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching category")
+        }
+    }
+}
+
+pub async fn create_category(
+    pool: web::Data<PgPool>,
+    category: web::Json<CreateCategory>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let code = category.code.trim().to_lowercase();
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return HttpResponse::BadRequest().json("Code must contain only letters, digits, '-' or '_'");
+    }
+    if category.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if let Err(message) = validate_defaults(
+        category.accounting_code.as_deref(),
+        category.default_useful_life_months,
+        category.default_residual_percent,
+    ) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    // Campos omitidos herdam da categoria pai
+    let (parent_accounting_code, parent_months, parent_percent) = match category.parent_id {
+        Some(parent_id) => match validate_parent(pool.get_ref(), user.tenant_id, None, parent_id).await {
+            Ok(row) => (
+                Some(row.get::<String, _>("accounting_code")),
+                Some(row.get::<i32, _>("default_useful_life_months")),
+                depreciation::optional_decimal(&row, "default_residual_percent"),
+            ),
+            Err(response) => return response,
+        },
+        None => (None, None, None),
+    };
+
+    let accounting_code = match category.accounting_code.as_deref().map(str::trim).map(str::to_string).or(parent_accounting_code) {
+        Some(accounting_code) => accounting_code,
+        None => return HttpResponse::BadRequest().json("Accounting code is required"),
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO categories (code, name, parent_id, accounting_code, default_useful_life_months, default_residual_percent, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id"
+    )
+    .bind(&code)
+    .bind(category.name.trim())
+    .bind(category.parent_id)
+    .bind(&accounting_code)
+    .bind(category.default_useful_life_months.or(parent_months).unwrap_or(DEFAULT_USEFUL_LIFE_MONTHS))
+    .bind(category.default_residual_percent.or(parent_percent).unwrap_or(0.0))
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let category_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating category: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Category code already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating category");
+        }
+    };
+
+    match fetch_category(pool.get_ref(), user.tenant_id, category_id).await {
+        Ok(category) => HttpResponse::Created().json(category),
+        Err(e) => {
+            eprintln!("Error fetching created category: {}", e);
+            HttpResponse::InternalServerError().json("Category created but error fetching details")
+        }
+    }
+}
+
+pub async fn update_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    category: web::Json<UpdateCategory>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let category_id = id.into_inner();
+
+    if let Some(name) = &category.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json("Name is required");
+        }
+    }
+    if let Err(message) = validate_defaults(
+        category.accounting_code.as_deref(),
+        category.default_useful_life_months,
+        category.default_residual_percent,
+    ) {
+        return HttpResponse::BadRequest().json(message);
+    }
+    if let Some(parent_id) = category.parent_id {
+        if let Err(response) = validate_parent(pool.get_ref(), user.tenant_id, Some(category_id), parent_id).await {
+            return response;
+        }
+    }
+
+    // Novos padrões valem para os próximos cadastros; bens existentes mantêm os seus
+    let result = sqlx::query(
+        "UPDATE categories SET
+            name = COALESCE($1, name),
+            parent_id = COALESCE($2, parent_id),
+            accounting_code = COALESCE($3, accounting_code),
+            default_useful_life_months = COALESCE($4, default_useful_life_months),
+            default_residual_percent = COALESCE($5, default_residual_percent),
+            active = COALESCE($6, active),
+            updated_at = NOW()
+         WHERE id = $7 AND tenant_id = $8"
+    )
+    .bind(category.name.as_deref().map(str::trim))
+    .bind(category.parent_id)
+    .bind(category.accounting_code.as_deref().map(str::trim))
+    .bind(category.default_useful_life_months)
+    .bind(category.default_residual_percent)
+    .bind(category.active)
+    .bind(category_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => return HttpResponse::NotFound().json("Category not found"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error updating category: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating category");
+        }
+    }
+
+    match fetch_category(pool.get_ref(), user.tenant_id, category_id).await {
+        Ok(category) => HttpResponse::Ok().json(category),
+        Err(e) => {
+            eprintln!("Error fetching updated category: {}", e);
+            HttpResponse::InternalServerError().json("Category updated but error fetching details")
+        }
+    }
+}
+
+pub async fn delete_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let category_id = id.into_inner();
+
+    // Categorias com subcategorias ou bens são mantidas (use active = false)
+    let usage = sqlx::query(
+        "SELECT
+            (SELECT COUNT(*) FROM categories WHERE parent_id = c.id) as children,
+            (SELECT COUNT(*) FROM patrimonies WHERE category_id = c.id) as assets
+         FROM categories c WHERE c.id = $1 AND c.tenant_id = $2"
+    )
+    .bind(category_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match usage {
+        Ok(None) => return HttpResponse::NotFound().json("Category not found"),
+        Ok(Some(row)) => {
+            if row.get::<i64, _>("children") > 0 {
+                return HttpResponse::Conflict().json("Category has child categories");
+            }
+            if row.get::<i64, _>("assets") > 0 {
+                return HttpResponse::Conflict().json("Category is in use; deactivate it instead");
+            }
+        }
+        Err(e) => {
+            eprintln!("Error checking category usage: {}", e);
+            return HttpResponse::InternalServerError().json("Error deleting category");
+        }
+    }
+
+    match sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(category_id)
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json("Category not found"),
+        Ok(_) => HttpResponse::Ok().json("Category deleted successfully"),
+        Err(e) => {
+            eprintln!("Error deleting category: {}", e);
+            HttpResponse::InternalServerError().json("Error deleting category")
+        }
+    }
+}
@@ -0,0 +1,707 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, ensure_visible};
+use super::department::{is_department_manager, validate_department};
+use super::write_off::{written_off_response, WRITTEN_OFF};
+use super::depreciation;
+use super::department_grant;
+use super::audit::{self, Actor};
+use crate::documents::terms::{self, ResponsibilityTermData, TermItem, DEFAULT_ORGANIZATION};
+
+#[derive(Deserialize)]
+pub struct CreateCustodian {
+    pub name: String,
+    pub registration_number: String,
+    pub position: Option<String>,
+    pub department: String,
+    pub email: Option<String>,
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateCustodian {
+    pub name: Option<String>,
+    pub registration_number: Option<String>,
+    pub position: Option<String>,
+    pub department: Option<String>,
+    pub email: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct CustodianQuery {
+    pub department: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct AssignCustodian {
+    pub custodian_id: Uuid,
+    pub notes: Option<String>,
+}
+
+const CUSTODIAN_SELECT: &str =
+    "SELECT c.id, c.name, c.registration_number, c.position, c.department, c.email, c.user_id, c.active,
+            c.created_at, c.updated_at,
+            (SELECT COUNT(*) FROM custody_assignments a WHERE a.custodian_id = c.id AND a.released_at IS NULL) as asset_count
+     FROM custodians c";
+
+fn custodian_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "name": row.get::<String, _>("name"),
+        "registration_number": row.get::<String, _>("registration_number"),
+        "position": row.get::<Option<String>, _>("position"),
+        "department": row.get::<String, _>("department"),
+        "email": row.get::<Option<String>, _>("email"),
+        "user_id": row.get::<Option<Uuid>, _>("user_id"),
+        "active": row.get::<bool, _>("active"),
+        "asset_count": row.get::<i64, _>("asset_count"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+const ASSIGNMENT_SELECT: &str =
+    "SELECT a.id, a.patrimony_id, p.plate, p.name as patrimony_name, a.custodian_id, c.name as custodian_name,
+            c.registration_number, a.notes, a.assigned_by, ua.username as assigned_by_name, a.assigned_at,
+            a.released_by, ur.username as released_by_name, a.released_at
+     FROM custody_assignments a
+     JOIN patrimonies p ON a.patrimony_id = p.id
+     JOIN custodians c ON a.custodian_id = c.id
+     LEFT JOIN users ua ON a.assigned_by = ua.id
+     LEFT JOIN users ur ON a.released_by = ur.id";
+
+fn assignment_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+        "plate": row.get::<String, _>("plate"),
+        "patrimony_name": row.get::<String, _>("patrimony_name"),
+        "custodian_id": row.get::<Uuid, _>("custodian_id"),
+        "custodian_name": row.get::<String, _>("custodian_name"),
+        "registration_number": row.get::<String, _>("registration_number"),
+        "notes": row.get::<Option<String>, _>("notes"),
+        "assigned_by": row.get::<Option<Uuid>, _>("assigned_by"),
+        "assigned_by_name": row.get::<Option<String>, _>("assigned_by_name"),
+        "assigned_at": row.get::<chrono::DateTime<Utc>, _>("assigned_at"),
+        "released_by": row.get::<Option<Uuid>, _>("released_by"),
+        "released_by_name": row.get::<Option<String>, _>("released_by_name"),
+        "released_at": row.get::<Option<chrono::DateTime<Utc>>, _>("released_at")
+    })
+}
+
+/// Encerra a guarda vigente do bem (usado na troca de responsável e na baixa)
+pub async fn release_active_custody(
+    transaction: &mut Transaction<'_, Postgres>,
+    patrimony_id: Uuid,
+    released_by: Uuid,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        "UPDATE custody_assignments SET released_at = NOW(), released_by = $1
+         WHERE patrimony_id = $2 AND released_at IS NULL"
+    )
+    .bind(released_by)
+    .bind(patrimony_id)
+    .execute(&mut **transaction)
+    .await
+    .map(|result| result.rows_affected())
+}
+
+pub async fn get_custodians(
+    pool: web::Data<PgPool>,
+    query: web::Query<CustodianQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE c.tenant_id = $3
+           AND ($1::varchar IS NULL OR c.department = $1)
+           AND ($2::boolean IS NULL OR c.active = $2)
+           AND {}
+         ORDER BY c.name",
+        CUSTODIAN_SELECT,
+        department_grant::filter_sql("c.department", 4)
+    ))
+    .bind(&query.department)
+    .bind(query.active)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| custodian_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(custodians) => HttpResponse::Ok().json(custodians),
+        Err(e) => {
+            eprintln!("Error fetching custodians: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching custodians")
+        }
+    }
+}
+
+pub async fn get_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let custodian_id = id.into_inner();
+
+    let custodian = match sqlx::query(&format!(
+        "{} WHERE c.id = $1 AND c.tenant_id = $2 AND {}",
+        CUSTODIAN_SELECT,
+        department_grant::filter_sql("c.department", 3)
+    ))
+        .bind(custodian_id)
+        .bind(user.tenant_id)
+        .bind(department_grant::restriction(&user))
+        .map(|row: PgRow| custodian_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(custodian) => custodian,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().json("Custodian not found"),
+        Err(e) => {
+            eprintln!("Error fetching custodian: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching custodian");
+        }
+    };
+
+    let assets = sqlx::query(&format!(
+        "{} WHERE a.custodian_id = $1 AND a.released_at IS NULL AND {} ORDER BY p.plate",
+        ASSIGNMENT_SELECT,
+        department_grant::filter_sql("p.department", 2)
+    ))
+    .bind(custodian_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| assignment_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match assets {
+        Ok(assets) => HttpResponse::Ok().json(serde_json::json!({
+            "custodian": custodian,
+            "assets": assets
+        })),
+        Err(e) => {
+            eprintln!("Error fetching custody assignments: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching custodian")
+        }
+    }
+}
+
+pub async fn create_custodian(
+    pool: web::Data<PgPool>,
+    custodian: web::Json<CreateCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if custodian.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if custodian.registration_number.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Registration number is required");
+    }
+    if custodian.department.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Department is required");
+    }
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, custodian.department.trim()).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO custodians (name, registration_number, position, department, email, user_id, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id"
+    )
+    .bind(custodian.name.trim())
+    .bind(custodian.registration_number.trim())
+    .bind(&custodian.position)
+    .bind(custodian.department.trim())
+    .bind(&custodian.email)
+    .bind(custodian.user_id)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let custodian_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating custodian: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Registration number already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating custodian");
+        }
+    };
+
+    match sqlx::query(&format!("{} WHERE c.id = $1", CUSTODIAN_SELECT))
+        .bind(custodian_id)
+        .map(|row: PgRow| custodian_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(custodian) => HttpResponse::Created().json(custodian),
+        Err(e) => {
+            eprintln!("Error fetching created custodian: {}", e);
+            HttpResponse::InternalServerError().json("Custodian created but error fetching details")
+        }
+    }
+}
+
+pub async fn update_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    custodian: web::Json<UpdateCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let custodian_id = id.into_inner();
+
+    if let Some(department) = &custodian.department {
+        if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, department.trim()).await {
+            return response;
+        }
+    }
+
+    // Responsável com bens sob guarda não pode ser desativado
+    if custodian.active == Some(false) {
+        match sqlx::query(
+            "SELECT COUNT(*) as total FROM custody_assignments a
+             JOIN custodians c ON c.id = a.custodian_id
+             WHERE a.custodian_id = $1 AND c.tenant_id = $2 AND a.released_at IS NULL"
+        )
+        .bind(custodian_id)
+        .bind(user.tenant_id)
+        .fetch_one(pool.get_ref())
+        .await
+        {
+            Ok(row) if row.get::<i64, _>("total") > 0 => {
+                return HttpResponse::Conflict().json("Custodian still has assets under custody");
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error checking custody assignments: {}", e);
+                return HttpResponse::InternalServerError().json("Error updating custodian");
+            }
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE custodians SET
+            name = COALESCE($1, name),
+            registration_number = COALESCE($2, registration_number),
+            position = COALESCE($3, position),
+            department = COALESCE($4, department),
+            email = COALESCE($5, email),
+            user_id = COALESCE($6, user_id),
+            active = COALESCE($7, active),
+            updated_at = NOW()
+         WHERE id = $8 AND tenant_id = $9"
+    )
+    .bind(custodian.name.as_deref().map(str::trim))
+    .bind(custodian.registration_number.as_deref().map(str::trim))
+    .bind(&custodian.position)
+    .bind(custodian.department.as_deref().map(str::trim))
+    .bind(&custodian.email)
+    .bind(custodian.user_id)
+    .bind(custodian.active)
+    .bind(custodian_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => return HttpResponse::NotFound().json("Custodian not found"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error updating custodian: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Registration number already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error updating custodian");
+        }
+    }
+
+    match sqlx::query(&format!("{} WHERE c.id = $1", CUSTODIAN_SELECT))
+        .bind(custodian_id)
+        .map(|row: PgRow| custodian_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(custodian) => HttpResponse::Ok().json(custodian),
+        Err(e) => {
+            eprintln!("Error fetching updated custodian: {}", e);
+            HttpResponse::InternalServerError().json("Custodian updated but error fetching details")
+        }
+    }
+}
+
+// Atribui um responsável ao bem, encerrando a guarda anterior
+pub async fn assign_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    assignment: web::Json<AssignCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error assigning custodian");
+        }
+    };
+
+    let patrimony = sqlx::query("SELECT department, status FROM patrimonies WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(patrimony_id)
+        .fetch_optional(&mut *transaction)
+        .await;
+
+    match patrimony {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") == WRITTEN_OFF {
+                return written_off_response();
+            }
+            if !is_department_manager(pool.get_ref(), &user, &row.get::<String, _>("department")).await {
+                return HttpResponse::Forbidden().json("Only a manager of the patrimony department can assign custodians");
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error assigning custodian");
+        }
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error assigning custodian");
+        }
+    };
+
+    match sqlx::query("SELECT active FROM custodians WHERE id = $1 AND tenant_id = $2")
+        .bind(assignment.custodian_id)
+        .bind(user.tenant_id)
+        .fetch_optional(&mut *transaction)
+        .await
+    {
+        Ok(Some(row)) if row.get::<bool, _>("active") => {}
+        Ok(Some(_)) => return HttpResponse::BadRequest().json("Custodian is inactive"),
+        Ok(None) => return HttpResponse::NotFound().json("Custodian not found"),
+        Err(e) => {
+            eprintln!("Error fetching custodian: {}", e);
+            return HttpResponse::InternalServerError().json("Error assigning custodian");
+        }
+    }
+
+    if let Err(e) = release_active_custody(&mut transaction, patrimony_id, user.id).await {
+        eprintln!("Error releasing previous custody: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error assigning custodian");
+    }
+
+    let insert_result = sqlx::query(
+        "INSERT INTO custody_assignments (patrimony_id, custodian_id, notes, assigned_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id"
+    )
+    .bind(patrimony_id)
+    .bind(assignment.custodian_id)
+    .bind(&assignment.notes)
+    .bind(user.id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let assignment_id = match insert_result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating custody assignment: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error assigning custodian");
+        }
+    };
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::UPDATE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error assigning custodian");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error assigning custodian");
+    }
+
+    println!("👤 Responsável atribuído ao patrimônio {}", patrimony_id);
+
+    match sqlx::query(&format!("{} WHERE a.id = $1", ASSIGNMENT_SELECT))
+        .bind(assignment_id)
+        .map(|row: PgRow| assignment_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(assignment) => HttpResponse::Created().json(assignment),
+        Err(e) => {
+            eprintln!("Error fetching custody assignment: {}", e);
+            HttpResponse::InternalServerError().json("Custodian assigned but error fetching details")
+        }
+    }
+}
+
+pub async fn release_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error releasing custodian");
+        }
+    };
+
+    match sqlx::query("SELECT department FROM patrimonies WHERE id = $1")
+        .bind(patrimony_id)
+        .fetch_optional(&mut *transaction)
+        .await
+    {
+        Ok(Some(row)) => {
+            if !is_department_manager(pool.get_ref(), &user, &row.get::<String, _>("department")).await {
+                return HttpResponse::Forbidden().json("Only a manager of the patrimony department can release custodians");
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error releasing custodian");
+        }
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error releasing custodian");
+        }
+    };
+
+    let released = match release_active_custody(&mut transaction, patrimony_id, user.id).await {
+        Ok(released) => released,
+        Err(e) => {
+            eprintln!("Error releasing custody: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error releasing custodian");
+        }
+    };
+
+    if released > 0 {
+        if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::UPDATE, before).await {
+            eprintln!("Error recording audit entry: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error releasing custodian");
+        }
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error releasing custodian");
+    }
+
+    if released > 0 {
+        HttpResponse::Ok().json("Custodian released successfully")
+    } else {
+        HttpResponse::NotFound().json("Patrimony has no active custodian")
+    }
+}
+
+pub async fn get_custody_history(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(&format!(
+        "{} WHERE a.patrimony_id = $1 ORDER BY a.assigned_at DESC",
+        ASSIGNMENT_SELECT
+    ))
+    .bind(patrimony_id)
+    .map(|row: PgRow| assignment_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(history) => HttpResponse::Ok().json(history),
+        Err(e) => {
+            eprintln!("Error fetching custody history: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching custody history")
+        }
+    }
+}
+
+// Termo de responsabilidade com todos os bens sob a guarda do servidor
+pub async fn get_responsibility_term(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let custodian_id = id.into_inner();
+
+    let custodian = match sqlx::query(
+        &format!(
+            "SELECT name, registration_number, position, department FROM custodians WHERE id = $1 AND tenant_id = $2 AND {}",
+            department_grant::filter_sql("department", 3)
+        )
+    )
+    .bind(custodian_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::NotFound().json("Custodian not found"),
+        Err(e) => {
+            eprintln!("Error fetching custodian: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating responsibility term");
+        }
+    };
+
+    let items = sqlx::query(
+        "SELECT p.plate, p.name, p.description, p.value
+         FROM custody_assignments a
+         JOIN patrimonies p ON a.patrimony_id = p.id
+         WHERE a.custodian_id = $1 AND a.released_at IS NULL
+         ORDER BY p.plate"
+    )
+    .bind(custodian_id)
+    .map(|row: PgRow| TermItem {
+        plate: row.get("plate"),
+        name: row.get("name"),
+        description: row.get::<Option<String>, _>("description").unwrap_or_default(),
+        value: depreciation::optional_decimal(&row, "value").unwrap_or(0.0),
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let items = match items {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("Error fetching custody assets: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating responsibility term");
+        }
+    };
+
+    let organization = if user.company_name.trim().is_empty() {
+        DEFAULT_ORGANIZATION.to_string()
+    } else {
+        user.company_name.clone()
+    };
+
+    let term = ResponsibilityTermData {
+        organization,
+        custodian_name: custodian.get("name"),
+        registration_number: custodian.get("registration_number"),
+        position: custodian.get("position"),
+        department: custodian.get("department"),
+        issued_at: Utc::now(),
+        items,
+    };
+
+    match terms::render_responsibility_term(&term) {
+        Ok(pdf) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!(
+                    "inline; filename=\"termo_responsabilidade_{}.pdf\"",
+                    term.registration_number.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>()
+                ),
+            ))
+            .body(pdf),
+        Err(e) => {
+            eprintln!("Error rendering responsibility term: {}", e);
+            HttpResponse::InternalServerError().json("Error generating responsibility term")
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, User};
+use super::tenant::DEFAULT_TENANT;
+use super::permissions;
+
+#[derive(Deserialize)]
+pub struct CreateDepartment {
+    pub code: String,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDepartment {
+    pub name: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct DepartmentListQuery {
+    pub active: Option<bool>,
+    pub tenant: Option<String>,     // Código da entidade; sem ele, a entidade padrão
+}
+
+#[derive(Deserialize)]
+pub struct AddDepartmentManager {
+    pub user_id: Uuid,
+}
+
+// O código é a chave usada em patrimonies, users e transfers; por isso não muda depois de criado
+const DEPARTMENT_SELECT: &str =
+    "SELECT d.id, d.code, d.name, d.parent_id, p.code as parent_code, p.name as parent_name,
+            d.active, d.created_at, d.updated_at,
+            (SELECT COUNT(*) FROM patrimonies pa WHERE pa.department = d.code AND pa.tenant_id = d.tenant_id AND pa.deleted_at IS NULL) as asset_count,
+            (SELECT COUNT(*) FROM users u WHERE u.department = d.code AND u.tenant_id = d.tenant_id) as user_count,
+            COALESCE((SELECT json_agg(json_build_object('id', u.id, 'username', u.username, 'email', u.email) ORDER BY u.username)
+                      FROM department_managers m JOIN users u ON u.id = m.user_id
+                      WHERE m.department_id = d.id), '[]'::json) as managers
+     FROM departments d
+     LEFT JOIN departments p ON p.id = d.parent_id";
+
+fn department_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "code": row.get::<String, _>("code"),
+        "name": row.get::<String, _>("name"),
+        "parent_id": row.get::<Option<Uuid>, _>("parent_id"),
+        "parent_code": row.get::<Option<String>, _>("parent_code"),
+        "parent_name": row.get::<Option<String>, _>("parent_name"),
+        "active": row.get::<bool, _>("active"),
+        "asset_count": row.get::<i64, _>("asset_count"),
+        "user_count": row.get::<i64, _>("user_count"),
+        "managers": row.get::<serde_json::Value, _>("managers"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+async fn fetch_department(pool: &PgPool, tenant_id: Uuid, department_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query(&format!("{} WHERE d.id = $1 AND d.tenant_id = $2", DEPARTMENT_SELECT))
+        .bind(department_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| department_json(&row))
+        .fetch_one(pool)
+        .await
+}
+
+/// Confere se o código informado é de um departamento cadastrado e ativo da entidade
+pub async fn validate_department(pool: &PgPool, tenant_id: Uuid, code: &str) -> Result<(), HttpResponse> {
+    match sqlx::query("SELECT active FROM departments WHERE code = $1 AND tenant_id = $2")
+        .bind(code)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(row)) if row.get::<bool, _>("active") => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::BadRequest().json(format!("Department '{}' is inactive", code))),
+        Ok(None) => Err(HttpResponse::BadRequest().json(format!("Unknown department '{}'", code))),
+        Err(e) => {
+            eprintln!("Error validating department: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating department"))
+        }
+    }
+}
+
+/// Quem atua em qualquer departamento, gestor cadastrado no departamento ou em uma
+/// secretaria acima dele, ou usuário com papel `department_manager` lotado no próprio departamento
+pub async fn is_department_manager(pool: &PgPool, user: &User, department: &str) -> bool {
+    if user.can(permissions::ALL_DEPARTMENTS) || (user.can(permissions::OWN_DEPARTMENT) && user.department == department) {
+        return true;
+    }
+
+    let result = sqlx::query(
+        "WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id FROM departments WHERE code = $1 AND tenant_id = $3
+            UNION ALL
+            SELECT d.id, d.parent_id FROM departments d JOIN ancestors a ON d.id = a.parent_id
+         )
+         SELECT EXISTS(
+            SELECT 1 FROM department_managers m JOIN ancestors a ON a.id = m.department_id WHERE m.user_id = $2
+         ) as is_manager"
+    )
+    .bind(department)
+    .bind(user.id)
+    .bind(user.tenant_id)
+    .fetch_one(pool)
+    .await;
+
+    match result {
+        Ok(row) => row.get::<bool, _>("is_manager"),
+        Err(e) => {
+            eprintln!("Error checking department managers: {}", e);
+            false
+        }
+    }
+}
+
+// A secretaria precisa existir na mesma entidade e não pode estar abaixo do próprio departamento
+async fn validate_parent(pool: &PgPool, tenant_id: Uuid, department_id: Option<Uuid>, parent_id: Uuid) -> Result<(), HttpResponse> {
+    let result = sqlx::query(
+        "SELECT EXISTS(SELECT 1 FROM departments WHERE id = $2 AND tenant_id = $3) as parent_exists,
+                $2::uuid IN (
+                    WITH RECURSIVE subtree AS (
+                        SELECT id FROM departments WHERE id = $1::uuid
+                        UNION ALL
+                        SELECT d.id FROM departments d JOIN subtree s ON d.parent_id = s.id
+                    ) SELECT id FROM subtree
+                ) as cycle"
+    )
+    .bind(department_id)
+    .bind(parent_id)
+    .bind(tenant_id)
+    .fetch_one(pool)
+    .await;
+
+    match result {
+        Ok(row) if !row.get::<bool, _>("parent_exists") => {
+            Err(HttpResponse::BadRequest().json("Parent department not found"))
+        }
+        Ok(row) if row.get::<bool, _>("cycle") => {
+            Err(HttpResponse::BadRequest().json("A department cannot be placed under itself"))
+        }
+        Ok(_) => Ok(()),
+        Err(e) => {
+            eprintln!("Error checking department hierarchy: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating parent department"))
+        }
+    }
+}
+
+// Rota pública: usada no cadastro de usuários
+pub async fn get_departments(
+    pool: web::Data<PgPool>,
+    query: web::Query<DepartmentListQuery>,
+) -> HttpResponse {
+    let result = sqlx::query(
+        "SELECT d.id, d.code, d.name, d.parent_id, p.code as parent_code, d.active
+         FROM departments d
+         JOIN tenants t ON t.id = d.tenant_id
+         LEFT JOIN departments p ON p.id = d.parent_id
+         WHERE t.code = $2 AND ($1::boolean IS NULL OR d.active = $1)
+         ORDER BY d.name"
+    )
+    .bind(query.active)
+    .bind(query.tenant.as_deref().unwrap_or(DEFAULT_TENANT))
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "code": row.get::<String, _>("code"),
+            "name": row.get::<String, _>("name"),
+            "parent_id": row.get::<Option<Uuid>, _>("parent_id"),
+            "parent_code": row.get::<Option<String>, _>("parent_code"),
+            "active": row.get::<bool, _>("active")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(departments) => HttpResponse::Ok().json(departments),
+        Err(e) => {
+            eprintln!("Error fetching departments: {:?}", e);
+            HttpResponse::InternalServerError().json(format!("Error fetching departments: {}", e))
+        }
+    }
+}
+
+pub async fn get_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let department_id = id.into_inner();
+
+    let department = match fetch_department(pool.get_ref(), user.tenant_id, department_id).await {
+        Ok(department) => department,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().json("Department not found"),
+        Err(e) => {
+            eprintln!("Error fetching department: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching department");
+        }
+    };
+
+    let children = sqlx::query(&format!("{} WHERE d.parent_id = $1 ORDER BY d.name", DEPARTMENT_SELECT))
+        .bind(department_id)
+        .map(|row: PgRow| department_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match children {
+        Ok(children) => HttpResponse::Ok().json(serde_json::json!({
+            "department": department,
+            "children": children
+        })),
+        Err(e) => {
+            eprintln!("Error fetching child departments: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching department")
+        }
+    }
+}
+
+pub async fn create_department(
+    pool: web::Data<PgPool>,
+    department: web::Json<CreateDepartment>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let code = department.code.trim().to_lowercase();
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return HttpResponse::BadRequest().json("Code must contain only letters, digits, '-' or '_'");
+    }
+    if department.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if let Some(parent_id) = department.parent_id {
+        if let Err(response) = validate_parent(pool.get_ref(), user.tenant_id, None, parent_id).await {
+            return response;
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO departments (code, name, parent_id, tenant_id) VALUES ($1, $2, $3, $4) RETURNING id"
+    )
+    .bind(&code)
+    .bind(department.name.trim())
+    .bind(department.parent_id)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let department_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating department: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Department code already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating department");
+        }
+    };
+
+    match fetch_department(pool.get_ref(), user.tenant_id, department_id).await {
+        Ok(department) => HttpResponse::Created().json(department),
+        Err(e) => {
+            eprintln!("Error fetching created department: {}", e);
+            HttpResponse::InternalServerError().json("Department created but error fetching details")
+        }
+    }
+}
+
+pub async fn update_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    department: web::Json<UpdateDepartment>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let department_id = id.into_inner();
+
+    if let Some(name) = &department.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json("Name is required");
+        }
+    }
+    if let Some(parent_id) = department.parent_id {
+        if let Err(response) = validate_parent(pool.get_ref(), user.tenant_id, Some(department_id), parent_id).await {
+            return response;
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE departments SET
+            name = COALESCE($1, name),
+            parent_id = COALESCE($2, parent_id),
+            active = COALESCE($3, active),
+            updated_at = NOW()
+         WHERE id = $4 AND tenant_id = $5"
+    )
+    .bind(department.name.as_deref().map(str::trim))
+    .bind(department.parent_id)
+    .bind(department.active)
+    .bind(department_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => return HttpResponse::NotFound().json("Department not found"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error updating department: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating department");
+        }
+    }
+
+    match fetch_department(pool.get_ref(), user.tenant_id, department_id).await {
+        Ok(department) => HttpResponse::Ok().json(department),
+        Err(e) => {
+            eprintln!("Error fetching updated department: {}", e);
+            HttpResponse::InternalServerError().json("Department updated but error fetching details")
+        }
+    }
+}
+
+pub async fn delete_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let department_id = id.into_inner();
+
+    // Departamentos com subordinados, bens, usuários ou histórico são mantidos (use active = false)
+    let usage = sqlx::query(
+        "SELECT
+            (SELECT COUNT(*) FROM departments WHERE parent_id = d.id) as children,
+            (SELECT COUNT(*) FROM patrimonies WHERE department = d.code AND tenant_id = d.tenant_id)
+              + (SELECT COUNT(*) FROM users WHERE department = d.code AND tenant_id = d.tenant_id)
+              + (SELECT COUNT(*) FROM transfers WHERE (from_department = d.code OR to_department = d.code) AND tenant_id = d.tenant_id)
+              + (SELECT COUNT(*) FROM custodians WHERE department = d.code AND tenant_id = d.tenant_id)
+              + (SELECT COUNT(*) FROM inventory_campaigns WHERE department = d.code AND tenant_id = d.tenant_id) as references_count
+         FROM departments d WHERE d.id = $1 AND d.tenant_id = $2"
+    )
+    .bind(department_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match usage {
+        Ok(Some(row)) => {
+            if row.get::<i64, _>("children") > 0 {
+                return HttpResponse::Conflict().json("Department has child departments");
+            }
+            if row.get::<i64, _>("references_count") > 0 {
+                return HttpResponse::Conflict().json("Department is in use; deactivate it instead");
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Department not found"),
+        Err(e) => {
+            eprintln!("Error checking department usage: {}", e);
+            return HttpResponse::InternalServerError().json("Error deleting department");
+        }
+    }
+
+    match sqlx::query("DELETE FROM departments WHERE id = $1")
+        .bind(department_id)
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json("Department deleted successfully"),
+        Err(e) => {
+            eprintln!("Error deleting department: {}", e);
+            HttpResponse::InternalServerError().json("Error deleting department")
+        }
+    }
+}
+
+pub async fn add_department_manager(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    manager: web::Json<AddDepartmentManager>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let department_id = id.into_inner();
+
+    let user_exists = sqlx::query("SELECT 1 FROM users WHERE id = $1 AND tenant_id = $2")
+        .bind(manager.user_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    match user_exists {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::BadRequest().json("User not found"),
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return HttpResponse::InternalServerError().json("Error adding department manager");
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO department_managers (department_id, user_id)
+         SELECT d.id, u.id FROM departments d, users u
+         WHERE d.id = $1 AND u.id = $2 AND d.tenant_id = $3 AND u.tenant_id = $3
+         ON CONFLICT DO NOTHING"
+    )
+    .bind(department_id)
+    .bind(manager.user_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error adding department manager: {}", e);
+        return HttpResponse::InternalServerError().json("Error adding department manager");
+    }
+
+    match fetch_department(pool.get_ref(), user.tenant_id, department_id).await {
+        Ok(department) => HttpResponse::Ok().json(department),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json("Department not found"),
+        Err(e) => {
+            eprintln!("Error fetching department: {}", e);
+            HttpResponse::InternalServerError().json("Manager added but error fetching department")
+        }
+    }
+}
+
+pub async fn remove_department_manager(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let (department_id, user_id) = path.into_inner();
+
+    let result = sqlx::query(
+        "DELETE FROM department_managers m USING departments d
+         WHERE d.id = m.department_id AND m.department_id = $1 AND m.user_id = $2 AND d.tenant_id = $3"
+    )
+    .bind(department_id)
+    .bind(user_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::NotFound().json("User is not a manager of this department")
+        }
+        Ok(_) => HttpResponse::Ok().json("Department manager removed successfully"),
+        Err(e) => {
+            eprintln!("Error removing department manager: {}", e);
+            HttpResponse::InternalServerError().json("Error removing department manager")
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, User};
+use super::department::validate_department;
+use super::permissions;
+
+#[derive(Deserialize)]
+pub struct CreateDepartmentGrant {
+    // Quem recebe o acesso: um usuário ou todos os lotados em um departamento
+    pub user_id: Option<Uuid>,
+    pub grantee_department: Option<String>,
+    pub department_id: Option<Uuid>,    // Sem ele, todos os departamentos da entidade
+}
+
+const GRANT_SELECT: &str =
+    "SELECT g.id, g.user_id, u.username, g.grantee_department, g.department_id,
+            d.code as department_code, d.name as department_name,
+            g.granted_by, gb.username as granted_by_name, g.created_at
+     FROM department_access_grants g
+     LEFT JOIN users u ON u.id = g.user_id
+     LEFT JOIN departments d ON d.id = g.department_id
+     LEFT JOIN users gb ON gb.id = g.granted_by";
+
+fn grant_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "user_id": row.get::<Option<Uuid>, _>("user_id"),
+        "username": row.get::<Option<String>, _>("username"),
+        "grantee_department": row.get::<Option<String>, _>("grantee_department"),
+        "department_id": row.get::<Option<Uuid>, _>("department_id"),
+        "department_code": row.get::<Option<String>, _>("department_code"),
+        "department_name": row.get::<Option<String>, _>("department_name"),
+        "granted_by": row.get::<Option<Uuid>, _>("granted_by"),
+        "granted_by_name": row.get::<Option<String>, _>("granted_by_name"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at")
+    })
+}
+
+/// Quem consulta todos os departamentos da entidade sem precisar de concessão
+pub fn sees_all_departments(user: &User) -> bool {
+    user.can(permissions::READ_ALL_DEPARTMENTS) || user.can(permissions::ALL_DEPARTMENTS)
+}
+
+/// Valor do parâmetro usado em `filter_sql`: nulo quando o usuário vê todos os departamentos
+pub fn restriction(user: &User) -> Option<Uuid> {
+    if sees_all_departments(user) {
+        None
+    } else {
+        Some(user.id)
+    }
+}
+
+/// Códigos dos departamentos visíveis ao usuário do parâmetro: lotação, departamentos que gerencia
+/// e concessões (dele ou do seu departamento), sempre com as subunidades
+pub fn visible_sql(param: usize) -> String {
+    format!(
+        "(WITH RECURSIVE visible AS (
+            SELECT d.id, d.code FROM departments d JOIN users u ON u.id = ${}::uuid AND u.tenant_id = d.tenant_id
+            WHERE d.code = u.department
+               OR EXISTS (SELECT 1 FROM department_managers m WHERE m.department_id = d.id AND m.user_id = u.id)
+               OR EXISTS (
+                    SELECT 1 FROM department_access_grants g
+                    WHERE g.tenant_id = u.tenant_id
+                      AND (g.user_id = u.id OR g.grantee_department = u.department)
+                      AND (g.department_id IS NULL OR g.department_id = d.id)
+               )
+            UNION
+            SELECT d.id, d.code FROM departments d JOIN visible v ON d.parent_id = v.id
+        ) SELECT code FROM visible)",
+        param
+    )
+}
+
+/// Condição sobre a coluna de departamento; sem restrição (parâmetro nulo) tudo passa
+pub fn filter_sql(column: &str, param: usize) -> String {
+    format!("(${}::uuid IS NULL OR {} IN {})", param, column, visible_sql(param))
+}
+
+/// Recusa (403) departamento fora dos visíveis ao usuário, ex.: ao cadastrar um bem nele
+pub async fn ensure_department_visible(pool: &PgPool, user: &User, department: &str) -> Result<(), HttpResponse> {
+    let Some(user_id) = restriction(user) else {
+        return Ok(());
+    };
+
+    match sqlx::query(&format!("SELECT $1::varchar IN {} as visible", visible_sql(2)))
+        .bind(department)
+        .bind(user_id)
+        .map(|row: PgRow| row.get::<bool, _>("visible"))
+        .fetch_one(pool)
+        .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Forbidden().json("Department is outside your departments")),
+        Err(e) => {
+            eprintln!("Error checking department visibility: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error checking department visibility"))
+        }
+    }
+}
+
+pub async fn get_department_grants(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(&format!("{} WHERE g.tenant_id = $1 ORDER BY g.created_at", GRANT_SELECT))
+        .bind(user.tenant_id)
+        .map(|row: PgRow| grant_json(&row))
+        .fetch_all(pool.get_ref())
+        .await
+    {
+        Ok(grants) => HttpResponse::Ok().json(grants),
+        Err(e) => {
+            eprintln!("Error fetching department grants: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching department grants")
+        }
+    }
+}
+
+pub async fn create_department_grant(
+    pool: web::Data<PgPool>,
+    grant: web::Json<CreateDepartmentGrant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let grantee_department = grant.grantee_department.as_deref().map(str::trim);
+    match (grant.user_id, grantee_department) {
+        (Some(grantee_id), None) => {
+            match sqlx::query("SELECT 1 FROM users WHERE id = $1 AND tenant_id = $2")
+                .bind(grantee_id)
+                .bind(user.tenant_id)
+                .fetch_optional(pool.get_ref())
+                .await
+            {
+                Ok(Some(_)) => {}
+                Ok(None) => return HttpResponse::BadRequest().json("User not found"),
+                Err(e) => {
+                    eprintln!("Error fetching user: {}", e);
+                    return HttpResponse::InternalServerError().json("Error creating department grant");
+                }
+            }
+        }
+        (None, Some(code)) => {
+            if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, code).await {
+                return response;
+            }
+        }
+        _ => return HttpResponse::BadRequest().json("Inform either user_id or grantee_department"),
+    }
+
+    if let Some(department_id) = grant.department_id {
+        match sqlx::query("SELECT 1 FROM departments WHERE id = $1 AND tenant_id = $2")
+            .bind(department_id)
+            .bind(user.tenant_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return HttpResponse::BadRequest().json("Department not found"),
+            Err(e) => {
+                eprintln!("Error fetching department: {}", e);
+                return HttpResponse::InternalServerError().json("Error creating department grant");
+            }
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO department_access_grants (tenant_id, user_id, grantee_department, department_id, granted_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id"
+    )
+    .bind(user.tenant_id)
+    .bind(grant.user_id)
+    .bind(grantee_department)
+    .bind(grant.department_id)
+    .bind(user.id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let grant_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating department grant: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Grant already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating department grant");
+        }
+    };
+
+    println!("🔓 Acesso a departamentos concedido por {}", user.username);
+
+    match sqlx::query(&format!("{} WHERE g.id = $1", GRANT_SELECT))
+        .bind(grant_id)
+        .map(|row: PgRow| grant_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(grant) => HttpResponse::Created().json(grant),
+        Err(e) => {
+            eprintln!("Error fetching department grant: {}", e);
+            HttpResponse::InternalServerError().json("Grant created but error fetching details")
+        }
+    }
+}
+
+pub async fn delete_department_grant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query("DELETE FROM department_access_grants WHERE id = $1 AND tenant_id = $2")
+        .bind(id.into_inner())
+        .bind(user.tenant_id)
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json("Grant not found"),
+        Ok(_) => HttpResponse::Ok().json("Grant revoked successfully"),
+        Err(e) => {
+            eprintln!("Error deleting department grant: {}", e);
+            HttpResponse::InternalServerError().json("Error revoking department grant")
+        }
+    }
+}
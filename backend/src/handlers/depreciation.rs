@@ -0,0 +1,569 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use sqlx::types::BigDecimal;
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, ensure_visible};
+use super::department_grant;
+
+// Métodos de depreciação suportados
+pub const STRAIGHT_LINE: &str = "straight_line";
+pub const DECLINING_BALANCE: &str = "declining_balance";
+
+// Vida útil padrão quando o bem não define a sua (10 anos)
+pub const DEFAULT_USEFUL_LIFE_MONTHS: i32 = 120;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DepreciationPolicy {
+    pub method: String,
+    pub useful_life_months: i32,
+    pub residual_value: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MonthlyDepreciation {
+    pub month: i32,
+    pub period: NaiveDate,
+    pub depreciation_amount: f64,
+    pub accumulated_depreciation: f64,
+    pub book_value: f64,
+}
+
+#[derive(Deserialize)]
+pub struct RunDepreciationRequest {
+    pub period: Option<String>, // formato YYYY-MM, padrão: mês anterior
+}
+
+#[derive(Deserialize)]
+pub struct DepreciationEntriesQuery {
+    pub period: Option<String>,
+    pub department: Option<String>,
+    pub patrimony_id: Option<Uuid>,
+}
+
+impl DepreciationPolicy {
+    // Completa os campos ausentes do bem com os valores padrão
+    pub fn resolve(
+        method: Option<String>,
+        useful_life_months: Option<i32>,
+        residual_value: Option<f64>,
+    ) -> Self {
+        DepreciationPolicy {
+            method: method.unwrap_or_else(|| STRAIGHT_LINE.to_string()),
+            useful_life_months: useful_life_months
+                .filter(|months| *months > 0)
+                .unwrap_or(DEFAULT_USEFUL_LIFE_MONTHS),
+            residual_value: residual_value.unwrap_or(0.0).max(0.0),
+        }
+    }
+
+    pub fn from_row(row: &PgRow) -> Self {
+        Self::resolve(
+            row.try_get::<Option<String>, _>("depreciation_method").ok().flatten(),
+            row.try_get::<Option<i32>, _>("useful_life_months").ok().flatten(),
+            optional_decimal(row, "residual_value"),
+        )
+    }
+}
+
+pub fn is_valid_method(method: &str) -> bool {
+    method == STRAIGHT_LINE || method == DECLINING_BALANCE
+}
+
+// Validação dos campos de depreciação enviados na criação/atualização do bem
+pub fn validate_policy_input(
+    method: Option<&str>,
+    useful_life_months: Option<i32>,
+    residual_value: Option<f64>,
+    value: f64,
+) -> Result<(), &'static str> {
+    if let Some(method) = method {
+        if !is_valid_method(method) {
+            return Err("Depreciation method must be 'straight_line' or 'declining_balance'");
+        }
+    }
+    if let Some(months) = useful_life_months {
+        if months <= 0 {
+            return Err("Useful life must be greater than zero months");
+        }
+    }
+    if let Some(residual) = residual_value {
+        if residual < 0.0 || residual > value {
+            return Err("Residual value must be between zero and the acquisition value");
+        }
+    }
+    Ok(())
+}
+
+pub fn optional_decimal(row: &PgRow, column: &str) -> Option<f64> {
+    match row.try_get::<Option<BigDecimal>, _>(column) {
+        Ok(Some(bd)) => bd.to_string().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+pub fn round_cents(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+// Os cálculos são feitos em centavos inteiros, como nas colunas NUMERIC; f64 só na entrada e na saída
+fn to_cents(value: f64) -> i64 {
+    (value * 100.0).round() as i64
+}
+
+fn from_cents(cents: i64) -> f64 {
+    cents as f64 / 100.0
+}
+
+// Valor já em centavos exatos, gravado sem passar por ponto flutuante no banco
+fn to_decimal(value: f64) -> BigDecimal {
+    BigDecimal::new(to_cents(value).into(), 2)
+}
+
+// Divisão arredondando o meio centavo para cima (operandos não negativos)
+fn div_round(numerator: i64, denominator: i64) -> i64 {
+    (2 * numerator + denominator) / (2 * denominator)
+}
+
+// Meses completos entre a aquisição e a data de referência
+fn months_between(start: NaiveDate, end: NaiveDate) -> i32 {
+    if end <= start {
+        return 0;
+    }
+    let mut months = (end.year() - start.year()) * 12 + end.month() as i32 - start.month() as i32;
+    if end.day() < start.day() {
+        months -= 1;
+    }
+    months.max(0)
+}
+
+fn first_day_of_month(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+// Depreciação acumulada, em centavos, ao fim de cada um dos primeiros `months` meses de uso
+fn accumulated_by_month(value: i64, policy: &DepreciationPolicy, months: i32) -> Vec<i64> {
+    let value = value.max(0);
+    let residual = to_cents(policy.residual_value).clamp(0, value);
+    let depreciable = value - residual;
+    let life = policy.useful_life_months.max(1) as i64;
+    let months = (months.max(0) as i64).min(life);
+
+    let mut accumulated = 0;
+    let mut series = Vec::with_capacity(months as usize);
+    for month in 1..=months {
+        accumulated = match policy.method.as_str() {
+            DECLINING_BALANCE => {
+                // Saldo decrescente duplo; passa para a linear quando ela dá a cota maior,
+                // e assim chega ao valor residual exatamente no fim da vida útil
+                let remaining = depreciable - accumulated;
+                let declining = div_round(2 * (value - accumulated), life);
+                let straight = div_round(remaining, life - month + 1);
+                accumulated + declining.max(straight).min(remaining)
+            }
+            _ => div_round(depreciable * month, life),
+        };
+        series.push(accumulated);
+    }
+    series
+}
+
+// Depreciação acumulada e valor contábil na data informada
+pub fn snapshot(
+    value: f64,
+    acquisition_date: NaiveDate,
+    policy: &DepreciationPolicy,
+    as_of: NaiveDate,
+) -> (f64, f64) {
+    let value = to_cents(value);
+    let months = months_between(acquisition_date, as_of);
+    let accumulated = accumulated_by_month(value, policy, months).last().copied().unwrap_or(0);
+    (from_cents(accumulated), from_cents(value - accumulated))
+}
+
+// Lançamentos mensais desde a aquisição até o período informado (inclusive)
+pub fn schedule(
+    value: f64,
+    acquisition_date: NaiveDate,
+    policy: &DepreciationPolicy,
+    up_to_period: NaiveDate,
+) -> Vec<MonthlyDepreciation> {
+    let value = to_cents(value);
+    let first_period = first_day_of_month(acquisition_date);
+    let months = months_between(first_period, first_day_of_month(up_to_period));
+
+    let mut entries = Vec::new();
+    let mut previous = 0;
+    for (month, accumulated) in (1..).zip(accumulated_by_month(value, policy, months)) {
+        let period = match first_period.checked_add_months(Months::new(month as u32)) {
+            Some(period) => period,
+            None => break,
+        };
+
+        entries.push(MonthlyDepreciation {
+            month,
+            period,
+            depreciation_amount: from_cents(accumulated - previous),
+            accumulated_depreciation: from_cents(accumulated),
+            book_value: from_cents(value - accumulated),
+        });
+        previous = accumulated;
+    }
+
+    entries
+}
+
+fn parse_period(period: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", period.trim()), "%Y-%m-%d").ok()
+}
+
+pub async fn get_patrimony_depreciation(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let patrimony = sqlx::query(
+        "SELECT id, plate, name, value, acquisition_date, depreciation_method, useful_life_months, residual_value
+         FROM patrimonies WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(patrimony_id)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    let row = match patrimony {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony for depreciation: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching depreciation");
+        }
+    };
+
+    let value = optional_decimal(&row, "value").unwrap_or(0.0);
+    let acquisition_date: NaiveDate = row.get("acquisition_date");
+    let policy = DepreciationPolicy::from_row(&row);
+    let today = Utc::now().date_naive();
+    let (accumulated_depreciation, book_value) = snapshot(value, acquisition_date, &policy, today);
+
+    let entries = sqlx::query(
+        "SELECT period, method, depreciation_amount, accumulated_depreciation, book_value, created_at
+         FROM depreciation_entries WHERE patrimony_id = $1 ORDER BY period"
+    )
+    .bind(patrimony_id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "period": row.get::<NaiveDate, _>("period"),
+            "method": row.get::<String, _>("method"),
+            "depreciation_amount": optional_decimal(&row, "depreciation_amount").unwrap_or(0.0),
+            "accumulated_depreciation": optional_decimal(&row, "accumulated_depreciation").unwrap_or(0.0),
+            "book_value": optional_decimal(&row, "book_value").unwrap_or(0.0),
+            "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match entries {
+        Ok(entries) => HttpResponse::Ok().json(serde_json::json!({
+            "patrimony_id": patrimony_id,
+            "plate": row.get::<String, _>("plate"),
+            "name": row.get::<String, _>("name"),
+            "acquisition_value": value,
+            "acquisition_date": acquisition_date,
+            "policy": policy,
+            "accumulated_depreciation": accumulated_depreciation,
+            "book_value": book_value,
+            "schedule": schedule(value, acquisition_date, &policy, today),
+            "entries": entries
+        })),
+        Err(e) => {
+            eprintln!("Error fetching depreciation entries: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching depreciation")
+        }
+    }
+}
+
+// Gera (e persiste) os lançamentos mensais dos bens da entidade até o período informado
+pub async fn run_depreciation(
+    pool: web::Data<PgPool>,
+    body: web::Json<RunDepreciationRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let period = match &body.period {
+        Some(period) => match parse_period(period) {
+            Some(date) => date,
+            None => return HttpResponse::BadRequest().json("Invalid period, expected YYYY-MM"),
+        },
+        None => {
+            let current_month = first_day_of_month(Utc::now().date_naive());
+            current_month.checked_sub_months(Months::new(1)).unwrap_or(current_month)
+        }
+    };
+
+    println!("📉 Calculando depreciação até {}", period.format("%Y-%m"));
+
+    let patrimonies = sqlx::query(
+        "SELECT id, value, acquisition_date, depreciation_method, useful_life_months, residual_value
+         FROM patrimonies
+         WHERE tenant_id = $1
+           AND status <> 'written_off' AND deleted_at IS NULL AND acquisition_date IS NOT NULL AND value IS NOT NULL"
+    )
+    .bind(user.tenant_id)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let patrimonies = match patrimonies {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching patrimonies for depreciation: {}", e);
+            return HttpResponse::InternalServerError().json("Error running depreciation");
+        }
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error running depreciation");
+        }
+    };
+
+    let mut created = 0u64;
+    for row in &patrimonies {
+        let patrimony_id: Uuid = row.get("id");
+        let value = optional_decimal(row, "value").unwrap_or(0.0);
+        let acquisition_date: NaiveDate = row.get("acquisition_date");
+        let policy = DepreciationPolicy::from_row(row);
+
+        for entry in schedule(value, acquisition_date, &policy, period) {
+            let result = sqlx::query(
+                "INSERT INTO depreciation_entries (patrimony_id, period, method, depreciation_amount, accumulated_depreciation, book_value)
+                 VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (patrimony_id, period) DO NOTHING"
+            )
+            .bind(patrimony_id)
+            .bind(entry.period)
+            .bind(&policy.method)
+            .bind(to_decimal(entry.depreciation_amount))
+            .bind(to_decimal(entry.accumulated_depreciation))
+            .bind(to_decimal(entry.book_value))
+            .execute(&mut *transaction)
+            .await;
+
+            match result {
+                Ok(result) => created += result.rows_affected(),
+                Err(e) => {
+                    eprintln!("Error saving depreciation entry: {}", e);
+                    let _ = transaction.rollback().await;
+                    return HttpResponse::InternalServerError().json("Error running depreciation");
+                }
+            }
+        }
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error running depreciation");
+    }
+
+    println!("✅ Depreciação calculada: {} lançamento(s) novo(s)", created);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "period": period.format("%Y-%m").to_string(),
+        "patrimonies": patrimonies.len(),
+        "entries_created": created
+    }))
+}
+
+pub async fn get_depreciation_entries(
+    pool: web::Data<PgPool>,
+    query: web::Query<DepreciationEntriesQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let period = match &query.period {
+        Some(period) => match parse_period(period) {
+            Some(date) => Some(date),
+            None => return HttpResponse::BadRequest().json("Invalid period, expected YYYY-MM"),
+        },
+        None => None,
+    };
+
+    let result = sqlx::query(&format!(
+        "SELECT d.id, d.patrimony_id, p.plate, p.name as patrimony_name, p.department, d.period, d.method,
+                d.depreciation_amount, d.accumulated_depreciation, d.book_value, d.created_at
+         FROM depreciation_entries d
+         JOIN patrimonies p ON d.patrimony_id = p.id
+         WHERE p.tenant_id = $4
+           AND ($1::date IS NULL OR d.period = $1)
+           AND ($2::varchar IS NULL OR p.department = $2)
+           AND ($3::uuid IS NULL OR d.patrimony_id = $3)
+           AND {}
+         ORDER BY d.period, p.plate",
+        department_grant::filter_sql("p.department", 5)
+    ))
+    .bind(period)
+    .bind(&query.department)
+    .bind(query.patrimony_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+            "plate": row.get::<String, _>("plate"),
+            "patrimony_name": row.get::<String, _>("patrimony_name"),
+            "department": row.get::<String, _>("department"),
+            "period": row.get::<NaiveDate, _>("period"),
+            "method": row.get::<String, _>("method"),
+            "depreciation_amount": optional_decimal(&row, "depreciation_amount").unwrap_or(0.0),
+            "accumulated_depreciation": optional_decimal(&row, "accumulated_depreciation").unwrap_or(0.0),
+            "book_value": optional_decimal(&row, "book_value").unwrap_or(0.0),
+            "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(entries) => {
+            let total: i64 = entries
+                .iter()
+                .filter_map(|entry| entry["depreciation_amount"].as_f64())
+                .map(to_cents)
+                .sum();
+            HttpResponse::Ok().json(serde_json::json!({
+                "count": entries.len(),
+                "total_depreciation": from_cents(total),
+                "entries": entries
+            }))
+        }
+        Err(e) => {
+            eprintln!("Error fetching depreciation entries: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching depreciation entries")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn policy(method: &str, useful_life_months: i32, residual_value: f64) -> DepreciationPolicy {
+        DepreciationPolicy::resolve(Some(method.to_string()), Some(useful_life_months), Some(residual_value))
+    }
+
+    fn amounts(entries: &[MonthlyDepreciation]) -> Vec<f64> {
+        entries.iter().map(|entry| entry.depreciation_amount).collect()
+    }
+
+    #[test]
+    fn months_between_counts_complete_months() {
+        assert_eq!(months_between(date(2024, 1, 15), date(2024, 1, 31)), 0);
+        assert_eq!(months_between(date(2024, 1, 15), date(2024, 2, 14)), 0);
+        assert_eq!(months_between(date(2024, 1, 15), date(2024, 2, 15)), 1);
+        assert_eq!(months_between(date(2024, 1, 31), date(2025, 3, 1)), 13);
+        assert_eq!(months_between(date(2024, 5, 1), date(2024, 1, 1)), 0);
+    }
+
+    #[test]
+    fn straight_line_spreads_cents_over_the_useful_life() {
+        let entries = schedule(1000.0, date(2024, 1, 1), &policy(STRAIGHT_LINE, 3, 0.0), date(2030, 1, 1));
+
+        assert_eq!(amounts(&entries), vec![333.33, 333.34, 333.33]);
+        assert_eq!(entries[0].period, date(2024, 2, 1));
+        assert_eq!(entries[2].accumulated_depreciation, 1000.0);
+        assert_eq!(entries[2].book_value, 0.0);
+    }
+
+    #[test]
+    fn straight_line_stops_at_the_residual_value() {
+        let policy = policy(STRAIGHT_LINE, 10, 100.0);
+        let entries = schedule(1000.0, date(2024, 1, 10), &policy, date(2030, 1, 1));
+
+        assert_eq!(entries.len(), 10);
+        assert!(entries.iter().all(|entry| entry.depreciation_amount == 90.0));
+        assert_eq!(entries[9].book_value, 100.0);
+        assert_eq!(snapshot(1000.0, date(2024, 1, 10), &policy, date(2029, 1, 1)), (900.0, 100.0));
+        assert_eq!(snapshot(1000.0, date(2024, 1, 10), &policy, date(2024, 4, 10)), (270.0, 730.0));
+    }
+
+    #[test]
+    fn declining_balance_switches_to_straight_line() {
+        let entries = schedule(1000.0, date(2024, 1, 1), &policy(DECLINING_BALANCE, 10, 0.0), date(2030, 1, 1));
+
+        assert_eq!(
+            amounts(&entries),
+            vec![200.0, 160.0, 128.0, 102.4, 81.92, 65.54, 65.54, 65.53, 65.54, 65.53]
+        );
+        assert_eq!(entries[9].accumulated_depreciation, 1000.0);
+        assert_eq!(entries[9].book_value, 0.0);
+    }
+
+    #[test]
+    fn declining_balance_ends_exactly_at_the_residual_value() {
+        let policy = policy(DECLINING_BALANCE, 60, 150.0);
+        let entries = schedule(2500.0, date(2023, 6, 20), &policy, date(2030, 1, 1));
+
+        assert_eq!(entries.len(), 60);
+        assert_eq!(entries[59].book_value, 150.0);
+        // Sem salto no último mês: a cota final é a mesma da linear dos meses anteriores
+        assert!((entries[59].depreciation_amount - entries[58].depreciation_amount).abs() <= 0.01);
+        assert!(entries.windows(2).all(|pair| pair[1].depreciation_amount <= pair[0].depreciation_amount + 0.01));
+        assert_eq!(snapshot(2500.0, date(2023, 6, 20), &policy, date(2030, 1, 1)), (2350.0, 150.0));
+    }
+
+    #[test]
+    fn partial_first_month_is_not_depreciated() {
+        let policy = policy(STRAIGHT_LINE, 12, 0.0);
+
+        assert!(schedule(1200.0, date(2024, 1, 15), &policy, date(2024, 1, 1)).is_empty());
+        let entries = schedule(1200.0, date(2024, 1, 15), &policy, date(2024, 2, 1));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].period, date(2024, 2, 1));
+        assert_eq!(entries[0].depreciation_amount, 100.0);
+
+        assert_eq!(snapshot(1200.0, date(2024, 1, 15), &policy, date(2024, 2, 14)), (0.0, 1200.0));
+        assert_eq!(snapshot(1200.0, date(2024, 1, 15), &policy, date(2024, 2, 15)), (100.0, 1100.0));
+    }
+
+    #[test]
+    fn residual_above_the_value_means_no_depreciation() {
+        let entries = schedule(500.0, date(2024, 1, 1), &policy(DECLINING_BALANCE, 12, 800.0), date(2030, 1, 1));
+
+        assert!(entries.iter().all(|entry| entry.depreciation_amount == 0.0 && entry.book_value == 500.0));
+    }
+}
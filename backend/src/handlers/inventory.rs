@@ -0,0 +1,715 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use sqlx::types::Json;
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::patrimony::auth_middleware;
+use super::department::validate_department;
+use super::department_grant;
+
+#[derive(Deserialize)]
+pub struct CreateCampaign {
+    pub name: String,
+    pub department: String,
+    pub year: Option<i32>,
+}
+
+#[derive(Deserialize)]
+pub struct CampaignQuery {
+    pub department: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSession {
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ScanRequest {
+    pub plates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReportItem {
+    pub patrimony_id: Uuid,
+    pub plate: String,
+    pub name: String,
+    pub department: String,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReportTotals {
+    pub found: usize,
+    pub missing: usize,
+    pub wrong_department: usize,
+    pub unknown: usize,
+    #[serde(default)]                   // Relatórios gravados antes da contagem de baixados
+    pub written_off: usize,
+}
+
+// Relatório de divergências do inventário físico
+#[derive(Serialize, Deserialize)]
+pub struct InventoryReport {
+    pub campaign_id: Uuid,
+    pub department: String,
+    pub generated_at: chrono::DateTime<Utc>,
+    pub totals: ReportTotals,
+    pub found: Vec<ReportItem>,
+    pub missing: Vec<ReportItem>,
+    pub wrong_department: Vec<ReportItem>,
+    pub unknown_plates: Vec<String>,
+    #[serde(default)]
+    pub written_off: Vec<ReportItem>,   // Lidos, mas já baixados: o bem não deveria estar em uso
+}
+
+const CAMPAIGN_SELECT: &str =
+    "SELECT c.id, c.name, c.department, c.year, c.status, c.opened_by, c.opened_at, c.closed_by, c.closed_at,
+            (SELECT COUNT(*) FROM inventory_scans s WHERE s.campaign_id = c.id) as scan_count,
+            (SELECT COUNT(*) FROM inventory_sessions se WHERE se.campaign_id = c.id) as session_count
+     FROM inventory_campaigns c";
+
+fn campaign_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "name": row.get::<String, _>("name"),
+        "department": row.get::<String, _>("department"),
+        "year": row.get::<i32, _>("year"),
+        "status": row.get::<String, _>("status"),
+        "opened_by": row.get::<Option<Uuid>, _>("opened_by"),
+        "opened_at": row.get::<chrono::DateTime<Utc>, _>("opened_at"),
+        "closed_by": row.get::<Option<Uuid>, _>("closed_by"),
+        "closed_at": row.get::<Option<chrono::DateTime<Utc>>, _>("closed_at"),
+        "scan_count": row.get::<i64, _>("scan_count"),
+        "session_count": row.get::<i64, _>("session_count")
+    })
+}
+
+fn report_item(row: &PgRow) -> ReportItem {
+    ReportItem {
+        patrimony_id: row.get("patrimony_id"),
+        plate: row.get("plate"),
+        name: row.get("name"),
+        department: row.get("department"),
+        status: row.get("status"),
+    }
+}
+
+// Confronta as placas lidas com os bens cadastrados no departamento da campanha;
+// placa de bem na lixeira aparece como desconhecida
+async fn build_report(
+    transaction: &mut Transaction<'_, Postgres>,
+    tenant_id: Uuid,
+    campaign_id: Uuid,
+    department: &str,
+) -> Result<InventoryReport, sqlx::Error> {
+    let scanned = sqlx::query(
+        "SELECT s.plate, p.id as patrimony_id, p.name, p.department, p.status
+         FROM inventory_scans s
+         LEFT JOIN patrimonies p ON p.plate = s.plate AND p.tenant_id = $2 AND p.deleted_at IS NULL
+         WHERE s.campaign_id = $1
+         ORDER BY s.plate"
+    )
+    .bind(campaign_id)
+    .bind(tenant_id)
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    let mut found = Vec::new();
+    let mut wrong_department = Vec::new();
+    let mut unknown_plates = Vec::new();
+    let mut written_off = Vec::new();
+
+    for row in &scanned {
+        match row.get::<Option<Uuid>, _>("patrimony_id") {
+            None => unknown_plates.push(row.get::<String, _>("plate")),
+            Some(_) => {
+                let item = report_item(row);
+                if item.status == "written_off" {
+                    written_off.push(item);
+                } else if item.department == department {
+                    found.push(item);
+                } else {
+                    wrong_department.push(item);
+                }
+            }
+        }
+    }
+
+    let missing = sqlx::query(
+        "SELECT p.id as patrimony_id, p.plate, p.name, p.department, p.status
+         FROM patrimonies p
+         WHERE p.department = $1
+           AND p.tenant_id = $3
+           AND p.status <> 'written_off'
+           AND p.deleted_at IS NULL
+           AND NOT EXISTS (SELECT 1 FROM inventory_scans s WHERE s.campaign_id = $2 AND s.plate = p.plate)
+         ORDER BY p.plate"
+    )
+    .bind(department)
+    .bind(campaign_id)
+    .bind(tenant_id)
+    .map(|row: PgRow| report_item(&row))
+    .fetch_all(&mut **transaction)
+    .await?;
+
+    Ok(InventoryReport {
+        campaign_id,
+        department: department.to_string(),
+        generated_at: Utc::now(),
+        totals: ReportTotals {
+            found: found.len(),
+            missing: missing.len(),
+            wrong_department: wrong_department.len(),
+            unknown: unknown_plates.len(),
+            written_off: written_off.len(),
+        },
+        found,
+        missing,
+        wrong_department,
+        unknown_plates,
+        written_off,
+    })
+}
+
+pub async fn create_campaign(
+    pool: web::Data<PgPool>,
+    campaign: web::Json<CreateCampaign>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if campaign.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if campaign.department.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Department is required");
+    }
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, campaign.department.trim()).await {
+        return response;
+    }
+
+    let year = campaign.year.unwrap_or_else(|| Utc::now().year());
+
+    let result = sqlx::query(
+        "INSERT INTO inventory_campaigns (name, department, year, opened_by, tenant_id)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id"
+    )
+    .bind(campaign.name.trim())
+    .bind(campaign.department.trim())
+    .bind(year)
+    .bind(user.id)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let campaign_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating inventory campaign: {}", e);
+            // Índice único parcial: apenas uma campanha aberta por departamento
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("There is already an open campaign for this department");
+            }
+            return HttpResponse::InternalServerError().json("Error creating inventory campaign");
+        }
+    };
+
+    println!("📋 Campanha de inventário aberta: {} ({})", campaign.name, campaign.department);
+
+    match sqlx::query(&format!("{} WHERE c.id = $1", CAMPAIGN_SELECT))
+        .bind(campaign_id)
+        .map(|row: PgRow| campaign_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(campaign) => HttpResponse::Created().json(campaign),
+        Err(e) => {
+            eprintln!("Error fetching created campaign: {}", e);
+            HttpResponse::InternalServerError().json("Campaign created but error fetching details")
+        }
+    }
+}
+
+pub async fn get_campaigns(
+    pool: web::Data<PgPool>,
+    query: web::Query<CampaignQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE c.tenant_id = $3
+           AND ($1::varchar IS NULL OR c.department = $1)
+           AND ($2::varchar IS NULL OR c.status = $2)
+           AND {}
+         ORDER BY c.opened_at DESC",
+        CAMPAIGN_SELECT,
+        department_grant::filter_sql("c.department", 4)
+    ))
+    .bind(&query.department)
+    .bind(&query.status)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| campaign_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(campaigns) => HttpResponse::Ok().json(campaigns),
+        Err(e) => {
+            eprintln!("Error fetching inventory campaigns: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching inventory campaigns")
+        }
+    }
+}
+
+pub async fn get_campaign(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let campaign_id = id.into_inner();
+
+    let campaign = match sqlx::query(&format!(
+        "{} WHERE c.id = $1 AND c.tenant_id = $2 AND {}",
+        CAMPAIGN_SELECT,
+        department_grant::filter_sql("c.department", 3)
+    ))
+        .bind(campaign_id)
+        .bind(user.tenant_id)
+        .bind(department_grant::restriction(&user))
+        .map(|row: PgRow| campaign_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(campaign) => campaign,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().json("Campaign not found"),
+        Err(e) => {
+            eprintln!("Error fetching inventory campaign: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching inventory campaign");
+        }
+    };
+
+    let sessions = sqlx::query(
+        "SELECT se.id, se.description, se.started_by, u.username as started_by_name, se.started_at, se.closed_at,
+                (SELECT COUNT(*) FROM inventory_scans s WHERE s.session_id = se.id) as scan_count
+         FROM inventory_sessions se
+         LEFT JOIN users u ON se.started_by = u.id
+         WHERE se.campaign_id = $1
+         ORDER BY se.started_at"
+    )
+    .bind(campaign_id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "description": row.get::<Option<String>, _>("description"),
+            "started_by": row.get::<Option<Uuid>, _>("started_by"),
+            "started_by_name": row.get::<Option<String>, _>("started_by_name"),
+            "started_at": row.get::<chrono::DateTime<Utc>, _>("started_at"),
+            "closed_at": row.get::<Option<chrono::DateTime<Utc>>, _>("closed_at"),
+            "scan_count": row.get::<i64, _>("scan_count")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match sessions {
+        Ok(sessions) => HttpResponse::Ok().json(serde_json::json!({
+            "campaign": campaign,
+            "sessions": sessions
+        })),
+        Err(e) => {
+            eprintln!("Error fetching inventory sessions: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching inventory campaign")
+        }
+    }
+}
+
+pub async fn create_session(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    session: web::Json<CreateSession>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let campaign_id = id.into_inner();
+
+    match sqlx::query("SELECT status FROM inventory_campaigns WHERE id = $1 AND tenant_id = $2")
+        .bind(campaign_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(row)) if row.get::<String, _>("status") == "open" => {}
+        Ok(Some(_)) => return HttpResponse::Conflict().json("Campaign is closed"),
+        Ok(None) => return HttpResponse::NotFound().json("Campaign not found"),
+        Err(e) => {
+            eprintln!("Error fetching inventory campaign: {}", e);
+            return HttpResponse::InternalServerError().json("Error creating inventory session");
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO inventory_sessions (campaign_id, description, started_by)
+         VALUES ($1, $2, $3)
+         RETURNING id, campaign_id, description, started_by, started_at"
+    )
+    .bind(campaign_id)
+    .bind(&session.description)
+    .bind(user.id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "campaign_id": row.get::<Uuid, _>("campaign_id"),
+            "description": row.get::<Option<String>, _>("description"),
+            "started_by": row.get::<Option<Uuid>, _>("started_by"),
+            "started_at": row.get::<chrono::DateTime<Utc>, _>("started_at")
+        })
+    })
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(session) => HttpResponse::Created().json(session),
+        Err(e) => {
+            eprintln!("Error creating inventory session: {}", e);
+            HttpResponse::InternalServerError().json("Error creating inventory session")
+        }
+    }
+}
+
+pub async fn close_session(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(
+        "UPDATE inventory_sessions SET closed_at = NOW()
+         WHERE id = $1 AND closed_at IS NULL
+           AND campaign_id IN (SELECT id FROM inventory_campaigns WHERE tenant_id = $2)"
+    )
+    .bind(id.into_inner())
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                HttpResponse::Ok().json("Session closed successfully")
+            } else {
+                HttpResponse::NotFound().json("Open session not found")
+            }
+        }
+        Err(e) => {
+            eprintln!("Error closing inventory session: {}", e);
+            HttpResponse::InternalServerError().json("Error closing inventory session")
+        }
+    }
+}
+
+// Registra as placas lidas em uma sessão de leitura
+pub async fn post_scans(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    scans: web::Json<ScanRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let session_id = id.into_inner();
+
+    let plates: Vec<String> = scans
+        .plates
+        .iter()
+        .map(|plate| plate.trim().to_string())
+        .filter(|plate| !plate.is_empty())
+        .collect();
+
+    if plates.is_empty() {
+        return HttpResponse::BadRequest().json("No plates provided");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error registering scans");
+        }
+    };
+
+    // Travas até o commit: a sessão não fecha no meio das leituras e a campanha
+    // só é encerrada (FOR UPDATE em close_campaign) depois que elas entram no relatório
+    let session = sqlx::query(
+        "SELECT se.campaign_id, se.closed_at, c.status as campaign_status
+         FROM inventory_sessions se
+         JOIN inventory_campaigns c ON se.campaign_id = c.id
+         WHERE se.id = $1 AND c.tenant_id = $2
+         FOR UPDATE OF se FOR SHARE OF c"
+    )
+    .bind(session_id)
+    .bind(user.tenant_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let campaign_id = match session {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("campaign_status") != "open" {
+                return HttpResponse::Conflict().json("Campaign is closed");
+            }
+            if row.get::<Option<chrono::DateTime<Utc>>, _>("closed_at").is_some() {
+                return HttpResponse::Conflict().json("Session is closed");
+            }
+            row.get::<Uuid, _>("campaign_id")
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Session not found"),
+        Err(e) => {
+            eprintln!("Error fetching inventory session: {}", e);
+            return HttpResponse::InternalServerError().json("Error registering scans");
+        }
+    };
+
+    let mut registered = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for plate in &plates {
+        // A placa é única dentro da entidade, então o vínculo com o bem é direto; bens na lixeira ficam sem vínculo
+        let result = sqlx::query(
+            "INSERT INTO inventory_scans (campaign_id, session_id, plate, patrimony_id, scanned_by)
+             VALUES ($1, $2, $3, (SELECT id FROM patrimonies WHERE plate = $3 AND tenant_id = $5 AND deleted_at IS NULL), $4)
+             ON CONFLICT (campaign_id, plate) DO NOTHING"
+        )
+        .bind(campaign_id)
+        .bind(session_id)
+        .bind(plate)
+        .bind(user.id)
+        .bind(user.tenant_id)
+        .execute(&mut *transaction)
+        .await;
+
+        match result {
+            Ok(result) if result.rows_affected() > 0 => registered.push(plate.clone()),
+            Ok(_) => duplicates.push(plate.clone()),
+            Err(e) => {
+                eprintln!("Error registering scan {}: {}", plate, e);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json("Error registering scans");
+            }
+        }
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error registering scans");
+    }
+
+    println!("📷 {} placa(s) registrada(s) na sessão {}", registered.len(), session_id);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "session_id": session_id,
+        "registered": registered,
+        "already_scanned": duplicates
+    }))
+}
+
+// Encerra a campanha e grava o relatório de divergências
+pub async fn close_campaign(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let campaign_id = id.into_inner();
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error closing campaign");
+        }
+    };
+
+    let campaign = sqlx::query(
+        "SELECT department, status FROM inventory_campaigns WHERE id = $1 AND tenant_id = $2 FOR UPDATE"
+    )
+    .bind(campaign_id)
+    .bind(user.tenant_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let department = match campaign {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") != "open" {
+                return HttpResponse::Conflict().json("Campaign is already closed");
+            }
+            row.get::<String, _>("department")
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Campaign not found"),
+        Err(e) => {
+            eprintln!("Error fetching inventory campaign: {}", e);
+            return HttpResponse::InternalServerError().json("Error closing campaign");
+        }
+    };
+
+    let report = match build_report(&mut transaction, user.tenant_id, campaign_id, &department).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Error building inventory report: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error closing campaign");
+        }
+    };
+
+    let update_result = sqlx::query(
+        "UPDATE inventory_campaigns SET status = 'closed', closed_by = $1, closed_at = NOW(), report = $2 WHERE id = $3"
+    )
+    .bind(user.id)
+    .bind(Json(&report))
+    .bind(campaign_id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = update_result {
+        eprintln!("Error closing inventory campaign: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error closing campaign");
+    }
+
+    // Sessões ainda abertas são encerradas junto com a campanha
+    let sessions_result = sqlx::query(
+        "UPDATE inventory_sessions SET closed_at = NOW() WHERE campaign_id = $1 AND closed_at IS NULL"
+    )
+    .bind(campaign_id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = sessions_result {
+        eprintln!("Error closing inventory sessions: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error closing campaign");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error closing campaign");
+    }
+
+    println!(
+        "✅ Campanha {} encerrada: {} encontrados, {} ausentes, {} em outro departamento, {} desconhecidos, {} baixados",
+        campaign_id,
+        report.totals.found,
+        report.totals.missing,
+        report.totals.wrong_department,
+        report.totals.unknown,
+        report.totals.written_off
+    );
+
+    HttpResponse::Ok().json(report)
+}
+
+// Relatório gravado no encerramento, ou parcial enquanto a campanha está aberta
+pub async fn get_campaign_report(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let campaign_id = id.into_inner();
+
+    let campaign = sqlx::query(&format!(
+        "SELECT department, status, report FROM inventory_campaigns WHERE id = $1 AND tenant_id = $2 AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
+    .bind(campaign_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    let department = match campaign {
+        Ok(Some(row)) => {
+            if let Some(Json(report)) = row.get::<Option<Json<InventoryReport>>, _>("report") {
+                return HttpResponse::Ok().json(report);
+            }
+            row.get::<String, _>("department")
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Campaign not found"),
+        Err(e) => {
+            eprintln!("Error fetching inventory campaign: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching inventory report");
+        }
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching inventory report");
+        }
+    };
+
+    let report = build_report(&mut transaction, user.tenant_id, campaign_id, &department).await;
+    let _ = transaction.rollback().await;
+
+    match report {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(e) => {
+            eprintln!("Error building inventory report: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching inventory report")
+        }
+    }
+}
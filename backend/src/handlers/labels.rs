@@ -0,0 +1,130 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use uuid::Uuid;
+use std::env;
+
+use super::patrimony::auth_middleware;
+use super::department_grant;
+use crate::documents::labels::{self, LabelData, DEFAULT_LAYOUT, LAYOUTS};
+
+#[derive(Deserialize)]
+pub struct LabelQuery {
+    pub ids: Option<String>,
+    pub department: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub layout: Option<String>,
+}
+
+// URL pública do bem codificada no QR Code
+fn asset_url(id: Uuid) -> String {
+    let base = env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| "http://localhost:3000".to_string());
+    format!("{}/patrimony/{}", base.trim_end_matches('/'), id)
+}
+
+pub async fn get_labels(
+    pool: web::Data<PgPool>,
+    query: web::Query<LabelQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let layout_code = query.layout.as_deref().unwrap_or(DEFAULT_LAYOUT);
+    let layout = match labels::find_layout(layout_code) {
+        Some(layout) => layout,
+        None => {
+            let available: Vec<&str> = LAYOUTS.iter().map(|layout| layout.code).collect();
+            return HttpResponse::BadRequest().json(format!(
+                "Invalid layout. Available layouts: {}",
+                available.join(", ")
+            ));
+        }
+    };
+
+    let ids = match &query.ids {
+        Some(ids) => {
+            let parsed: Result<Vec<Uuid>, _> = ids
+                .split(',')
+                .map(|id| id.trim())
+                .filter(|id| !id.is_empty())
+                .map(Uuid::parse_str)
+                .collect();
+            match parsed {
+                Ok(parsed) => Some(parsed),
+                Err(_) => return HttpResponse::BadRequest().json("Invalid patrimony id in ids"),
+            }
+        }
+        None => None,
+    };
+
+    if ids.is_none() && query.department.is_none() && query.from.is_none() && query.to.is_none() {
+        return HttpResponse::BadRequest().json("Provide ids, department or a date range");
+    }
+
+    // O período considera a data de cadastro do bem; só bens que o usuário pode consultar
+    let result = sqlx::query(&format!(
+        "SELECT id, plate, name, department
+         FROM patrimonies
+         WHERE tenant_id = $1
+           AND {}
+           AND status <> 'written_off'
+           AND deleted_at IS NULL
+           AND ($3::uuid[] IS NULL OR id = ANY($3))
+           AND ($4::varchar IS NULL OR department = $4)
+           AND ($5::date IS NULL OR created_at::date >= $5)
+           AND ($6::date IS NULL OR created_at::date <= $6)
+         ORDER BY department, plate",
+        department_grant::filter_sql("department", 2)
+    ))
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .bind(&ids)
+    .bind(&query.department)
+    .bind(query.from)
+    .bind(query.to)
+    .map(|row: PgRow| {
+        let id: Uuid = row.get("id");
+        LabelData {
+            plate: row.get("plate"),
+            name: row.get("name"),
+            department: row.get("department"),
+            url: asset_url(id),
+        }
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let items = match result {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("Error fetching patrimonies for labels: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching patrimonies");
+        }
+    };
+
+    if items.is_empty() {
+        return HttpResponse::NotFound().json("No patrimonies found for the given filters");
+    }
+
+    match labels::render_labels(layout, &items) {
+        Ok(pdf) => {
+            println!("🏷️ {} etiqueta(s) geradas no formato {}", items.len(), layout.code);
+            HttpResponse::Ok()
+                .content_type("application/pdf")
+                .insert_header(("Content-Disposition", "inline; filename=\"etiquetas.pdf\""))
+                .body(pdf)
+        }
+        Err(e) => {
+            eprintln!("Error rendering labels: {}", e);
+            HttpResponse::InternalServerError().json("Error generating labels")
+        }
+    }
+}
@@ -0,0 +1,612 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, ensure_visible};
+use super::transfer::belongs_to_department;
+use super::write_off::{written_off_response, WRITTEN_OFF};
+use super::audit::{self, Actor};
+
+// Níveis da árvore de localização, do mais amplo ao mais específico
+pub const LOCATION_KINDS: [&str; 3] = ["building", "floor", "room"];
+
+#[derive(Deserialize)]
+pub struct CreateLocation {
+    pub name: String,
+    pub kind: String,
+    pub code: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateLocation {
+    pub name: Option<String>,
+    pub code: Option<String>,
+    pub parent_id: Option<Uuid>,
+    pub description: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct MovePatrimony {
+    pub location_id: Uuid,
+    pub notes: Option<String>,
+}
+
+/// Subconsulta com o local informado e todos os seus descendentes
+pub fn subtree_sql(param: usize) -> String {
+    format!(
+        "(WITH RECURSIVE subtree AS (
+            SELECT id FROM locations WHERE id = ${}::uuid
+            UNION ALL
+            SELECT l.id FROM locations l JOIN subtree s ON l.parent_id = s.id
+        ) SELECT id FROM subtree)",
+        param
+    )
+}
+
+// Caminho completo (Prédio / Andar / Sala) calculado a partir das raízes
+const LOCATION_SELECT: &str =
+    "WITH RECURSIVE tree AS (
+        SELECT id, name::text as path, 0 as depth FROM locations WHERE parent_id IS NULL
+        UNION ALL
+        SELECT l.id, t.path || ' / ' || l.name, t.depth + 1 FROM locations l JOIN tree t ON l.parent_id = t.id
+     )
+     SELECT l.id, l.name, l.kind, l.code, l.parent_id, l.description, l.active, l.created_at, l.updated_at,
+            tree.path, tree.depth,
+            (SELECT COUNT(*) FROM patrimonies p WHERE p.location_id = l.id AND p.deleted_at IS NULL) as asset_count
+     FROM locations l
+     JOIN tree ON tree.id = l.id";
+
+fn location_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "name": row.get::<String, _>("name"),
+        "kind": row.get::<String, _>("kind"),
+        "code": row.get::<Option<String>, _>("code"),
+        "parent_id": row.get::<Option<Uuid>, _>("parent_id"),
+        "description": row.get::<Option<String>, _>("description"),
+        "active": row.get::<bool, _>("active"),
+        "path": row.get::<String, _>("path"),
+        "depth": row.get::<i32, _>("depth"),
+        "asset_count": row.get::<i64, _>("asset_count"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+fn kind_rank(kind: &str) -> Option<usize> {
+    LOCATION_KINDS.iter().position(|k| *k == kind)
+}
+
+// O pai precisa ser de um nível acima (sala dentro de andar ou prédio, andar dentro de prédio)
+async fn validate_parent(pool: &PgPool, tenant_id: Uuid, kind: &str, parent_id: Option<Uuid>) -> Result<(), HttpResponse> {
+    let rank = kind_rank(kind).unwrap_or(0);
+
+    let parent_id = match parent_id {
+        Some(parent_id) => parent_id,
+        None if rank == 0 => return Ok(()),
+        None => return Err(HttpResponse::BadRequest().json("Only buildings can be top-level locations")),
+    };
+
+    match sqlx::query("SELECT kind FROM locations WHERE id = $1 AND tenant_id = $2")
+        .bind(parent_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(row)) => {
+            let parent_rank = kind_rank(&row.get::<String, _>("kind")).unwrap_or(0);
+            if parent_rank >= rank {
+                Err(HttpResponse::BadRequest().json(format!("A {} cannot be placed inside this location", kind)))
+            } else {
+                Ok(())
+            }
+        }
+        Ok(None) => Err(HttpResponse::BadRequest().json("Parent location not found")),
+        Err(e) => {
+            eprintln!("Error fetching parent location: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating parent location"))
+        }
+    }
+}
+
+async fn fetch_location(pool: &PgPool, tenant_id: Uuid, location_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query(&format!("{} WHERE l.id = $1 AND l.tenant_id = $2", LOCATION_SELECT))
+        .bind(location_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| location_json(&row))
+        .fetch_one(pool)
+        .await
+}
+
+/// O local de destino precisa ser da entidade e estar ativo
+pub async fn validate_location(pool: &PgPool, tenant_id: Uuid, location_id: Uuid) -> Result<(), HttpResponse> {
+    match sqlx::query("SELECT active FROM locations WHERE id = $1 AND tenant_id = $2")
+        .bind(location_id)
+        .bind(tenant_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(row)) if row.get::<bool, _>("active") => Ok(()),
+        Ok(Some(_)) => Err(HttpResponse::BadRequest().json("Location is inactive")),
+        Ok(None) => Err(HttpResponse::NotFound().json("Location not found")),
+        Err(e) => {
+            eprintln!("Error fetching location: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating location"))
+        }
+    }
+}
+
+/// Registra a movimentação física do bem e atualiza o local atual
+pub async fn record_move(
+    transaction: &mut Transaction<'_, Postgres>,
+    patrimony_id: Uuid,
+    from_location_id: Option<Uuid>,
+    to_location_id: Uuid,
+    moved_by: Uuid,
+    notes: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO location_moves (patrimony_id, from_location_id, to_location_id, moved_by, notes)
+         VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(patrimony_id)
+    .bind(from_location_id)
+    .bind(to_location_id)
+    .bind(moved_by)
+    .bind(notes)
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query("UPDATE patrimonies SET location_id = $1, updated_at = NOW() WHERE id = $2")
+        .bind(to_location_id)
+        .bind(patrimony_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn get_locations(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!("{} WHERE l.tenant_id = $1 ORDER BY tree.path", LOCATION_SELECT))
+        .bind(user.tenant_id)
+        .map(|row: PgRow| location_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(locations) => HttpResponse::Ok().json(locations),
+        Err(e) => {
+            eprintln!("Error fetching locations: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching locations")
+        }
+    }
+}
+
+pub async fn get_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let location_id = id.into_inner();
+
+    let location = match fetch_location(pool.get_ref(), user.tenant_id, location_id).await {
+        Ok(location) => location,
+        Err(sqlx::Error::RowNotFound) => return HttpResponse::NotFound().json("Location not found"),
+        Err(e) => {
+            eprintln!("Error fetching location: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching location");
+        }
+    };
+
+    let children = sqlx::query(&format!("{} WHERE l.parent_id = $1 ORDER BY l.name", LOCATION_SELECT))
+        .bind(location_id)
+        .map(|row: PgRow| location_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match children {
+        Ok(children) => HttpResponse::Ok().json(serde_json::json!({
+            "location": location,
+            "children": children
+        })),
+        Err(e) => {
+            eprintln!("Error fetching child locations: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching location")
+        }
+    }
+}
+
+pub async fn create_location(
+    pool: web::Data<PgPool>,
+    location: web::Json<CreateLocation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if location.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if kind_rank(&location.kind).is_none() {
+        return HttpResponse::BadRequest().json(format!(
+            "Invalid kind, expected one of: {}",
+            LOCATION_KINDS.join(", ")
+        ));
+    }
+    if let Err(response) = validate_parent(pool.get_ref(), user.tenant_id, &location.kind, location.parent_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO locations (name, kind, code, parent_id, description, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id"
+    )
+    .bind(location.name.trim())
+    .bind(&location.kind)
+    .bind(&location.code)
+    .bind(location.parent_id)
+    .bind(&location.description)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let location_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating location: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Location code already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating location");
+        }
+    };
+
+    match fetch_location(pool.get_ref(), user.tenant_id, location_id).await {
+        Ok(location) => HttpResponse::Created().json(location),
+        Err(e) => {
+            eprintln!("Error fetching created location: {}", e);
+            HttpResponse::InternalServerError().json("Location created but error fetching details")
+        }
+    }
+}
+
+pub async fn update_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    location: web::Json<UpdateLocation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let location_id = id.into_inner();
+
+    if let Some(parent_id) = location.parent_id {
+        let kind = match sqlx::query("SELECT kind FROM locations WHERE id = $1 AND tenant_id = $2")
+            .bind(location_id)
+            .bind(user.tenant_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(row)) => row.get::<String, _>("kind"),
+            Ok(None) => return HttpResponse::NotFound().json("Location not found"),
+            Err(e) => {
+                eprintln!("Error fetching location: {}", e);
+                return HttpResponse::InternalServerError().json("Error updating location");
+            }
+        };
+
+        if let Err(response) = validate_parent(pool.get_ref(), user.tenant_id, &kind, Some(parent_id)).await {
+            return response;
+        }
+
+        // Impede ciclos: o novo pai não pode estar dentro do próprio local
+        let cycle = sqlx::query(&format!("SELECT $2::uuid IN {} as cycle", subtree_sql(1)))
+            .bind(location_id)
+            .bind(parent_id)
+            .fetch_one(pool.get_ref())
+            .await;
+
+        match cycle {
+            Ok(row) if row.get::<bool, _>("cycle") => {
+                return HttpResponse::BadRequest().json("A location cannot be moved inside itself");
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("Error checking location hierarchy: {}", e);
+                return HttpResponse::InternalServerError().json("Error updating location");
+            }
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE locations SET
+            name = COALESCE($1, name),
+            code = COALESCE($2, code),
+            parent_id = COALESCE($3, parent_id),
+            description = COALESCE($4, description),
+            active = COALESCE($5, active),
+            updated_at = NOW()
+         WHERE id = $6 AND tenant_id = $7"
+    )
+    .bind(location.name.as_deref().map(str::trim))
+    .bind(&location.code)
+    .bind(location.parent_id)
+    .bind(&location.description)
+    .bind(location.active)
+    .bind(location_id)
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => return HttpResponse::NotFound().json("Location not found"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error updating location: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Location code already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error updating location");
+        }
+    }
+
+    match fetch_location(pool.get_ref(), user.tenant_id, location_id).await {
+        Ok(location) => HttpResponse::Ok().json(location),
+        Err(e) => {
+            eprintln!("Error fetching updated location: {}", e);
+            HttpResponse::InternalServerError().json("Location updated but error fetching details")
+        }
+    }
+}
+
+pub async fn delete_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let location_id = id.into_inner();
+
+    // Locais com sublocais, bens ou histórico de movimentação são mantidos (use active = false)
+    let usage = sqlx::query(
+        "SELECT
+            (SELECT COUNT(*) FROM locations WHERE parent_id = l.id) as children,
+            (SELECT COUNT(*) FROM patrimonies WHERE location_id = l.id) as assets,
+            (SELECT COUNT(*) FROM location_moves WHERE from_location_id = l.id OR to_location_id = l.id) as moves
+         FROM locations l WHERE l.id = $1 AND l.tenant_id = $2"
+    )
+    .bind(location_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match usage {
+        Ok(None) => return HttpResponse::NotFound().json("Location not found"),
+        Ok(Some(row)) => {
+            if row.get::<i64, _>("children") > 0 {
+                return HttpResponse::Conflict().json("Location has child locations");
+            }
+            if row.get::<i64, _>("assets") > 0 || row.get::<i64, _>("moves") > 0 {
+                return HttpResponse::Conflict().json("Location is in use; deactivate it instead");
+            }
+        }
+        Err(e) => {
+            eprintln!("Error checking location usage: {}", e);
+            return HttpResponse::InternalServerError().json("Error deleting location");
+        }
+    }
+
+    let result = sqlx::query("DELETE FROM locations WHERE id = $1")
+        .bind(location_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                HttpResponse::Ok().json("Location deleted successfully")
+            } else {
+                HttpResponse::NotFound().json("Location not found")
+            }
+        }
+        Err(e) => {
+            eprintln!("Error deleting location: {}", e);
+            HttpResponse::InternalServerError().json("Error deleting location")
+        }
+    }
+}
+
+// Movimentação física do bem (não altera o departamento, ao contrário das transferências)
+pub async fn move_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    movement: web::Json<MovePatrimony>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    if let Err(response) = validate_location(pool.get_ref(), user.tenant_id, movement.location_id).await {
+        return response;
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error moving patrimony");
+        }
+    };
+
+    let patrimony = sqlx::query(
+        "SELECT department, status, location_id FROM patrimonies WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+    )
+    .bind(patrimony_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let from_location_id = match patrimony {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") == WRITTEN_OFF {
+                return written_off_response();
+            }
+            if !belongs_to_department(&user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can move it");
+            }
+            row.get::<Option<Uuid>, _>("location_id")
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error moving patrimony");
+        }
+    };
+
+    if from_location_id == Some(movement.location_id) {
+        return HttpResponse::BadRequest().json("Patrimony is already at this location");
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error moving patrimony");
+        }
+    };
+
+    if let Err(e) = record_move(
+        &mut transaction,
+        patrimony_id,
+        from_location_id,
+        movement.location_id,
+        user.id,
+        movement.notes.as_deref(),
+    )
+    .await
+    {
+        eprintln!("Error recording location move: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error moving patrimony");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::MOVE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error moving patrimony");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error moving patrimony");
+    }
+
+    println!("🚚 Patrimônio {} movido para o local {}", patrimony_id, movement.location_id);
+
+    HttpResponse::Ok().json("Patrimony moved successfully")
+}
+
+pub async fn get_patrimony_moves(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "SELECT m.id, m.patrimony_id, m.from_location_id, lf.name as from_location_name,
+                m.to_location_id, lt.name as to_location_name, m.notes,
+                m.moved_by, u.username as moved_by_name, m.moved_at
+         FROM location_moves m
+         LEFT JOIN locations lf ON m.from_location_id = lf.id
+         JOIN locations lt ON m.to_location_id = lt.id
+         LEFT JOIN users u ON m.moved_by = u.id
+         WHERE m.patrimony_id = $1
+         ORDER BY m.moved_at DESC"
+    )
+    .bind(patrimony_id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+            "from_location_id": row.get::<Option<Uuid>, _>("from_location_id"),
+            "from_location_name": row.get::<Option<String>, _>("from_location_name"),
+            "to_location_id": row.get::<Uuid, _>("to_location_id"),
+            "to_location_name": row.get::<String, _>("to_location_name"),
+            "notes": row.get::<Option<String>, _>("notes"),
+            "moved_by": row.get::<Option<Uuid>, _>("moved_by"),
+            "moved_by_name": row.get::<Option<String>, _>("moved_by_name"),
+            "moved_at": row.get::<chrono::DateTime<Utc>, _>("moved_at")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(moves) => HttpResponse::Ok().json(moves),
+        Err(e) => {
+            eprintln!("Error fetching location moves: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching location moves")
+        }
+    }
+}
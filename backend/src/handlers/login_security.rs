@@ -0,0 +1,483 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgExecutor, PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use bcrypt::{hash, verify, DEFAULT_COST};
+use std::env;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, User};
+
+const DEFAULT_MAX_FAILURES: i32 = 5;
+const DEFAULT_LOCKOUT_MINUTES: i64 = 15;
+const DEFAULT_IP_FREE_FAILURES: i64 = 10;
+
+// Falhas por IP contam dentro desta janela
+const IP_WINDOW_MINUTES: i64 = 15;
+// Atraso exponencial: 1s, 2s, 4s... até o teto
+const BACKOFF_BASE_SECONDS: i64 = 1;
+const BACKOFF_MAX_SECONDS: i64 = 300;
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+
+// Motivos de falha gravados em login_attempts
+pub const UNKNOWN_USER: &str = "unknown_user";
+pub const INVALID_PASSWORD: &str = "invalid_password";
+pub const LOCKED: &str = "locked";
+pub const THROTTLED: &str = "throttled";
+pub const DEACTIVATED: &str = "deactivated";
+pub const TENANT_INACTIVE: &str = "tenant_inactive";
+pub const INVALID_TWO_FACTOR: &str = "invalid_two_factor";
+// Reservada antes de conferir a senha; se o login não terminar, continua contando como falha
+const PENDING: &str = "pending";
+
+// Hash de comparação para usuário inexistente, no mesmo custo das senhas cadastradas
+static DUMMY_PASSWORD_HASH: LazyLock<String> =
+    LazyLock::new(|| hash("dummy-password-for-unknown-users", DEFAULT_COST).unwrap_or_default());
+
+#[derive(Deserialize)]
+pub struct LoginAttemptQuery {
+    pub username: Option<String>,
+    pub user_id: Option<Uuid>,
+    pub ip_address: Option<String>,
+    pub success: Option<bool>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+fn env_number<T: std::str::FromStr + PartialOrd + Default>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<T>().ok())
+        .filter(|value| *value > T::default())
+        .unwrap_or(default)
+}
+
+/// Falhas seguidas que bloqueiam a conta (LOGIN_MAX_FAILURES)
+fn max_failures() -> i32 {
+    env_number("LOGIN_MAX_FAILURES", DEFAULT_MAX_FAILURES)
+}
+
+fn lockout_minutes() -> i64 {
+    env_number("LOGIN_LOCKOUT_MINUTES", DEFAULT_LOCKOUT_MINUTES)
+}
+
+/// Falhas do mesmo IP toleradas na janela antes de começar o atraso (LOGIN_IP_FREE_FAILURES)
+fn ip_free_failures() -> i64 {
+    env_number("LOGIN_IP_FREE_FAILURES", DEFAULT_IP_FREE_FAILURES)
+}
+
+// Espera exigida depois de `failures` falhas, das quais `free` não geram atraso
+fn backoff_seconds(failures: i64, free: i64) -> i64 {
+    if failures <= free {
+        return 0;
+    }
+    let exponent = (failures - free - 1).min(16) as u32;
+    (BACKOFF_BASE_SECONDS * 2_i64.pow(exponent)).min(BACKOFF_MAX_SECONDS)
+}
+
+// Segundos que ainda faltam desde a última falha
+fn remaining(wait: i64, last_failure: Option<DateTime<Utc>>) -> i64 {
+    match last_failure {
+        Some(last_failure) if wait > 0 => (wait - (Utc::now() - last_failure).num_seconds()).max(0),
+        _ => 0,
+    }
+}
+
+/// Proxies reversos cujo X-Forwarded-For é aceito (TRUSTED_PROXIES, IPs separados por vírgula).
+/// Sem a variável vale só o endereço da conexão, que o cliente não consegue forjar
+fn trusted_proxies() -> Vec<IpAddr> {
+    env::var("TRUSTED_PROXIES")
+        .map(|value| value.split(',').filter_map(|ip| ip.trim().parse().ok()).collect())
+        .unwrap_or_default()
+}
+
+// Atrás de proxy confiável, o cliente é o último salto do X-Forwarded-For que não é outro
+// proxy confiável: os saltos anteriores foram escritos pelo próprio cliente
+fn resolve_client_ip(peer: IpAddr, forwarded_for: &[&str], trusted: &[IpAddr]) -> IpAddr {
+    if !trusted.contains(&peer) {
+        return peer;
+    }
+    forwarded_for
+        .iter()
+        .rev()
+        .flat_map(|header| header.rsplit(','))
+        .map_while(|hop| hop.trim().parse::<IpAddr>().ok())
+        .find(|ip| !trusted.contains(ip))
+        .unwrap_or(peer)
+}
+
+/// IP de quem chama (tentativas de login, sessões, auditoria)
+pub fn client_ip(req: &HttpRequest) -> Option<String> {
+    let peer = req.peer_addr()?.ip();
+    let forwarded_for: Vec<&str> = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .collect();
+    Some(resolve_client_ip(peer, &forwarded_for, &trusted_proxies()).to_string())
+}
+
+fn user_agent(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(actix_web::http::header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+// Falhas do IP na janela, contando as tentativas ainda em andamento
+async fn ip_failures<'e, E: PgExecutor<'e>>(executor: E, ip_address: &str) -> Result<(i64, Option<DateTime<Utc>>), sqlx::Error> {
+    sqlx::query(
+        "SELECT COUNT(*) as failures, MAX(created_at) as last_failure
+         FROM login_attempts
+         WHERE ip_address = $1 AND NOT success AND failure_reason <> $2
+           AND created_at > NOW() - $3 * INTERVAL '1 minute'"
+    )
+    .bind(ip_address)
+    .bind(THROTTLED)
+    .bind(IP_WINDOW_MINUTES as f64)
+    .map(|row: PgRow| (row.get::<i64, _>("failures"), row.get::<Option<DateTime<Utc>>, _>("last_failure")))
+    .fetch_one(executor)
+    .await
+}
+
+/// Espera exigida ao IP da requisição (0 = pode tentar)
+pub async fn ip_retry_after(pool: &PgPool, req: &HttpRequest) -> Result<i64, sqlx::Error> {
+    let Some(ip_address) = client_ip(req) else {
+        return Ok(0);
+    };
+
+    let (failures, last_failure) = ip_failures(pool, &ip_address).await?;
+    Ok(remaining(backoff_seconds(failures, ip_free_failures()), last_failure))
+}
+
+/// Resultado da reserva de uma tentativa de login
+pub enum Reservation {
+    /// Pode conferir a senha; o id identifica a tentativa em login_attempts
+    Granted(i64),
+    /// O IP ou a conta precisam esperar estes segundos
+    Throttled(i64),
+}
+
+/// Reserva a tentativa antes de conferir a senha. As esperas do IP e da conta são calculadas
+/// e a tentativa é gravada sob lock do IP e da conta, contando as que ainda estão em andamento:
+/// uma rajada paralela não passa inteira pela verificação antes de a primeira falha ser gravada
+pub async fn reserve_attempt(pool: &PgPool, username: &str, user: Option<&User>, req: &HttpRequest) -> Result<Reservation, sqlx::Error> {
+    let ip_address = client_ip(req);
+    let mut transaction = pool.begin().await?;
+    let mut wait = 0;
+
+    if let Some(ip_address) = &ip_address {
+        sqlx::query("SELECT pg_advisory_xact_lock(hashtext('login_ip:' || $1))")
+            .bind(ip_address)
+            .execute(&mut *transaction)
+            .await?;
+
+        let (failures, last_failure) = ip_failures(&mut *transaction, ip_address).await?;
+        wait = remaining(backoff_seconds(failures, ip_free_failures()), last_failure);
+    }
+
+    if let Some(user) = user {
+        let (failed_login_count, last_failed_login_at) = sqlx::query(
+            "SELECT failed_login_count, last_failed_login_at FROM users WHERE id = $1 FOR UPDATE"
+        )
+        .bind(user.id)
+        .map(|row: PgRow| (row.get::<i32, _>("failed_login_count"), row.get::<Option<DateTime<Utc>>, _>("last_failed_login_at")))
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let (pending, last_pending) = sqlx::query(
+            "SELECT COUNT(*) as pending, MAX(created_at) as last_pending
+             FROM login_attempts
+             WHERE LOWER(username) = LOWER($1) AND failure_reason = $2
+               AND created_at > NOW() - $3 * INTERVAL '1 minute'"
+        )
+        .bind(&user.username)
+        .bind(PENDING)
+        .bind(IP_WINDOW_MINUTES as f64)
+        .map(|row: PgRow| (row.get::<i64, _>("pending"), row.get::<Option<DateTime<Utc>>, _>("last_pending")))
+        .fetch_one(&mut *transaction)
+        .await?;
+
+        let failures = failed_login_count as i64 + pending;
+        wait = wait.max(remaining(backoff_seconds(failures, 0), last_failed_login_at.max(last_pending)));
+    }
+
+    let attempt_id = sqlx::query(
+        "INSERT INTO login_attempts (username, user_id, tenant_id, ip_address, user_agent, success, failure_reason)
+         VALUES ($1, $2, $3, $4, $5, FALSE, $6)
+         RETURNING id"
+    )
+    .bind(username)
+    .bind(user.map(|user| user.id))
+    .bind(user.map(|user| user.tenant_id))
+    .bind(ip_address)
+    .bind(user_agent(req))
+    .bind(if wait > 0 { THROTTLED } else { PENDING })
+    .map(|row: PgRow| row.get::<i64, _>("id"))
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(if wait > 0 { Reservation::Throttled(wait) } else { Reservation::Granted(attempt_id) })
+}
+
+/// Fecha a tentativa reservada; sem `failure_reason` ela foi bem-sucedida. Erros não impedem o login
+pub async fn finish_attempt(pool: &PgPool, attempt_id: i64, failure_reason: Option<&str>) {
+    let result = sqlx::query("UPDATE login_attempts SET success = $2, failure_reason = $3 WHERE id = $1")
+        .bind(attempt_id)
+        .bind(failure_reason.is_none())
+        .bind(failure_reason)
+        .execute(pool)
+        .await;
+
+    if let Err(e) = result {
+        eprintln!("Error recording login attempt: {}", e);
+    }
+}
+
+/// Descarta a tentativa reservada (senha certa com 2FA: o login só conta depois do código)
+pub async fn discard_attempt(pool: &PgPool, attempt_id: i64) {
+    if let Err(e) = sqlx::query("DELETE FROM login_attempts WHERE id = $1").bind(attempt_id).execute(pool).await {
+        eprintln!("Error discarding login attempt: {}", e);
+    }
+}
+
+/// Confere a senha do login fora das threads do servidor (o bcrypt é lento de propósito).
+/// Sem hash (usuário inexistente) confere contra um hash qualquer, para levar o mesmo tempo
+/// de uma senha errada
+pub async fn verify_password(password: &str, password_hash: Option<&str>) -> bool {
+    let password = password.to_string();
+    let password_hash = password_hash.map(str::to_string);
+
+    web::block(move || match password_hash {
+        Some(password_hash) => verify(&password, &password_hash).unwrap_or(false),
+        None => {
+            let _ = verify(&password, &DUMMY_PASSWORD_HASH);
+            false
+        }
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Conta a falha; ao chegar no limite a conta fica bloqueada e o contador recomeça.
+/// Devolve o fim do bloqueio quando esta falha bloqueou a conta
+pub async fn register_failure(pool: &PgPool, user_id: Uuid) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    sqlx::query(
+        "UPDATE users SET
+            failed_login_count = CASE WHEN failed_login_count + 1 >= $2 THEN 0 ELSE failed_login_count + 1 END,
+            last_failed_login_at = NOW(),
+            locked_until = CASE WHEN failed_login_count + 1 >= $2 THEN NOW() + $3 * INTERVAL '1 minute' ELSE locked_until END
+         WHERE id = $1
+         RETURNING CASE WHEN failed_login_count = 0 THEN locked_until END as locked_until"
+    )
+    .bind(user_id)
+    .bind(max_failures())
+    .bind(lockout_minutes() as f64)
+    .map(|row: PgRow| row.get::<Option<DateTime<Utc>>, _>("locked_until"))
+    .fetch_one(pool)
+    .await
+}
+
+/// Login bem-sucedido zera as falhas
+pub async fn register_success(pool: &PgPool, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET failed_login_count = 0, last_failed_login_at = NULL, locked_until = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+/// Grava a tentativa; sem `failure_reason` ela foi bem-sucedida. Erros não impedem o login
+pub async fn record_attempt(pool: &PgPool, username: &str, user: Option<&User>, req: &HttpRequest, failure_reason: Option<&str>) {
+    let result = sqlx::query(
+        "INSERT INTO login_attempts (username, user_id, tenant_id, ip_address, user_agent, success, failure_reason)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(username)
+    .bind(user.map(|user| user.id))
+    .bind(user.map(|user| user.tenant_id))
+    .bind(client_ip(req))
+    .bind(user_agent(req))
+    .bind(failure_reason.is_none())
+    .bind(failure_reason)
+    .execute(pool)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error recording login attempt: {}", e);
+    }
+}
+
+pub fn too_many_attempts(seconds: i64) -> HttpResponse {
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", seconds.to_string()))
+        .json(format!("Too many failed login attempts, retry in {} seconds", seconds))
+}
+
+pub fn account_locked(locked_until: DateTime<Utc>) -> HttpResponse {
+    let seconds = (locked_until - Utc::now()).num_seconds().max(1);
+    HttpResponse::Locked()
+        .insert_header(("Retry-After", seconds.to_string()))
+        .json(format!("Account is temporarily locked until {}", locked_until.to_rfc3339()))
+}
+
+fn attempt_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<i64, _>("id"),
+        "username": row.get::<String, _>("username"),
+        "user_id": row.get::<Option<Uuid>, _>("user_id"),
+        "ip_address": row.get::<Option<String>, _>("ip_address"),
+        "user_agent": row.get::<Option<String>, _>("user_agent"),
+        "success": row.get::<bool, _>("success"),
+        "failure_reason": row.get::<Option<String>, _>("failure_reason"),
+        "created_at": row.get::<DateTime<Utc>, _>("created_at")
+    })
+}
+
+// Revisão de segurança: tentativas da entidade; o superadministrador vê também as de usuários inexistentes
+pub async fn get_login_attempts(
+    pool: web::Data<PgPool>,
+    query: web::Query<LoginAttemptQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+
+    let filter =
+        "(tenant_id = $1 OR ($2 AND tenant_id IS NULL))
+         AND ($3::varchar IS NULL OR LOWER(username) = LOWER($3))
+         AND ($4::uuid IS NULL OR user_id = $4)
+         AND ($5::varchar IS NULL OR ip_address = $5)
+         AND ($6::boolean IS NULL OR success = $6)
+         AND ($7::date IS NULL OR created_at::date >= $7)
+         AND ($8::date IS NULL OR created_at::date <= $8)";
+
+    let total = sqlx::query(&format!("SELECT COUNT(*) as total FROM login_attempts WHERE {}", filter))
+        .bind(user.tenant_id)
+        .bind(user.is_super_admin)
+        .bind(&query.username)
+        .bind(query.user_id)
+        .bind(&query.ip_address)
+        .bind(query.success)
+        .bind(query.from)
+        .bind(query.to)
+        .map(|row: PgRow| row.get::<i64, _>("total"))
+        .fetch_one(pool.get_ref())
+        .await;
+
+    let total = match total {
+        Ok(total) => total,
+        Err(e) => {
+            eprintln!("Error counting login attempts: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching login attempts");
+        }
+    };
+
+    let attempts = sqlx::query(&format!(
+        "SELECT id, username, user_id, ip_address, user_agent, success, failure_reason, created_at
+         FROM login_attempts
+         WHERE {}
+         ORDER BY id DESC
+         LIMIT $9 OFFSET $10",
+        filter
+    ))
+    .bind(user.tenant_id)
+    .bind(user.is_super_admin)
+    .bind(&query.username)
+    .bind(query.user_id)
+    .bind(&query.ip_address)
+    .bind(query.success)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(per_page)
+    .bind((page - 1) * per_page)
+    .map(|row: PgRow| attempt_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match attempts {
+        Ok(attempts) => HttpResponse::Ok().json(serde_json::json!({
+            "total": total,
+            "page": page,
+            "per_page": per_page,
+            "attempts": attempts
+        })),
+        Err(e) => {
+            eprintln!("Error fetching login attempts: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching login attempts")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn backoff_starts_after_free_failures_and_doubles() {
+        assert_eq!(backoff_seconds(0, 0), 0);
+        assert_eq!(backoff_seconds(10, 10), 0);
+        assert_eq!(backoff_seconds(11, 10), 1);
+        assert_eq!(backoff_seconds(12, 10), 2);
+        assert_eq!(backoff_seconds(13, 10), 4);
+        assert_eq!(backoff_seconds(1, 0), 1);
+        assert_eq!(backoff_seconds(4, 0), 8);
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff_seconds(9, 0), 256);
+        assert_eq!(backoff_seconds(10, 0), BACKOFF_MAX_SECONDS);
+        assert_eq!(backoff_seconds(1_000, 0), BACKOFF_MAX_SECONDS);
+    }
+
+    #[test]
+    fn remaining_counts_down_from_last_failure() {
+        assert_eq!(remaining(0, Some(Utc::now())), 0);
+        assert_eq!(remaining(60, None), 0);
+        let left = remaining(60, Some(Utc::now() - Duration::seconds(20)));
+        assert!((39..=40).contains(&left), "{}", left);
+        assert_eq!(remaining(60, Some(Utc::now() - Duration::seconds(90))), 0);
+    }
+
+    #[test]
+    fn forwarded_for_is_ignored_without_trusted_proxy() {
+        let peer = ip("203.0.113.7");
+        assert_eq!(resolve_client_ip(peer, &["198.51.100.1"], &[]), peer);
+        assert_eq!(resolve_client_ip(peer, &["198.51.100.1"], &[ip("10.0.0.1")]), peer);
+    }
+
+    #[test]
+    fn forwarded_for_skips_client_written_hops() {
+        let proxy = ip("10.0.0.1");
+        // O cliente mandou "1.1.1.1"; o proxy acrescentou o endereço real dele
+        assert_eq!(resolve_client_ip(proxy, &["1.1.1.1, 198.51.100.9"], &[proxy]), ip("198.51.100.9"));
+        // Dois proxies confiáveis em sequência
+        let edge = ip("10.0.0.2");
+        assert_eq!(resolve_client_ip(proxy, &["198.51.100.9", "10.0.0.2"], &[proxy, edge]), ip("198.51.100.9"));
+        // Sem cabeçalho, ou com lixo, fica o próprio proxy
+        assert_eq!(resolve_client_ip(proxy, &[], &[proxy]), proxy);
+        assert_eq!(resolve_client_ip(proxy, &["unknown"], &[proxy]), proxy);
+    }
+}
@@ -0,0 +1,934 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use actix_multipart::Multipart;
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+use std::fs;
+use std::path::Path;
+use futures_util::TryStreamExt;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use super::patrimony::{auth_middleware, ensure_visible};
+use super::transfer::belongs_to_department;
+use super::write_off::{written_off_response, WRITTEN_OFF};
+use super::depreciation;
+use super::maintenance_schedule;
+use super::audit::{self, Actor};
+use super::department_grant;
+
+// Status do bem enquanto houver ordem de serviço aberta
+pub const MAINTENANCE: &str = "maintenance";
+
+// Estados da ordem de serviço
+pub const OPEN: &str = "open";
+pub const CLOSED: &str = "closed";
+pub const CANCELLED: &str = "cancelled";
+
+// Tipos de anexo aceitos
+pub const ATTACHMENT_KINDS: [&str; 3] = ["quote", "invoice", "other"];
+const ATTACHMENT_EXTENSIONS: [&str; 4] = ["pdf", "jpg", "jpeg", "png"];
+
+#[derive(Deserialize)]
+pub struct OpenMaintenanceOrder {
+    pub problem_description: String,
+    pub vendor: Option<String>,
+    pub due_item_id: Option<Uuid>,      // Item de manutenção preventiva atendido pela ordem
+}
+
+#[derive(Deserialize)]
+pub struct UpdateMaintenanceOrder {
+    pub problem_description: Option<String>,
+    pub vendor: Option<String>,
+    pub cost: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct CloseMaintenanceOrder {
+    pub resolution: Option<String>,
+    pub cost: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct CancelMaintenanceOrder {
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MaintenanceQuery {
+    pub status: Option<String>,
+    pub department: Option<String>,
+    pub vendor: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Deserialize)]
+pub struct AttachmentQuery {
+    pub kind: Option<String>,
+}
+
+const ORDER_SELECT: &str =
+    "SELECT o.id, o.patrimony_id, p.plate, p.name as patrimony_name, p.department, o.status, o.problem_description,
+            o.vendor, o.previous_status, o.opened_by, uo.username as opened_by_name, o.opened_at,
+            o.closed_by, uc.username as closed_by_name, o.closed_at, o.resolution, o.cost, o.updated_at
+     FROM maintenance_orders o
+     JOIN patrimonies p ON o.patrimony_id = p.id
+     LEFT JOIN users uo ON o.opened_by = uo.id
+     LEFT JOIN users uc ON o.closed_by = uc.id";
+
+fn order_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+        "plate": row.get::<String, _>("plate"),
+        "patrimony_name": row.get::<String, _>("patrimony_name"),
+        "department": row.get::<String, _>("department"),
+        "status": row.get::<String, _>("status"),
+        "problem_description": row.get::<String, _>("problem_description"),
+        "vendor": row.get::<Option<String>, _>("vendor"),
+        "previous_status": row.get::<String, _>("previous_status"),
+        "opened_by": row.get::<Option<Uuid>, _>("opened_by"),
+        "opened_by_name": row.get::<Option<String>, _>("opened_by_name"),
+        "opened_at": row.get::<chrono::DateTime<Utc>, _>("opened_at"),
+        "closed_by": row.get::<Option<Uuid>, _>("closed_by"),
+        "closed_by_name": row.get::<Option<String>, _>("closed_by_name"),
+        "closed_at": row.get::<Option<chrono::DateTime<Utc>>, _>("closed_at"),
+        "resolution": row.get::<Option<String>, _>("resolution"),
+        "cost": depreciation::optional_decimal(row, "cost"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+fn attachment_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "kind": row.get::<String, _>("kind"),
+        "file_url": row.get::<String, _>("file_url"),
+        "original_name": row.get::<Option<String>, _>("original_name"),
+        "uploaded_by": row.get::<Option<Uuid>, _>("uploaded_by"),
+        "uploaded_at": row.get::<chrono::DateTime<Utc>, _>("uploaded_at")
+    })
+}
+
+async fn load_order(pool: &PgPool, tenant_id: Uuid, order_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    let order = sqlx::query(&format!("{} WHERE o.id = $1 AND p.tenant_id = $2", ORDER_SELECT))
+        .bind(order_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| order_json(&row))
+        .fetch_one(pool)
+        .await?;
+
+    let attachments = sqlx::query(
+        "SELECT id, kind, file_url, original_name, uploaded_by, uploaded_at
+         FROM maintenance_attachments WHERE order_id = $1 ORDER BY uploaded_at"
+    )
+    .bind(order_id)
+    .map(|row: PgRow| attachment_json(&row))
+    .fetch_all(pool)
+    .await?;
+
+    let mut order = order;
+    order["attachments"] = serde_json::Value::Array(attachments);
+    Ok(order)
+}
+
+async fn fetch_order(pool: &PgPool, tenant_id: Uuid, order_id: Uuid, status: Option<actix_web::http::StatusCode>) -> HttpResponse {
+    match load_order(pool, tenant_id, order_id).await {
+        Ok(order) => HttpResponse::build(status.unwrap_or(actix_web::http::StatusCode::OK)).json(order),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json("Maintenance order not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance order: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching maintenance order")
+        }
+    }
+}
+
+fn validate_cost(cost: Option<f64>) -> Result<(), HttpResponse> {
+    match cost {
+        Some(cost) if cost < 0.0 => Err(HttpResponse::BadRequest().json("Cost cannot be negative")),
+        _ => Ok(()),
+    }
+}
+
+/// Indica se o bem tem ordem de serviço aberta (o status só muda pela ordem)
+pub async fn has_open_order(pool: &PgPool, patrimony_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query("SELECT EXISTS(SELECT 1 FROM maintenance_orders WHERE patrimony_id = $1 AND status = $2) as open")
+        .bind(patrimony_id)
+        .bind(OPEN)
+        .fetch_one(pool)
+        .await
+        .map(|row| row.get::<bool, _>("open"))
+}
+
+/// Cancela a ordem aberta do bem sem restaurar o status (usado na baixa)
+pub async fn cancel_open_order(
+    transaction: &mut Transaction<'_, Postgres>,
+    patrimony_id: Uuid,
+    cancelled_by: Uuid,
+    resolution: &str,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        "UPDATE maintenance_orders SET status = $1, closed_by = $2, closed_at = NOW(), resolution = $3, updated_at = NOW()
+         WHERE patrimony_id = $4 AND status = $5"
+    )
+    .bind(CANCELLED)
+    .bind(cancelled_by)
+    .bind(resolution)
+    .bind(patrimony_id)
+    .bind(OPEN)
+    .execute(&mut **transaction)
+    .await
+    .map(|result| result.rows_affected())
+}
+
+pub async fn open_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    order: web::Json<OpenMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    if order.problem_description.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Problem description is required");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error opening maintenance order");
+        }
+    };
+
+    let current_status = match sqlx::query("SELECT department, status FROM patrimonies WHERE id = $1 AND deleted_at IS NULL FOR UPDATE")
+        .bind(patrimony_id)
+        .fetch_optional(&mut *transaction)
+        .await
+    {
+        Ok(Some(row)) => {
+            let status: String = row.get("status");
+            if status == WRITTEN_OFF {
+                return written_off_response();
+            }
+            if !belongs_to_department(&user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can open maintenance orders");
+            }
+            status
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error opening maintenance order");
+        }
+    };
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error opening maintenance order");
+        }
+    };
+
+    // Bem marcado manualmente como em manutenção volta a 'active' no fechamento
+    let previous_status = if current_status == MAINTENANCE { "active".to_string() } else { current_status };
+
+    let result = sqlx::query(
+        "INSERT INTO maintenance_orders (patrimony_id, problem_description, vendor, previous_status, opened_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id"
+    )
+    .bind(patrimony_id)
+    .bind(order.problem_description.trim())
+    .bind(order.vendor.as_deref().map(str::trim).filter(|vendor| !vendor.is_empty()))
+    .bind(&previous_status)
+    .bind(user.id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let order_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating maintenance order: {}", e);
+            let _ = transaction.rollback().await;
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Patrimony already has an open maintenance order");
+            }
+            return HttpResponse::InternalServerError().json("Error opening maintenance order");
+        }
+    };
+
+    if let Some(item_id) = order.due_item_id {
+        match maintenance_schedule::link_order(&mut transaction, item_id, patrimony_id, order_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let _ = transaction.rollback().await;
+                return HttpResponse::BadRequest().json("Pending maintenance item not found for this patrimony");
+            }
+            Err(e) => {
+                eprintln!("Error linking maintenance item: {}", e);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json("Error opening maintenance order");
+            }
+        }
+    }
+
+    if let Err(e) = sqlx::query("UPDATE patrimonies SET status = $1, updated_at = NOW() WHERE id = $2")
+        .bind(MAINTENANCE)
+        .bind(patrimony_id)
+        .execute(&mut *transaction)
+        .await
+    {
+        eprintln!("Error updating patrimony status: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error opening maintenance order");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::MAINTENANCE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error opening maintenance order");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error opening maintenance order");
+    }
+
+    println!("🔧 Ordem de serviço {} aberta para o patrimônio {}", order_id, patrimony_id);
+
+    fetch_order(pool.get_ref(), user.tenant_id, order_id, Some(actix_web::http::StatusCode::CREATED)).await
+}
+
+// Fecha ou cancela a ordem aberta e devolve o bem ao status anterior
+async fn finish_order(
+    pool: &PgPool,
+    user: &super::patrimony::User,
+    actor: &Actor,
+    order_id: Uuid,
+    status: &str,
+    resolution: Option<&str>,
+    cost: Option<f64>,
+) -> HttpResponse {
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating maintenance order");
+        }
+    };
+
+    let order = sqlx::query(
+        "SELECT o.patrimony_id, o.status, o.previous_status, p.department
+         FROM maintenance_orders o
+         JOIN patrimonies p ON o.patrimony_id = p.id
+         WHERE o.id = $1 AND p.tenant_id = $2
+         FOR UPDATE OF o, p"
+    )
+    .bind(order_id)
+    .bind(user.tenant_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let (patrimony_id, previous_status) = match order {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") != OPEN {
+                return HttpResponse::Conflict().json("Maintenance order is not open");
+            }
+            if !belongs_to_department(user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can update this maintenance order");
+            }
+            (row.get::<Uuid, _>("patrimony_id"), row.get::<String, _>("previous_status"))
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Maintenance order not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance order: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating maintenance order");
+        }
+    };
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating maintenance order");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE maintenance_orders SET status = $1, closed_by = $2, closed_at = NOW(),
+            resolution = COALESCE($3, resolution), cost = COALESCE($4, cost), updated_at = NOW()
+         WHERE id = $5"
+    )
+    .bind(status)
+    .bind(user.id)
+    .bind(resolution)
+    .bind(cost)
+    .bind(order_id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error closing maintenance order: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error updating maintenance order");
+    }
+
+    if let Err(e) = sqlx::query(
+        "UPDATE patrimonies SET status = $1, updated_at = NOW() WHERE id = $2 AND status = $3"
+    )
+    .bind(&previous_status)
+    .bind(patrimony_id)
+    .bind(MAINTENANCE)
+    .execute(&mut *transaction)
+    .await
+    {
+        eprintln!("Error restoring patrimony status: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error updating maintenance order");
+    }
+
+    let completed_by = if status == CLOSED { Some(user.id) } else { None };
+    if let Err(e) = maintenance_schedule::settle_order_items(&mut transaction, order_id, completed_by).await {
+        eprintln!("Error updating preventive maintenance items: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error updating maintenance order");
+    }
+
+    // Volta do bem ao status anterior fica na trilha de auditoria
+    if let Err(e) = audit::record_change(&mut transaction, actor, audit::PATRIMONY, patrimony_id, audit::MAINTENANCE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error updating maintenance order");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating maintenance order");
+    }
+
+    println!("🔧 Ordem de serviço {} finalizada ({})", order_id, status);
+
+    fetch_order(pool, user.tenant_id, order_id, None).await
+}
+
+pub async fn close_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    close: web::Json<CloseMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if let Err(response) = validate_cost(close.cost) {
+        return response;
+    }
+
+    finish_order(pool.get_ref(), &user, &Actor::new(&user, &req), id.into_inner(), CLOSED, close.resolution.as_deref(), close.cost).await
+}
+
+pub async fn cancel_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    cancel: web::Json<CancelMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    finish_order(pool.get_ref(), &user, &Actor::new(&user, &req), id.into_inner(), CANCELLED, cancel.reason.as_deref(), None).await
+}
+
+pub async fn update_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    order: web::Json<UpdateMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let order_id = id.into_inner();
+
+    if let Some(description) = &order.problem_description {
+        if description.trim().is_empty() {
+            return HttpResponse::BadRequest().json("Problem description is required");
+        }
+    }
+    if let Err(response) = validate_cost(order.cost) {
+        return response;
+    }
+
+    match sqlx::query(
+        "SELECT o.status, p.department FROM maintenance_orders o JOIN patrimonies p ON o.patrimony_id = p.id
+         WHERE o.id = $1 AND p.tenant_id = $2"
+    )
+    .bind(order_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") != OPEN {
+                return HttpResponse::Conflict().json("Only open maintenance orders can be edited");
+            }
+            if !belongs_to_department(&user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can update this maintenance order");
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Maintenance order not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance order: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating maintenance order");
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE maintenance_orders SET
+            problem_description = COALESCE($1, problem_description),
+            vendor = COALESCE($2, vendor),
+            cost = COALESCE($3, cost),
+            updated_at = NOW()
+         WHERE id = $4 AND status = $5"
+    )
+    .bind(order.problem_description.as_deref().map(str::trim))
+    .bind(order.vendor.as_deref().map(str::trim))
+    .bind(order.cost)
+    .bind(order_id)
+    .bind(OPEN)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::Conflict().json("Only open maintenance orders can be edited")
+        }
+        Ok(_) => fetch_order(pool.get_ref(), user.tenant_id, order_id, None).await,
+        Err(e) => {
+            eprintln!("Error updating maintenance order: {}", e);
+            HttpResponse::InternalServerError().json("Error updating maintenance order")
+        }
+    }
+}
+
+pub async fn get_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let order_id = id.into_inner();
+
+    // Ordem de bem fora dos departamentos visíveis responde como inexistente
+    match sqlx::query("SELECT patrimony_id FROM maintenance_orders WHERE id = $1")
+        .bind(order_id)
+        .map(|row: PgRow| row.get::<Uuid, _>("patrimony_id"))
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(patrimony_id)) => {
+            if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+                return response;
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Maintenance order not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance order: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching maintenance order");
+        }
+    }
+
+    fetch_order(pool.get_ref(), user.tenant_id, order_id, None).await
+}
+
+pub async fn get_maintenance_orders(
+    pool: web::Data<PgPool>,
+    query: web::Query<MaintenanceQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE p.tenant_id = $6
+           AND ($1::varchar IS NULL OR o.status = $1)
+           AND ($2::varchar IS NULL OR p.department = $2)
+           AND ($3::varchar IS NULL OR o.vendor ILIKE '%' || $3 || '%')
+           AND ($4::date IS NULL OR o.opened_at::date >= $4)
+           AND ($5::date IS NULL OR o.opened_at::date <= $5)
+           AND {}
+         ORDER BY o.opened_at DESC",
+        ORDER_SELECT,
+        department_grant::filter_sql("p.department", 7)
+    ))
+    .bind(&query.status)
+    .bind(&query.department)
+    .bind(&query.vendor)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| order_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(orders) => HttpResponse::Ok().json(orders),
+        Err(e) => {
+            eprintln!("Error fetching maintenance orders: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching maintenance orders")
+        }
+    }
+}
+
+// Histórico de manutenção do bem com o custo acumulado
+pub async fn get_patrimony_maintenance(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let patrimony = match sqlx::query("SELECT plate, name, value FROM patrimonies WHERE id = $1 AND deleted_at IS NULL")
+        .bind(patrimony_id)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching maintenance history");
+        }
+    };
+
+    let orders = sqlx::query(&format!("{} WHERE o.patrimony_id = $1 ORDER BY o.opened_at DESC", ORDER_SELECT))
+        .bind(patrimony_id)
+        .map(|row: PgRow| order_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    let orders = match orders {
+        Ok(orders) => orders,
+        Err(e) => {
+            eprintln!("Error fetching maintenance history: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching maintenance history");
+        }
+    };
+
+    let total_cost = depreciation::round_cents(
+        orders
+            .iter()
+            .filter(|order| order["status"] == CLOSED)
+            .filter_map(|order| order["cost"].as_f64())
+            .sum(),
+    );
+    let value = depreciation::optional_decimal(&patrimony, "value").unwrap_or(0.0);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "patrimony_id": patrimony_id,
+        "plate": patrimony.get::<String, _>("plate"),
+        "name": patrimony.get::<String, _>("name"),
+        "value": value,
+        "order_count": orders.len(),
+        "total_cost": total_cost,
+        // Custo de manutenção em % do valor de aquisição (apoia decisões de baixa)
+        "cost_percent_of_value": if value > 0.0 { Some(depreciation::round_cents(total_cost / value * 100.0)) } else { None },
+        "orders": orders
+    }))
+}
+
+// Custos de ordens fechadas por departamento e por bem, no período de fechamento
+pub async fn get_maintenance_costs(
+    pool: web::Data<PgPool>,
+    query: web::Query<MaintenanceQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let filter = format!(
+        "o.status = 'closed'
+         AND p.tenant_id = $4
+         AND ($1::varchar IS NULL OR p.department = $1)
+         AND ($2::date IS NULL OR o.closed_at::date >= $2)
+         AND ($3::date IS NULL OR o.closed_at::date <= $3)
+         AND {}",
+        department_grant::filter_sql("p.department", 5)
+    );
+
+    let by_department = sqlx::query(&format!(
+        "SELECT p.department, COUNT(*) as order_count, COALESCE(SUM(o.cost), 0) as total_cost
+         FROM maintenance_orders o
+         JOIN patrimonies p ON o.patrimony_id = p.id
+         WHERE {}
+         GROUP BY p.department
+         ORDER BY total_cost DESC",
+        filter
+    ))
+    .bind(&query.department)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "department": row.get::<String, _>("department"),
+            "order_count": row.get::<i64, _>("order_count"),
+            "total_cost": depreciation::optional_decimal(&row, "total_cost").unwrap_or(0.0)
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let by_department = match by_department {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching maintenance costs by department: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching maintenance costs");
+        }
+    };
+
+    let by_patrimony = sqlx::query(&format!(
+        "SELECT p.id, p.plate, p.name, p.department, p.value, COUNT(*) as order_count, COALESCE(SUM(o.cost), 0) as total_cost
+         FROM maintenance_orders o
+         JOIN patrimonies p ON o.patrimony_id = p.id
+         WHERE {}
+         GROUP BY p.id, p.plate, p.name, p.department, p.value
+         ORDER BY total_cost DESC",
+        filter
+    ))
+    .bind(&query.department)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "patrimony_id": row.get::<Uuid, _>("id"),
+            "plate": row.get::<String, _>("plate"),
+            "name": row.get::<String, _>("name"),
+            "department": row.get::<String, _>("department"),
+            "value": depreciation::optional_decimal(&row, "value").unwrap_or(0.0),
+            "order_count": row.get::<i64, _>("order_count"),
+            "total_cost": depreciation::optional_decimal(&row, "total_cost").unwrap_or(0.0)
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let by_patrimony = match by_patrimony {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching maintenance costs by patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching maintenance costs");
+        }
+    };
+
+    let total_cost = depreciation::round_cents(
+        by_department.iter().filter_map(|row| row["total_cost"].as_f64()).sum(),
+    );
+    let order_count: i64 = by_department.iter().filter_map(|row| row["order_count"].as_i64()).sum();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "department": query.department,
+        "from": query.from,
+        "to": query.to,
+        "order_count": order_count,
+        "total_cost": total_cost,
+        "by_department": by_department,
+        "by_patrimony": by_patrimony
+    }))
+}
+
+// Grava o anexo e registra o envio na trilha de auditoria do bem
+async fn store_attachment(
+    pool: &PgPool,
+    actor: &Actor,
+    patrimony_id: Uuid,
+    order_id: Uuid,
+    kind: &str,
+    file_url: &str,
+    original_name: &str,
+) -> Result<serde_json::Value, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let before = audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await?;
+
+    let stored = sqlx::query(
+        "INSERT INTO maintenance_attachments (order_id, kind, file_url, original_name, uploaded_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id, kind, file_url, original_name, uploaded_by, uploaded_at"
+    )
+    .bind(order_id)
+    .bind(kind)
+    .bind(file_url)
+    .bind(original_name)
+    .bind(actor.id)
+    .map(|row: PgRow| attachment_json(&row))
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    audit::record_change(&mut transaction, actor, audit::PATRIMONY, patrimony_id, audit::UPLOAD, before).await?;
+    transaction.commit().await?;
+    Ok(stored)
+}
+
+pub async fn upload_maintenance_attachment(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    query: web::Query<AttachmentQuery>,
+    mut payload: Multipart,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let order_id = id.into_inner();
+    let kind = query.kind.clone().unwrap_or_else(|| "other".to_string());
+    if !ATTACHMENT_KINDS.contains(&kind.as_str()) {
+        return HttpResponse::BadRequest().json(format!(
+            "Invalid attachment kind, expected one of: {}",
+            ATTACHMENT_KINDS.join(", ")
+        ));
+    }
+
+    let patrimony_id = match sqlx::query(
+        "SELECT o.patrimony_id, p.department FROM maintenance_orders o JOIN patrimonies p ON o.patrimony_id = p.id
+         WHERE o.id = $1 AND p.tenant_id = $2"
+    )
+    .bind(order_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(row)) => {
+            if !belongs_to_department(&user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can attach documents to this order");
+            }
+            row.get::<Uuid, _>("patrimony_id")
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Maintenance order not found"),
+        Err(e) => {
+            eprintln!("Erro ao verificar ordem de serviço: {:?}", e);
+            return HttpResponse::InternalServerError().json("Erro ao verificar ordem de serviço");
+        }
+    };
+
+    let docs_dir = "./documents";
+    if !Path::new(docs_dir).exists() {
+        if let Err(e) = fs::create_dir_all(docs_dir) {
+            eprintln!("Erro ao criar diretório de documentos: {:?}", e);
+            return HttpResponse::InternalServerError().json("Erro ao criar diretório de documentos");
+        }
+    }
+
+    let mut uploaded = Vec::new();
+
+    while let Ok(Some(mut field)) = payload.try_next().await {
+        let filename = field.content_disposition().get_filename().unwrap_or("anexo.pdf").to_string();
+        let extension = Path::new(&filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase())
+            .unwrap_or_default();
+        if !ATTACHMENT_EXTENSIONS.contains(&extension.as_str()) {
+            return HttpResponse::BadRequest().json("Os anexos devem ser PDF, JPG ou PNG");
+        }
+
+        let new_filename = format!("maintenance_{}.{}", Uuid::new_v4(), extension);
+        let filepath = format!("{}/{}", docs_dir, new_filename);
+
+        let mut file = match File::create(&filepath).await {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Erro ao criar arquivo: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao salvar documento");
+            }
+        };
+
+        while let Ok(Some(chunk)) = field.try_next().await {
+            if let Err(e) = file.write_all(&chunk).await {
+                eprintln!("Erro ao escrever arquivo: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao salvar dados do documento");
+            }
+        }
+
+        let file_url = format!("/documents/{}", new_filename);
+        let result = store_attachment(pool.get_ref(), &Actor::new(&user, &req), patrimony_id, order_id, &kind, &file_url, &filename).await;
+
+        match result {
+            Ok(attachment) => {
+                println!("✅ Anexo da ordem de serviço salvo: {}", file_url);
+                uploaded.push(attachment);
+            }
+            Err(e) => {
+                eprintln!("❌ Erro ao registrar anexo: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao registrar anexo");
+            }
+        }
+    }
+
+    if uploaded.is_empty() {
+        return HttpResponse::BadRequest().json("Nenhum documento fornecido");
+    }
+
+    HttpResponse::Created().json(uploaded)
+}
@@ -0,0 +1,1035 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, ensure_visible, User};
+use super::transfer::belongs_to_department;
+use super::write_off::{written_off_response, WRITTEN_OFF};
+use super::depreciation;
+use super::department_grant;
+use super::permissions;
+
+// Estados dos itens gerados pela rotina
+pub const DUE: &str = "due";
+pub const OVERDUE: &str = "overdue";
+pub const DONE: &str = "done";
+pub const SKIPPED: &str = "skipped";
+
+const DEFAULT_SCHEDULER_INTERVAL_SECS: u64 = 3600;
+
+#[derive(Deserialize)]
+pub struct CreateMaintenanceSchedule {
+    pub name: String,
+    pub description: Option<String>,
+    pub patrimony_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub interval_days: Option<i32>,
+    pub interval_meter: Option<f64>,
+    pub meter_unit: Option<String>,
+    pub lead_days: Option<i32>,
+    pub lead_meter: Option<f64>,
+    pub start_date: Option<NaiveDate>,
+    pub start_meter: Option<f64>,
+}
+
+// O alvo (bem ou categoria) não muda; informar um intervalo troca o tipo do plano
+#[derive(Deserialize)]
+pub struct UpdateMaintenanceSchedule {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub interval_days: Option<i32>,
+    pub interval_meter: Option<f64>,
+    pub meter_unit: Option<String>,
+    pub lead_days: Option<i32>,
+    pub lead_meter: Option<f64>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct ScheduleQuery {
+    pub patrimony_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub active: Option<bool>,
+}
+
+#[derive(Deserialize)]
+pub struct UpcomingQuery {
+    pub department: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResolveDueItem {
+    pub meter_reading: Option<f64>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateMeterReading {
+    pub reading: f64,
+    pub notes: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct GenerationSummary {
+    pub evaluated: usize,
+    pub created: usize,
+    pub escalated: usize,
+}
+
+const SCHEDULE_SELECT: &str =
+    "SELECT s.id, s.name, s.description, s.patrimony_id, p.plate, s.category_id, c.code as category_code,
+            s.interval_days, s.interval_meter, s.meter_unit, s.lead_days, s.lead_meter, s.start_date, s.start_meter, s.active,
+            s.created_by, s.created_at, s.updated_at,
+            (SELECT COUNT(*) FROM maintenance_due_items d WHERE d.schedule_id = s.id AND d.status IN ('due', 'overdue')) as pending_count
+     FROM maintenance_schedules s
+     LEFT JOIN patrimonies p ON s.patrimony_id = p.id
+     LEFT JOIN categories c ON s.category_id = c.id";
+
+// Entidade dona do plano: a do bem ou a da categoria
+const SCHEDULE_TENANT_SQL: &str = "COALESCE(p.tenant_id, c.tenant_id)";
+
+const LATEST_READING_SQL: &str =
+    "(SELECT r.reading FROM patrimony_meter_readings r WHERE r.patrimony_id = p.id ORDER BY r.recorded_at DESC LIMIT 1)";
+
+fn schedule_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "name": row.get::<String, _>("name"),
+        "description": row.get::<Option<String>, _>("description"),
+        "patrimony_id": row.get::<Option<Uuid>, _>("patrimony_id"),
+        "plate": row.get::<Option<String>, _>("plate"),
+        "category_id": row.get::<Option<Uuid>, _>("category_id"),
+        "category_code": row.get::<Option<String>, _>("category_code"),
+        "interval_days": row.get::<Option<i32>, _>("interval_days"),
+        "interval_meter": depreciation::optional_decimal(row, "interval_meter"),
+        "meter_unit": row.get::<Option<String>, _>("meter_unit"),
+        "lead_days": row.get::<i32, _>("lead_days"),
+        "lead_meter": depreciation::optional_decimal(row, "lead_meter"),
+        "start_date": row.get::<NaiveDate, _>("start_date"),
+        "start_meter": depreciation::optional_decimal(row, "start_meter"),
+        "active": row.get::<bool, _>("active"),
+        "pending_count": row.get::<i64, _>("pending_count"),
+        "created_by": row.get::<Option<Uuid>, _>("created_by"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+async fn fetch_schedule(pool: &PgPool, tenant_id: Uuid, schedule_id: Uuid, status: Option<actix_web::http::StatusCode>) -> HttpResponse {
+    match sqlx::query(&format!("{} WHERE s.id = $1 AND {} = $2", SCHEDULE_SELECT, SCHEDULE_TENANT_SQL))
+        .bind(schedule_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| schedule_json(&row))
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(schedule)) => HttpResponse::build(status.unwrap_or(actix_web::http::StatusCode::OK)).json(schedule),
+        Ok(None) => HttpResponse::NotFound().json("Maintenance schedule not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance schedule: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching maintenance schedule")
+        }
+    }
+}
+
+// Planos de um bem seguem o departamento do bem; planos de categoria exigem permissão própria
+async fn authorize_target(pool: &PgPool, user: &User, patrimony_id: Option<Uuid>) -> Result<(), HttpResponse> {
+    let Some(patrimony_id) = patrimony_id else {
+        if !user.can(permissions::MAINTENANCE_PLAN) {
+            return Err(HttpResponse::Forbidden().json(format!("Permission '{}' required for category schedules", permissions::MAINTENANCE_PLAN)));
+        }
+        return Ok(());
+    };
+
+    match sqlx::query("SELECT department, status FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL")
+        .bind(patrimony_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") == WRITTEN_OFF {
+                return Err(written_off_response());
+            }
+            if !belongs_to_department(user, &row.get::<String, _>("department")) {
+                return Err(HttpResponse::Forbidden().json("Only the patrimony department can manage its maintenance schedules"));
+            }
+            Ok(())
+        }
+        Ok(None) => Err(HttpResponse::BadRequest().json("Patrimony not found")),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating patrimony"))
+        }
+    }
+}
+
+fn validate_intervals(
+    interval_days: Option<i32>,
+    interval_meter: Option<f64>,
+    lead_days: Option<i32>,
+    lead_meter: Option<f64>,
+) -> Result<(), HttpResponse> {
+    if interval_days.is_some() && interval_meter.is_some() {
+        return Err(HttpResponse::BadRequest().json("Use either interval_days or interval_meter, not both"));
+    }
+    if interval_days.is_some_and(|days| days <= 0) || interval_meter.is_some_and(|meter| meter <= 0.0) {
+        return Err(HttpResponse::BadRequest().json("Maintenance interval must be greater than zero"));
+    }
+    if lead_days.is_some_and(|days| days < 0) || lead_meter.is_some_and(|meter| meter < 0.0) {
+        return Err(HttpResponse::BadRequest().json("Alert lead cannot be negative"));
+    }
+    Ok(())
+}
+
+// Próximo vencimento de um plano para um bem, a partir da última conclusão ou do início do plano
+struct Evaluation {
+    status: &'static str,
+    due_date: Option<NaiveDate>,
+    due_meter: Option<f64>,
+}
+
+fn evaluate(row: &PgRow, today: NaiveDate) -> Option<Evaluation> {
+    if let Some(days) = row.get::<Option<i32>, _>("interval_days") {
+        let baseline = row
+            .get::<Option<NaiveDate>, _>("last_done_date")
+            .unwrap_or_else(|| row.get::<NaiveDate, _>("start_date"));
+        let due_date = baseline + Duration::days(days as i64);
+        let lead = Duration::days(row.get::<i32, _>("lead_days") as i64);
+
+        let status = if today > due_date {
+            OVERDUE
+        } else if today >= due_date - lead {
+            DUE
+        } else {
+            return None;
+        };
+        return Some(Evaluation { status, due_date: Some(due_date), due_meter: None });
+    }
+
+    let interval = depreciation::optional_decimal(row, "interval_meter")?;
+    let current = depreciation::optional_decimal(row, "current_meter")?;
+    let baseline = depreciation::optional_decimal(row, "last_done_meter")
+        .or_else(|| depreciation::optional_decimal(row, "start_meter"))?;
+    let due_meter = baseline + interval;
+    let lead = depreciation::optional_decimal(row, "lead_meter").unwrap_or(0.0);
+
+    let status = if current > due_meter {
+        OVERDUE
+    } else if current >= due_meter - lead {
+        DUE
+    } else {
+        return None;
+    };
+    Some(Evaluation { status, due_date: None, due_meter: Some(due_meter) })
+}
+
+/// Gera itens a vencer/vencidos para os planos ativos e escala os pendentes que venceram
+pub async fn generate_due_items(pool: &PgPool) -> Result<GenerationSummary, sqlx::Error> {
+    let targets = sqlx::query(&format!(
+        "WITH RECURSIVE category_tree AS (
+            SELECT id as root_id, id FROM categories
+            UNION ALL
+            SELECT t.root_id, c.id FROM categories c JOIN category_tree t ON c.parent_id = t.id
+         )
+         SELECT s.id as schedule_id, p.id as patrimony_id, s.interval_days, s.interval_meter,
+                s.lead_days, s.lead_meter, s.start_date,
+                last_done.completed_at::date as last_done_date, last_done.completed_meter as last_done_meter,
+                pending.id as pending_id, pending.status as pending_status,
+                pending.due_date as pending_due_date, pending.due_meter as pending_due_meter,
+                {} as current_meter,
+                COALESCE(
+                    s.start_meter,
+                    (SELECT r.reading FROM patrimony_meter_readings r
+                     WHERE r.patrimony_id = p.id AND r.recorded_at::date < s.start_date
+                     ORDER BY r.recorded_at DESC LIMIT 1),
+                    (SELECT r.reading FROM patrimony_meter_readings r
+                     WHERE r.patrimony_id = p.id ORDER BY r.recorded_at LIMIT 1)
+                ) as start_meter
+         FROM maintenance_schedules s
+         JOIN patrimonies p ON p.id = s.patrimony_id
+             OR p.category_id IN (SELECT id FROM category_tree WHERE root_id = s.category_id)
+         LEFT JOIN LATERAL (
+             SELECT d.completed_at, d.completed_meter FROM maintenance_due_items d
+             WHERE d.schedule_id = s.id AND d.patrimony_id = p.id AND d.status IN ('done', 'skipped')
+             ORDER BY d.completed_at DESC LIMIT 1
+         ) last_done ON TRUE
+         LEFT JOIN LATERAL (
+             SELECT d.id, d.status, d.due_date, d.due_meter FROM maintenance_due_items d
+             WHERE d.schedule_id = s.id AND d.patrimony_id = p.id AND d.status IN ('due', 'overdue')
+         ) pending ON TRUE
+         WHERE s.active AND p.status <> $1 AND p.deleted_at IS NULL",
+        LATEST_READING_SQL
+    ))
+    .bind(WRITTEN_OFF)
+    .fetch_all(pool)
+    .await?;
+
+    let today = Utc::now().date_naive();
+    let mut summary = GenerationSummary { evaluated: targets.len(), ..Default::default() };
+
+    for row in &targets {
+        // Item já pendente: só passa de 'due' para 'overdue' quando o vencimento é ultrapassado
+        if let Some(pending_id) = row.get::<Option<Uuid>, _>("pending_id") {
+            if row.get::<Option<String>, _>("pending_status").as_deref() != Some(DUE) {
+                continue;
+            }
+            let date_passed = row
+                .get::<Option<NaiveDate>, _>("pending_due_date")
+                .is_some_and(|due_date| today > due_date);
+            let meter_passed = match (
+                depreciation::optional_decimal(row, "pending_due_meter"),
+                depreciation::optional_decimal(row, "current_meter"),
+            ) {
+                (Some(due_meter), Some(current)) => current > due_meter,
+                _ => false,
+            };
+            if date_passed || meter_passed {
+                sqlx::query("UPDATE maintenance_due_items SET status = $1, updated_at = NOW() WHERE id = $2 AND status = $3")
+                    .bind(OVERDUE)
+                    .bind(pending_id)
+                    .bind(DUE)
+                    .execute(pool)
+                    .await?;
+                summary.escalated += 1;
+            }
+            continue;
+        }
+
+        let Some(evaluation) = evaluate(row, today) else {
+            continue;
+        };
+
+        let result = sqlx::query(
+            "INSERT INTO maintenance_due_items (schedule_id, patrimony_id, status, due_date, due_meter)
+             VALUES ($1, $2, $3, $4, $5)
+             ON CONFLICT DO NOTHING"
+        )
+        .bind(row.get::<Uuid, _>("schedule_id"))
+        .bind(row.get::<Uuid, _>("patrimony_id"))
+        .bind(evaluation.status)
+        .bind(evaluation.due_date)
+        .bind(evaluation.due_meter)
+        .execute(pool)
+        .await?;
+        summary.created += result.rows_affected() as usize;
+    }
+
+    Ok(summary)
+}
+
+/// Rotina em segundo plano que gera os itens periodicamente
+pub async fn run_scheduler(pool: PgPool) {
+    let seconds = env::var("MAINTENANCE_SCHEDULER_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_SCHEDULER_INTERVAL_SECS);
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(seconds));
+
+    loop {
+        interval.tick().await;
+        match generate_due_items(&pool).await {
+            Ok(summary) => {
+                if summary.created > 0 || summary.escalated > 0 {
+                    println!(
+                        "🗓️ Manutenção preventiva: {} itens gerados, {} vencidos",
+                        summary.created, summary.escalated
+                    );
+                }
+            }
+            Err(e) => eprintln!("Error generating preventive maintenance items: {}", e),
+        }
+    }
+}
+
+/// Vincula um item pendente à ordem de serviço aberta para atendê-lo
+pub async fn link_order(
+    transaction: &mut Transaction<'_, Postgres>,
+    item_id: Uuid,
+    patrimony_id: Uuid,
+    order_id: Uuid,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "UPDATE maintenance_due_items SET order_id = $1, updated_at = NOW()
+         WHERE id = $2 AND patrimony_id = $3 AND status IN ('due', 'overdue') AND order_id IS NULL"
+    )
+    .bind(order_id)
+    .bind(item_id)
+    .bind(patrimony_id)
+    .execute(&mut **transaction)
+    .await
+    .map(|result| result.rows_affected() > 0)
+}
+
+/// Fechar a ordem conclui os itens vinculados; cancelá-la apenas os desvincula
+pub async fn settle_order_items(
+    transaction: &mut Transaction<'_, Postgres>,
+    order_id: Uuid,
+    completed_by: Option<Uuid>,
+) -> Result<u64, sqlx::Error> {
+    let result = match completed_by {
+        Some(user_id) => {
+            sqlx::query(
+                "UPDATE maintenance_due_items d SET status = 'done', completed_by = $2, completed_at = NOW(),
+                    completed_meter = (SELECT r.reading FROM patrimony_meter_readings r
+                                       WHERE r.patrimony_id = d.patrimony_id ORDER BY r.recorded_at DESC LIMIT 1),
+                    updated_at = NOW()
+                 WHERE d.order_id = $1 AND d.status IN ('due', 'overdue')"
+            )
+            .bind(order_id)
+            .bind(user_id)
+            .execute(&mut **transaction)
+            .await?
+        }
+        None => {
+            sqlx::query(
+                "UPDATE maintenance_due_items SET order_id = NULL, updated_at = NOW()
+                 WHERE order_id = $1 AND status IN ('due', 'overdue')"
+            )
+            .bind(order_id)
+            .execute(&mut **transaction)
+            .await?
+        }
+    };
+    Ok(result.rows_affected())
+}
+
+pub async fn get_maintenance_schedules(
+    pool: web::Data<PgPool>,
+    query: web::Query<ScheduleQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE {} = $4
+           AND ($1::uuid IS NULL OR s.patrimony_id = $1)
+           AND ($2::uuid IS NULL OR s.category_id = $2)
+           AND ($3::boolean IS NULL OR s.active = $3)
+           AND (s.patrimony_id IS NULL OR {})
+         ORDER BY s.name",
+        SCHEDULE_SELECT, SCHEDULE_TENANT_SQL, department_grant::filter_sql("p.department", 5)
+    ))
+    .bind(query.patrimony_id)
+    .bind(query.category_id)
+    .bind(query.active)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| schedule_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(e) => {
+            eprintln!("Error fetching maintenance schedules: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching maintenance schedules")
+        }
+    }
+}
+
+pub async fn get_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    fetch_schedule(pool.get_ref(), user.tenant_id, id.into_inner(), None).await
+}
+
+pub async fn create_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    schedule: web::Json<CreateMaintenanceSchedule>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if schedule.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Schedule name is required");
+    }
+    if schedule.patrimony_id.is_some() == schedule.category_id.is_some() {
+        return HttpResponse::BadRequest().json("Provide either patrimony_id or category_id");
+    }
+    if schedule.interval_days.is_none() && schedule.interval_meter.is_none() {
+        return HttpResponse::BadRequest().json("Provide either interval_days or interval_meter");
+    }
+    if schedule.start_meter.is_some_and(|meter| meter < 0.0) {
+        return HttpResponse::BadRequest().json("Start meter cannot be negative");
+    }
+    if let Err(response) = validate_intervals(
+        schedule.interval_days,
+        schedule.interval_meter,
+        schedule.lead_days,
+        schedule.lead_meter,
+    ) {
+        return response;
+    }
+    if let Err(response) = authorize_target(pool.get_ref(), &user, schedule.patrimony_id).await {
+        return response;
+    }
+    if let Some(category_id) = schedule.category_id {
+        match sqlx::query("SELECT 1 FROM categories WHERE id = $1 AND tenant_id = $2")
+            .bind(category_id)
+            .bind(user.tenant_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return HttpResponse::BadRequest().json("Category not found"),
+            Err(e) => {
+                eprintln!("Error fetching category: {}", e);
+                return HttpResponse::InternalServerError().json("Error creating maintenance schedule");
+            }
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO maintenance_schedules
+            (name, description, patrimony_id, category_id, interval_days, interval_meter, meter_unit,
+             lead_days, lead_meter, start_date, start_meter, created_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, COALESCE($8, 7), COALESCE($9, 0), COALESCE($10, CURRENT_DATE), $11, $12)
+         RETURNING id"
+    )
+    .bind(schedule.name.trim())
+    .bind(&schedule.description)
+    .bind(schedule.patrimony_id)
+    .bind(schedule.category_id)
+    .bind(schedule.interval_days)
+    .bind(schedule.interval_meter)
+    .bind(&schedule.meter_unit)
+    .bind(schedule.lead_days)
+    .bind(schedule.lead_meter)
+    .bind(schedule.start_date)
+    .bind(schedule.start_meter)
+    .bind(user.id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(schedule_id) => {
+            println!("🗓️ Plano de manutenção criado: {}", schedule.name.trim());
+            fetch_schedule(pool.get_ref(), user.tenant_id, schedule_id, Some(actix_web::http::StatusCode::CREATED)).await
+        }
+        Err(e) => {
+            eprintln!("Error creating maintenance schedule: {}", e);
+            HttpResponse::InternalServerError().json("Error creating maintenance schedule")
+        }
+    }
+}
+
+// Carrega o bem alvo do plano para checar a permissão de quem altera
+async fn schedule_target(pool: &PgPool, tenant_id: Uuid, schedule_id: Uuid) -> Result<Option<Uuid>, HttpResponse> {
+    match sqlx::query(&format!(
+        "SELECT s.patrimony_id FROM maintenance_schedules s
+         LEFT JOIN patrimonies p ON s.patrimony_id = p.id
+         LEFT JOIN categories c ON s.category_id = c.id
+         WHERE s.id = $1 AND {} = $2",
+        SCHEDULE_TENANT_SQL
+    ))
+    .bind(schedule_id)
+    .bind(tenant_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(row)) => Ok(row.get::<Option<Uuid>, _>("patrimony_id")),
+        Ok(None) => Err(HttpResponse::NotFound().json("Maintenance schedule not found")),
+        Err(e) => {
+            eprintln!("Error fetching maintenance schedule: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error fetching maintenance schedule"))
+        }
+    }
+}
+
+pub async fn update_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    schedule: web::Json<UpdateMaintenanceSchedule>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let schedule_id = id.into_inner();
+
+    if schedule.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+        return HttpResponse::BadRequest().json("Schedule name is required");
+    }
+    if let Err(response) = validate_intervals(
+        schedule.interval_days,
+        schedule.interval_meter,
+        schedule.lead_days,
+        schedule.lead_meter,
+    ) {
+        return response;
+    }
+    let patrimony_id = match schedule_target(pool.get_ref(), user.tenant_id, schedule_id).await {
+        Ok(patrimony_id) => patrimony_id,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_target(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "UPDATE maintenance_schedules SET
+            name = COALESCE($1, name),
+            description = COALESCE($2, description),
+            interval_days = CASE WHEN $3::integer IS NOT NULL THEN $3 WHEN $4::numeric IS NOT NULL THEN NULL ELSE interval_days END,
+            interval_meter = CASE WHEN $4::numeric IS NOT NULL THEN $4 WHEN $3::integer IS NOT NULL THEN NULL ELSE interval_meter END,
+            meter_unit = COALESCE($5, meter_unit),
+            lead_days = COALESCE($6, lead_days),
+            lead_meter = COALESCE($7, lead_meter),
+            active = COALESCE($8, active),
+            updated_at = NOW()
+         WHERE id = $9"
+    )
+    .bind(schedule.name.as_deref().map(str::trim))
+    .bind(&schedule.description)
+    .bind(schedule.interval_days)
+    .bind(schedule.interval_meter)
+    .bind(&schedule.meter_unit)
+    .bind(schedule.lead_days)
+    .bind(schedule.lead_meter)
+    .bind(schedule.active)
+    .bind(schedule_id)
+    .execute(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(_) => fetch_schedule(pool.get_ref(), user.tenant_id, schedule_id, None).await,
+        Err(e) => {
+            eprintln!("Error updating maintenance schedule: {}", e);
+            HttpResponse::InternalServerError().json("Error updating maintenance schedule")
+        }
+    }
+}
+
+pub async fn delete_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let schedule_id = id.into_inner();
+
+    let patrimony_id = match schedule_target(pool.get_ref(), user.tenant_id, schedule_id).await {
+        Ok(patrimony_id) => patrimony_id,
+        Err(response) => return response,
+    };
+    if let Err(response) = authorize_target(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    // Planos com histórico de itens não são removidos
+    match sqlx::query("SELECT EXISTS(SELECT 1 FROM maintenance_due_items WHERE schedule_id = $1) as in_use")
+        .bind(schedule_id)
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(row) if row.get::<bool, _>("in_use") => {
+            return HttpResponse::Conflict().json("Maintenance schedule has generated items; deactivate it instead");
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error checking maintenance schedule usage: {}", e);
+            return HttpResponse::InternalServerError().json("Error deleting maintenance schedule");
+        }
+    }
+
+    match sqlx::query("DELETE FROM maintenance_schedules WHERE id = $1")
+        .bind(schedule_id)
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => {
+            eprintln!("Error deleting maintenance schedule: {}", e);
+            HttpResponse::InternalServerError().json("Error deleting maintenance schedule")
+        }
+    }
+}
+
+// Executa a rotina de geração imediatamente (sem esperar o próximo ciclo)
+pub async fn run_maintenance_schedules(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match generate_due_items(pool.get_ref()).await {
+        Ok(summary) => HttpResponse::Ok().json(summary),
+        Err(e) => {
+            eprintln!("Error generating preventive maintenance items: {}", e);
+            HttpResponse::InternalServerError().json("Error generating preventive maintenance items")
+        }
+    }
+}
+
+// Manutenções preventivas a vencer e vencidas, agrupadas por departamento
+pub async fn get_upcoming_maintenance(
+    pool: web::Data<PgPool>,
+    query: web::Query<UpcomingQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if let Some(status) = &query.status {
+        if status != DUE && status != OVERDUE {
+            return HttpResponse::BadRequest().json("Status must be 'due' or 'overdue'");
+        }
+    }
+
+    let result = sqlx::query(&format!(
+        "SELECT d.id, d.schedule_id, s.name as schedule_name, d.patrimony_id, p.plate, p.name as patrimony_name,
+                p.department, d.status, d.due_date, (d.due_date - CURRENT_DATE) as days_until_due,
+                d.due_meter, s.meter_unit, {} as current_meter, d.order_id, d.generated_at
+         FROM maintenance_due_items d
+         JOIN maintenance_schedules s ON d.schedule_id = s.id
+         JOIN patrimonies p ON d.patrimony_id = p.id
+         WHERE d.status IN ('due', 'overdue')
+           AND p.tenant_id = $4
+           AND p.status <> $1
+           AND p.deleted_at IS NULL
+           AND ($2::varchar IS NULL OR p.department = $2)
+           AND ($3::varchar IS NULL OR d.status = $3)
+           AND {}
+         ORDER BY p.department, d.status = 'overdue' DESC, d.due_date NULLS LAST, p.plate",
+        LATEST_READING_SQL,
+        department_grant::filter_sql("p.department", 5)
+    ))
+    .bind(WRITTEN_OFF)
+    .bind(&query.department)
+    .bind(&query.status)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "schedule_id": row.get::<Uuid, _>("schedule_id"),
+            "schedule_name": row.get::<String, _>("schedule_name"),
+            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+            "plate": row.get::<String, _>("plate"),
+            "patrimony_name": row.get::<String, _>("patrimony_name"),
+            "department": row.get::<String, _>("department"),
+            "status": row.get::<String, _>("status"),
+            "due_date": row.get::<Option<NaiveDate>, _>("due_date"),
+            "days_until_due": row.get::<Option<i32>, _>("days_until_due"),
+            "due_meter": depreciation::optional_decimal(&row, "due_meter"),
+            "current_meter": depreciation::optional_decimal(&row, "current_meter"),
+            "meter_unit": row.get::<Option<String>, _>("meter_unit"),
+            "order_id": row.get::<Option<Uuid>, _>("order_id"),
+            "generated_at": row.get::<chrono::DateTime<Utc>, _>("generated_at")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let items = match result {
+        Ok(items) => items,
+        Err(e) => {
+            eprintln!("Error fetching upcoming maintenance: {}", e);
+            return HttpResponse::InternalServerError().json("Error fetching upcoming maintenance");
+        }
+    };
+
+    let overdue_count = items.iter().filter(|item| item["status"] == OVERDUE).count();
+    let mut by_department: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for item in items {
+        let department = item["department"].as_str().unwrap_or_default().to_string();
+        by_department.entry(department).or_default().push(item);
+    }
+
+    let departments: Vec<serde_json::Value> = by_department
+        .into_iter()
+        .map(|(department, items)| {
+            let overdue = items.iter().filter(|item| item["status"] == OVERDUE).count();
+            serde_json::json!({
+                "department": department,
+                "overdue_count": overdue,
+                "due_count": items.len() - overdue,
+                "items": items
+            })
+        })
+        .collect();
+    let total: usize = departments.iter().filter_map(|d| d["items"].as_array().map(Vec::len)).sum();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "overdue_count": overdue_count,
+        "due_count": total - overdue_count,
+        "by_department": departments
+    }))
+}
+
+// Conclui ou dispensa um item pendente; o próximo ciclo conta a partir desta data/leitura
+async fn resolve_due_item(
+    pool: &PgPool,
+    user: &User,
+    item_id: Uuid,
+    status: &str,
+    resolve: &ResolveDueItem,
+) -> HttpResponse {
+    if resolve.meter_reading.is_some_and(|reading| reading < 0.0) {
+        return HttpResponse::BadRequest().json("Meter reading cannot be negative");
+    }
+
+    match sqlx::query(
+        "SELECT d.status, p.department FROM maintenance_due_items d JOIN patrimonies p ON d.patrimony_id = p.id
+         WHERE d.id = $1 AND p.tenant_id = $2"
+    )
+    .bind(item_id)
+    .bind(user.tenant_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(row)) => {
+            let current: String = row.get("status");
+            if current != DUE && current != OVERDUE {
+                return HttpResponse::Conflict().json("Maintenance item is not pending");
+            }
+            if !belongs_to_department(user, &row.get::<String, _>("department")) {
+                return HttpResponse::Forbidden().json("Only the patrimony department can resolve this maintenance item");
+            }
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Maintenance item not found"),
+        Err(e) => {
+            eprintln!("Error fetching maintenance item: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating maintenance item");
+        }
+    }
+
+    let result = sqlx::query(
+        "UPDATE maintenance_due_items d SET status = $1, completed_by = $2, completed_at = NOW(),
+            completed_meter = COALESCE($3, (SELECT r.reading FROM patrimony_meter_readings r
+                                            WHERE r.patrimony_id = d.patrimony_id ORDER BY r.recorded_at DESC LIMIT 1)),
+            notes = COALESCE($4, notes), updated_at = NOW()
+         WHERE d.id = $5 AND d.status IN ('due', 'overdue')
+         RETURNING d.id, d.schedule_id, d.patrimony_id, d.status, d.due_date, d.due_meter, d.order_id,
+                   d.completed_by, d.completed_at, d.completed_meter, d.notes"
+    )
+    .bind(status)
+    .bind(user.id)
+    .bind(resolve.meter_reading)
+    .bind(&resolve.notes)
+    .bind(item_id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "schedule_id": row.get::<Uuid, _>("schedule_id"),
+            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+            "status": row.get::<String, _>("status"),
+            "due_date": row.get::<Option<NaiveDate>, _>("due_date"),
+            "due_meter": depreciation::optional_decimal(&row, "due_meter"),
+            "order_id": row.get::<Option<Uuid>, _>("order_id"),
+            "completed_by": row.get::<Option<Uuid>, _>("completed_by"),
+            "completed_at": row.get::<Option<chrono::DateTime<Utc>>, _>("completed_at"),
+            "completed_meter": depreciation::optional_decimal(&row, "completed_meter"),
+            "notes": row.get::<Option<String>, _>("notes")
+        })
+    })
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(item)) => HttpResponse::Ok().json(item),
+        Ok(None) => HttpResponse::Conflict().json("Maintenance item is not pending"),
+        Err(e) => {
+            eprintln!("Error updating maintenance item: {}", e);
+            HttpResponse::InternalServerError().json("Error updating maintenance item")
+        }
+    }
+}
+
+pub async fn complete_due_item(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    resolve: web::Json<ResolveDueItem>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    resolve_due_item(pool.get_ref(), &user, id.into_inner(), DONE, &resolve).await
+}
+
+pub async fn skip_due_item(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    resolve: web::Json<ResolveDueItem>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    resolve_due_item(pool.get_ref(), &user, id.into_inner(), SKIPPED, &resolve).await
+}
+
+fn reading_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+        "reading": depreciation::optional_decimal(row, "reading"),
+        "notes": row.get::<Option<String>, _>("notes"),
+        "recorded_by": row.get::<Option<Uuid>, _>("recorded_by"),
+        "recorded_at": row.get::<chrono::DateTime<Utc>, _>("recorded_at")
+    })
+}
+
+pub async fn create_meter_reading(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    reading: web::Json<CreateMeterReading>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    if reading.reading < 0.0 {
+        return HttpResponse::BadRequest().json("Meter reading cannot be negative");
+    }
+    if let Err(response) = authorize_target(pool.get_ref(), &user, Some(patrimony_id)).await {
+        return response;
+    }
+
+    // Hodômetros e horímetros só avançam
+    match sqlx::query(
+        "SELECT reading FROM patrimony_meter_readings WHERE patrimony_id = $1 ORDER BY recorded_at DESC LIMIT 1"
+    )
+    .bind(patrimony_id)
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(row)) => {
+            let last = depreciation::optional_decimal(&row, "reading").unwrap_or(0.0);
+            if reading.reading < last {
+                return HttpResponse::BadRequest().json(format!(
+                    "Meter reading cannot be lower than the last reading ({})",
+                    last
+                ));
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("Error fetching meter readings: {}", e);
+            return HttpResponse::InternalServerError().json("Error recording meter reading");
+        }
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO patrimony_meter_readings (patrimony_id, reading, notes, recorded_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING id, patrimony_id, reading, notes, recorded_by, recorded_at"
+    )
+    .bind(patrimony_id)
+    .bind(reading.reading)
+    .bind(&reading.notes)
+    .bind(user.id)
+    .map(|row: PgRow| reading_json(&row))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(reading) => HttpResponse::Created().json(reading),
+        Err(e) => {
+            eprintln!("Error recording meter reading: {}", e);
+            HttpResponse::InternalServerError().json("Error recording meter reading")
+        }
+    }
+}
+
+pub async fn get_meter_readings(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "SELECT id, patrimony_id, reading, notes, recorded_by, recorded_at
+         FROM patrimony_meter_readings WHERE patrimony_id = $1 ORDER BY recorded_at DESC"
+    )
+    .bind(patrimony_id)
+    .map(|row: PgRow| reading_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(readings) => HttpResponse::Ok().json(readings),
+        Err(e) => {
+            eprintln!("Error fetching meter readings: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching meter readings")
+        }
+    }
+}
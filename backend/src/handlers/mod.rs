@@ -1,6 +1,30 @@
 // handlers/mod.rs
 pub mod patrimony;
 pub mod transfer;
+pub mod depreciation;
+pub mod write_off;
+pub mod inventory;
+pub mod labels;
+pub mod plate_sequence;
+pub mod custody;
+pub mod location;
+pub mod department;
+pub mod category;
+pub mod maintenance;
+pub mod maintenance_schedule;
+pub mod audit;
+pub mod audit_chain;
+pub mod trash;
+pub mod tenant;
+pub mod permissions;
+pub mod department_grant;
+pub mod session;
+pub mod user;
+pub mod password_policy;
+pub mod profile;
+pub mod password_reset;
+pub mod login_security;
+pub mod two_factor;
 
 use actix_web::{web, HttpResponse, HttpRequest};
 use actix_multipart::Multipart;
@@ -10,28 +34,94 @@ use uuid::Uuid;
 // Re-export dos tipos para facilitar o uso
 pub use patrimony::{
     CreatePatrimony, UpdatePatrimony, DepartmentQuery, 
-    CreateUser, LoginRequest, User, LoginResponse
+    CreateUser, RegisterUser, LoginRequest, User, LoginResponse
 };
-pub use transfer::CreateTransfer;
+pub use transfer::{CreateTransfer, CreateTransferBatch, RejectTransfer, PendingTransfersQuery, TransferListQuery};
+pub use depreciation::{RunDepreciationRequest, DepreciationEntriesQuery};
+pub use write_off::{CreateWriteOff, WriteOffQuery};
+pub use inventory::{CreateCampaign, CampaignQuery, CreateSession, ScanRequest};
+pub use labels::LabelQuery;
+pub use plate_sequence::{CreatePlateSequence, UpdatePlateSequence};
+pub use custody::{CreateCustodian, UpdateCustodian, CustodianQuery, AssignCustodian};
+pub use location::{CreateLocation, UpdateLocation, MovePatrimony};
+pub use department::{CreateDepartment, UpdateDepartment, DepartmentListQuery, AddDepartmentManager};
+pub use category::{CreateCategory, UpdateCategory, CategoryQuery};
+pub use maintenance::{
+    OpenMaintenanceOrder, UpdateMaintenanceOrder, CloseMaintenanceOrder, CancelMaintenanceOrder,
+    MaintenanceQuery, AttachmentQuery
+};
+pub use maintenance_schedule::{
+    CreateMaintenanceSchedule, UpdateMaintenanceSchedule, ScheduleQuery, UpcomingQuery,
+    ResolveDueItem, CreateMeterReading
+};
+pub use audit::AuditQuery;
+pub use trash::TrashQuery;
+pub use tenant::{CreateTenant, UpdateTenant};
+pub use permissions::{UpdateRolePermissions, UpdateRoleTwoFactor};
+pub use department_grant::CreateDepartmentGrant;
+pub use session::RefreshRequest;
+pub use user::{UpdateUser, ResetPassword, CreateInvitation};
+pub use profile::{UpdateProfile, ChangePassword};
+pub use password_reset::{PasswordResetRequest, ConfirmPasswordReset};
+pub use login_security::LoginAttemptQuery;
+pub use two_factor::{TwoFactorLogin, TwoFactorCode, DisableTwoFactor};
 
 pub use patrimony::{
     debug_images, register_user, login_user, get_users,
-    upload_document, auth_middleware
+    upload_document, auth_middleware, serve_image, serve_document
 };
 
 // Rotas públicas (não requerem autenticação)
 pub async fn register_user_handler(
     pool: web::Data<PgPool>,
-    user_data: web::Json<CreateUser>,
+    user_data: web::Json<RegisterUser>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    patrimony::register_user(pool, user_data).await
+    patrimony::register_user(pool, user_data, req).await
 }
 
 pub async fn login_user_handler(
     pool: web::Data<PgPool>,
     login_data: web::Json<LoginRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    patrimony::login_user(pool, login_data, req).await
+}
+
+pub async fn verify_two_factor_login_handler(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorLogin>,
+    req: HttpRequest,
+) -> HttpResponse {
+    two_factor::verify_login(pool, body, req).await
+}
+
+pub async fn refresh_session_handler(
+    pool: web::Data<PgPool>,
+    body: web::Json<RefreshRequest>,
+) -> HttpResponse {
+    session::refresh_session(pool, body).await
+}
+
+pub async fn request_password_reset_handler(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn crate::mail::Mailer>,
+    body: web::Json<PasswordResetRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    password_reset::request_password_reset(pool, mailer, body, req).await
+}
+
+pub async fn confirm_password_reset_handler(
+    pool: web::Data<PgPool>,
+    body: web::Json<ConfirmPasswordReset>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    patrimony::login_user(pool, login_data).await
+    password_reset::confirm_password_reset(pool, body, req).await
+}
+
+pub async fn get_password_policy() -> HttpResponse {
+    password_policy::get_password_policy().await
 }
 
 pub async fn health_check() -> HttpResponse {
@@ -42,8 +132,11 @@ pub async fn health_check() -> HttpResponse {
     }))
 }
 
-pub async fn get_departments(pool: web::Data<PgPool>) -> HttpResponse {
-    patrimony::get_departments(pool).await
+pub async fn get_departments(
+    pool: web::Data<PgPool>,
+    query: web::Query<DepartmentListQuery>,
+) -> HttpResponse {
+    department::get_departments(pool, query).await
 }
 
 // Rotas protegidas (requerem autenticação)
@@ -149,11 +242,11 @@ pub async fn transfer_patrimony(
 // handlers/mod.rs - Atualize a função get_transfers
 pub async fn get_transfers(
     pool: web::Data<PgPool>,
-    patrimony_id: web::Query<Option<Uuid>>,
+    query: web::Query<TransferListQuery>,
     req: HttpRequest,
 ) -> HttpResponse {
     match patrimony::auth_middleware(&req, pool.get_ref()).await {
-        Ok(Some(_)) => transfer::get_transfers(pool, patrimony_id, req).await,
+        Ok(Some(_)) => transfer::get_transfers(pool, query, req).await,
         Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => e,
     }
@@ -170,6 +263,79 @@ pub async fn get_transfer(
     }
 }
 
+pub async fn approve_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::approve_transfer(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn reject_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    rejection: web::Json<RejectTransfer>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::reject_transfer(pool, id, rejection, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn receive_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::receive_transfer(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_pending_transfers(
+    pool: web::Data<PgPool>,
+    query: web::Query<PendingTransfersQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::get_pending_transfers(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_transfer_batch(
+    pool: web::Data<PgPool>,
+    batch: web::Json<CreateTransferBatch>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::create_transfer_batch(pool, batch, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_transfer_term(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => transfer::get_transfer_term(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
 pub async fn get_stats(
     pool: web::Data<PgPool>,
     query: web::Query<DepartmentQuery>,
@@ -199,12 +365,7 @@ pub async fn get_users_handler(
     req: HttpRequest,
 ) -> HttpResponse {
     match patrimony::auth_middleware(&req, pool.get_ref()).await {
-        Ok(Some(user)) => {
-            if user.role != "admin" {
-                return HttpResponse::Forbidden().json("Admin access required");
-            }
-            patrimony::get_users(pool, req).await
-        }
+        Ok(Some(_)) => patrimony::get_users(pool, req).await,
         Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => e,
     }
@@ -219,4 +380,1289 @@ pub async fn debug_images_handler(
         Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => e,
     }
-}
\ No newline at end of file
+}
+
+pub async fn get_patrimony_depreciation(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => depreciation::get_patrimony_depreciation(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn run_depreciation(
+    pool: web::Data<PgPool>,
+    body: web::Json<RunDepreciationRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => depreciation::run_depreciation(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_depreciation_entries(
+    pool: web::Data<PgPool>,
+    query: web::Query<DepreciationEntriesQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => depreciation::get_depreciation_entries(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn write_off_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    write_off: web::Json<CreateWriteOff>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => write_off::write_off_patrimony(pool, id, write_off, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn upload_write_off_document(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    payload: Multipart,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => write_off::upload_write_off_document(pool, id, payload, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_write_offs(
+    pool: web::Data<PgPool>,
+    query: web::Query<WriteOffQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => write_off::get_write_offs(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_patrimony_write_off(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => write_off::get_patrimony_write_off(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_campaign(
+    pool: web::Data<PgPool>,
+    campaign: web::Json<CreateCampaign>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::create_campaign(pool, campaign, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_campaigns(
+    pool: web::Data<PgPool>,
+    query: web::Query<CampaignQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::get_campaigns(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_campaign(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::get_campaign(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_session(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    session: web::Json<CreateSession>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::create_session(pool, id, session, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn close_session(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::close_session(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn post_scans(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    scans: web::Json<ScanRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::post_scans(pool, id, scans, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn close_campaign(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::close_campaign(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_campaign_report(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => inventory::get_campaign_report(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_labels(
+    pool: web::Data<PgPool>,
+    query: web::Query<LabelQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => labels::get_labels(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_plate_sequences(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => plate_sequence::get_plate_sequences(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_plate_sequence(
+    pool: web::Data<PgPool>,
+    sequence: web::Json<CreatePlateSequence>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => plate_sequence::create_plate_sequence(pool, sequence, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_plate_sequence(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    sequence: web::Json<UpdatePlateSequence>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => plate_sequence::update_plate_sequence(pool, id, sequence, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_plate_sequence(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => plate_sequence::delete_plate_sequence(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_custodians(
+    pool: web::Data<PgPool>,
+    query: web::Query<CustodianQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::get_custodians(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::get_custodian(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_custodian(
+    pool: web::Data<PgPool>,
+    custodian: web::Json<CreateCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::create_custodian(pool, custodian, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    custodian: web::Json<UpdateCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::update_custodian(pool, id, custodian, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn assign_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    assignment: web::Json<AssignCustodian>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::assign_custodian(pool, id, assignment, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn release_custodian(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::release_custodian(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_custody_history(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::get_custody_history(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_responsibility_term(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => custody::get_responsibility_term(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_locations(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::get_locations(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::get_location(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_location(
+    pool: web::Data<PgPool>,
+    location: web::Json<CreateLocation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::create_location(pool, location, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    location: web::Json<UpdateLocation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::update_location(pool, id, location, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_location(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::delete_location(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn move_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    movement: web::Json<MovePatrimony>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::move_patrimony(pool, id, movement, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_patrimony_moves(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => location::get_patrimony_moves(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::get_department(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_department(
+    pool: web::Data<PgPool>,
+    department: web::Json<CreateDepartment>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::create_department(pool, department, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    department: web::Json<UpdateDepartment>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::update_department(pool, id, department, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_department(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::delete_department(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn add_department_manager(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    manager: web::Json<AddDepartmentManager>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::add_department_manager(pool, id, manager, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn remove_department_manager(
+    pool: web::Data<PgPool>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department::remove_department_manager(pool, path, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_categories(
+    pool: web::Data<PgPool>,
+    query: web::Query<CategoryQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => category::get_categories(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => category::get_category(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_category(
+    pool: web::Data<PgPool>,
+    category: web::Json<CreateCategory>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => category::create_category(pool, category, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    category: web::Json<UpdateCategory>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => category::update_category(pool, id, category, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_category(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => category::delete_category(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn open_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    order: web::Json<OpenMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::open_maintenance_order(pool, id, order, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_maintenance_orders(
+    pool: web::Data<PgPool>,
+    query: web::Query<MaintenanceQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::get_maintenance_orders(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_maintenance_costs(
+    pool: web::Data<PgPool>,
+    query: web::Query<MaintenanceQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::get_maintenance_costs(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::get_maintenance_order(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    order: web::Json<UpdateMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::update_maintenance_order(pool, id, order, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn close_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    close: web::Json<CloseMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::close_maintenance_order(pool, id, close, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn cancel_maintenance_order(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    cancel: web::Json<CancelMaintenanceOrder>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::cancel_maintenance_order(pool, id, cancel, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn upload_maintenance_attachment(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    query: web::Query<AttachmentQuery>,
+    payload: Multipart,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::upload_maintenance_attachment(pool, id, query, payload, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_patrimony_maintenance(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance::get_patrimony_maintenance(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_maintenance_schedules(
+    pool: web::Data<PgPool>,
+    query: web::Query<ScheduleQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::get_maintenance_schedules(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::get_maintenance_schedule(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    schedule: web::Json<CreateMaintenanceSchedule>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::create_maintenance_schedule(pool, schedule, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    schedule: web::Json<UpdateMaintenanceSchedule>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::update_maintenance_schedule(pool, id, schedule, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_maintenance_schedule(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::delete_maintenance_schedule(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn run_maintenance_schedules(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::run_maintenance_schedules(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_upcoming_maintenance(
+    pool: web::Data<PgPool>,
+    query: web::Query<UpcomingQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::get_upcoming_maintenance(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn complete_due_item(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    resolve: web::Json<ResolveDueItem>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::complete_due_item(pool, id, resolve, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn skip_due_item(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    resolve: web::Json<ResolveDueItem>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::skip_due_item(pool, id, resolve, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_meter_reading(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    reading: web::Json<CreateMeterReading>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::create_meter_reading(pool, id, reading, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_meter_readings(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => maintenance_schedule::get_meter_readings(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_audit_log(
+    pool: web::Data<PgPool>,
+    query: web::Query<AuditQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => audit::get_audit_log(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn verify_audit_chain(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => audit_chain::verify_audit_chain(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_audit_checkpoint(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => audit_chain::create_audit_checkpoint(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_audit_checkpoints(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => audit_chain::get_audit_checkpoints(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_trash(
+    pool: web::Data<PgPool>,
+    query: web::Query<TrashQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => trash::get_trash(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn restore_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => trash::restore_patrimony(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn purge_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => trash::purge_patrimony(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_tenants(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => tenant::get_tenants(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_tenant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => tenant::get_tenant(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_tenant(
+    pool: web::Data<PgPool>,
+    tenant_data: web::Json<CreateTenant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => tenant::create_tenant(pool, tenant_data, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_tenant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    tenant_data: web::Json<UpdateTenant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => tenant::update_tenant(pool, id, tenant_data, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_roles(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => permissions::get_roles(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_permissions(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => permissions::get_permissions(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_role_permissions(
+    pool: web::Data<PgPool>,
+    code: web::Path<String>,
+    update: web::Json<UpdateRolePermissions>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => permissions::update_role_permissions(pool, code, update, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_department_grants(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department_grant::get_department_grants(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_department_grant(
+    pool: web::Data<PgPool>,
+    grant: web::Json<CreateDepartmentGrant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department_grant::create_department_grant(pool, grant, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn delete_department_grant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => department_grant::delete_department_grant(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn logout(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => session::logout(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_user_sessions(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => session::get_user_sessions(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn revoke_user_sessions(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => session::revoke_sessions(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::get_user(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_user(
+    pool: web::Data<PgPool>,
+    user_data: web::Json<CreateUser>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::create_user(pool, user_data, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    user_data: web::Json<UpdateUser>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::update_user(pool, id, user_data, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn deactivate_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::deactivate_user(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn reactivate_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::reactivate_user(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn reset_user_password(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    body: web::Json<ResetPassword>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::reset_password(pool, id, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_invitations(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::get_invitations(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn create_invitation(
+    pool: web::Data<PgPool>,
+    invitation: web::Json<CreateInvitation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::create_invitation(pool, invitation, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn revoke_invitation(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::revoke_invitation(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_profile(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => profile::get_profile(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_profile(
+    pool: web::Data<PgPool>,
+    body: web::Json<UpdateProfile>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => profile::update_profile(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn change_password(
+    pool: web::Data<PgPool>,
+    body: web::Json<ChangePassword>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => profile::change_password(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn unlock_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::unlock_user(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn get_login_attempts(
+    pool: web::Data<PgPool>,
+    query: web::Query<LoginAttemptQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => login_security::get_login_attempts(pool, query, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn setup_two_factor(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => two_factor::setup_two_factor(pool, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn enable_two_factor(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorCode>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => two_factor::enable_two_factor(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn disable_two_factor(
+    pool: web::Data<PgPool>,
+    body: web::Json<DisableTwoFactor>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => two_factor::disable_two_factor(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn regenerate_recovery_codes(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorCode>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => two_factor::regenerate_recovery_codes(pool, body, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn reset_user_two_factor(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => user::reset_two_factor(pool, id, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_role_two_factor(
+    pool: web::Data<PgPool>,
+    code: web::Path<String>,
+    update: web::Json<UpdateRoleTwoFactor>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match patrimony::auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(_)) => permissions::update_role_two_factor(pool, code, update, req).await,
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
@@ -0,0 +1,183 @@
+use actix_web::HttpResponse;
+use sqlx::{PgExecutor, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use bcrypt::verify;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Serialize;
+use std::env;
+use uuid::Uuid;
+
+const DEFAULT_MIN_LENGTH: usize = 8;
+const DEFAULT_HISTORY: i64 = 5;
+
+// Tamanho mínimo das senhas geradas (temporárias), mesmo que a política exija menos
+const GENERATED_LENGTH: usize = 16;
+
+const UPPERCASE: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+const LOWERCASE: &[u8] = b"abcdefghijkmnpqrstuvwxyz";
+const DIGITS: &[u8] = b"23456789";
+const SYMBOLS: &[u8] = b"!@#$%&*-_=+?";
+
+/// Política de senhas, lida das variáveis de ambiente
+#[derive(Serialize, Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    pub history: i64,                   // Quantas senhas recentes (incluindo a atual) não podem voltar; 0 desliga
+}
+
+fn env_flag(name: &str, default: bool) -> bool {
+    match env::var(name).ok().as_deref().map(str::trim) {
+        Some("true") | Some("1") => true,
+        Some("false") | Some("0") => false,
+        _ => default,
+    }
+}
+
+fn env_number<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse::<T>().ok())
+        .unwrap_or(default)
+}
+
+impl PasswordPolicy {
+    pub fn from_env() -> Self {
+        PasswordPolicy {
+            min_length: env_number("PASSWORD_MIN_LENGTH", DEFAULT_MIN_LENGTH).max(1),
+            require_uppercase: env_flag("PASSWORD_REQUIRE_UPPERCASE", true),
+            require_lowercase: env_flag("PASSWORD_REQUIRE_LOWERCASE", true),
+            require_digit: env_flag("PASSWORD_REQUIRE_DIGIT", true),
+            require_symbol: env_flag("PASSWORD_REQUIRE_SYMBOL", false),
+            history: env_number("PASSWORD_HISTORY", DEFAULT_HISTORY).max(0),
+        }
+    }
+
+    /// Regras que a senha não cumpre (vazio quando está de acordo)
+    pub fn violations(&self, password: &str, username: &str) -> Vec<String> {
+        let mut violations = Vec::new();
+        if password.chars().count() < self.min_length {
+            violations.push(format!("minimum length is {}", self.min_length));
+        }
+        if self.require_uppercase && !password.chars().any(char::is_uppercase) {
+            violations.push("requires an uppercase letter".to_string());
+        }
+        if self.require_lowercase && !password.chars().any(char::is_lowercase) {
+            violations.push("requires a lowercase letter".to_string());
+        }
+        if self.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("requires a digit".to_string());
+        }
+        if self.require_symbol && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push("requires a symbol".to_string());
+        }
+        let username = username.trim().to_lowercase();
+        if !username.is_empty() && password.to_lowercase().contains(&username) {
+            violations.push("cannot contain the username".to_string());
+        }
+        violations
+    }
+
+    /// Senha aleatória que cumpre a política, para senhas temporárias
+    pub fn generate(&self) -> String {
+        let mut rng = rand::thread_rng();
+        let mut classes: Vec<&[u8]> = vec![UPPERCASE, LOWERCASE, DIGITS];
+        if self.require_symbol {
+            classes.push(SYMBOLS);
+        }
+
+        // Um caractere de cada classe e o resto de qualquer uma delas, embaralhado
+        let mut chars: Vec<u8> = classes.iter().map(|class| *class.choose(&mut rng).unwrap()).collect();
+        let alphabet: Vec<u8> = classes.concat();
+        while chars.len() < self.min_length.max(GENERATED_LENGTH) {
+            chars.push(alphabet[rng.gen_range(0..alphabet.len())]);
+        }
+        chars.shuffle(&mut rng);
+        String::from_utf8(chars).unwrap_or_default()
+    }
+}
+
+/// Confere a senha contra a política; a resposta de erro lista as regras não cumpridas
+pub fn validate(password: &str, username: &str) -> Result<(), HttpResponse> {
+    let violations = PasswordPolicy::from_env().violations(password, username);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(HttpResponse::BadRequest().json(format!("Password does not meet the policy: {}", violations.join(", "))))
+    }
+}
+
+/// Recusa a senha se for a atual ou uma das anteriores guardadas no histórico
+pub async fn ensure_not_reused<'e, E: PgExecutor<'e>>(executor: E, user_id: Uuid, password: &str) -> Result<(), HttpResponse> {
+    let history = PasswordPolicy::from_env().history;
+    if history == 0 {
+        return Ok(());
+    }
+
+    let result = sqlx::query(
+        "SELECT password_hash FROM users WHERE id = $1
+         UNION ALL
+         (SELECT password_hash FROM password_history WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2)"
+    )
+    .bind(user_id)
+    .bind(history - 1)
+    .map(|row: PgRow| row.get::<String, _>("password_hash"))
+    .fetch_all(executor)
+    .await;
+
+    match result {
+        Ok(hashes) => {
+            if hashes.iter().any(|hash| verify(password, hash).unwrap_or(false)) {
+                return Err(HttpResponse::BadRequest().json(format!("Password cannot be one of the last {} passwords", history)));
+            }
+            Ok(())
+        }
+        Err(e) => {
+            eprintln!("Error fetching password history: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating password"))
+        }
+    }
+}
+
+/// Troca o hash da senha guardando o anterior no histórico (só os mais recentes são mantidos)
+pub async fn replace_password(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    password_hash: &str,
+    must_change_password: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO password_history (user_id, password_hash) SELECT id, password_hash FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    sqlx::query(
+        "UPDATE users SET password_hash = $2, must_change_password = $3, updated_at = NOW() WHERE id = $1"
+    )
+    .bind(user_id)
+    .bind(password_hash)
+    .bind(must_change_password)
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query(
+        "DELETE FROM password_history WHERE user_id = $1 AND id NOT IN (
+             SELECT id FROM password_history WHERE user_id = $1 ORDER BY created_at DESC LIMIT $2
+         )"
+    )
+    .bind(user_id)
+    .bind(PasswordPolicy::from_env().history.max(1))
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(())
+}
+
+// Rota pública: as telas de cadastro e troca de senha mostram as regras em vigor
+pub async fn get_password_policy() -> HttpResponse {
+    HttpResponse::Ok().json(PasswordPolicy::from_env())
+}
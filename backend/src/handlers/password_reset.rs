@@ -0,0 +1,251 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use std::env;
+use uuid::Uuid;
+use bcrypt::{hash, DEFAULT_COST};
+
+use super::patrimony::get_user_by_id;
+use super::audit::{self, Actor};
+use super::password_policy;
+use super::session;
+use super::login_security;
+use crate::mail::{Email, Mailer};
+
+const DEFAULT_TTL_MINUTES: i64 = 60;
+const DEFAULT_APP_URL: &str = "http://localhost:3000";
+
+// Mesma resposta exista ou não a conta, para não revelar quem está cadastrado
+const REQUEST_ACCEPTED: &str = "If the account exists and has an email, a reset link was sent";
+
+#[derive(Deserialize)]
+pub struct PasswordResetRequest {
+    pub login: String,                  // Nome de usuário ou e-mail
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmPasswordReset {
+    pub token: String,
+    pub new_password: String,
+}
+
+fn reset_token_ttl() -> Duration {
+    let minutes = env::var("PASSWORD_RESET_TTL_MINUTES")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(DEFAULT_TTL_MINUTES);
+    Duration::minutes(minutes)
+}
+
+// Endereço da tela de redefinição no frontend (APP_URL)
+fn reset_link(token: &str) -> String {
+    let app_url = env::var("APP_URL").unwrap_or_else(|_| DEFAULT_APP_URL.to_string());
+    format!("{}/reset-password?token={}", app_url.trim_end_matches('/'), token)
+}
+
+fn reset_email(to: String, username: &str, token: &str, ttl: Duration) -> Email {
+    Email {
+        to,
+        subject: "Redefinição de senha".to_string(),
+        body: format!(
+            "Olá, {}.\n\n\
+             Recebemos um pedido para redefinir a sua senha no Sistema de Gestão de Patrimônio.\n\
+             Para escolher uma nova senha, acesse o endereço abaixo em até {} minutos:\n\n{}\n\n\
+             O link só pode ser usado uma vez. Se você não fez o pedido, ignore esta mensagem.\n",
+            username,
+            ttl.num_minutes(),
+            reset_link(token)
+        ),
+    }
+}
+
+// Rota pública: gera o token e envia o link por e-mail
+pub async fn request_password_reset(
+    pool: web::Data<PgPool>,
+    mailer: web::Data<dyn Mailer>,
+    body: web::Json<PasswordResetRequest>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let login = body.login.trim();
+    if login.is_empty() {
+        return HttpResponse::BadRequest().json("Username or email is required");
+    }
+
+    // Contas ativas com e-mail; um pedido por minuto por conta
+    let result = sqlx::query(
+        "SELECT u.id, u.username, u.email
+         FROM users u
+         JOIN tenants t ON t.id = u.tenant_id
+         WHERE (u.username = $1 OR LOWER(u.email) = LOWER($1))
+           AND u.email IS NOT NULL AND u.active AND t.active
+           AND NOT EXISTS (
+               SELECT 1 FROM password_reset_tokens r
+               WHERE r.user_id = u.id AND r.used_at IS NULL AND r.created_at > NOW() - INTERVAL '1 minute'
+           )"
+    )
+    .bind(login)
+    .map(|row: PgRow| (row.get::<Uuid, _>("id"), row.get::<String, _>("username"), row.get::<String, _>("email")))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let accounts = match result {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            eprintln!("Error fetching user for password reset: {}", e);
+            return HttpResponse::InternalServerError().json("Error requesting password reset");
+        }
+    };
+
+    let ttl = reset_token_ttl();
+    let ip_address = login_security::client_ip(&req);
+
+    for (user_id, username, email) in accounts {
+        let token = session::random_token(32);
+
+        let mut transaction = match pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                eprintln!("Error starting transaction: {}", e);
+                return HttpResponse::InternalServerError().json("Error requesting password reset");
+            }
+        };
+
+        // Um novo pedido invalida os links anteriores ainda não usados
+        let result = sqlx::query("DELETE FROM password_reset_tokens WHERE user_id = $1 AND used_at IS NULL")
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await;
+        let result = match result {
+            Ok(_) => sqlx::query(
+                "INSERT INTO password_reset_tokens (token_hash, user_id, ip_address, expires_at) VALUES ($1, $2, $3, $4)"
+            )
+            .bind(session::hash_token(&token))
+            .bind(user_id)
+            .bind(&ip_address)
+            .bind(Utc::now() + ttl)
+            .execute(&mut *transaction)
+            .await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            eprintln!("Error creating password reset token: {}", e);
+            return HttpResponse::InternalServerError().json("Error requesting password reset");
+        }
+
+        if let Err(e) = transaction.commit().await {
+            eprintln!("Error committing password reset token: {}", e);
+            return HttpResponse::InternalServerError().json("Error requesting password reset");
+        }
+
+        // Envio em segundo plano: o tempo de resposta não denuncia se a conta existe
+        let mailer = mailer.clone().into_inner();
+        let email = reset_email(email, &username, &token, ttl);
+        tokio::spawn(async move {
+            match mailer.send(email).await {
+                Ok(_) => println!("🔑 Link de redefinição de senha enviado para '{}'", username),
+                Err(e) => eprintln!("Error sending password reset email to '{}': {}", username, e),
+            }
+        });
+    }
+
+    HttpResponse::Ok().json(REQUEST_ACCEPTED)
+}
+
+// Rota pública: troca a senha com o token recebido por e-mail e encerra as sessões abertas
+pub async fn confirm_password_reset(
+    pool: web::Data<PgPool>,
+    body: web::Json<ConfirmPasswordReset>,
+    req: HttpRequest,
+) -> HttpResponse {
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+
+    let result = sqlx::query(
+        "SELECT r.user_id
+         FROM password_reset_tokens r
+         JOIN users u ON u.id = r.user_id
+         WHERE r.token_hash = $1 AND r.used_at IS NULL AND r.expires_at > NOW() AND u.active
+         FOR UPDATE OF r"
+    )
+    .bind(session::hash_token(&body.token))
+    .map(|row: PgRow| row.get::<Uuid, _>("user_id"))
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let user_id = match result {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return HttpResponse::BadRequest().json("Invalid or expired reset token"),
+        Err(e) => {
+            eprintln!("Error fetching password reset token: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+
+    let user = match get_user_by_id(pool.get_ref(), user_id).await {
+        Ok((user, true)) => user,
+        Ok((_, false)) => return HttpResponse::Forbidden().json("Tenant is inactive"),
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+
+    if let Err(response) = password_policy::validate(&body.new_password, &user.username) {
+        return response;
+    }
+    if let Err(response) = password_policy::ensure_not_reused(pool.get_ref(), user.id, &body.new_password).await {
+        return response;
+    }
+    let password_hash = match hash(&body.new_password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
+    };
+
+    let before = match audit::snapshot(&mut *transaction, audit::USER, user.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+
+    let result = match password_policy::replace_password(&mut transaction, user.id, &password_hash, false).await {
+        Ok(_) => {
+            sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+                .bind(user.id)
+                .execute(&mut *transaction)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    let result = match result {
+        Ok(_) => session::revoke_user_sessions(&mut *transaction, user.id, session::PASSWORD_RESET).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Error resetting password: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::RESET_PASSWORD, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing password reset: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    println!("🔑 Senha do usuário '{}' redefinida pelo link enviado por e-mail", user.username);
+
+    HttpResponse::Ok().json("Password reset successfully")
+}
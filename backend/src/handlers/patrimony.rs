@@ -12,18 +12,23 @@ use actix_multipart::Multipart;
 use std::io::Write;
 use tokio::fs::File;
 use tokio::io::AsyncWriteExt;
-use bcrypt::{hash, verify, DEFAULT_COST};
-use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
-use std::env;
+use bcrypt::{hash, DEFAULT_COST};
+use std::collections::HashMap;
 use sqlx::types::BigDecimal;
 
-// Estruturas para autenticação JWT
-#[derive(Serialize, Deserialize)]
-struct Claims {
-    sub: String, // user ID
-    exp: usize,  // expiration time
-    role: String,
-}
+use super::depreciation::{self, DepreciationPolicy};
+use super::plate_sequence;
+use super::location;
+use super::department;
+use super::department_grant;
+use super::category;
+use super::write_off;
+use super::maintenance;
+use super::audit::{self, Actor};
+use super::session;
+use super::password_policy;
+use super::login_security::{self, Reservation};
+use super::two_factor;
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Patrimony {
@@ -46,11 +51,18 @@ pub struct Patrimony {
     pub created_by: Option<Uuid>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub depreciation_method: String,
+    pub useful_life_months: i32,
+    pub residual_value: f64,
+    pub accumulated_depreciation: f64,
+    pub book_value: f64,
+    pub location_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct CreatePatrimony {
-    pub plate: String,
+    pub plate: Option<String>,
     pub name: String,
     pub description: String,
     pub acquisition_date: NaiveDate,
@@ -60,6 +72,12 @@ pub struct CreatePatrimony {
     pub invoice_number: Option<String>,
     pub commitment_number: Option<String>,
     pub denf_se_number: Option<String>,
+    pub depreciation_method: Option<String>,
+    pub useful_life_months: Option<i32>,
+    pub residual_value: Option<f64>,
+    pub plate_sequence_id: Option<Uuid>,
+    pub location_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
 }
 
 #[derive(Deserialize)]
@@ -74,12 +92,18 @@ pub struct UpdatePatrimony {
     pub invoice_number: Option<String>,
     pub commitment_number: Option<String>,
     pub denf_se_number: Option<String>,
+    pub depreciation_method: Option<String>,
+    pub useful_life_months: Option<i32>,
+    pub residual_value: Option<f64>,
+    pub category_id: Option<Uuid>,
 }
 
 #[derive(Deserialize)]
 pub struct DepartmentQuery {
     pub department: Option<String>,
     pub status: Option<String>,
+    pub location_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
 }
 
 #[derive(Serialize)]
@@ -90,7 +114,11 @@ pub struct StatsResponse {
     pub maintenance: i64,
     pub written_off: i64,
     pub total_value: f64,
+    pub written_off_value: f64,
+    pub total_accumulated_depreciation: f64,
+    pub total_book_value: f64,
     pub by_department: Vec<DepartmentStats>,
+    pub by_category: Vec<CategoryStats>,
 }
 
 #[derive(Serialize)]
@@ -98,6 +126,21 @@ pub struct DepartmentStats {
     pub department: String,
     pub count: i64,
     pub total_value: f64,
+    pub accumulated_depreciation: f64,
+    pub book_value: f64,
+}
+
+// Bens sem categoria aparecem com category_id nulo
+#[derive(Serialize)]
+pub struct CategoryStats {
+    pub category_id: Option<Uuid>,
+    pub code: Option<String>,
+    pub name: Option<String>,
+    pub accounting_code: Option<String>,
+    pub count: i64,
+    pub total_value: f64,
+    pub accumulated_depreciation: f64,
+    pub book_value: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -108,6 +151,14 @@ pub struct User {
     pub username: String,
     pub email: Option<String>,
     pub role: String,
+    pub tenant_id: Uuid,
+    pub is_super_admin: bool,
+    pub active: bool,
+    pub must_change_password: bool,     // Senha temporária definida pelo administrador
+    pub locked_until: Option<chrono::DateTime<chrono::Utc>>,   // Bloqueio por tentativas de login erradas
+    pub two_factor_enabled: bool,
+    pub two_factor_required: bool,      // O papel exige 2FA (roles.requires_two_factor)
+    pub permissions: Vec<String>,       // Permissões do papel (role_permissions)
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -122,6 +173,16 @@ pub struct CreateUser {
     pub role: Option<String>,
 }
 
+// Cadastro público: só com convite, que define entidade, departamento e papel
+#[derive(Deserialize, Debug)]
+pub struct RegisterUser {
+    pub invite_token: String,
+    pub username: String,
+    pub password: String,
+    pub email: Option<String>,
+    pub company_name: Option<String>,   // Sem ele, o nome da entidade
+}
+
 #[derive(Deserialize)]
 pub struct LoginRequest {
     pub username: String,
@@ -131,6 +192,8 @@ pub struct LoginRequest {
 #[derive(Serialize)]
 pub struct LoginResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,
     pub user: User,
 }
 
@@ -201,6 +264,46 @@ fn convert_to_f64(row: &PgRow, column: &str) -> f64 {
 }
 
 
+const PATRIMONY_COLUMNS: &str = "id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at, depreciation_method, useful_life_months, residual_value, location_id, category_id";
+
+// Monta o Patrimony a partir de uma linha com as colunas de PATRIMONY_COLUMNS
+fn patrimony_from_row(row: &PgRow) -> Patrimony {
+    let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(row);
+    let value = convert_to_f64(row, "value");
+    let acquisition_date: NaiveDate = row.get("acquisition_date");
+    let policy = DepreciationPolicy::from_row(row);
+    let (accumulated_depreciation, book_value) =
+        depreciation::snapshot(value, acquisition_date, &policy, Utc::now().date_naive());
+
+    Patrimony {
+        id: row.get("id"),
+        plate: row.get("plate"),
+        name: row.get("name"),
+        description: row.get("description"),
+        acquisition_date,
+        value,
+        department: row.get("department"),
+        status: row.get("status"),
+        invoice_number: row.get("invoice_number"),
+        commitment_number: row.get("commitment_number"),
+        denf_se_number: row.get("denf_se_number"),
+        invoice_file,
+        commitment_file,
+        denf_se_file,
+        image_url,
+        created_by: row.get("created_by"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+        depreciation_method: policy.method,
+        useful_life_months: policy.useful_life_months,
+        residual_value: policy.residual_value,
+        accumulated_depreciation,
+        book_value,
+        location_id: row.get("location_id"),
+        category_id: row.get("category_id"),
+    }
+}
+
 fn get_image_url(row: &PgRow) -> Option<String> {
     match row.try_get::<Option<String>, _>("image_url") {
         Ok(Some(url)) if !url.is_empty() => Some(url),
@@ -239,6 +342,33 @@ fn get_document_urls(row: &PgRow) -> (
     (invoice_file, commitment_file, denf_se_file, image_url)
 }
 
+pub const USER_COLUMNS: &str = "id, company_name, department, username, email, role, tenant_id, is_super_admin, active, must_change_password, locked_until, created_at, updated_at,
+    totp_enabled as two_factor_enabled,
+    COALESCE((SELECT r.requires_two_factor FROM roles r WHERE r.code = users.role), FALSE) as two_factor_required,
+    ARRAY(SELECT rp.permission FROM role_permissions rp WHERE rp.role = users.role ORDER BY rp.permission)::text[] as permissions";
+
+// Monta o User a partir de uma linha com as colunas de USER_COLUMNS
+pub fn user_from_row(row: &PgRow) -> User {
+    User {
+        id: row.get("id"),
+        company_name: row.get("company_name"),
+        department: row.get("department"),
+        username: row.get("username"),
+        email: row.get("email"),
+        role: row.get("role"),
+        tenant_id: row.get("tenant_id"),
+        is_super_admin: row.get("is_super_admin"),
+        active: row.get("active"),
+        must_change_password: row.get("must_change_password"),
+        locked_until: row.get("locked_until"),
+        two_factor_enabled: row.get("two_factor_enabled"),
+        two_factor_required: row.get("two_factor_required"),
+        permissions: row.get("permissions"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
 // Middleware de autenticação
 pub async fn auth_middleware(
     req: &HttpRequest,
@@ -250,24 +380,26 @@ pub async fn auth_middleware(
         if let Ok(header_str) = header.to_str() {
             if header_str.starts_with("Bearer ") {
                 let token = &header_str[7..];
-                let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
                 
-                match decode::<Claims>(
-                    token,
-                    &DecodingKey::from_secret(secret.as_ref()),
-                    &Validation::default(),
-                ) {
-                    Ok(token_data) => {
-                        let user_id = Uuid::parse_str(&token_data.claims.sub).map_err(|_| {
-                            HttpResponse::Unauthorized().json("Invalid user ID in token")
-                        })?;
-                        
-                        match get_user_by_id(pool, user_id).await {
-                            Ok(user) => return Ok(Some(user)),
-                            Err(_) => return Err(HttpResponse::Unauthorized().json("Invalid user")),
-                        }
+                let Some((user_id, session_id)) = session::decode_access_token(token) else {
+                    return Err(HttpResponse::Unauthorized().json("Invalid token"));
+                };
+                
+                // Logout ou revogação pelo administrador cortam o acesso antes de o token expirar
+                match session::is_active(pool, session_id, user_id).await {
+                    Ok(true) => {}
+                    Ok(false) => return Err(HttpResponse::Unauthorized().json("Session revoked")),
+                    Err(e) => {
+                        eprintln!("Error checking session: {}", e);
+                        return Err(HttpResponse::InternalServerError().json("Error checking session"));
                     }
-                    Err(_) => return Err(HttpResponse::Unauthorized().json("Invalid token")),
+                }
+                
+                match get_user_by_id(pool, user_id).await {
+                    Ok((user, _)) if !user.active => return Err(HttpResponse::Forbidden().json("User is deactivated")),
+                    Ok((user, true)) => return Ok(Some(user)),
+                    Ok((_, false)) => return Err(HttpResponse::Forbidden().json("Tenant is inactive")),
+                    Err(_) => return Err(HttpResponse::Unauthorized().json("Invalid user")),
                 }
             }
         }
@@ -276,27 +408,64 @@ pub async fn auth_middleware(
     Err(HttpResponse::Unauthorized().json("Authorization header required"))
 }
 
-// Função auxiliar para buscar usuário por ID
-async fn get_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<User, sqlx::Error> {
-    sqlx::query(
-        "SELECT id, company_name, department, username, email, role, created_at, updated_at 
-         FROM users WHERE id = $1"
-    )
+// Função auxiliar para buscar usuário por ID (junto com a situação da entidade)
+pub async fn get_user_by_id(pool: &PgPool, user_id: Uuid) -> Result<(User, bool), sqlx::Error> {
+    sqlx::query(&format!(
+        "SELECT {}, (SELECT active FROM tenants t WHERE t.id = users.tenant_id) as tenant_active
+         FROM users WHERE id = $1",
+        USER_COLUMNS
+    ))
     .bind(user_id)
-    .map(|row: PgRow| User {
-        id: row.get("id"),
-        company_name: row.get("company_name"),
-        department: row.get("department"),
-        username: row.get("username"),
-        email: row.get("email"),
-        role: row.get("role"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    .map(|row: PgRow| (user_from_row(&row), row.get::<bool, _>("tenant_active")))
     .fetch_one(pool)
     .await
 }
 
+/// Bem da entidade e de um departamento visível ao usuário; 404 para os demais
+pub async fn ensure_visible(pool: &PgPool, user: &User, patrimony_id: Uuid) -> Result<(), HttpResponse> {
+    match sqlx::query(&format!(
+        "SELECT 1 FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
+    .bind(patrimony_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(user))
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json("Patrimony not found")),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error fetching patrimony"))
+        }
+    }
+}
+
+// Grava a referência do arquivo enviado e registra o envio na trilha de auditoria
+async fn store_file_reference(
+    pool: &PgPool,
+    actor: &Actor,
+    patrimony_id: Uuid,
+    column_name: &str,
+    file_url: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let before = audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await?;
+
+    let result = sqlx::query(&format!("UPDATE patrimonies SET {} = $1, updated_at = NOW() WHERE id = $2", column_name))
+        .bind(file_url)
+        .bind(patrimony_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    if result.rows_affected() > 0 {
+        audit::record_change(&mut transaction, actor, audit::PATRIMONY, patrimony_id, audit::UPLOAD, before).await?;
+        transaction.commit().await?;
+    }
+    Ok(result.rows_affected())
+}
+
 pub async fn upload_image(
     pool: web::Data<PgPool>,
     id: web::Path<Uuid>,
@@ -304,7 +473,7 @@ pub async fn upload_image(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -313,9 +482,14 @@ pub async fn upload_image(
     let patrimony_id = id.into_inner();
     println!("📤 Upload de imagem para patrimônio: {}", patrimony_id);
 
-    // Verificar se o patrimônio existe
-    let patrimony_exists = match sqlx::query("SELECT id FROM patrimonies WHERE id = $1")
+    // Verificar se o patrimônio existe e está em um departamento visível ao usuário
+    let patrimony_exists = match sqlx::query(&format!(
+        "SELECT id FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
         .bind(patrimony_id)
+        .bind(user.tenant_id)
+        .bind(department_grant::restriction(&user))
         .fetch_optional(pool.get_ref())
         .await
     {
@@ -331,6 +505,10 @@ pub async fn upload_image(
         return HttpResponse::NotFound().json("Patrimônio não encontrado");
     }
 
+    if let Err(response) = write_off::ensure_not_written_off(pool.get_ref(), patrimony_id).await {
+        return response;
+    }
+
     // Processar o upload
     while let Ok(Some(mut field)) = payload.try_next().await {
         let filename = field.content_disposition().get_filename().unwrap_or("image.jpg").to_string();
@@ -368,11 +546,7 @@ pub async fn upload_image(
 
         // Atualizar banco
         let image_url = format!("/uploads/{}", new_filename);
-        let result = sqlx::query("UPDATE patrimonies SET image_url = $1, updated_at = NOW() WHERE id = $2")
-            .bind(&image_url)
-            .bind(patrimony_id)
-            .execute(pool.get_ref())
-            .await;
+        let result = store_file_reference(pool.get_ref(), &Actor::new(&user, &req), patrimony_id, "image_url", &image_url).await;
 
         match result {
             Ok(_) => {
@@ -399,7 +573,7 @@ pub async fn upload_document(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -408,9 +582,14 @@ pub async fn upload_document(
     let (patrimony_id, doc_type) = path.into_inner();
     println!("📤 Upload de documento {} para patrimônio: {}", doc_type, patrimony_id);
 
-    // Verificar se o patrimônio existe
-    let patrimony_exists = match sqlx::query("SELECT id FROM patrimonies WHERE id = $1")
+    // Verificar se o patrimônio existe e está em um departamento visível ao usuário
+    let patrimony_exists = match sqlx::query(&format!(
+        "SELECT id FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
         .bind(patrimony_id)
+        .bind(user.tenant_id)
+        .bind(department_grant::restriction(&user))
         .fetch_optional(pool.get_ref())
         .await
     {
@@ -426,6 +605,10 @@ pub async fn upload_document(
         return HttpResponse::NotFound().json("Patrimônio não encontrado");
     }
 
+    if let Err(response) = write_off::ensure_not_written_off(pool.get_ref(), patrimony_id).await {
+        return response;
+    }
+
     // Tipos de documento permitidos
     let allowed_types = vec!["invoice", "commitment", "denf"];
     if !allowed_types.contains(&doc_type.as_str()) {
@@ -478,15 +661,11 @@ pub async fn upload_document(
             _ => "",
         };
 
-        let result = sqlx::query(&format!("UPDATE patrimonies SET {} = $1, updated_at = NOW() WHERE id = $2", column_name))
-            .bind(&document_url)
-            .bind(patrimony_id)
-            .execute(pool.get_ref())
-            .await;
+        let result = store_file_reference(pool.get_ref(), &Actor::new(&user, &req), patrimony_id, column_name, &document_url).await;
 
         match result {
-            Ok(result) => {
-                if result.rows_affected() > 0 {
+            Ok(rows_affected) => {
+                if rows_affected > 0 {
                     println!("✅ Documento salvo: {}", document_url);
                     return HttpResponse::Ok().json(serde_json::json!({
                         "message": "Documento enviado com sucesso",
@@ -507,49 +686,90 @@ pub async fn upload_document(
     HttpResponse::BadRequest().json("Nenhum documento fornecido")
 }
 
-// Adicione esta função para servir arquivos estáticos
-pub async fn serve_image(filename: web::Path<String>) -> HttpResponse {
-    let filepath = format!("./uploads/{}", filename);
-    println!("📁 Servindo imagem: {}", filepath);
-    
-    match fs::read(&filepath) {
-        Ok(content) => {
-            let content_type = match filepath.split('.').last() {
-                Some("jpg") | Some("jpeg") => "image/jpeg",
-                Some("png") => "image/png",
-                Some("gif") => "image/gif",
-                Some("webp") => "image/webp",
-                _ => "application/octet-stream",
-            };
-            
-            HttpResponse::Ok()
-                .content_type(content_type)
-                .body(content)
-        }
+// Arquivo enviado pertence a um bem (imagem, notas, baixa ou anexo de manutenção);
+// só é entregue se o bem for visível ao usuário
+async fn authorize_file(pool: &PgPool, user: &User, file_url: &str) -> Result<(), HttpResponse> {
+    let owner = sqlx::query(
+        "SELECT id as patrimony_id FROM patrimonies
+         WHERE $1 IN (image_url, invoice_file, commitment_file, denf_se_file)
+         UNION ALL
+         SELECT patrimony_id FROM write_offs WHERE document_file = $1
+         UNION ALL
+         SELECT o.patrimony_id FROM maintenance_attachments a
+         JOIN maintenance_orders o ON a.order_id = o.id
+         WHERE a.file_url = $1
+         LIMIT 1"
+    )
+    .bind(file_url)
+    .map(|row: PgRow| row.get::<Uuid, _>("patrimony_id"))
+    .fetch_optional(pool)
+    .await;
+
+    match owner {
+        Ok(Some(patrimony_id)) => ensure_visible(pool, user, patrimony_id)
+            .await
+            .map_err(|_| HttpResponse::NotFound().json("File not found")),
+        Ok(None) => Err(HttpResponse::NotFound().json("File not found")),
         Err(e) => {
-            eprintln!("❌ Erro ao ler imagem: {:?}", e);
-            HttpResponse::NotFound().json("Image not found")
+            eprintln!("Error fetching file owner: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error fetching file"))
         }
     }
 }
 
-pub async fn serve_document(filename: web::Path<String>) -> HttpResponse {
-    let filepath = format!("./documents/{}", filename);
-    println!("📁 Servindo documento: {}", filepath);
-    
+fn file_content_type(filepath: &str) -> &'static str {
+    match filepath.rsplit('.').next().map(|ext| ext.to_ascii_lowercase()).as_deref() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("pdf") => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+async fn serve_file(pool: &PgPool, req: &HttpRequest, folder: &str, filename: &str) -> HttpResponse {
+    let user = match auth_middleware(req, pool).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    // A URL gravada no cadastro é a chave; nomes fora dela (ou com "..") não chegam ao disco
+    if let Err(response) = authorize_file(pool, &user, &format!("/{}/{}", folder, filename)).await {
+        return response;
+    }
+
+    let filepath = format!("./{}/{}", folder, filename);
+    println!("📁 Servindo arquivo: {}", filepath);
+
     match fs::read(&filepath) {
-        Ok(content) => {
-            HttpResponse::Ok()
-                .content_type("application/pdf")
-                .body(content)
-        }
+        Ok(content) => HttpResponse::Ok()
+            .content_type(file_content_type(&filepath))
+            .body(content),
         Err(e) => {
-            eprintln!("❌ Erro ao ler documento: {:?}", e);
-            HttpResponse::NotFound().json("Document not found")
+            eprintln!("❌ Erro ao ler arquivo: {:?}", e);
+            HttpResponse::NotFound().json("File not found")
         }
     }
 }
 
+pub async fn serve_image(
+    pool: web::Data<PgPool>,
+    filename: web::Path<String>,
+    req: HttpRequest,
+) -> HttpResponse {
+    serve_file(pool.get_ref(), &req, "uploads", &filename).await
+}
+
+pub async fn serve_document(
+    pool: web::Data<PgPool>,
+    filename: web::Path<String>,
+    req: HttpRequest,
+) -> HttpResponse {
+    serve_file(pool.get_ref(), &req, "documents", &filename).await
+}
+
 
 pub async fn health_check() -> HttpResponse {
     HttpResponse::Ok().json(serde_json::json!({
@@ -564,7 +784,7 @@ pub async fn get_patrimonies(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -575,90 +795,53 @@ pub async fn get_patrimonies(
     let department_filter = query.department.clone();
     let status_filter = query.status.clone();
     
-    let mut sql = "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at FROM patrimonies".to_string();
-    let mut params = Vec::new();
-    let mut where_clauses = Vec::new();
+    let mut sql = format!("SELECT {} FROM patrimonies", PATRIMONY_COLUMNS);
+    let mut params: Vec<String> = Vec::new();
+    // Somente bens da entidade do usuário; os da lixeira ficam fora da listagem
+    params.push(user.tenant_id.to_string());
+    let mut where_clauses: Vec<String> = vec!["tenant_id = $1::uuid".to_string(), "deleted_at IS NULL".to_string()];
+    
+    // Sem concessão, só os departamentos visíveis ao usuário
+    if let Some(user_id) = department_grant::restriction(&user) {
+        params.push(user_id.to_string());
+        where_clauses.push(format!("department IN {}", department_grant::visible_sql(params.len())));
+    }
     
     if let Some(ref dept) = department_filter {
-        where_clauses.push("department = $1");
-        params.push(dept);
+        params.push(dept.clone());
+        where_clauses.push(format!("department = ${}", params.len()));
     }
     
     if let Some(ref status) = status_filter {
-        where_clauses.push("status = $2");
-        params.push(status);
+        params.push(status.clone());
+        where_clauses.push(format!("status = ${}", params.len()));
+    }
+    
+    // Filtro por local inclui os sublocais (prédio -> andares -> salas)
+    if let Some(location_id) = query.location_id {
+        params.push(location_id.to_string());
+        where_clauses.push(format!("location_id IN {}", location::subtree_sql(params.len())));
     }
     
-    if !where_clauses.is_empty() {
-        sql.push_str(" WHERE ");
-        sql.push_str(&where_clauses.join(" AND "));
+    // Filtro por categoria inclui as subcategorias
+    if let Some(category_id) = query.category_id {
+        params.push(category_id.to_string());
+        where_clauses.push(format!("category_id IN {}", category::subtree_sql(params.len())));
     }
     
+    sql.push_str(" WHERE ");
+    sql.push_str(&where_clauses.join(" AND "));
+    
     sql.push_str(" ORDER BY created_at DESC");
 
-    let result = if params.is_empty() {
-        sqlx::query(&sql)
-            .map(|row: PgRow| {
-                let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-                
-                Patrimony {
-                    id: row.get("id"),
-                    plate: row.get("plate"),
-                    name: row.get("name"),
-                    description: row.get("description"),
-                    acquisition_date: row.get("acquisition_date"),
-                    value: convert_to_f64(&row, "value"),
-                    department: row.get("department"),
-                    status: row.get("status"),
-                    invoice_number: row.get("invoice_number"),
-                    commitment_number: row.get("commitment_number"),
-                    denf_se_number: row.get("denf_se_number"),
-                    invoice_file,
-                    commitment_file,
-                    denf_se_file,
-                    image_url,
-                    created_by: row.get("created_by"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                }
-            })
-            .fetch_all(pool.get_ref())
-            .await
-    } else {
-        let mut query = sqlx::query(&sql);
-        
-        for param in params {
-            query = query.bind(param);
-        }
-        
-        query
-            .map(|row: PgRow| {
-                let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-                
-                Patrimony {
-                    id: row.get("id"),
-                    plate: row.get("plate"),
-                    name: row.get("name"),
-                    description: row.get("description"),
-                    acquisition_date: row.get("acquisition_date"),
-                    value: convert_to_f64(&row, "value"),
-                    department: row.get("department"),
-                    status: row.get("status"),
-                    invoice_number: row.get("invoice_number"),
-                    commitment_number: row.get("commitment_number"),
-                    denf_se_number: row.get("denf_se_number"),
-                    invoice_file,
-                    commitment_file,
-                    denf_se_file,
-                    image_url,
-                    created_by: row.get("created_by"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                }
-            })
-            .fetch_all(pool.get_ref())
-            .await
-    };
+    let mut sql_query = sqlx::query(&sql);
+    for param in params {
+        sql_query = sql_query.bind(param);
+    }
+    let result = sql_query
+        .map(|row: PgRow| patrimony_from_row(&row))
+        .fetch_all(pool.get_ref())
+        .await;
 
     match result {
         Ok(patrimonies) => {
@@ -678,7 +861,7 @@ pub async fn get_patrimony(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -687,33 +870,16 @@ pub async fn get_patrimony(
     let patrimony_id = id.into_inner();
     
     let result = sqlx::query(
-        "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at FROM patrimonies WHERE id = $1"
+        &format!(
+            "SELECT {} FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {}",
+            PATRIMONY_COLUMNS,
+            department_grant::filter_sql("department", 3)
+        )
     )
     .bind(patrimony_id)
-    .map(|row: PgRow| {
-        let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-        
-        Patrimony {
-            id: row.get("id"),
-            plate: row.get("plate"),
-            name: row.get("name"),
-            description: row.get("description"),
-            acquisition_date: row.get("acquisition_date"),
-            value: convert_to_f64(&row, "value"),
-            department: row.get("department"),
-            status: row.get("status"),
-            invoice_number: row.get("invoice_number"),
-            commitment_number: row.get("commitment_number"),
-            denf_se_number: row.get("denf_se_number"),
-            invoice_file,
-            commitment_file,
-            denf_se_file,
-            image_url,
-            created_by: row.get("created_by"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    })
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| patrimony_from_row(&row))
     .fetch_one(pool.get_ref())
     .await;
 
@@ -750,7 +916,7 @@ pub async fn create_patrimony(
 
     // 🔍 DEBUG SIMPLES - como na versão anterior
     println!("📥 Dados recebidos para criar patrimônio:");
-    println!("  Plate: {}", patrimony.plate);
+    println!("  Plate: {:?}", patrimony.plate);
     println!("  Name: {}", patrimony.name);
     println!("  Description: {}", patrimony.description);
     println!("  Acquisition Date: {}", patrimony.acquisition_date);
@@ -762,24 +928,104 @@ pub async fn create_patrimony(
     println!("  DENF/SE Number: {:?}", patrimony.denf_se_number);
 
     // ✅ VALIDAÇÕES BÁSICAS (como antes)
-    if patrimony.plate.trim().is_empty() {
-        return HttpResponse::BadRequest().json("Plate é obrigatório");
-    }
     if patrimony.name.trim().is_empty() {
         return HttpResponse::BadRequest().json("Name é obrigatório");
     }
     if patrimony.value <= 0.0 {
         return HttpResponse::BadRequest().json("Value deve ser maior que zero");
     }
+    if patrimony.status == write_off::WRITTEN_OFF {
+        return HttpResponse::BadRequest().json("Use the write-off endpoint to write off a patrimony");
+    }
+    if let Err(response) = department::validate_department(pool.get_ref(), user.tenant_id, &patrimony.department).await {
+        return response;
+    }
+    if let Err(response) = department_grant::ensure_department_visible(pool.get_ref(), &user, &patrimony.department).await {
+        return response;
+    }
+    if let Some(location_id) = patrimony.location_id {
+        if let Err(response) = location::validate_location(pool.get_ref(), user.tenant_id, location_id).await {
+            return response;
+        }
+    }
+    if let Err(message) = depreciation::validate_policy_input(
+        patrimony.depreciation_method.as_deref(),
+        patrimony.useful_life_months,
+        patrimony.residual_value,
+        patrimony.value,
+    ) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("❌ Erro ao iniciar transação: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error creating patrimony: {}", e));
+        }
+    };
+
+    // Vida útil e valor residual não informados vêm da categoria
+    let category_defaults = match patrimony.category_id {
+        Some(category_id) => match category::load_defaults(&mut transaction, user.tenant_id, category_id).await {
+            Ok(Some(defaults)) if defaults.active => Some(defaults),
+            Ok(found) => {
+                let _ = transaction.rollback().await;
+                return if found.is_some() {
+                    HttpResponse::BadRequest().json("Category is inactive")
+                } else {
+                    HttpResponse::BadRequest().json("Category not found")
+                };
+            }
+            Err(e) => {
+                eprintln!("❌ Erro ao buscar categoria: {:?}", e);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json(format!("Error creating patrimony: {}", e));
+            }
+        },
+        None => None,
+    };
+    let useful_life_months = patrimony
+        .useful_life_months
+        .or(category_defaults.as_ref().map(|defaults| defaults.useful_life_months));
+    let residual_value = patrimony
+        .residual_value
+        .or(category_defaults.as_ref().map(|defaults| defaults.residual_value(patrimony.value)));
+
+    // Placa informada pelo cliente ou próxima da sequência configurada
+    let manual_plate = patrimony.plate.as_deref().map(str::trim).filter(|plate| !plate.is_empty());
+    let category_code = category_defaults.as_ref().map(|defaults| defaults.code.as_str());
+    let plate = match manual_plate {
+        Some(plate) => plate.to_string(),
+        None => match plate_sequence::allocate_plate(&mut transaction, user.tenant_id, patrimony.plate_sequence_id, category_code, &patrimony.department).await {
+            Ok(Some(plate)) => {
+                println!("🔢 Placa gerada automaticamente: {}", plate);
+                plate
+            }
+            Ok(None) => {
+                let _ = transaction.rollback().await;
+                return if patrimony.plate_sequence_id.is_some() {
+                    HttpResponse::BadRequest().json("Plate sequence not found")
+                } else {
+                    HttpResponse::BadRequest().json("Plate é obrigatório (nenhuma sequência configurada para o departamento)")
+                };
+            }
+            Err(e) => {
+                eprintln!("❌ Erro ao gerar placa: {:?}", e);
+                let _ = transaction.rollback().await;
+                return HttpResponse::InternalServerError().json(format!("Error creating patrimony: {}", e));
+            }
+        },
+    };
 
     // ✅ CORREÇÃO CRÍTICA: Usar a mesma simplicidade da versão anterior
     // O PostgreSQL aceita Option<String> diretamente, não precisa converter para Option<&str>
     let result = sqlx::query(
-        "INSERT INTO patrimonies (id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, created_by) 
-         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) 
+        "INSERT INTO patrimonies (id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, created_by, depreciation_method, useful_life_months, residual_value, category_id, tenant_id) 
+         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16) 
          RETURNING id"
     )
-    .bind(&patrimony.plate)
+    .bind(&plate)
     .bind(&patrimony.name)
     .bind(&patrimony.description)
     .bind(patrimony.acquisition_date)
@@ -790,45 +1036,53 @@ pub async fn create_patrimony(
     .bind(&patrimony.commitment_number) // ✅ Usar Option<String> diretamente
     .bind(&patrimony.denf_se_number) // ✅ Usar Option<String> diretamente
     .bind(user.id)
+    .bind(&patrimony.depreciation_method)
+    .bind(useful_life_months)
+    .bind(residual_value)
+    .bind(patrimony.category_id)
+    .bind(user.tenant_id)
     .map(|row: PgRow| row.get::<Uuid, _>("id"))
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *transaction)
     .await;
 
     println!("📊 Resultado do INSERT: {:?}", result);
 
+    // Local inicial entra no histórico de movimentações
+    let result = match (result, patrimony.location_id) {
+        (Ok(record_id), Some(location_id)) => {
+            location::record_move(&mut transaction, record_id, None, location_id, user.id, None)
+                .await
+                .map(|_| record_id)
+        }
+        (result, _) => result,
+    };
+
+    // Auditoria na mesma transação do cadastro
+    let result = match result {
+        Ok(record_id) => audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, record_id, audit::CREATE, None)
+            .await
+            .map(|_| record_id),
+        Err(e) => Err(e),
+    };
+
+    let result = match result {
+        Ok(record_id) => transaction.commit().await.map(|_| record_id),
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            Err(e)
+        }
+    };
+
     match result {
         Ok(record_id) => {
             println!("✅ INSERT bem-sucedido, ID: {}", record_id);
             
             // Buscar o patrimônio completo criado
             let new_patrimony = sqlx::query(
-                "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at FROM patrimonies WHERE id = $1"
+                &format!("SELECT {} FROM patrimonies WHERE id = $1", PATRIMONY_COLUMNS)
             )
             .bind(record_id)
-            .map(|row: PgRow| {
-                let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-                
-                Patrimony {
-                    id: row.get("id"),
-                    plate: row.get("plate"),
-                    name: row.get("name"),
-                    description: row.get("description"),
-                    acquisition_date: row.get("acquisition_date"),
-                    value: convert_to_f64(&row, "value"),
-                    department: row.get("department"),
-                    status: row.get("status"),
-                    invoice_number: row.get("invoice_number"),
-                    commitment_number: row.get("commitment_number"),
-                    denf_se_number: row.get("denf_se_number"),
-                    invoice_file,
-                    commitment_file,
-                    denf_se_file,
-                    image_url,
-                    created_by: row.get("created_by"),
-                    created_at: row.get("created_at"),
-                    updated_at: row.get("updated_at"),
-                }
-            })
+            .map(|row: PgRow| patrimony_from_row(&row))
             .fetch_one(pool.get_ref())
             .await;
 
@@ -863,7 +1117,7 @@ pub async fn update_patrimony(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -874,10 +1128,13 @@ pub async fn update_patrimony(
     println!("🔄 Iniciando update do patrimônio: {}", patrimony_id);
 
     // Primeiro buscar o patrimônio existente
-    let existing_result = sqlx::query(
-        "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number FROM patrimonies WHERE id = $1"
-    )
+    let existing_result = sqlx::query(&format!(
+        "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
     .bind(patrimony_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
     .fetch_one(pool.get_ref())
     .await;
 
@@ -899,6 +1156,29 @@ pub async fn update_patrimony(
     let existing_value = convert_to_f64(&existing_row, "value");
     let existing_department = existing_row.get::<String, _>("department");
     let existing_status = existing_row.get::<String, _>("status");
+
+    // Bens baixados são somente leitura; a baixa só ocorre pelo endpoint próprio
+    if existing_status == write_off::WRITTEN_OFF {
+        return write_off::written_off_response();
+    }
+    if patrimony.status.as_deref() == Some(write_off::WRITTEN_OFF) {
+        return HttpResponse::BadRequest().json("Use the write-off endpoint to write off a patrimony");
+    }
+    // Enquanto houver ordem de serviço aberta, o status é controlado pela ordem
+    if patrimony.status.as_ref().is_some_and(|s| *s != existing_status) {
+        match maintenance::has_open_order(pool.get_ref(), patrimony_id).await {
+            Ok(true) => return HttpResponse::Conflict().json("Patrimony has an open maintenance order; close or cancel it to change the status"),
+            Ok(false) => {}
+            Err(e) => {
+                eprintln!("Error checking maintenance orders: {}", e);
+                return HttpResponse::InternalServerError().json("Error updating patrimony");
+            }
+        }
+    }
+    // Mudança de departamento só pelo fluxo de transferência (solicitação → aprovação → recebimento)
+    if patrimony.department.as_ref().is_some_and(|d| *d != existing_department) {
+        return HttpResponse::BadRequest().json("Department changes must go through POST /api/transfer");
+    }
     
     // ✅ CORREÇÃO para campos Option - usar unwrap_or_default()
     let existing_invoice_number = existing_row.get::<Option<String>, _>("invoice_number").unwrap_or_default();
@@ -936,7 +1216,6 @@ pub async fn update_patrimony(
     let description = patrimony.description.as_ref().unwrap_or(&existing_description);
     let acquisition_date = patrimony.acquisition_date.unwrap_or(existing_acquisition_date);
     let value = patrimony.value.unwrap_or(existing_value);
-    let department = patrimony.department.as_ref().unwrap_or(&existing_department);
     let status = patrimony.status.as_ref().unwrap_or(&existing_status);
     let invoice_number = patrimony.invoice_number.as_ref().unwrap_or(&existing_invoice_number);
     let commitment_number = patrimony.commitment_number.as_ref().unwrap_or(&existing_commitment_number);
@@ -949,29 +1228,91 @@ pub async fn update_patrimony(
     println!("  Description: {}", description);
     println!("  Acquisition Date: {}", acquisition_date);
     println!("  Value: {}", value);
-    println!("  Department: {}", department);
+    println!("  Department: {}", existing_department);
     println!("  Status: {}", status);
     println!("  Invoice Number: '{}'", invoice_number);
     println!("  Commitment Number: '{}'", commitment_number);
     println!("  DENF/SE Number: '{}'", denf_se_number);
 
+    if let Err(message) = depreciation::validate_policy_input(
+        patrimony.depreciation_method.as_deref(),
+        patrimony.useful_life_months,
+        patrimony.residual_value,
+        value,
+    ) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    if let Some(category_id) = patrimony.category_id {
+        match sqlx::query("SELECT active FROM categories WHERE id = $1 AND tenant_id = $2")
+            .bind(category_id)
+            .bind(user.tenant_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(row)) if row.get::<bool, _>("active") => {}
+            Ok(Some(_)) => return HttpResponse::BadRequest().json("Category is inactive"),
+            Ok(None) => return HttpResponse::BadRequest().json("Category not found"),
+            Err(e) => {
+                eprintln!("❌ Error fetching category: {:?}", e);
+                return HttpResponse::InternalServerError().json("Error updating patrimony");
+            }
+        }
+    }
+
+    // Estado anterior e alteração gravados juntos com o registro de auditoria
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("❌ Error starting transaction: {:?}", e);
+            return HttpResponse::InternalServerError().json("Error updating patrimony");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("❌ Error fetching patrimony for audit: {:?}", e);
+            return HttpResponse::InternalServerError().json("Error updating patrimony");
+        }
+    };
+
+    // Campos de depreciação ausentes mantêm o valor atual (COALESCE); se o bem
+    // ainda não tiver vida útil ou valor residual, usa os padrões da nova categoria
     let result = sqlx::query(
-        "UPDATE patrimonies SET plate = $1, name = $2, description = $3, acquisition_date = $4, value = $5, department = $6, status = $7, invoice_number = $8, commitment_number = $9, denf_se_number = $10, updated_at = NOW() WHERE id = $11"
+        "UPDATE patrimonies SET plate = $1, name = $2, description = $3, acquisition_date = $4, value = $5, department = $6, status = $7, invoice_number = $8, commitment_number = $9, denf_se_number = $10, depreciation_method = COALESCE($12, depreciation_method),
+            useful_life_months = COALESCE($13, useful_life_months, (SELECT default_useful_life_months FROM categories WHERE id = $15)),
+            residual_value = COALESCE($14, residual_value, (SELECT ROUND(($5 * default_residual_percent / 100)::numeric, 2) FROM categories WHERE id = $15)),
+            category_id = COALESCE($15, category_id), updated_at = NOW() WHERE id = $11 AND tenant_id = $16"
     )
     .bind(plate)
     .bind(name)
     .bind(description)
     .bind(acquisition_date)
     .bind(value)
-    .bind(department)
+    .bind(&existing_department)
     .bind(status)
     .bind(invoice_number)
     .bind(commitment_number)
     .bind(denf_se_number)
     .bind(patrimony_id)
-    .execute(pool.get_ref())
+    .bind(&patrimony.depreciation_method)
+    .bind(patrimony.useful_life_months)
+    .bind(patrimony.residual_value)
+    .bind(patrimony.category_id)
+    .bind(user.tenant_id)
+    .execute(&mut *transaction)
     .await;
 
+    let result = match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            match audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::UPDATE, before).await {
+                Ok(()) => transaction.commit().await.map(|_| result),
+                Err(e) => Err(e),
+            }
+        }
+        other => other,
+    };
+
     match result {
         Ok(result) => {
             if result.rows_affected() > 0 {
@@ -979,33 +1320,10 @@ pub async fn update_patrimony(
                 
                 // Buscar o patrimônio atualizado
                 let updated_patrimony = sqlx::query(
-                    "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at FROM patrimonies WHERE id = $1"
+                    &format!("SELECT {} FROM patrimonies WHERE id = $1", PATRIMONY_COLUMNS)
                 )
                 .bind(patrimony_id)
-                .map(|row: PgRow| {
-                    let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-                    
-                    Patrimony {
-                        id: row.get("id"),
-                        plate: row.get("plate"),
-                        name: row.get("name"),
-                        description: row.get("description"),
-                        acquisition_date: row.get("acquisition_date"),
-                        value: convert_to_f64(&row, "value"),
-                        department: row.get("department"),
-                        status: row.get("status"),
-                        invoice_number: row.get("invoice_number"),
-                        commitment_number: row.get("commitment_number"),
-                        denf_se_number: row.get("denf_se_number"),
-                        invoice_file,
-                        commitment_file,
-                        denf_se_file,
-                        image_url,
-                        created_by: row.get("created_by"),
-                        created_at: row.get("created_at"),
-                        updated_at: row.get("updated_at"),
-                    }
-                })
+                .map(|row: PgRow| patrimony_from_row(&row))
                 .fetch_one(pool.get_ref())
                 .await;
 
@@ -1042,25 +1360,90 @@ pub async fn delete_patrimony(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
     };
 
     let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    // Bens baixados permanecem no histórico
+    if let Err(response) = write_off::ensure_not_written_off(pool.get_ref(), patrimony_id).await {
+        return response;
+    }
+
+    // Transferência ou manutenção em aberto precisa ser encerrada antes
+    match sqlx::query(
+        "SELECT EXISTS(SELECT 1 FROM transfers WHERE patrimony_id = $1 AND status IN ('pending', 'approved')) as open_transfer"
+    )
+    .bind(patrimony_id)
+    .fetch_one(pool.get_ref())
+    .await
+    {
+        Ok(row) if row.get::<bool, _>("open_transfer") => {
+            return HttpResponse::Conflict().json("Patrimony has an open transfer; finish or reject it before deleting");
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error checking open transfers: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error deleting patrimony: {}", e));
+        }
+    }
+    match maintenance::has_open_order(pool.get_ref(), patrimony_id).await {
+        Ok(true) => {
+            return HttpResponse::Conflict().json("Patrimony has an open maintenance order; close or cancel it before deleting");
+        }
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("Error checking maintenance orders: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error deleting patrimony: {}", e));
+        }
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error deleting patrimony: {}", e));
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error deleting patrimony: {}", e));
+        }
+    };
     
+    // Exclusão lógica: o bem vai para a lixeira e o histórico é preservado
     let result = sqlx::query(
-        "DELETE FROM patrimonies WHERE id = $1"
+        "UPDATE patrimonies SET deleted_at = NOW(), deleted_by = $2, updated_at = NOW() WHERE id = $1 AND tenant_id = $3 AND deleted_at IS NULL"
     )
     .bind(patrimony_id)
-    .execute(pool.get_ref())
+    .bind(user.id)
+    .bind(user.tenant_id)
+    .execute(&mut *transaction)
     .await;
 
+    let result = match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            match audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::DELETE, before).await {
+                Ok(()) => transaction.commit().await.map(|_| result),
+                Err(e) => Err(e),
+            }
+        }
+        other => other,
+    };
+
     match result {
         Ok(result) => {
             if result.rows_affected() > 0 {
-                HttpResponse::Ok().json("Patrimony deleted successfully")
+                HttpResponse::Ok().json("Patrimony moved to trash")
             } else {
                 HttpResponse::NotFound().json("Patrimony not found")
             }
@@ -1078,7 +1461,7 @@ pub async fn debug_images(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -1086,7 +1469,8 @@ pub async fn debug_images(
 
     println!("🔍 Debug: Buscando imagens no banco de dados");
     
-    match sqlx::query("SELECT id, plate, name, image_url FROM patrimonies WHERE image_url IS NOT NULL AND image_url != ''")
+    match sqlx::query("SELECT id, plate, name, image_url FROM patrimonies WHERE image_url IS NOT NULL AND image_url != '' AND tenant_id = $1 AND deleted_at IS NULL")
+        .bind(user.tenant_id)
         .fetch_all(pool.get_ref())
         .await
     {
@@ -1124,19 +1508,24 @@ pub async fn get_stats(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
     };
 
     let department_filter = query.department.clone();
+    // Estatísticas só dos departamentos visíveis ao usuário
+    let restriction = department_grant::restriction(&user);
     
     let total_result = if let Some(ref dept) = department_filter {
-        sqlx::query(
-            "SELECT COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE department = $1"
-        )
+        sqlx::query(&format!(
+            "SELECT COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE department = $1 AND tenant_id = $2 AND status <> 'written_off' AND deleted_at IS NULL AND {}",
+            department_grant::filter_sql("department", 3)
+        ))
         .bind(dept)
+        .bind(user.tenant_id)
+        .bind(restriction)
         .map(|row: PgRow| {
             let count: i64 = row.get("count");
             let total_value: f64 = convert_to_f64(&row, "total_value");
@@ -1145,9 +1534,12 @@ pub async fn get_stats(
         .fetch_one(pool.get_ref())
         .await
     } else {
-        sqlx::query(
-            "SELECT COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies"
-        )
+        sqlx::query(&format!(
+            "SELECT COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE tenant_id = $1 AND status <> 'written_off' AND deleted_at IS NULL AND {}",
+            department_grant::filter_sql("department", 2)
+        ))
+        .bind(user.tenant_id)
+        .bind(restriction)
         .map(|row: PgRow| {
             let count: i64 = row.get("count");
             let total_value: f64 = convert_to_f64(&row, "total_value");
@@ -1166,25 +1558,33 @@ pub async fn get_stats(
     };
 
     let status_result = if let Some(ref dept) = department_filter {
-        sqlx::query(
-            "SELECT status, COUNT(*) as count FROM patrimonies WHERE department = $1 GROUP BY status"
-        )
+        sqlx::query(&format!(
+            "SELECT status, COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE department = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {} GROUP BY status",
+            department_grant::filter_sql("department", 3)
+        ))
         .bind(dept)
+        .bind(user.tenant_id)
+        .bind(restriction)
         .map(|row: PgRow| {
             let status: String = row.get("status");
             let count: i64 = row.get("count");
-            (status, count)
+            let total_value: f64 = convert_to_f64(&row, "total_value");
+            (status, count, total_value)
         })
         .fetch_all(pool.get_ref())
         .await
     } else {
-        sqlx::query(
-            "SELECT status, COUNT(*) as count FROM patrimonies GROUP BY status"
-        )
+        sqlx::query(&format!(
+            "SELECT status, COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE tenant_id = $1 AND deleted_at IS NULL AND {} GROUP BY status",
+            department_grant::filter_sql("department", 2)
+        ))
+        .bind(user.tenant_id)
+        .bind(restriction)
         .map(|row: PgRow| {
             let status: String = row.get("status");
             let count: i64 = row.get("count");
-            (status, count)
+            let total_value: f64 = convert_to_f64(&row, "total_value");
+            (status, count, total_value)
         })
         .fetch_all(pool.get_ref())
         .await
@@ -1202,29 +1602,91 @@ pub async fn get_stats(
     let mut inactive = 0;
     let mut maintenance = 0;
     let mut written_off = 0;
+    let mut written_off_value = 0.0;
 
-    for (status, count) in status_counts {
+    for (status, count, status_value) in status_counts {
         match status.as_str() {
             "active" => active = count,
             "inactive" => inactive = count,
             "maintenance" => maintenance = count,
-            "written_off" => written_off = count,
+            "written_off" => {
+                written_off = count;
+                written_off_value = status_value;
+            }
             _ => {}
         }
     }
 
+    // Depreciação calculada bem a bem (o método pode variar entre os bens)
+    let depreciation_result = sqlx::query(&format!(
+        "SELECT department, category_id, value, acquisition_date, depreciation_method, useful_life_months, residual_value
+         FROM patrimonies
+         WHERE acquisition_date IS NOT NULL AND status <> 'written_off' AND deleted_at IS NULL AND tenant_id = $2 AND ($1::varchar IS NULL OR department = $1)
+           AND {}",
+        department_grant::filter_sql("department", 3)
+    ))
+    .bind(&department_filter)
+    .bind(user.tenant_id)
+    .bind(restriction)
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let depreciation_rows = match depreciation_result {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching depreciation stats: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error fetching statistics: {}", e));
+        }
+    };
+
+    let today = Utc::now().date_naive();
+    let mut depreciation_by_department: HashMap<String, (f64, f64)> = HashMap::new();
+    let mut depreciation_by_category: HashMap<Option<Uuid>, (f64, f64)> = HashMap::new();
+    for row in &depreciation_rows {
+        let policy = DepreciationPolicy::from_row(row);
+        let (accumulated, book_value) = depreciation::snapshot(
+            convert_to_f64(row, "value"),
+            row.get("acquisition_date"),
+            &policy,
+            today,
+        );
+        let totals = depreciation_by_department
+            .entry(row.get::<String, _>("department"))
+            .or_insert((0.0, 0.0));
+        totals.0 += accumulated;
+        totals.1 += book_value;
+        let totals = depreciation_by_category
+            .entry(row.get::<Option<Uuid>, _>("category_id"))
+            .or_insert((0.0, 0.0));
+        totals.0 += accumulated;
+        totals.1 += book_value;
+    }
+    let total_accumulated_depreciation =
+        depreciation::round_cents(depreciation_by_department.values().map(|totals| totals.0).sum());
+    let total_book_value =
+        depreciation::round_cents(depreciation_by_department.values().map(|totals| totals.1).sum());
+
     let department_result = if department_filter.is_none() {
-        match sqlx::query(
-            "SELECT department, COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies GROUP BY department ORDER BY count DESC"
-        )
+        match sqlx::query(&format!(
+            "SELECT department, COUNT(*) as count, COALESCE(SUM(value), 0) as total_value FROM patrimonies WHERE tenant_id = $1 AND status <> 'written_off' AND deleted_at IS NULL AND {} GROUP BY department ORDER BY count DESC",
+            department_grant::filter_sql("department", 2)
+        ))
+        .bind(user.tenant_id)
+        .bind(restriction)
         .map(|row: PgRow| {
             let department: String = row.get("department");
             let count: i64 = row.get("count");
             let total_value: f64 = convert_to_f64(&row, "total_value");
+            let (accumulated_depreciation, book_value) = depreciation_by_department
+                .get(&department)
+                .copied()
+                .unwrap_or((0.0, 0.0));
             DepartmentStats {
                 department,
                 count,
                 total_value,
+                accumulated_depreciation,
+                book_value,
             }
         })
         .fetch_all(pool.get_ref())
@@ -1240,6 +1702,48 @@ pub async fn get_stats(
         Vec::new()
     };
 
+    // Totais por categoria (conta PCASP), respeitando o filtro de departamento
+    let category_result = sqlx::query(&format!(
+        "SELECT p.category_id, c.code, c.name, c.accounting_code, COUNT(*) as count, COALESCE(SUM(p.value), 0) as total_value
+         FROM patrimonies p
+         LEFT JOIN categories c ON c.id = p.category_id
+         WHERE p.status <> 'written_off' AND p.deleted_at IS NULL AND p.tenant_id = $2 AND ($1::varchar IS NULL OR p.department = $1)
+           AND {}
+         GROUP BY p.category_id, c.code, c.name, c.accounting_code
+         ORDER BY c.accounting_code NULLS LAST",
+        department_grant::filter_sql("p.department", 3)
+    ))
+    .bind(&department_filter)
+    .bind(user.tenant_id)
+    .bind(restriction)
+    .map(|row: PgRow| {
+        let category_id: Option<Uuid> = row.get("category_id");
+        let (accumulated_depreciation, book_value) = depreciation_by_category
+            .get(&category_id)
+            .copied()
+            .unwrap_or((0.0, 0.0));
+        CategoryStats {
+            category_id,
+            code: row.get("code"),
+            name: row.get("name"),
+            accounting_code: row.get("accounting_code"),
+            count: row.get("count"),
+            total_value: convert_to_f64(&row, "total_value"),
+            accumulated_depreciation: depreciation::round_cents(accumulated_depreciation),
+            book_value: depreciation::round_cents(book_value),
+        }
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let category_result = match category_result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Error fetching category stats: {:?}", e);
+            return HttpResponse::InternalServerError().json(format!("Error fetching statistics: {}", e));
+        }
+    };
+
     HttpResponse::Ok().json(StatsResponse {
         total: total_count,
         active,
@@ -1247,7 +1751,11 @@ pub async fn get_stats(
         maintenance,
         written_off,
         total_value,
+        written_off_value,
+        total_accumulated_depreciation,
+        total_book_value,
         by_department: department_result,
+        by_category: category_result,
     })
 }
 
@@ -1257,7 +1765,7 @@ pub async fn get_patrimonies_by_department(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
@@ -1266,33 +1774,16 @@ pub async fn get_patrimonies_by_department(
     let department_filter = department.into_inner();
     
     let result = sqlx::query(
-        "SELECT id, plate, name, description, acquisition_date, value, department, status, invoice_number, commitment_number, denf_se_number, invoice_file, commitment_file, denf_se_file, image_url, created_by, created_at, updated_at FROM patrimonies WHERE department = $1 ORDER BY created_at DESC"
+        &format!(
+            "SELECT {} FROM patrimonies WHERE department = $1 AND tenant_id = $2 AND deleted_at IS NULL AND {} ORDER BY created_at DESC",
+            PATRIMONY_COLUMNS,
+            department_grant::filter_sql("department", 3)
+        )
     )
     .bind(&department_filter)
-    .map(|row: PgRow| {
-        let (invoice_file, commitment_file, denf_se_file, image_url) = get_document_urls(&row);
-        
-        Patrimony {
-            id: row.get("id"),
-            plate: row.get("plate"),
-            name: row.get("name"),
-            description: row.get("description"),
-            acquisition_date: row.get("acquisition_date"),
-            value: convert_to_f64(&row, "value"),
-            department: row.get("department"),
-            status: row.get("status"),
-            invoice_number: row.get("invoice_number"),
-            commitment_number: row.get("commitment_number"),
-            denf_se_number: row.get("denf_se_number"),
-            invoice_file,
-            commitment_file,
-            denf_se_file,
-            image_url,
-            created_by: row.get("created_by"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    })
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| patrimony_from_row(&row))
     .fetch_all(pool.get_ref())
     .await;
 
@@ -1315,36 +1806,54 @@ pub async fn get_patrimonies_by_department(
     }
 }
 
-pub async fn get_departments(pool: web::Data<PgPool>) -> HttpResponse {
-    let result = sqlx::query(
-        "SELECT DISTINCT department FROM patrimonies ORDER BY department"
+// Funções de autenticação
+pub async fn register_user(
+    pool: web::Data<PgPool>,
+    user_data: web::Json<RegisterUser>,
+    req: HttpRequest,
+) -> HttpResponse {
+    if user_data.username.trim().is_empty() || user_data.password.is_empty() {
+        return HttpResponse::BadRequest().json("Username and password are required");
+    }
+    if let Err(response) = password_policy::validate(&user_data.password, &user_data.username) {
+        return response;
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {:?}", e);
+            return HttpResponse::InternalServerError().json("Error creating user");
+        }
+    };
+
+    // Convite válido: não usado, não revogado, dentro do prazo e de entidade ativa
+    let invitation = sqlx::query(
+        "SELECT i.id, i.tenant_id, i.department, i.role, i.email, t.name as tenant_name
+         FROM user_invitations i
+         JOIN tenants t ON t.id = i.tenant_id
+         WHERE i.token_hash = $1 AND i.accepted_at IS NULL AND i.revoked_at IS NULL
+           AND i.expires_at > NOW() AND t.active
+         FOR UPDATE OF i"
     )
-    .map(|row: PgRow| row.get::<String, _>("department"))
-    .fetch_all(pool.get_ref())
+    .bind(session::hash_token(&user_data.invite_token))
+    .fetch_optional(&mut *transaction)
     .await;
 
-    match result {
-        Ok(departments) => HttpResponse::Ok().json(departments),
+    let invitation = match invitation {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::Forbidden().json("Registration requires a valid invitation"),
         Err(e) => {
-            eprintln!("Error fetching departments: {:?}", e);
-            HttpResponse::InternalServerError().json(format!("Error fetching departments: {}", e))
+            eprintln!("Error fetching invitation: {:?}", e);
+            return HttpResponse::InternalServerError().json("Error creating user");
         }
-    }
-}
-
-// Funções de autenticação
-pub async fn register_user(
-    pool: web::Data<PgPool>,
-    user_data: web::Json<CreateUser>,
-) -> HttpResponse {
-    // Verificar se o usuário já existe
-    let existing_user = sqlx::query("SELECT id FROM users WHERE username = $1")
-        .bind(&user_data.username)
-        .fetch_optional(pool.get_ref())
-        .await;
+    };
+    let invitation_id: Uuid = invitation.get("id");
+    let tenant_id: Uuid = invitation.get("tenant_id");
+    let department: String = invitation.get("department");
 
-    if let Ok(Some(_)) = existing_user {
-        return HttpResponse::BadRequest().json("Username already exists");
+    if let Err(response) = department::validate_department(pool.get_ref(), tenant_id, &department).await {
+        return response;
     }
 
     // Hash da senha
@@ -1353,34 +1862,57 @@ pub async fn register_user(
         Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
     };
 
-    let role = user_data.role.clone().unwrap_or_else(|| "user".to_string());
-
-    let result = sqlx::query(
-        "INSERT INTO users (id, company_name, department, username, password_hash, email, role) 
-         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6) 
-         RETURNING id, company_name, department, username, email, role, created_at, updated_at"
-    )
-    .bind(&user_data.company_name)
-    .bind(&user_data.department)
-    .bind(&user_data.username)
+    let company_name = user_data
+        .company_name
+        .clone()
+        .unwrap_or_else(|| invitation.get("tenant_name"));
+    let email = user_data.email.clone().or_else(|| invitation.get("email"));
+
+    let result = sqlx::query(&format!(
+        "INSERT INTO users (id, company_name, department, username, password_hash, email, role, tenant_id) 
+         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7) 
+         RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(&company_name)
+    .bind(&department)
+    .bind(user_data.username.trim())
     .bind(password_hash)
-    .bind(&user_data.email)
-    .bind(&role)
-    .map(|row: PgRow| User {
-        id: row.get("id"),
-        company_name: row.get("company_name"),
-        department: row.get("department"),
-        username: row.get("username"),
-        email: row.get("email"),
-        role: row.get("role"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
-    .fetch_one(pool.get_ref())
+    .bind(&email)
+    .bind(invitation.get::<String, _>("role"))
+    .bind(tenant_id)
+    .map(|row: PgRow| user_from_row(&row))
+    .fetch_one(&mut *transaction)
     .await;
 
+    let user = match result {
+        Ok(user) => user,
+        Err(e) => {
+            eprintln!("Error creating user: {:?}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::BadRequest().json("Username already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating user");
+        }
+    };
+
+    let accepted = sqlx::query("UPDATE user_invitations SET accepted_at = NOW(), accepted_user_id = $2 WHERE id = $1")
+        .bind(invitation_id)
+        .bind(user.id)
+        .execute(&mut *transaction)
+        .await;
+
+    // Auto-cadastro: o próprio usuário criado é o autor do registro
+    let result = match accepted {
+        Ok(_) => match audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::CREATE, None).await {
+            Ok(()) => transaction.commit().await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+
     match result {
-        Ok(user) => HttpResponse::Created().json(user),
+        Ok(()) => HttpResponse::Created().json(user),
         Err(e) => {
             eprintln!("Error creating user: {:?}", e);
             HttpResponse::InternalServerError().json("Error creating user")
@@ -1391,82 +1923,98 @@ pub async fn register_user(
 pub async fn login_user(
     pool: web::Data<PgPool>,
     login_data: web::Json<LoginRequest>,
+    req: HttpRequest,
 ) -> HttpResponse {
-    let result = sqlx::query(
-        "SELECT id, company_name, department, username, password_hash, email, role, created_at, updated_at 
-         FROM users WHERE username = $1"
-    )
-    .bind(&login_data.username)
-    .map(|row: PgRow| {
-        let password_hash: String = row.get("password_hash");
-        User {
-            id: row.get("id"),
-            company_name: row.get("company_name"),
-            department: row.get("department"),
-            username: row.get("username"),
-            email: row.get("email"),
-            role: row.get("role"),
-            created_at: row.get("created_at"),
-            updated_at: row.get("updated_at"),
-        }
-    })
+    let username = login_data.username.as_str();
+
+    // Usuário, hash da senha e situação da entidade em uma consulta só
+    let result = sqlx::query(&format!(
+        "SELECT {}, password_hash,
+                (SELECT active FROM tenants t WHERE t.id = users.tenant_id) as tenant_active
+         FROM users WHERE username = $1",
+        USER_COLUMNS
+    ))
+    .bind(username)
     .fetch_optional(pool.get_ref())
     .await;
 
-    match result {
-        Ok(Some(user)) => {
-            // Verificar senha (precisa buscar o hash separadamente)
-            let password_hash_result: Result<(String,), _> = sqlx::query_as(
-                "SELECT password_hash FROM users WHERE username = $1"
-            )
-            .bind(&login_data.username)
-            .fetch_one(pool.get_ref())
-            .await;
-
-            match password_hash_result {
-                Ok((password_hash,)) => {
-                    match verify(&login_data.password, &password_hash) {
-                        Ok(valid) if valid => {
-                            // Gerar JWT
-                            let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "secret".to_string());
-                            let expiration = Utc::now()
-                                .checked_add_signed(chrono::Duration::hours(24))
-                                .expect("valid timestamp")
-                                .timestamp() as usize;
-
-                            let claims = Claims {
-                                sub: user.id.to_string(),
-                                exp: expiration,
-                                role: user.role.clone(),
-                            };
-
-                            let token = encode(
-                                &Header::default(),
-                                &claims,
-                                &EncodingKey::from_secret(secret.as_ref()),
-                            );
-
-                            match token {
-                                Ok(token) => {
-                                    HttpResponse::Ok().json(LoginResponse {
-                                        token,
-                                        user,
-                                    })
-                                }
-                                Err(_) => HttpResponse::InternalServerError().json("Error generating token"),
-                            }
-                        }
-                        _ => HttpResponse::Unauthorized().json("Invalid credentials"),
-                    }
-                }
-                Err(_) => HttpResponse::Unauthorized().json("Invalid credentials"),
-            }
-        }
-        Ok(None) => HttpResponse::Unauthorized().json("Invalid credentials"),
+    let row = match result {
+        Ok(row) => row,
         Err(e) => {
             eprintln!("Error during login: {:?}", e);
-            HttpResponse::InternalServerError().json("Error during login")
+            return HttpResponse::InternalServerError().json("Error during login");
         }
+    };
+    let user = row.as_ref().map(user_from_row);
+
+    // Atraso exponencial do IP e da conta; a tentativa fica reservada até a senha ser conferida
+    let attempt_id = match login_security::reserve_attempt(pool.get_ref(), username, user.as_ref(), &req).await {
+        Ok(Reservation::Granted(attempt_id)) => attempt_id,
+        Ok(Reservation::Throttled(seconds)) => return login_security::too_many_attempts(seconds),
+        Err(e) => {
+            eprintln!("Error checking login attempts: {:?}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    };
+
+    let (Some(row), Some(user)) = (row, user) else {
+        login_security::verify_password(&login_data.password, None).await;
+        login_security::finish_attempt(pool.get_ref(), attempt_id, Some(login_security::UNKNOWN_USER)).await;
+        return HttpResponse::Unauthorized().json("Invalid credentials");
+    };
+
+    // Bloqueio vale antes de conferir a senha
+    if let Some(locked_until) = user.locked_until.filter(|until| *until > Utc::now()) {
+        login_security::finish_attempt(pool.get_ref(), attempt_id, Some(login_security::LOCKED)).await;
+        return login_security::account_locked(locked_until);
+    }
+
+    if !login_security::verify_password(&login_data.password, Some(row.get::<&str, _>("password_hash"))).await {
+        login_security::finish_attempt(pool.get_ref(), attempt_id, Some(login_security::INVALID_PASSWORD)).await;
+        return match login_security::register_failure(pool.get_ref(), user.id).await {
+            Ok(Some(locked_until)) => {
+                println!("🔒 Conta '{}' bloqueada por tentativas de login erradas", user.username);
+                login_security::account_locked(locked_until)
+            }
+            Ok(None) => HttpResponse::Unauthorized().json("Invalid credentials"),
+            Err(e) => {
+                eprintln!("Error registering login failure: {:?}", e);
+                HttpResponse::Unauthorized().json("Invalid credentials")
+            }
+        };
+    }
+
+    if !row.get::<bool, _>("tenant_active") {
+        login_security::finish_attempt(pool.get_ref(), attempt_id, Some(login_security::TENANT_INACTIVE)).await;
+        return HttpResponse::Forbidden().json("Tenant is inactive");
+    }
+    if !user.active {
+        login_security::finish_attempt(pool.get_ref(), attempt_id, Some(login_security::DEACTIVATED)).await;
+        return HttpResponse::Forbidden().json("User is deactivated");
+    }
+
+    // Com 2FA a senha só abre o desafio; as falhas são zeradas depois do código
+    if user.two_factor_enabled {
+        login_security::discard_attempt(pool.get_ref(), attempt_id).await;
+        return two_factor::challenge_response(pool.get_ref(), &user).await;
+    }
+
+    if let Err(e) = login_security::register_success(pool.get_ref(), user.id).await {
+        eprintln!("Error resetting login failures: {:?}", e);
+    }
+    login_security::finish_attempt(pool.get_ref(), attempt_id, None).await;
+
+    // Abrir a sessão: token de acesso curto + token de renovação
+    match session::start_session(pool.get_ref(), &user, &req).await {
+        Ok(tokens) => {
+            HttpResponse::Ok().json(LoginResponse {
+                token: tokens.token,
+                refresh_token: tokens.refresh_token,
+                expires_in: tokens.expires_in,
+                user,
+            })
+        }
+        Err(response) => response,
     }
 }
 
@@ -1474,32 +2022,19 @@ pub async fn get_users(
     pool: web::Data<PgPool>,
     req: HttpRequest,
 ) -> HttpResponse {
-    // Verificar autenticação e permissões de admin
+    // Verificar autenticação
     let user = match auth_middleware(&req, pool.get_ref()).await {
-        Ok(Some(user)) => {
-            if user.role != "admin" {
-                return HttpResponse::Forbidden().json("Admin access required");
-            }
-            user
-        }
+        Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
     };
 
-    let result = sqlx::query(
-        "SELECT id, company_name, department, username, email, role, created_at, updated_at 
-         FROM users ORDER BY created_at DESC"
-    )
-    .map(|row: PgRow| User {
-        id: row.get("id"),
-        company_name: row.get("company_name"),
-        department: row.get("department"),
-        username: row.get("username"),
-        email: row.get("email"),
-        role: row.get("role"),
-        created_at: row.get("created_at"),
-        updated_at: row.get("updated_at"),
-    })
+    let result = sqlx::query(&format!(
+        "SELECT {} FROM users WHERE tenant_id = $1 ORDER BY created_at DESC",
+        USER_COLUMNS
+    ))
+    .bind(user.tenant_id)
+    .map(|row: PgRow| user_from_row(&row))
     .fetch_all(pool.get_ref())
     .await;
 
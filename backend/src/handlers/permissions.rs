@@ -0,0 +1,484 @@
+use actix_web::{web, Error, HttpResponse, HttpRequest};
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use serde::Deserialize;
+
+use super::patrimony::{auth_middleware, User};
+
+// Papéis
+pub const ADMIN_ROLE: &str = "admin";
+pub const DEFAULT_ROLE: &str = "read_only";
+
+// Permissões (tabela permissions)
+pub const PATRIMONY_READ: &str = "patrimony.read";
+pub const PATRIMONY_WRITE: &str = "patrimony.write";
+pub const PATRIMONY_DELETE: &str = "patrimony.delete";
+pub const TRASH_MANAGE: &str = "trash.manage";
+pub const TRANSFER_READ: &str = "transfer.read";
+pub const TRANSFER_REQUEST: &str = "transfer.request";
+pub const TRANSFER_APPROVE: &str = "transfer.approve";
+pub const TRANSFER_RECEIVE: &str = "transfer.receive";
+pub const WRITE_OFF_MANAGE: &str = "write_off.manage";
+pub const DEPRECIATION_RUN: &str = "depreciation.run";
+pub const INVENTORY_MANAGE: &str = "inventory.manage";
+pub const INVENTORY_COUNT: &str = "inventory.count";
+pub const CUSTODY_MANAGE: &str = "custody.manage";
+pub const CUSTODIAN_MANAGE: &str = "custodian.manage";
+pub const LOCATION_MANAGE: &str = "location.manage";
+pub const DEPARTMENT_MANAGE: &str = "department.manage";
+pub const ALL_DEPARTMENTS: &str = "department.all";
+pub const OWN_DEPARTMENT: &str = "department.own";
+pub const READ_ALL_DEPARTMENTS: &str = "department.read_all";
+pub const CATEGORY_MANAGE: &str = "category.manage";
+pub const PLATE_SEQUENCE_MANAGE: &str = "plate_sequence.manage";
+pub const MAINTENANCE_READ: &str = "maintenance.read";
+pub const MAINTENANCE_MANAGE: &str = "maintenance.manage";
+pub const MAINTENANCE_PLAN: &str = "maintenance.plan";
+pub const AUDIT_READ: &str = "audit.read";
+pub const AUDIT_MANAGE: &str = "audit.manage";
+pub const USER_READ: &str = "user.read";
+pub const USER_MANAGE: &str = "user.manage";
+
+#[derive(Deserialize)]
+pub struct UpdateRolePermissions {
+    pub permissions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateRoleTwoFactor {
+    pub required: bool,
+}
+
+/// O que a rota exige de quem chama
+#[derive(Clone, Copy, Debug)]
+pub enum Access {
+    Public,
+    /// Rotas da própria conta: liberadas mesmo com troca de senha ou cadastro de 2FA pendente
+    Account,
+    Authenticated,
+    Permission(&'static str),
+    SuperAdmin,
+}
+
+use Access::{Account, Authenticated, Permission, Public, SuperAdmin};
+
+// Todas as rotas da API: rota sem entrada aqui é recusada
+const ROUTES: &[(&str, &str, Access)] = &[
+    ("POST", "/api/register", Public),
+    ("POST", "/api/login", Public),
+    ("POST", "/api/login/two-factor", Public),
+    ("POST", "/api/auth/refresh", Public),
+    ("POST", "/api/password-reset/request", Public),
+    ("POST", "/api/password-reset/confirm", Public),
+    ("POST", "/api/logout", Account),
+    ("GET", "/api/health", Public),
+    ("GET", "/api/departments", Public),
+    ("POST", "/api/departments", Permission(DEPARTMENT_MANAGE)),
+    ("GET", "/api/debug/uploads", Public),
+    ("GET", "/api/patrimony", Permission(PATRIMONY_READ)),
+    ("POST", "/api/patrimony", Permission(PATRIMONY_WRITE)),
+    ("GET", "/api/patrimony/{id}", Permission(PATRIMONY_READ)),
+    ("PUT", "/api/patrimony/{id}", Permission(PATRIMONY_WRITE)),
+    ("DELETE", "/api/patrimony/{id}", Permission(PATRIMONY_DELETE)),
+    ("POST", "/api/patrimony/{id}/restore", Permission(TRASH_MANAGE)),
+    ("DELETE", "/api/patrimony/{id}/purge", Permission(TRASH_MANAGE)),
+    ("GET", "/api/patrimonies/trash", Permission(TRASH_MANAGE)),
+    ("POST", "/api/patrimony/{id}/image", Permission(PATRIMONY_WRITE)),
+    ("POST", "/api/patrimony/{id}/document/{doc_type}", Permission(PATRIMONY_WRITE)),
+    ("GET", "/api/uploads/{filename}", Permission(PATRIMONY_READ)),
+    ("GET", "/api/documents/{filename}", Permission(PATRIMONY_READ)),
+    ("POST", "/api/transfer", Permission(TRANSFER_REQUEST)),
+    ("GET", "/api/transfers", Permission(TRANSFER_READ)),
+    ("GET", "/api/transfer/{id}", Permission(TRANSFER_READ)),
+    ("POST", "/api/transfer/{id}/approve", Permission(TRANSFER_APPROVE)),
+    ("POST", "/api/transfer/{id}/reject", Permission(TRANSFER_APPROVE)),
+    ("POST", "/api/transfer/{id}/receive", Permission(TRANSFER_RECEIVE)),
+    ("GET", "/api/transfers/pending", Permission(TRANSFER_READ)),
+    ("POST", "/api/transfers/batch", Permission(TRANSFER_REQUEST)),
+    ("GET", "/api/transfer/{id}/term.pdf", Permission(TRANSFER_READ)),
+    ("GET", "/api/stats", Permission(PATRIMONY_READ)),
+    ("GET", "/api/users", Permission(USER_READ)),
+    ("GET", "/api/debug/images", Permission(PATRIMONY_READ)),
+    ("GET", "/api/patrimonies/department/{department}", Permission(PATRIMONY_READ)),
+    ("GET", "/api/patrimony/{id}/depreciation", Permission(PATRIMONY_READ)),
+    ("POST", "/api/depreciation/run", Permission(DEPRECIATION_RUN)),
+    ("GET", "/api/depreciation/entries", Permission(PATRIMONY_READ)),
+    ("POST", "/api/patrimony/{id}/write-off", Permission(WRITE_OFF_MANAGE)),
+    ("GET", "/api/patrimony/{id}/write-off", Permission(PATRIMONY_READ)),
+    ("GET", "/api/write-offs", Permission(PATRIMONY_READ)),
+    ("POST", "/api/write-off/{id}/document", Permission(WRITE_OFF_MANAGE)),
+    ("POST", "/api/inventory/campaigns", Permission(INVENTORY_MANAGE)),
+    ("GET", "/api/inventory/campaigns", Permission(PATRIMONY_READ)),
+    ("GET", "/api/inventory/campaigns/{id}", Permission(PATRIMONY_READ)),
+    ("POST", "/api/inventory/campaigns/{id}/sessions", Permission(INVENTORY_COUNT)),
+    ("POST", "/api/inventory/campaigns/{id}/close", Permission(INVENTORY_MANAGE)),
+    ("GET", "/api/inventory/campaigns/{id}/report", Permission(PATRIMONY_READ)),
+    ("POST", "/api/inventory/sessions/{id}/scans", Permission(INVENTORY_COUNT)),
+    ("POST", "/api/inventory/sessions/{id}/close", Permission(INVENTORY_COUNT)),
+    ("GET", "/api/labels", Permission(PATRIMONY_READ)),
+    ("GET", "/api/plate-sequences", Authenticated),
+    ("POST", "/api/plate-sequences", Permission(PLATE_SEQUENCE_MANAGE)),
+    ("PUT", "/api/plate-sequences/{id}", Permission(PLATE_SEQUENCE_MANAGE)),
+    ("DELETE", "/api/plate-sequences/{id}", Permission(PLATE_SEQUENCE_MANAGE)),
+    ("GET", "/api/custodians", Authenticated),
+    ("POST", "/api/custodians", Permission(CUSTODIAN_MANAGE)),
+    ("GET", "/api/custodians/{id}", Authenticated),
+    ("PUT", "/api/custodians/{id}", Permission(CUSTODIAN_MANAGE)),
+    ("GET", "/api/custodians/{id}/term.pdf", Permission(PATRIMONY_READ)),
+    ("POST", "/api/patrimony/{id}/custodian", Permission(CUSTODY_MANAGE)),
+    ("DELETE", "/api/patrimony/{id}/custodian", Permission(CUSTODY_MANAGE)),
+    ("GET", "/api/patrimony/{id}/custody-history", Permission(PATRIMONY_READ)),
+    ("GET", "/api/locations", Authenticated),
+    ("POST", "/api/locations", Permission(LOCATION_MANAGE)),
+    ("GET", "/api/locations/{id}", Authenticated),
+    ("PUT", "/api/locations/{id}", Permission(LOCATION_MANAGE)),
+    ("DELETE", "/api/locations/{id}", Permission(LOCATION_MANAGE)),
+    ("POST", "/api/patrimony/{id}/move", Permission(PATRIMONY_WRITE)),
+    ("GET", "/api/patrimony/{id}/moves", Permission(PATRIMONY_READ)),
+    ("GET", "/api/departments/{id}", Authenticated),
+    ("PUT", "/api/departments/{id}", Permission(DEPARTMENT_MANAGE)),
+    ("DELETE", "/api/departments/{id}", Permission(DEPARTMENT_MANAGE)),
+    ("POST", "/api/departments/{id}/managers", Permission(DEPARTMENT_MANAGE)),
+    ("DELETE", "/api/departments/{id}/managers/{user_id}", Permission(DEPARTMENT_MANAGE)),
+    ("GET", "/api/department-grants", Permission(DEPARTMENT_MANAGE)),
+    ("POST", "/api/department-grants", Permission(DEPARTMENT_MANAGE)),
+    ("DELETE", "/api/department-grants/{id}", Permission(DEPARTMENT_MANAGE)),
+    ("GET", "/api/categories", Authenticated),
+    ("POST", "/api/categories", Permission(CATEGORY_MANAGE)),
+    ("GET", "/api/categories/{id}", Authenticated),
+    ("PUT", "/api/categories/{id}", Permission(CATEGORY_MANAGE)),
+    ("DELETE", "/api/categories/{id}", Permission(CATEGORY_MANAGE)),
+    ("POST", "/api/patrimony/{id}/maintenance", Permission(MAINTENANCE_MANAGE)),
+    ("GET", "/api/patrimony/{id}/maintenance", Permission(MAINTENANCE_READ)),
+    ("GET", "/api/maintenance", Permission(MAINTENANCE_READ)),
+    ("GET", "/api/maintenance/costs", Permission(MAINTENANCE_READ)),
+    ("GET", "/api/maintenance/upcoming", Permission(MAINTENANCE_READ)),
+    ("GET", "/api/maintenance/schedules", Permission(MAINTENANCE_READ)),
+    ("POST", "/api/maintenance/schedules", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/schedules/run", Permission(MAINTENANCE_PLAN)),
+    ("GET", "/api/maintenance/schedules/{id}", Permission(MAINTENANCE_READ)),
+    ("PUT", "/api/maintenance/schedules/{id}", Permission(MAINTENANCE_MANAGE)),
+    ("DELETE", "/api/maintenance/schedules/{id}", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/due/{id}/complete", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/due/{id}/skip", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/patrimony/{id}/meter", Permission(MAINTENANCE_MANAGE)),
+    ("GET", "/api/patrimony/{id}/meter", Permission(MAINTENANCE_READ)),
+    ("GET", "/api/maintenance/{id}", Permission(MAINTENANCE_READ)),
+    ("PUT", "/api/maintenance/{id}", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/{id}/close", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/{id}/cancel", Permission(MAINTENANCE_MANAGE)),
+    ("POST", "/api/maintenance/{id}/attachments", Permission(MAINTENANCE_MANAGE)),
+    ("GET", "/api/audit", Permission(AUDIT_READ)),
+    ("GET", "/api/audit/verify", Permission(AUDIT_READ)),
+    ("GET", "/api/audit/checkpoints", Permission(AUDIT_READ)),
+    ("POST", "/api/audit/checkpoints", Permission(AUDIT_MANAGE)),
+    ("GET", "/api/tenants", SuperAdmin),
+    ("POST", "/api/tenants", SuperAdmin),
+    ("GET", "/api/tenants/{id}", SuperAdmin),
+    ("PUT", "/api/tenants/{id}", SuperAdmin),
+    ("GET", "/api/roles", Permission(USER_READ)),
+    ("GET", "/api/permissions", Permission(USER_READ)),
+    ("PUT", "/api/roles/{code}/permissions", SuperAdmin),
+    ("GET", "/api/users/{id}/sessions", Permission(USER_MANAGE)),
+    ("POST", "/api/users/{id}/revoke-sessions", Permission(USER_MANAGE)),
+    ("POST", "/api/users", Permission(USER_MANAGE)),
+    ("GET", "/api/users/{id}", Permission(USER_READ)),
+    ("PUT", "/api/users/{id}", Permission(USER_MANAGE)),
+    ("POST", "/api/users/{id}/deactivate", Permission(USER_MANAGE)),
+    ("POST", "/api/users/{id}/reactivate", Permission(USER_MANAGE)),
+    ("POST", "/api/users/{id}/reset-password", Permission(USER_MANAGE)),
+    ("GET", "/api/invitations", Permission(USER_MANAGE)),
+    ("POST", "/api/invitations", Permission(USER_MANAGE)),
+    ("DELETE", "/api/invitations/{id}", Permission(USER_MANAGE)),
+    ("GET", "/api/me", Account),
+    ("PUT", "/api/me", Account),
+    ("PUT", "/api/me/password", Account),
+    ("GET", "/api/password-policy", Public),
+    ("POST", "/api/users/{id}/unlock", Permission(USER_MANAGE)),
+    ("GET", "/api/login-attempts", Permission(AUDIT_READ)),
+    ("POST", "/api/me/two-factor/setup", Account),
+    ("POST", "/api/me/two-factor/enable", Account),
+    ("POST", "/api/me/two-factor/disable", Account),
+    ("POST", "/api/me/two-factor/recovery-codes", Account),
+    ("POST", "/api/users/{id}/reset-two-factor", Permission(USER_MANAGE)),
+    ("PUT", "/api/roles/{code}/two-factor", SuperAdmin),
+];
+
+impl User {
+    pub fn can(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == permission)
+    }
+}
+
+pub fn route_access(method: &str, pattern: &str) -> Option<Access> {
+    ROUTES
+        .iter()
+        .find(|(route_method, route_pattern, _)| *route_method == method && *route_pattern == pattern)
+        .map(|(_, _, access)| *access)
+}
+
+// Recusa com a resposta adequada, ou None se o acesso é permitido
+async fn check_access(req: &ServiceRequest, access: Access) -> Option<HttpResponse> {
+    if matches!(access, Public) {
+        return None;
+    }
+
+    let Some(pool) = req.app_data::<web::Data<PgPool>>() else {
+        return Some(HttpResponse::InternalServerError().json("Database pool not configured"));
+    };
+
+    let user = match auth_middleware(req.request(), pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Some(HttpResponse::Unauthorized().json("Authentication required")),
+        Err(response) => return Some(response),
+    };
+
+    if matches!(access, Account) {
+        return None;
+    }
+
+    // Com senha temporária, só as rotas da própria conta (perfil, troca de senha, logout) ficam liberadas
+    if user.must_change_password {
+        return Some(HttpResponse::Forbidden().json("Password change required"));
+    }
+    // Papel com 2FA obrigatório: até ativar, só as rotas da própria conta (onde o 2FA é cadastrado)
+    if user.two_factor_required && !user.two_factor_enabled {
+        return Some(HttpResponse::Forbidden().json("Two-factor authentication enrollment required"));
+    }
+
+    match access {
+        Permission(permission) if !user.can(permission) => {
+            Some(HttpResponse::Forbidden().json(format!("Permission '{}' required", permission)))
+        }
+        SuperAdmin if !user.is_super_admin => Some(HttpResponse::Forbidden().json("Super admin access required")),
+        _ => None,
+    }
+}
+
+/// Middleware: aplica a permissão de cada rota da API antes do handler
+pub async fn enforce(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    // Arquivos estáticos e preflight ficam fora da matriz
+    if !req.path().starts_with("/api/") || req.method() == actix_web::http::Method::OPTIONS {
+        return next.call(req).await.map(ServiceResponse::map_into_boxed_body);
+    }
+
+    let pattern = req.match_pattern();
+    let access = pattern.as_deref().and_then(|pattern| route_access(req.method().as_str(), pattern));
+
+    let denied = match (access, pattern) {
+        (Some(access), _) => check_access(&req, access).await,
+        // Rota inexistente: o roteamento responde 404
+        (None, None) => None,
+        (None, Some(pattern)) => {
+            eprintln!("❌ Rota sem permissão configurada: {} {}", req.method(), pattern);
+            Some(HttpResponse::Forbidden().json("Route has no permission configured"))
+        }
+    };
+
+    match denied {
+        Some(response) => Ok(req.into_response(response)),
+        None => next.call(req).await.map(ServiceResponse::map_into_boxed_body),
+    }
+}
+
+fn role_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "code": row.get::<String, _>("code"),
+        "name": row.get::<String, _>("name"),
+        "description": row.get::<Option<String>, _>("description"),
+        "permissions": row.get::<Vec<String>, _>("permissions"),
+        "requires_two_factor": row.get::<bool, _>("requires_two_factor"),
+        "user_count": row.get::<i64, _>("user_count")
+    })
+}
+
+pub async fn get_roles(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(
+        "SELECT r.code, r.name, r.description, r.requires_two_factor,
+                ARRAY(SELECT rp.permission FROM role_permissions rp WHERE rp.role = r.code ORDER BY rp.permission)::text[] as permissions,
+                (SELECT COUNT(*) FROM users u WHERE u.role = r.code AND u.tenant_id = $1) as user_count
+         FROM roles r
+         ORDER BY r.name"
+    )
+    .bind(user.tenant_id)
+    .map(|row: PgRow| role_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(roles) => HttpResponse::Ok().json(roles),
+        Err(e) => {
+            eprintln!("Error fetching roles: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching roles")
+        }
+    }
+}
+
+pub async fn get_permissions(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query("SELECT code, description FROM permissions ORDER BY code")
+        .map(|row: PgRow| {
+            serde_json::json!({
+                "code": row.get::<String, _>("code"),
+                "description": row.get::<String, _>("description")
+            })
+        })
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(permissions) => HttpResponse::Ok().json(permissions),
+        Err(e) => {
+            eprintln!("Error fetching permissions: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching permissions")
+        }
+    }
+}
+
+/// Substitui as permissões do papel; os papéis valem para todas as entidades
+pub async fn update_role_permissions(
+    pool: web::Data<PgPool>,
+    code: web::Path<String>,
+    update: web::Json<UpdateRolePermissions>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let role = code.into_inner();
+
+    // O administrador mantém todas as permissões para não trancar a entidade
+    if role == ADMIN_ROLE {
+        return HttpResponse::BadRequest().json("The admin role always has every permission");
+    }
+
+    let mut permissions = update.permissions.clone();
+    permissions.sort();
+    permissions.dedup();
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating role permissions");
+        }
+    };
+
+    let checks = sqlx::query(
+        "SELECT EXISTS(SELECT 1 FROM roles WHERE code = $1) as role_exists,
+                ARRAY(SELECT p FROM unnest($2::varchar[]) p WHERE p NOT IN (SELECT code FROM permissions))::text[] as unknown"
+    )
+    .bind(&role)
+    .bind(&permissions)
+    .fetch_one(&mut *transaction)
+    .await;
+
+    match checks {
+        Ok(row) if !row.get::<bool, _>("role_exists") => return HttpResponse::NotFound().json("Role not found"),
+        Ok(row) => {
+            let unknown: Vec<String> = row.get("unknown");
+            if !unknown.is_empty() {
+                return HttpResponse::BadRequest().json(format!("Unknown permissions: {}", unknown.join(", ")));
+            }
+        }
+        Err(e) => {
+            eprintln!("Error validating role permissions: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating role permissions");
+        }
+    }
+
+    let result = sqlx::query("DELETE FROM role_permissions WHERE role = $1")
+        .bind(&role)
+        .execute(&mut *transaction)
+        .await;
+    let result = match result {
+        Ok(_) => sqlx::query("INSERT INTO role_permissions (role, permission) SELECT $1, unnest($2::varchar[])")
+            .bind(&role)
+            .bind(&permissions)
+            .execute(&mut *transaction)
+            .await,
+        Err(e) => Err(e),
+    };
+    let result = match result {
+        Ok(_) => transaction.commit().await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Error updating role permissions: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating role permissions");
+    }
+
+    println!("🔐 Permissões do papel '{}' atualizadas por {}: {:?}", role, user.username, permissions);
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "role": role,
+        "permissions": permissions
+    }))
+}
+
+/// Liga ou desliga o 2FA obrigatório para quem tem o papel
+pub async fn update_role_two_factor(
+    pool: web::Data<PgPool>,
+    code: web::Path<String>,
+    update: web::Json<UpdateRoleTwoFactor>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let role = code.into_inner();
+
+    match sqlx::query("UPDATE roles SET requires_two_factor = $2 WHERE code = $1")
+        .bind(&role)
+        .bind(update.required)
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json("Role not found"),
+        Ok(_) => {
+            println!("🔐 2FA {} para o papel '{}' por {}", if update.required { "obrigatório" } else { "opcional" }, role, user.username);
+            HttpResponse::Ok().json(serde_json::json!({
+                "role": role,
+                "requires_two_factor": update.required
+            }))
+        }
+        Err(e) => {
+            eprintln!("Error updating role two-factor policy: {}", e);
+            HttpResponse::InternalServerError().json("Error updating role")
+        }
+    }
+}
@@ -0,0 +1,356 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{Datelike, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::auth_middleware;
+use super::department::validate_department;
+
+// Escopos de numeração aceitos
+pub const SCOPE_TYPES: [&str; 2] = ["department", "category"];
+
+const MAX_PADDING: i32 = 12;
+
+#[derive(Deserialize)]
+pub struct CreatePlateSequence {
+    pub scope_type: String,
+    pub scope_value: String,
+    pub prefix: String,
+    pub padding: Option<i32>,
+    pub include_year: Option<bool>,
+    pub separator: Option<String>,
+    pub next_value: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdatePlateSequence {
+    pub prefix: Option<String>,
+    pub padding: Option<i32>,
+    pub include_year: Option<bool>,
+    pub separator: Option<String>,
+    pub next_value: Option<i64>,
+}
+
+const SEQUENCE_COLUMNS: &str =
+    "id, scope_type, scope_value, prefix, padding, include_year, separator, next_value, current_year, created_at, updated_at";
+
+/// Monta a placa no formato PREFIXO[ANO]CONTADOR, com separador opcional entre as partes
+pub fn format_plate(prefix: &str, separator: &str, include_year: bool, year: i32, value: i64, padding: i32) -> String {
+    let counter = format!("{:0width$}", value, width = padding.max(1) as usize);
+    if include_year {
+        format!("{}{}{}{}{}", prefix, separator, year, separator, counter)
+    } else {
+        format!("{}{}{}", prefix, separator, counter)
+    }
+}
+
+// Valor que a próxima alocação vai usar (o contador reinicia na virada do ano)
+fn effective_next_value(include_year: bool, current_year: Option<i32>, next_value: i64, year: i32) -> i64 {
+    if include_year && current_year != Some(year) {
+        1
+    } else {
+        next_value
+    }
+}
+
+fn sequence_json(row: &PgRow) -> serde_json::Value {
+    let prefix: String = row.get("prefix");
+    let separator: String = row.get("separator");
+    let include_year: bool = row.get("include_year");
+    let padding: i32 = row.get("padding");
+    let next_value: i64 = row.get("next_value");
+    let current_year: Option<i32> = row.get("current_year");
+
+    let year = Utc::now().year();
+    let upcoming = effective_next_value(include_year, current_year, next_value, year);
+
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "scope_type": row.get::<String, _>("scope_type"),
+        "scope_value": row.get::<String, _>("scope_value"),
+        "prefix": prefix,
+        "padding": padding,
+        "include_year": include_year,
+        "separator": separator,
+        "next_value": next_value,
+        "current_year": current_year,
+        "next_plate": format_plate(&prefix, &separator, include_year, year, upcoming, padding),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+fn validate_format(prefix: Option<&str>, padding: Option<i32>, next_value: Option<i64>) -> Result<(), &'static str> {
+    if let Some(prefix) = prefix {
+        if prefix.trim().is_empty() {
+            return Err("Prefix is required");
+        }
+    }
+    if let Some(padding) = padding {
+        if !(1..=MAX_PADDING).contains(&padding) {
+            return Err("Padding must be between 1 and 12");
+        }
+    }
+    if let Some(next_value) = next_value {
+        if next_value < 1 {
+            return Err("Next value must be at least 1");
+        }
+    }
+    Ok(())
+}
+
+/// Aloca a próxima placa dentro da transação de cadastro do bem.
+/// O FOR UPDATE serializa cadastros concorrentes na mesma sequência e, como o
+/// incremento faz parte da transação, um cadastro que falha não consome número.
+/// Sem `sequence_id`, usa a sequência da categoria (código) e, na falta dela, a do
+/// departamento, sempre entre as da entidade. Retorna None se não houver sequência.
+pub async fn allocate_plate(
+    transaction: &mut Transaction<'_, Postgres>,
+    tenant_id: Uuid,
+    sequence_id: Option<Uuid>,
+    category: Option<&str>,
+    department: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let row = match sequence_id {
+        Some(sequence_id) => {
+            sqlx::query(
+                "SELECT id, prefix, padding, include_year, separator, next_value, current_year
+                 FROM plate_sequences WHERE id = $1 AND tenant_id = $2 FOR UPDATE"
+            )
+            .bind(sequence_id)
+            .bind(tenant_id)
+            .fetch_optional(&mut **transaction)
+            .await?
+        }
+        None => {
+            sqlx::query(
+                "SELECT id, prefix, padding, include_year, separator, next_value, current_year
+                 FROM plate_sequences
+                 WHERE tenant_id = $3
+                   AND ((scope_type = 'department' AND scope_value = $1) OR (scope_type = 'category' AND scope_value = $2))
+                 ORDER BY scope_type = 'category' DESC
+                 LIMIT 1
+                 FOR UPDATE"
+            )
+            .bind(department)
+            .bind(category)
+            .bind(tenant_id)
+            .fetch_optional(&mut **transaction)
+            .await?
+        }
+    };
+
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(None),
+    };
+
+    let id: Uuid = row.get("id");
+    let prefix: String = row.get("prefix");
+    let separator: String = row.get("separator");
+    let include_year: bool = row.get("include_year");
+    let padding: i32 = row.get("padding");
+    let current_year: Option<i32> = row.get("current_year");
+    let year = Utc::now().year();
+    let value = effective_next_value(include_year, current_year, row.get("next_value"), year);
+
+    sqlx::query(
+        "UPDATE plate_sequences SET next_value = $1, current_year = $2, updated_at = NOW() WHERE id = $3"
+    )
+    .bind(value + 1)
+    .bind(year)
+    .bind(id)
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok(Some(format_plate(&prefix, &separator, include_year, year, value, padding)))
+}
+
+pub async fn get_plate_sequences(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "SELECT {} FROM plate_sequences WHERE tenant_id = $1 ORDER BY scope_type, scope_value",
+        SEQUENCE_COLUMNS
+    ))
+    .bind(user.tenant_id)
+    .map(|row: PgRow| sequence_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(sequences) => HttpResponse::Ok().json(sequences),
+        Err(e) => {
+            eprintln!("Error fetching plate sequences: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching plate sequences")
+        }
+    }
+}
+
+pub async fn create_plate_sequence(
+    pool: web::Data<PgPool>,
+    sequence: web::Json<CreatePlateSequence>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if !SCOPE_TYPES.contains(&sequence.scope_type.as_str()) {
+        return HttpResponse::BadRequest().json(format!(
+            "Invalid scope type, expected one of: {}",
+            SCOPE_TYPES.join(", ")
+        ));
+    }
+    if sequence.scope_value.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Scope value is required");
+    }
+    // Sequências por categoria usam o código da categoria como escopo
+    if sequence.scope_type == "category" {
+        match sqlx::query("SELECT 1 FROM categories WHERE code = $1 AND tenant_id = $2")
+            .bind(sequence.scope_value.trim())
+            .bind(user.tenant_id)
+            .fetch_optional(pool.get_ref())
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return HttpResponse::BadRequest().json("Category not found for this scope value"),
+            Err(e) => {
+                eprintln!("Error fetching category: {}", e);
+                return HttpResponse::InternalServerError().json("Error creating plate sequence");
+            }
+        }
+    } else if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, sequence.scope_value.trim()).await {
+        return response;
+    }
+    if let Err(message) = validate_format(Some(&sequence.prefix), sequence.padding, sequence.next_value) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let result = sqlx::query(&format!(
+        "INSERT INTO plate_sequences (scope_type, scope_value, prefix, padding, include_year, separator, next_value, tenant_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING {}",
+        SEQUENCE_COLUMNS
+    ))
+    .bind(&sequence.scope_type)
+    .bind(sequence.scope_value.trim())
+    .bind(sequence.prefix.trim())
+    .bind(sequence.padding.unwrap_or(3))
+    .bind(sequence.include_year.unwrap_or(false))
+    .bind(sequence.separator.as_deref().unwrap_or(""))
+    .bind(sequence.next_value.unwrap_or(1))
+    .bind(user.tenant_id)
+    .map(|row: PgRow| sequence_json(&row))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(sequence) => HttpResponse::Created().json(sequence),
+        Err(e) => {
+            eprintln!("Error creating plate sequence: {}", e);
+            if e.to_string().contains("duplicate key") {
+                HttpResponse::Conflict().json("A plate sequence already exists for this scope")
+            } else {
+                HttpResponse::InternalServerError().json("Error creating plate sequence")
+            }
+        }
+    }
+}
+
+pub async fn update_plate_sequence(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    sequence: web::Json<UpdatePlateSequence>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if let Err(message) = validate_format(sequence.prefix.as_deref(), sequence.padding, sequence.next_value) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let result = sqlx::query(&format!(
+        "UPDATE plate_sequences SET
+            prefix = COALESCE($1, prefix),
+            padding = COALESCE($2, padding),
+            include_year = COALESCE($3, include_year),
+            separator = COALESCE($4, separator),
+            next_value = COALESCE($5, next_value),
+            current_year = CASE WHEN $5 IS NULL THEN current_year ELSE EXTRACT(YEAR FROM NOW())::int END,
+            updated_at = NOW()
+         WHERE id = $6 AND tenant_id = $7
+         RETURNING {}",
+        SEQUENCE_COLUMNS
+    ))
+    .bind(sequence.prefix.as_deref().map(str::trim))
+    .bind(sequence.padding)
+    .bind(sequence.include_year)
+    .bind(&sequence.separator)
+    .bind(sequence.next_value)
+    .bind(id.into_inner())
+    .bind(user.tenant_id)
+    .map(|row: PgRow| sequence_json(&row))
+    .fetch_optional(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(Some(sequence)) => HttpResponse::Ok().json(sequence),
+        Ok(None) => HttpResponse::NotFound().json("Plate sequence not found"),
+        Err(e) => {
+            eprintln!("Error updating plate sequence: {}", e);
+            HttpResponse::InternalServerError().json("Error updating plate sequence")
+        }
+    }
+}
+
+pub async fn delete_plate_sequence(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query("DELETE FROM plate_sequences WHERE id = $1 AND tenant_id = $2")
+        .bind(id.into_inner())
+        .bind(user.tenant_id)
+        .execute(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(result) => {
+            if result.rows_affected() > 0 {
+                HttpResponse::Ok().json("Plate sequence deleted successfully")
+            } else {
+                HttpResponse::NotFound().json("Plate sequence not found")
+            }
+        }
+        Err(e) => {
+            eprintln!("Error deleting plate sequence: {}", e);
+            HttpResponse::InternalServerError().json("Error deleting plate sequence")
+        }
+    }
+}
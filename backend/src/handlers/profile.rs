@@ -0,0 +1,193 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use serde::Deserialize;
+use bcrypt::{hash, verify, DEFAULT_COST};
+
+use super::patrimony::{auth_middleware, get_user_by_id};
+use super::audit::{self, Actor};
+use super::password_policy;
+use super::session;
+
+// Dados que o próprio usuário pode alterar; departamento e papel continuam com a gestão de usuários
+#[derive(Deserialize)]
+pub struct UpdateProfile {
+    pub company_name: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePassword {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+pub async fn get_profile(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => HttpResponse::Ok().json(user),
+        Ok(None) => HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => e,
+    }
+}
+
+pub async fn update_profile(
+    pool: web::Data<PgPool>,
+    profile: web::Json<UpdateProfile>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let email = profile.email.as_deref().map(str::trim);
+    if let Some(email) = email {
+        if !email.is_empty() && !email.contains('@') {
+            return HttpResponse::BadRequest().json("Invalid email");
+        }
+    }
+    let company_name = profile.company_name.as_deref().map(str::trim);
+    if company_name == Some("") {
+        return HttpResponse::BadRequest().json("Company name cannot be empty");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating profile");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, user.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating profile");
+        }
+    };
+
+    // E-mail vazio remove o e-mail cadastrado
+    let result = sqlx::query(
+        "UPDATE users SET
+            company_name = COALESCE($1, company_name),
+            email = CASE WHEN $2::text IS NULL THEN email ELSE NULLIF($2, '') END,
+            updated_at = NOW()
+         WHERE id = $3"
+    )
+    .bind(company_name)
+    .bind(email)
+    .bind(user.id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error updating profile: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating profile");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::UPDATE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating profile");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing profile update: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating profile");
+    }
+
+    match get_user_by_id(pool.get_ref(), user.id).await {
+        Ok((updated, _)) => HttpResponse::Ok().json(updated),
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            HttpResponse::InternalServerError().json("Profile updated but error fetching details")
+        }
+    }
+}
+
+pub async fn change_password(
+    pool: web::Data<PgPool>,
+    body: web::Json<ChangePassword>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+    let Some(session_id) = session::current_session(&req) else {
+        return HttpResponse::Unauthorized().json("Invalid token");
+    };
+
+    let current_hash = match sqlx::query("SELECT password_hash FROM users WHERE id = $1")
+        .bind(user.id)
+        .map(|row: PgRow| row.get::<String, _>("password_hash"))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(current_hash) => current_hash,
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return HttpResponse::InternalServerError().json("Error changing password");
+        }
+    };
+    if !verify(&body.current_password, &current_hash).unwrap_or(false) {
+        return HttpResponse::BadRequest().json("Current password is incorrect");
+    }
+
+    if let Err(response) = password_policy::validate(&body.new_password, &user.username) {
+        return response;
+    }
+    if let Err(response) = password_policy::ensure_not_reused(pool.get_ref(), user.id, &body.new_password).await {
+        return response;
+    }
+    let password_hash = match hash(&body.new_password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error changing password");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, user.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error changing password");
+        }
+    };
+
+    // A sessão atual continua; as demais (outros aparelhos) são encerradas
+    let result = match password_policy::replace_password(&mut transaction, user.id, &password_hash, false).await {
+        Ok(_) => session::revoke_other_sessions(&mut *transaction, user.id, session_id, session::PASSWORD_CHANGED).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Error changing password: {}", e);
+        return HttpResponse::InternalServerError().json("Error changing password");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::CHANGE_PASSWORD, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error changing password");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing password change: {}", e);
+        return HttpResponse::InternalServerError().json("Error changing password");
+    }
+
+    println!("🔑 Senha alterada pelo próprio usuário '{}'", user.username);
+
+    HttpResponse::Ok().json("Password changed successfully")
+}
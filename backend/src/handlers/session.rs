@@ -0,0 +1,441 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgExecutor, PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, get_user_by_id, User};
+use super::login_security;
+
+const DEFAULT_ACCESS_TOKEN_MINUTES: i64 = 15;
+const DEFAULT_REFRESH_TOKEN_DAYS: i64 = 30;
+
+// Motivos de revogação da sessão
+pub const LOGOUT: &str = "logout";
+pub const ADMIN_REVOKED: &str = "admin";
+pub const TOKEN_REUSE: &str = "reuse";
+pub const DEACTIVATED: &str = "deactivated";
+pub const PASSWORD_RESET: &str = "password_reset";
+pub const PASSWORD_CHANGED: &str = "password_changed";
+
+// Estruturas para autenticação JWT
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: String, // user ID
+    sid: String, // sessão (tabela sessions)
+    exp: usize,  // expiration time
+    role: String,
+}
+
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64,                // Validade do token de acesso, em segundos
+}
+
+/// Segredo de assinatura dos tokens (obrigatório: o servidor não sobe sem ele)
+pub fn jwt_secret() -> Option<Vec<u8>> {
+    env::var("JWT_SECRET")
+        .ok()
+        .filter(|secret| !secret.is_empty())
+        .map(String::into_bytes)
+}
+
+fn env_duration(name: &str, default: i64) -> i64 {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(default)
+}
+
+fn access_token_ttl() -> Duration {
+    Duration::minutes(env_duration("ACCESS_TOKEN_TTL_MINUTES", DEFAULT_ACCESS_TOKEN_MINUTES))
+}
+
+fn refresh_token_ttl() -> Duration {
+    Duration::days(env_duration("REFRESH_TOKEN_TTL_DAYS", DEFAULT_REFRESH_TOKEN_DAYS))
+}
+
+/// Hash gravado no lugar dos tokens opacos (renovação, convites)
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Token aleatório em hexadecimal com o número de bytes informado
+pub fn random_token(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+fn bearer_token(req: &HttpRequest) -> Option<&str> {
+    req.headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "))
+}
+
+/// Usuário e sessão do token de acesso; None se inválido, expirado ou anterior às sessões
+pub fn decode_access_token(token: &str) -> Option<(Uuid, Uuid)> {
+    let secret = jwt_secret()?;
+    let data = decode::<Claims>(token, &DecodingKey::from_secret(&secret), &Validation::default()).ok()?;
+    let user_id = Uuid::parse_str(&data.claims.sub).ok()?;
+    let session_id = Uuid::parse_str(&data.claims.sid).ok()?;
+    Some((user_id, session_id))
+}
+
+/// Sessão do token de acesso enviado na requisição
+pub fn current_session(req: &HttpRequest) -> Option<Uuid> {
+    bearer_token(req)
+        .and_then(decode_access_token)
+        .map(|(_, session_id)| session_id)
+}
+
+/// Sessão não revogada e dentro da validade
+pub async fn is_active(pool: &PgPool, session_id: Uuid, user_id: Uuid) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "SELECT EXISTS(
+            SELECT 1 FROM sessions
+            WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL AND expires_at > NOW()
+         ) as active"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .map(|row: PgRow| row.get::<bool, _>("active"))
+    .fetch_one(pool)
+    .await
+}
+
+fn issue_access_token(user: &User, session_id: Uuid) -> Result<String, HttpResponse> {
+    let Some(secret) = jwt_secret() else {
+        return Err(HttpResponse::InternalServerError().json("Token signing key not configured"));
+    };
+    let claims = Claims {
+        sub: user.id.to_string(),
+        sid: session_id.to_string(),
+        exp: (Utc::now() + access_token_ttl()).timestamp() as usize,
+        role: user.role.clone(),
+    };
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(&secret))
+        .map_err(|_| HttpResponse::InternalServerError().json("Error generating token"))
+}
+
+async fn issue_refresh_token<'e, E: PgExecutor<'e>>(executor: E, session_id: Uuid) -> Result<String, sqlx::Error> {
+    let token = random_token(32);
+    sqlx::query("INSERT INTO refresh_tokens (token_hash, session_id, expires_at) VALUES ($1, $2, $3)")
+        .bind(hash_token(&token))
+        .bind(session_id)
+        .bind(Utc::now() + refresh_token_ttl())
+        .execute(executor)
+        .await?;
+    Ok(token)
+}
+
+/// Abre a sessão do login e devolve o token de acesso e o de renovação
+pub async fn start_session(pool: &PgPool, user: &User, req: &HttpRequest) -> Result<TokenPair, HttpResponse> {
+    let ip_address = login_security::client_ip(req);
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let result = async {
+        let mut transaction = pool.begin().await?;
+        let session_id = sqlx::query(
+            "INSERT INTO sessions (user_id, ip_address, user_agent, expires_at) VALUES ($1, $2, $3, $4) RETURNING id"
+        )
+        .bind(user.id)
+        .bind(ip_address)
+        .bind(user_agent)
+        .bind(Utc::now() + refresh_token_ttl())
+        .map(|row: PgRow| row.get::<Uuid, _>("id"))
+        .fetch_one(&mut *transaction)
+        .await?;
+        let refresh_token = issue_refresh_token(&mut *transaction, session_id).await?;
+        transaction.commit().await?;
+        Ok::<_, sqlx::Error>((session_id, refresh_token))
+    }
+    .await;
+
+    let (session_id, refresh_token) = match result {
+        Ok(session) => session,
+        Err(e) => {
+            eprintln!("Error creating session: {}", e);
+            return Err(HttpResponse::InternalServerError().json("Error creating session"));
+        }
+    };
+
+    Ok(TokenPair {
+        token: issue_access_token(user, session_id)?,
+        refresh_token,
+        expires_in: access_token_ttl().num_seconds(),
+    })
+}
+
+/// Encerra todas as sessões ativas do usuário; devolve quantas foram revogadas
+pub async fn revoke_user_sessions<'e, E: PgExecutor<'e>>(executor: E, user_id: Uuid, reason: &str) -> Result<u64, sqlx::Error> {
+    sqlx::query("UPDATE sessions SET revoked_at = NOW(), revoked_reason = $2 WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .bind(reason)
+        .execute(executor)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+/// Revoga as sessões do usuário menos a informada (a que está em uso)
+pub async fn revoke_other_sessions<'e, E: PgExecutor<'e>>(executor: E, user_id: Uuid, keep: Uuid, reason: &str) -> Result<u64, sqlx::Error> {
+    sqlx::query("UPDATE sessions SET revoked_at = NOW(), revoked_reason = $3 WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL")
+        .bind(user_id)
+        .bind(keep)
+        .bind(reason)
+        .execute(executor)
+        .await
+        .map(|result| result.rows_affected())
+}
+
+// Rota pública: troca um token de renovação válido por um novo par de tokens
+pub async fn refresh_session(
+    pool: web::Data<PgPool>,
+    body: web::Json<RefreshRequest>,
+) -> HttpResponse {
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error refreshing session");
+        }
+    };
+
+    let token = sqlx::query(
+        "SELECT r.session_id, s.user_id, r.used_at IS NOT NULL as used, r.expires_at > NOW() as valid,
+                s.revoked_at IS NULL as active
+         FROM refresh_tokens r
+         JOIN sessions s ON s.id = r.session_id
+         WHERE r.token_hash = $1
+         FOR UPDATE OF r, s"
+    )
+    .bind(hash_token(&body.refresh_token))
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let row = match token {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::Unauthorized().json("Invalid refresh token"),
+        Err(e) => {
+            eprintln!("Error fetching refresh token: {}", e);
+            return HttpResponse::InternalServerError().json("Error refreshing session");
+        }
+    };
+    let session_id: Uuid = row.get("session_id");
+    let user_id: Uuid = row.get("user_id");
+
+    if !row.get::<bool, _>("active") {
+        return HttpResponse::Unauthorized().json("Session revoked");
+    }
+
+    // Token já trocado sendo reapresentado: possível roubo, a sessão inteira cai
+    if row.get::<bool, _>("used") {
+        let revoked = sqlx::query("UPDATE sessions SET revoked_at = NOW(), revoked_reason = $2 WHERE id = $1")
+            .bind(session_id)
+            .bind(TOKEN_REUSE)
+            .execute(&mut *transaction)
+            .await;
+        if let Err(e) = revoked {
+            eprintln!("Error revoking session: {}", e);
+            return HttpResponse::InternalServerError().json("Error refreshing session");
+        }
+        if let Err(e) = transaction.commit().await {
+            eprintln!("Error committing session revocation: {}", e);
+            return HttpResponse::InternalServerError().json("Error refreshing session");
+        }
+        eprintln!("⚠️ Token de renovação reutilizado; sessão {} revogada", session_id);
+        return HttpResponse::Unauthorized().json("Refresh token already used; session revoked");
+    }
+
+    if !row.get::<bool, _>("valid") {
+        return HttpResponse::Unauthorized().json("Refresh token expired");
+    }
+
+    let user = match get_user_by_id(pool.get_ref(), user_id).await {
+        Ok((user, _)) if !user.active => return HttpResponse::Forbidden().json("User is deactivated"),
+        Ok((user, true)) => user,
+        Ok((_, false)) => return HttpResponse::Forbidden().json("Tenant is inactive"),
+        Err(_) => return HttpResponse::Unauthorized().json("Invalid user"),
+    };
+
+    let rotated = async {
+        sqlx::query("UPDATE refresh_tokens SET used_at = NOW() WHERE token_hash = $1")
+            .bind(hash_token(&body.refresh_token))
+            .execute(&mut *transaction)
+            .await?;
+        sqlx::query("UPDATE sessions SET last_used_at = NOW(), expires_at = $2 WHERE id = $1")
+            .bind(session_id)
+            .bind(Utc::now() + refresh_token_ttl())
+            .execute(&mut *transaction)
+            .await?;
+        issue_refresh_token(&mut *transaction, session_id).await
+    }
+    .await;
+
+    let refresh_token = match rotated {
+        Ok(token) => token,
+        Err(e) => {
+            eprintln!("Error rotating refresh token: {}", e);
+            return HttpResponse::InternalServerError().json("Error refreshing session");
+        }
+    };
+
+    let token = match issue_access_token(&user, session_id) {
+        Ok(token) => token,
+        Err(response) => return response,
+    };
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing refresh token: {}", e);
+        return HttpResponse::InternalServerError().json("Error refreshing session");
+    }
+
+    HttpResponse::Ok().json(TokenPair {
+        token,
+        refresh_token,
+        expires_in: access_token_ttl().num_seconds(),
+    })
+}
+
+pub async fn logout(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let Some(session_id) = current_session(&req) else {
+        return HttpResponse::Unauthorized().json("Invalid token");
+    };
+
+    match sqlx::query(
+        "UPDATE sessions SET revoked_at = NOW(), revoked_reason = $3 WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL"
+    )
+    .bind(session_id)
+    .bind(user.id)
+    .bind(LOGOUT)
+    .execute(pool.get_ref())
+    .await
+    {
+        Ok(_) => HttpResponse::Ok().json("Logged out successfully"),
+        Err(e) => {
+            eprintln!("Error revoking session: {}", e);
+            HttpResponse::InternalServerError().json("Error logging out")
+        }
+    }
+}
+
+// Usuário da mesma entidade de quem gerencia
+async fn ensure_same_tenant_user(pool: &PgPool, user: &User, user_id: Uuid) -> Result<(), HttpResponse> {
+    match sqlx::query("SELECT 1 FROM users WHERE id = $1 AND tenant_id = $2")
+        .bind(user_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(HttpResponse::NotFound().json("User not found")),
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error fetching user"))
+        }
+    }
+}
+
+pub async fn get_user_sessions(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let user_id = id.into_inner();
+    if let Err(response) = ensure_same_tenant_user(pool.get_ref(), &user, user_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(
+        "SELECT id, ip_address, user_agent, created_at, last_used_at, expires_at
+         FROM sessions
+         WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+         ORDER BY last_used_at DESC"
+    )
+    .bind(user_id)
+    .map(|row: PgRow| {
+        serde_json::json!({
+            "id": row.get::<Uuid, _>("id"),
+            "ip_address": row.get::<Option<String>, _>("ip_address"),
+            "user_agent": row.get::<Option<String>, _>("user_agent"),
+            "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+            "last_used_at": row.get::<chrono::DateTime<Utc>, _>("last_used_at"),
+            "expires_at": row.get::<chrono::DateTime<Utc>, _>("expires_at")
+        })
+    })
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(sessions) => HttpResponse::Ok().json(sessions),
+        Err(e) => {
+            eprintln!("Error fetching sessions: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching sessions")
+        }
+    }
+}
+
+/// Desliga o usuário de todos os dispositivos (ex.: servidor exonerado)
+pub async fn revoke_sessions(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let user_id = id.into_inner();
+    if let Err(response) = ensure_same_tenant_user(pool.get_ref(), &user, user_id).await {
+        return response;
+    }
+
+    match revoke_user_sessions(pool.get_ref(), user_id, ADMIN_REVOKED).await {
+        Ok(revoked) => {
+            println!("🔒 {} sessão(ões) do usuário {} revogada(s) por {}", revoked, user_id, user.username);
+            HttpResponse::Ok().json(serde_json::json!({ "revoked_sessions": revoked }))
+        }
+        Err(e) => {
+            eprintln!("Error revoking sessions: {}", e);
+            HttpResponse::InternalServerError().json("Error revoking sessions")
+        }
+    }
+}
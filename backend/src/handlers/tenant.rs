@@ -0,0 +1,332 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+use bcrypt::{hash, DEFAULT_COST};
+
+use super::patrimony::{auth_middleware, user_from_row, USER_COLUMNS};
+use super::audit::{self, Actor};
+use super::permissions;
+use super::password_policy;
+
+// Entidade dos dados anteriores à separação por entidades
+pub const DEFAULT_TENANT: &str = "default";
+
+#[derive(Deserialize)]
+pub struct CreateTenant {
+    pub code: String,
+    pub name: String,
+    pub document_number: Option<String>,
+    // Primeiro departamento e administrador da entidade
+    pub department_code: String,
+    pub department_name: String,
+    pub admin_username: String,
+    pub admin_password: String,
+    pub admin_email: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTenant {
+    pub name: Option<String>,
+    pub document_number: Option<String>,
+    pub active: Option<bool>,
+}
+
+const TENANT_SELECT: &str =
+    "SELECT t.id, t.code, t.name, t.document_number, t.active, t.created_at, t.updated_at,
+            (SELECT COUNT(*) FROM users u WHERE u.tenant_id = t.id) as user_count,
+            (SELECT COUNT(*) FROM departments d WHERE d.tenant_id = t.id) as department_count,
+            (SELECT COUNT(*) FROM patrimonies p WHERE p.tenant_id = t.id AND p.deleted_at IS NULL) as asset_count
+     FROM tenants t";
+
+fn tenant_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "code": row.get::<String, _>("code"),
+        "name": row.get::<String, _>("name"),
+        "document_number": row.get::<Option<String>, _>("document_number"),
+        "active": row.get::<bool, _>("active"),
+        "user_count": row.get::<i64, _>("user_count"),
+        "department_count": row.get::<i64, _>("department_count"),
+        "asset_count": row.get::<i64, _>("asset_count"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "updated_at": row.get::<chrono::DateTime<Utc>, _>("updated_at")
+    })
+}
+
+async fn fetch_tenant(pool: &PgPool, tenant_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query(&format!("{} WHERE t.id = $1", TENANT_SELECT))
+        .bind(tenant_id)
+        .map(|row: PgRow| tenant_json(&row))
+        .fetch_one(pool)
+        .await
+}
+
+fn valid_code(code: &str) -> bool {
+    !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+pub async fn get_tenants(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(&format!("{} ORDER BY t.name", TENANT_SELECT))
+        .map(|row: PgRow| tenant_json(&row))
+        .fetch_all(pool.get_ref())
+        .await
+    {
+        Ok(tenants) => HttpResponse::Ok().json(tenants),
+        Err(e) => {
+            eprintln!("Error fetching tenants: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching tenants")
+        }
+    }
+}
+
+pub async fn get_tenant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let _user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match fetch_tenant(pool.get_ref(), id.into_inner()).await {
+        Ok(tenant) => HttpResponse::Ok().json(tenant),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json("Tenant not found"),
+        Err(e) => {
+            eprintln!("Error fetching tenant: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching tenant")
+        }
+    }
+}
+
+/// Cria a entidade já com o primeiro departamento e o administrador local
+pub async fn create_tenant(
+    pool: web::Data<PgPool>,
+    tenant: web::Json<CreateTenant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let code = tenant.code.trim().to_lowercase();
+    let department_code = tenant.department_code.trim().to_lowercase();
+    if !valid_code(&code) || !valid_code(&department_code) {
+        return HttpResponse::BadRequest().json("Code must contain only letters, digits, '-' or '_'");
+    }
+    if tenant.name.trim().is_empty() || tenant.department_name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Name is required");
+    }
+    if tenant.admin_username.trim().is_empty() || tenant.admin_password.is_empty() {
+        return HttpResponse::BadRequest().json("Admin username and password are required");
+    }
+    if let Err(response) = password_policy::validate(&tenant.admin_password, &tenant.admin_username) {
+        return response;
+    }
+
+    let password_hash = match hash(&tenant.admin_password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error creating tenant");
+        }
+    };
+
+    let result = sqlx::query(
+        "INSERT INTO tenants (code, name, document_number) VALUES ($1, $2, $3) RETURNING id"
+    )
+    .bind(&code)
+    .bind(tenant.name.trim())
+    .bind(&tenant.document_number)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let tenant_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating tenant: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Tenant code already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating tenant");
+        }
+    };
+
+    if let Err(e) = sqlx::query("INSERT INTO departments (code, name, tenant_id) VALUES ($1, $2, $3)")
+        .bind(&department_code)
+        .bind(tenant.department_name.trim())
+        .bind(tenant_id)
+        .execute(&mut *transaction)
+        .await
+    {
+        eprintln!("Error creating tenant department: {}", e);
+        if e.to_string().contains("duplicate key") {
+            return HttpResponse::Conflict().json("Department code already exists");
+        }
+        return HttpResponse::InternalServerError().json("Error creating tenant");
+    }
+
+    let admin = sqlx::query(&format!(
+        "INSERT INTO users (id, company_name, department, username, password_hash, email, role, tenant_id)
+         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7)
+         RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(tenant.name.trim())
+    .bind(&department_code)
+    .bind(tenant.admin_username.trim())
+    .bind(password_hash)
+    .bind(&tenant.admin_email)
+    .bind(permissions::ADMIN_ROLE)
+    .bind(tenant_id)
+    .map(|row: PgRow| user_from_row(&row))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let admin = match admin {
+        Ok(admin) => admin,
+        Err(e) => {
+            eprintln!("Error creating tenant admin: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Username already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating tenant");
+        }
+    };
+
+    let actor = Actor::new(&user, &req);
+    let audit_result = match audit::record_change(&mut transaction, &actor, audit::TENANT, tenant_id, audit::CREATE, None).await {
+        Ok(()) => audit::record_change(&mut transaction, &actor, audit::USER, admin.id, audit::CREATE, None).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = audit_result {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error creating tenant");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing tenant: {}", e);
+        return HttpResponse::InternalServerError().json("Error creating tenant");
+    }
+
+    println!("🏛️ Entidade '{}' criada por {}", code, user.username);
+
+    match fetch_tenant(pool.get_ref(), tenant_id).await {
+        Ok(tenant) => HttpResponse::Created().json(serde_json::json!({
+            "tenant": tenant,
+            "admin": admin
+        })),
+        Err(e) => {
+            eprintln!("Error fetching created tenant: {}", e);
+            HttpResponse::InternalServerError().json("Tenant created but error fetching details")
+        }
+    }
+}
+
+pub async fn update_tenant(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    tenant: web::Json<UpdateTenant>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let tenant_id = id.into_inner();
+
+    if let Some(name) = &tenant.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json("Name is required");
+        }
+    }
+    // Desativar a própria entidade bloquearia o acesso de quem a gerencia
+    if tenant.active == Some(false) && tenant_id == user.tenant_id {
+        return HttpResponse::BadRequest().json("Cannot deactivate your own tenant");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating tenant");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::TENANT, tenant_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching tenant for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating tenant");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE tenants SET
+            name = COALESCE($1, name),
+            document_number = COALESCE($2, document_number),
+            active = COALESCE($3, active),
+            updated_at = NOW()
+         WHERE id = $4"
+    )
+    .bind(tenant.name.as_deref().map(str::trim))
+    .bind(&tenant.document_number)
+    .bind(tenant.active)
+    .bind(tenant_id)
+    .execute(&mut *transaction)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => return HttpResponse::NotFound().json("Tenant not found"),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error updating tenant: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating tenant");
+        }
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::TENANT, tenant_id, audit::UPDATE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating tenant");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing tenant update: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating tenant");
+    }
+
+    match fetch_tenant(pool.get_ref(), tenant_id).await {
+        Ok(tenant) => HttpResponse::Ok().json(tenant),
+        Err(e) => {
+            eprintln!("Error fetching updated tenant: {}", e);
+            HttpResponse::InternalServerError().json("Tenant updated but error fetching details")
+        }
+    }
+}
@@ -5,7 +5,21 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use super::patrimony::auth_middleware; // Importar o middleware de autenticação
+use super::patrimony::{auth_middleware, User}; // Importar o middleware de autenticação
+use super::write_off::{written_off_response, WRITTEN_OFF};
+use super::depreciation;
+use super::department::{is_department_manager, validate_department};
+use super::audit::{self, Actor};
+use super::permissions;
+use super::department_grant;
+use crate::documents::terms::{self, TermItem, TransferTermData, DEFAULT_ORGANIZATION};
+
+// Estados da transferência: solicitada pela origem, aprovada pelo gestor do destino
+// e confirmada como recebida (só então o bem muda de departamento)
+pub const PENDING: &str = "pending";
+pub const APPROVED: &str = "approved";
+pub const REJECTED: &str = "rejected";
+pub const RECEIVED: &str = "received";
 
 #[derive(Serialize, Deserialize)]
 pub struct Transfer {
@@ -14,8 +28,17 @@ pub struct Transfer {
     pub from_department: String,
     pub to_department: String,
     pub reason: String,
+    pub status: String,
+    pub batch_id: Option<Uuid>,
     pub transferred_by: Option<Uuid>,
     pub transferred_at: chrono::DateTime<Utc>,
+    pub approved_by: Option<Uuid>,
+    pub approved_at: Option<chrono::DateTime<Utc>>,
+    pub rejected_by: Option<Uuid>,
+    pub rejected_at: Option<chrono::DateTime<Utc>>,
+    pub rejection_reason: Option<String>,
+    pub received_by: Option<Uuid>,
+    pub received_at: Option<chrono::DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
@@ -25,6 +48,105 @@ pub struct CreateTransfer {
     pub reason: String,
 }
 
+// Vários bens movidos juntos compartilham um batch_id e um único termo
+#[derive(Deserialize)]
+pub struct CreateTransferBatch {
+    pub patrimony_ids: Vec<Uuid>,
+    pub to_department: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct RejectTransfer {
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct PendingTransfersQuery {
+    pub department: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TransferListQuery {
+    pub patrimony_id: Option<Uuid>,
+}
+
+// transferred_by/transferred_at registram a solicitação
+const TRANSFER_SELECT: &str =
+    "SELECT t.id, t.patrimony_id, p.name as patrimony_name, p.plate as patrimony_plate,
+            t.from_department, t.to_department, t.reason, t.status, t.batch_id,
+            t.transferred_by, u.username as transferred_by_name, t.transferred_at,
+            t.approved_by, ua.username as approved_by_name, t.approved_at,
+            t.rejected_by, ur.username as rejected_by_name, t.rejected_at, t.rejection_reason,
+            t.received_by, urc.username as received_by_name, t.received_at
+     FROM transfers t
+     JOIN patrimonies p ON t.patrimony_id = p.id
+     LEFT JOIN users u ON t.transferred_by = u.id
+     LEFT JOIN users ua ON t.approved_by = ua.id
+     LEFT JOIN users ur ON t.rejected_by = ur.id
+     LEFT JOIN users urc ON t.received_by = urc.id";
+
+fn transfer_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+        "patrimony_name": row.get::<String, _>("patrimony_name"),
+        "patrimony_plate": row.get::<String, _>("patrimony_plate"),
+        "from_department": row.get::<String, _>("from_department"),
+        "to_department": row.get::<String, _>("to_department"),
+        "reason": row.get::<Option<String>, _>("reason"),
+        "status": row.get::<String, _>("status"),
+        "batch_id": row.get::<Option<Uuid>, _>("batch_id"),
+        "transferred_by": row.get::<Option<Uuid>, _>("transferred_by"),
+        "transferred_by_name": row.get::<Option<String>, _>("transferred_by_name"),
+        "transferred_at": row.get::<chrono::DateTime<Utc>, _>("transferred_at"),
+        "approved_by": row.get::<Option<Uuid>, _>("approved_by"),
+        "approved_by_name": row.get::<Option<String>, _>("approved_by_name"),
+        "approved_at": row.get::<Option<chrono::DateTime<Utc>>, _>("approved_at"),
+        "rejected_by": row.get::<Option<Uuid>, _>("rejected_by"),
+        "rejected_by_name": row.get::<Option<String>, _>("rejected_by_name"),
+        "rejected_at": row.get::<Option<chrono::DateTime<Utc>>, _>("rejected_at"),
+        "rejection_reason": row.get::<Option<String>, _>("rejection_reason"),
+        "received_by": row.get::<Option<Uuid>, _>("received_by"),
+        "received_by_name": row.get::<Option<String>, _>("received_by_name"),
+        "received_at": row.get::<Option<chrono::DateTime<Utc>>, _>("received_at")
+    })
+}
+
+// Transferências com origem ou destino em um departamento visível ao usuário do parâmetro
+fn visible_filter(param: usize) -> String {
+    format!(
+        "({} OR {})",
+        department_grant::filter_sql("t.from_department", param),
+        department_grant::filter_sql("t.to_department", param)
+    )
+}
+
+/// Quem atua em qualquer departamento ou qualquer usuário lotado no departamento
+pub fn belongs_to_department(user: &User, department: &str) -> bool {
+    user.can(permissions::ALL_DEPARTMENTS) || user.department == department
+}
+
+async fn load_transfer(pool: &PgPool, tenant_id: Uuid, transfer_id: Uuid) -> Result<serde_json::Value, sqlx::Error> {
+    sqlx::query(&format!("{} WHERE t.id = $1 AND t.tenant_id = $2", TRANSFER_SELECT))
+        .bind(transfer_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| transfer_json(&row))
+        .fetch_one(pool)
+        .await
+}
+
+async fn fetch_transfer(pool: &PgPool, tenant_id: Uuid, transfer_id: Uuid) -> HttpResponse {
+    match load_transfer(pool, tenant_id, transfer_id).await {
+        Ok(transfer) => HttpResponse::Ok().json(transfer),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json("Transfer not found"),
+        Err(e) => {
+            eprintln!("Error fetching transfer: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching transfer")
+        }
+    }
+}
+
 pub async fn transfer_patrimony(
     pool: web::Data<PgPool>,
     transfer: web::Json<CreateTransfer>,
@@ -39,14 +161,21 @@ pub async fn transfer_patrimony(
 
     // Buscar o patrimônio para obter o departamento atual
     let patrimony_result = sqlx::query(
-        "SELECT department FROM patrimonies WHERE id = $1"
+        "SELECT department, status FROM patrimonies WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NULL"
     )
     .bind(transfer.patrimony_id)
+    .bind(user.tenant_id)
     .fetch_optional(pool.get_ref())
     .await;
 
     let from_department = match patrimony_result {
-        Ok(Some(row)) => row.get::<String, _>("department"),
+        Ok(Some(row)) => {
+            // Bens baixados não podem ser transferidos
+            if row.get::<String, _>("status") == WRITTEN_OFF {
+                return written_off_response();
+            }
+            row.get::<String, _>("department")
+        }
         Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
         Err(e) => {
             eprintln!("Error fetching patrimony: {}", e);
@@ -54,12 +183,19 @@ pub async fn transfer_patrimony(
         }
     };
 
+    // A solicitação parte do departamento de origem
+    if !belongs_to_department(&user, &from_department) {
+        return HttpResponse::Forbidden().json("Only the source department can request this transfer");
+    }
+
     // Verificar se o departamento de destino é diferente
     if from_department == transfer.to_department {
         return HttpResponse::BadRequest().json("Cannot transfer to the same department");
     }
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, &transfer.to_department).await {
+        return response;
+    }
 
-    // Iniciar transação
     let mut transaction = match pool.begin().await {
         Ok(tx) => tx,
         Err(e) => {
@@ -68,10 +204,10 @@ pub async fn transfer_patrimony(
         }
     };
 
-    // 1. Registrar a transferência
+    // Registrar a solicitação; o departamento do bem só muda no recebimento
     let transfer_result = sqlx::query(
-        "INSERT INTO transfers (id, patrimony_id, from_department, to_department, reason, transferred_by) 
-         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5) 
+        "INSERT INTO transfers (id, patrimony_id, from_department, to_department, reason, transferred_by, status, tenant_id)
+         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7)
          RETURNING id"
     )
     .bind(transfer.patrimony_id)
@@ -79,143 +215,417 @@ pub async fn transfer_patrimony(
     .bind(&transfer.to_department)
     .bind(&transfer.reason)
     .bind(user.id)
+    .bind(PENDING)
+    .bind(user.tenant_id)
     .map(|row: PgRow| row.get::<Uuid, _>("id"))
     .fetch_one(&mut *transaction)
     .await;
 
+    let transfer_result = match transfer_result {
+        Ok(transfer_id) => audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::TRANSFER, transfer_id, audit::CREATE, None)
+            .await
+            .map(|_| transfer_id),
+        Err(e) => Err(e),
+    };
+    let transfer_result = match transfer_result {
+        Ok(transfer_id) => transaction.commit().await.map(|_| transfer_id),
+        Err(e) => Err(e),
+    };
+
+    let transfer_id = match transfer_result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating transfer record: {}", e);
+            // Índice único parcial: apenas uma transferência em aberto por bem
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Patrimony already has an open transfer");
+            }
+            return HttpResponse::InternalServerError().json("Error processing transfer");
+        }
+    };
+
+    println!("📦 Transferência solicitada: {} -> {}", from_department, transfer.to_department);
+
+    match load_transfer(pool.get_ref(), user.tenant_id, transfer_id).await {
+        Ok(transfer) => HttpResponse::Created().json(transfer),
+        Err(e) => {
+            eprintln!("Error fetching created transfer: {}", e);
+            HttpResponse::InternalServerError().json("Transfer requested but error fetching details")
+        }
+    }
+}
+
+pub async fn approve_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let transfer_id = id.into_inner();
+
+    let transfer = sqlx::query("SELECT to_department, status FROM transfers WHERE id = $1 AND tenant_id = $2")
+        .bind(transfer_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let to_department = match transfer {
+        Ok(Some(row)) => row.get::<String, _>("to_department"),
+        Ok(None) => return HttpResponse::NotFound().json("Transfer not found"),
+        Err(e) => {
+            eprintln!("Error fetching transfer: {}", e);
+            return HttpResponse::InternalServerError().json("Error approving transfer");
+        }
+    };
+
+    if !is_department_manager(pool.get_ref(), &user, &to_department).await {
+        return HttpResponse::Forbidden().json("Only a manager of the destination department can approve this transfer");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error approving transfer");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::TRANSFER, transfer_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching transfer for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error approving transfer");
+        }
+    };
+
+    // A condição de status garante a transição mesmo com requisições concorrentes
+    let result = sqlx::query(
+        "UPDATE transfers SET status = $1, approved_by = $2, approved_at = NOW() WHERE id = $3 AND status = $4"
+    )
+    .bind(APPROVED)
+    .bind(user.id)
+    .bind(transfer_id)
+    .bind(PENDING)
+    .execute(&mut *transaction)
+    .await;
+
+    let result = match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            match audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::TRANSFER, transfer_id, audit::APPROVE, before).await {
+                Ok(()) => transaction.commit().await.map(|_| result),
+                Err(e) => Err(e),
+            }
+        }
+        other => other,
+    };
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            println!("✅ Transferência {} aprovada por {}", transfer_id, user.username);
+            fetch_transfer(pool.get_ref(), user.tenant_id, transfer_id).await
+        }
+        Ok(_) => HttpResponse::Conflict().json("Only pending transfers can be approved"),
+        Err(e) => {
+            eprintln!("Error approving transfer: {}", e);
+            HttpResponse::InternalServerError().json("Error approving transfer")
+        }
+    }
+}
+
+pub async fn reject_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    rejection: web::Json<RejectTransfer>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if rejection.reason.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Rejection reason is required");
+    }
+
+    let transfer_id = id.into_inner();
+
+    let transfer = sqlx::query("SELECT to_department FROM transfers WHERE id = $1 AND tenant_id = $2")
+        .bind(transfer_id)
+        .bind(user.tenant_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+    let to_department = match transfer {
+        Ok(Some(row)) => row.get::<String, _>("to_department"),
+        Ok(None) => return HttpResponse::NotFound().json("Transfer not found"),
+        Err(e) => {
+            eprintln!("Error fetching transfer: {}", e);
+            return HttpResponse::InternalServerError().json("Error rejecting transfer");
+        }
+    };
+
+    if !is_department_manager(pool.get_ref(), &user, &to_department).await {
+        return HttpResponse::Forbidden().json("Only a manager of the destination department can reject this transfer");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error rejecting transfer");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::TRANSFER, transfer_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching transfer for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error rejecting transfer");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE transfers SET status = $1, rejected_by = $2, rejected_at = NOW(), rejection_reason = $3
+         WHERE id = $4 AND status = $5"
+    )
+    .bind(REJECTED)
+    .bind(user.id)
+    .bind(rejection.reason.trim())
+    .bind(transfer_id)
+    .bind(PENDING)
+    .execute(&mut *transaction)
+    .await;
+
+    let result = match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            match audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::TRANSFER, transfer_id, audit::REJECT, before).await {
+                Ok(()) => transaction.commit().await.map(|_| result),
+                Err(e) => Err(e),
+            }
+        }
+        other => other,
+    };
+
+    match result {
+        Ok(result) if result.rows_affected() > 0 => {
+            println!("❌ Transferência {} rejeitada por {}", transfer_id, user.username);
+            fetch_transfer(pool.get_ref(), user.tenant_id, transfer_id).await
+        }
+        Ok(_) => HttpResponse::Conflict().json("Only pending transfers can be rejected"),
+        Err(e) => {
+            eprintln!("Error rejecting transfer: {}", e);
+            HttpResponse::InternalServerError().json("Error rejecting transfer")
+        }
+    }
+}
+
+// Confirmação de recebimento: o bem passa para o departamento de destino
+pub async fn receive_transfer(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let transfer_id = id.into_inner();
+
+    // Iniciar transação
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error receiving transfer");
+        }
+    };
+
+    let transfer = sqlx::query(
+        "SELECT t.patrimony_id, t.from_department, t.to_department, t.status,
+                p.department as current_department, p.status as patrimony_status
+         FROM transfers t
+         JOIN patrimonies p ON t.patrimony_id = p.id
+         WHERE t.id = $1 AND t.tenant_id = $2
+         FOR UPDATE"
+    )
+    .bind(transfer_id)
+    .bind(user.tenant_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let (patrimony_id, to_department) = match transfer {
+        Ok(Some(row)) => {
+            let to_department: String = row.get("to_department");
+            if !belongs_to_department(&user, &to_department) {
+                return HttpResponse::Forbidden().json("Only the destination department can confirm receipt");
+            }
+            if row.get::<String, _>("status") != APPROVED {
+                return HttpResponse::Conflict().json("Only approved transfers can be received");
+            }
+            if row.get::<String, _>("patrimony_status") == WRITTEN_OFF {
+                return written_off_response();
+            }
+            // O bem mudou de departamento por outro caminho desde a solicitação
+            if row.get::<String, _>("current_department") != row.get::<String, _>("from_department") {
+                return HttpResponse::Conflict().json("Patrimony is no longer in the source department");
+            }
+            (row.get::<Uuid, _>("patrimony_id"), to_department)
+        }
+        Ok(None) => return HttpResponse::NotFound().json("Transfer not found"),
+        Err(e) => {
+            eprintln!("Error fetching transfer: {}", e);
+            return HttpResponse::InternalServerError().json("Error receiving transfer");
+        }
+    };
+
+    let before_transfer = audit::snapshot(&mut *transaction, audit::TRANSFER, transfer_id).await;
+    let before_patrimony = audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await;
+    let (before_transfer, before_patrimony) = match (before_transfer, before_patrimony) {
+        (Ok(transfer), Ok(patrimony)) => (transfer, patrimony),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error fetching records for audit: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error receiving transfer");
+        }
+    };
+
+    // 1. Registrar o recebimento
+    let transfer_result = sqlx::query(
+        "UPDATE transfers SET status = $1, received_by = $2, received_at = NOW() WHERE id = $3"
+    )
+    .bind(RECEIVED)
+    .bind(user.id)
+    .bind(transfer_id)
+    .execute(&mut *transaction)
+    .await;
+
     if let Err(e) = transfer_result {
-        eprintln!("Error creating transfer record: {}", e);
+        eprintln!("Error updating transfer record: {}", e);
         let _ = transaction.rollback().await;
-        return HttpResponse::InternalServerError().json("Error processing transfer");
+        return HttpResponse::InternalServerError().json("Error receiving transfer");
     }
 
     // 2. Atualizar o departamento do patrimônio
     let update_result = sqlx::query(
         "UPDATE patrimonies SET department = $1, updated_at = NOW() WHERE id = $2"
     )
-    .bind(&transfer.to_department)
-    .bind(transfer.patrimony_id)
+    .bind(&to_department)
+    .bind(patrimony_id)
     .execute(&mut *transaction)
     .await;
 
     if let Err(e) = update_result {
         eprintln!("Error updating patrimony department: {}", e);
         let _ = transaction.rollback().await;
-        return HttpResponse::InternalServerError().json("Error processing transfer");
+        return HttpResponse::InternalServerError().json("Error receiving transfer");
+    }
+
+    // 3. Auditoria da transferência e da mudança de departamento do bem
+    let actor = Actor::new(&user, &req);
+    let audit_result = match audit::record_change(&mut transaction, &actor, audit::TRANSFER, transfer_id, audit::RECEIVE, before_transfer).await {
+        Ok(()) => audit::record_change(&mut transaction, &actor, audit::PATRIMONY, patrimony_id, audit::RECEIVE, before_patrimony).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = audit_result {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error receiving transfer");
     }
 
     // Commit da transação
     if let Err(e) = transaction.commit().await {
         eprintln!("Error committing transaction: {}", e);
-        return HttpResponse::InternalServerError().json("Error processing transfer");
+        return HttpResponse::InternalServerError().json("Error receiving transfer");
     }
 
-    // Retornar a transferência criada
-    let created_transfer = sqlx::query(
-        "SELECT t.id, t.patrimony_id, t.from_department, t.to_department, t.reason, t.transferred_by, t.transferred_at, p.name as patrimony_name
-         FROM transfers t
-         JOIN patrimonies p ON t.patrimony_id = p.id
-         WHERE t.id = $1"
-    )
-    .bind(transfer_result.unwrap())
+    println!("📥 Transferência {} recebida em {}", transfer_id, to_department);
+
+    fetch_transfer(pool.get_ref(), user.tenant_id, transfer_id).await
+}
+
+// Transferências em aberto (pendentes ou aprovadas) que envolvem o departamento
+pub async fn get_pending_transfers(
+    pool: web::Data<PgPool>,
+    query: web::Query<PendingTransfersQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let department = query.department.clone().unwrap_or_else(|| user.department.clone());
+
+    let result = sqlx::query(&format!(
+        "{} WHERE t.tenant_id = $4 AND t.status IN ($1, $2) AND (t.to_department = $3 OR t.from_department = $3) AND {}
+         ORDER BY t.transferred_at",
+        TRANSFER_SELECT,
+        visible_filter(5)
+    ))
+    .bind(PENDING)
+    .bind(APPROVED)
+    .bind(&department)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
     .map(|row: PgRow| {
-        let patrimony_name: String = row.get("patrimony_name");
-        let transferred_by: Option<Uuid> = row.get("transferred_by");
-        
-        serde_json::json!({
-            "id": row.get::<Uuid, _>("id"),
-            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
-            "patrimony_name": patrimony_name,
-            "from_department": row.get::<String, _>("from_department"),
-            "to_department": row.get::<String, _>("to_department"),
-            "reason": row.get::<String, _>("reason"),
-            "transferred_by": transferred_by,
-            "transferred_at": row.get::<chrono::DateTime<Utc>, _>("transferred_at")
-        })
+        let mut transfer = transfer_json(&row);
+        let direction = if row.get::<String, _>("to_department") == department {
+            "incoming"
+        } else {
+            "outgoing"
+        };
+        transfer["direction"] = serde_json::json!(direction);
+        transfer
     })
-    .fetch_one(pool.get_ref())
+    .fetch_all(pool.get_ref())
     .await;
 
-    match created_transfer {
-        Ok(transfer) => HttpResponse::Created().json(transfer),
+    match result {
+        Ok(transfers) => HttpResponse::Ok().json(transfers),
         Err(e) => {
-            eprintln!("Error fetching created transfer: {}", e);
-            HttpResponse::InternalServerError().json("Transfer completed but error fetching details")
+            eprintln!("Error fetching pending transfers: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching pending transfers")
         }
     }
 }
 
 pub async fn get_transfers(
     pool: web::Data<PgPool>,
-    patrimony_id: web::Query<Option<Uuid>>,
+    query: web::Query<TransferListQuery>,
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
     };
 
-    let patrimony_filter = patrimony_id.into_inner();
-    
-    let result = if let Some(pid) = patrimony_filter {
-        sqlx::query(
-            "SELECT t.id, t.patrimony_id, p.name as patrimony_name, t.from_department, t.to_department, t.reason, t.transferred_by, u.username as transferred_by_name, t.transferred_at 
-             FROM transfers t
-             JOIN patrimonies p ON t.patrimony_id = p.id
-             LEFT JOIN users u ON t.transferred_by = u.id
-             WHERE t.patrimony_id = $1 
-             ORDER BY t.transferred_at DESC"
-        )
-        .bind(pid)
-        .map(|row: PgRow| {
-            let transferred_by: Option<Uuid> = row.get("transferred_by");
-            let transferred_by_name: Option<String> = row.get("transferred_by_name");
-            let patrimony_name: String = row.get("patrimony_name");
-            
-            serde_json::json!({
-                "id": row.get::<Uuid, _>("id"),
-                "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
-                "patrimony_name": patrimony_name,
-                "from_department": row.get::<String, _>("from_department"),
-                "to_department": row.get::<String, _>("to_department"),
-                "reason": row.get::<String, _>("reason"),
-                "transferred_by": transferred_by,
-                "transferred_by_name": transferred_by_name,
-                "transferred_at": row.get::<chrono::DateTime<Utc>, _>("transferred_at")
-            })
-        })
-        .fetch_all(pool.get_ref())
-        .await
-    } else {
-        sqlx::query(
-            "SELECT t.id, t.patrimony_id, p.name as patrimony_name, t.from_department, t.to_department, t.reason, t.transferred_by, u.username as transferred_by_name, t.transferred_at 
-             FROM transfers t
-             JOIN patrimonies p ON t.patrimony_id = p.id
-             LEFT JOIN users u ON t.transferred_by = u.id
-             ORDER BY t.transferred_at DESC"
-        )
-        .map(|row: PgRow| {
-            let transferred_by: Option<Uuid> = row.get("transferred_by");
-            let transferred_by_name: Option<String> = row.get("transferred_by_name");
-            let patrimony_name: String = row.get("patrimony_name");
-            
-            serde_json::json!({
-                "id": row.get::<Uuid, _>("id"),
-                "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
-                "patrimony_name": patrimony_name,
-                "from_department": row.get::<String, _>("from_department"),
-                "to_department": row.get::<String, _>("to_department"),
-                "reason": row.get::<String, _>("reason"),
-                "transferred_by": transferred_by,
-                "transferred_by_name": transferred_by_name,
-                "transferred_at": row.get::<chrono::DateTime<Utc>, _>("transferred_at")
-            })
-        })
-        .fetch_all(pool.get_ref())
-        .await
-    };
+    // Sem concessão, só as transferências que envolvem departamentos visíveis ao usuário
+    let result = sqlx::query(&format!(
+        "{} WHERE t.tenant_id = $1 AND ($2::uuid IS NULL OR t.patrimony_id = $2) AND {}
+         ORDER BY t.transferred_at DESC",
+        TRANSFER_SELECT,
+        visible_filter(3)
+    ))
+    .bind(user.tenant_id)
+    .bind(query.patrimony_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| transfer_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
 
     match result {
         Ok(transfers) => HttpResponse::Ok().json(transfers),
@@ -232,39 +642,19 @@ pub async fn get_transfer(
     req: HttpRequest,
 ) -> HttpResponse {
     // Verificar autenticação
-    let _user = match auth_middleware(&req, pool.get_ref()).await {
+    let user = match auth_middleware(&req, pool.get_ref()).await {
         Ok(Some(user)) => user,
         Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
         Err(e) => return e,
     };
 
-    let result = sqlx::query(
-        "SELECT t.id, t.patrimony_id, p.name as patrimony_name, t.from_department, t.to_department, t.reason, t.transferred_by, u.username as transferred_by_name, t.transferred_at 
-         FROM transfers t
-         JOIN patrimonies p ON t.patrimony_id = p.id
-         LEFT JOIN users u ON t.transferred_by = u.id
-         WHERE t.id = $1"
-    )
-    .bind(id.into_inner())
-    .map(|row: PgRow| {
-        let transferred_by: Option<Uuid> = row.get("transferred_by");
-        let transferred_by_name: Option<String> = row.get("transferred_by_name");
-        let patrimony_name: String = row.get("patrimony_name");
-        
-        serde_json::json!({
-            "id": row.get::<Uuid, _>("id"),
-            "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
-            "patrimony_name": patrimony_name,
-            "from_department": row.get::<String, _>("from_department"),
-            "to_department": row.get::<String, _>("to_department"),
-            "reason": row.get::<String, _>("reason"),
-            "transferred_by": transferred_by,
-            "transferred_by_name": transferred_by_name,
-            "transferred_at": row.get::<chrono::DateTime<Utc>, _>("transferred_at")
-        })
-    })
-    .fetch_one(pool.get_ref())
-    .await;
+    let result = sqlx::query(&format!("{} WHERE t.id = $1 AND t.tenant_id = $2 AND {}", TRANSFER_SELECT, visible_filter(3)))
+        .bind(id.into_inner())
+        .bind(user.tenant_id)
+        .bind(department_grant::restriction(&user))
+        .map(|row: PgRow| transfer_json(&row))
+        .fetch_one(pool.get_ref())
+        .await;
 
     match result {
         Ok(transfer) => HttpResponse::Ok().json(transfer),
@@ -274,4 +664,230 @@ pub async fn get_transfer(
             HttpResponse::InternalServerError().json("Error fetching transfer")
         }
     }
-}
\ No newline at end of file
+}
+
+pub async fn create_transfer_batch(
+    pool: web::Data<PgPool>,
+    batch: web::Json<CreateTransferBatch>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let mut patrimony_ids = batch.patrimony_ids.clone();
+    patrimony_ids.sort();
+    patrimony_ids.dedup();
+
+    if patrimony_ids.is_empty() {
+        return HttpResponse::BadRequest().json("At least one patrimony is required");
+    }
+
+    // Iniciar transação
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error processing transfer");
+        }
+    };
+
+    let patrimonies = sqlx::query(
+        "SELECT id, plate, department, status FROM patrimonies WHERE id = ANY($1) AND tenant_id = $2 AND deleted_at IS NULL FOR UPDATE"
+    )
+    .bind(&patrimony_ids)
+    .bind(user.tenant_id)
+    .fetch_all(&mut *transaction)
+    .await;
+
+    let patrimonies = match patrimonies {
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching patrimonies: {}", e);
+            return HttpResponse::InternalServerError().json("Error processing transfer");
+        }
+    };
+
+    if patrimonies.len() != patrimony_ids.len() {
+        return HttpResponse::NotFound().json("Patrimony not found");
+    }
+
+    // Um termo combinado tem uma única origem
+    let from_department: String = patrimonies[0].get("department");
+    for row in &patrimonies {
+        if row.get::<String, _>("status") == WRITTEN_OFF {
+            return written_off_response();
+        }
+        if row.get::<String, _>("department") != from_department {
+            return HttpResponse::BadRequest().json("All patrimonies in a batch must belong to the same department");
+        }
+    }
+
+    if !belongs_to_department(&user, &from_department) {
+        return HttpResponse::Forbidden().json("Only the source department can request this transfer");
+    }
+    if from_department == batch.to_department {
+        return HttpResponse::BadRequest().json("Cannot transfer to the same department");
+    }
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, &batch.to_department).await {
+        return response;
+    }
+
+    let batch_id = Uuid::new_v4();
+    let actor = Actor::new(&user, &req);
+
+    for row in &patrimonies {
+        let result = sqlx::query(
+            "INSERT INTO transfers (id, patrimony_id, from_department, to_department, reason, transferred_by, status, batch_id, tenant_id)
+             VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, $8)
+             RETURNING id"
+        )
+        .bind(row.get::<Uuid, _>("id"))
+        .bind(&from_department)
+        .bind(&batch.to_department)
+        .bind(&batch.reason)
+        .bind(user.id)
+        .bind(PENDING)
+        .bind(batch_id)
+        .bind(user.tenant_id)
+        .map(|row: PgRow| row.get::<Uuid, _>("id"))
+        .fetch_one(&mut *transaction)
+        .await;
+
+        let result = match result {
+            Ok(transfer_id) => audit::record_change(&mut transaction, &actor, audit::TRANSFER, transfer_id, audit::CREATE, None).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error creating transfer record: {}", e);
+            let _ = transaction.rollback().await;
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json(format!(
+                    "Patrimony {} already has an open transfer",
+                    row.get::<String, _>("plate")
+                ));
+            }
+            return HttpResponse::InternalServerError().json("Error processing transfer");
+        }
+    }
+
+    // Commit da transação
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error processing transfer");
+    }
+
+    println!("📦 Lote de {} transferência(s) solicitado: {} -> {}", patrimonies.len(), from_department, batch.to_department);
+
+    let result = sqlx::query(&format!("{} WHERE t.batch_id = $1 AND t.tenant_id = $2 ORDER BY p.plate", TRANSFER_SELECT))
+        .bind(batch_id)
+        .bind(user.tenant_id)
+        .map(|row: PgRow| transfer_json(&row))
+        .fetch_all(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(transfers) => HttpResponse::Created().json(serde_json::json!({
+            "batch_id": batch_id,
+            "transfers": transfers
+        })),
+        Err(e) => {
+            eprintln!("Error fetching created transfers: {}", e);
+            HttpResponse::InternalServerError().json("Transfer requested but error fetching details")
+        }
+    }
+}
+
+// Termo de transferência; transferências do mesmo lote saem em um termo combinado
+pub async fn get_transfer_term(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let transfer_id = id.into_inner();
+
+    let result = sqlx::query(&format!(
+        "SELECT t.id, t.batch_id, t.from_department, t.to_department, t.reason, t.status, t.transferred_at,
+                p.plate, p.name, p.description, p.value,
+                u.username as requested_by, u.company_name,
+                ua.username as approved_by, urc.username as received_by
+         FROM transfers t
+         JOIN patrimonies p ON t.patrimony_id = p.id
+         LEFT JOIN users u ON t.transferred_by = u.id
+         LEFT JOIN users ua ON t.approved_by = ua.id
+         LEFT JOIN users urc ON t.received_by = urc.id
+         WHERE t.tenant_id = $2
+           AND (t.id = $1 OR t.batch_id = (SELECT batch_id FROM transfers WHERE id = $1))
+           AND {}
+         ORDER BY p.plate",
+        visible_filter(3)
+    ))
+    .bind(transfer_id)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    let rows = match result {
+        Ok(rows) if rows.is_empty() => return HttpResponse::NotFound().json("Transfer not found"),
+        Ok(rows) => rows,
+        Err(e) => {
+            eprintln!("Error fetching transfer for term: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating transfer term");
+        }
+    };
+
+    let first = &rows[0];
+    let batch_id: Option<Uuid> = first.get("batch_id");
+    let number = batch_id.unwrap_or(transfer_id).simple().to_string()[..8].to_uppercase();
+
+    let term = TransferTermData {
+        organization: first
+            .get::<Option<String>, _>("company_name")
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_ORGANIZATION.to_string()),
+        number,
+        requested_at: first.get("transferred_at"),
+        status: first.get("status"),
+        from_department: first.get("from_department"),
+        to_department: first.get("to_department"),
+        reason: first.get::<Option<String>, _>("reason").unwrap_or_default(),
+        requested_by: first.get("requested_by"),
+        approved_by: first.get("approved_by"),
+        received_by: first.get("received_by"),
+        items: rows
+            .iter()
+            .map(|row| TermItem {
+                plate: row.get("plate"),
+                name: row.get("name"),
+                description: row.get::<Option<String>, _>("description").unwrap_or_default(),
+                value: depreciation::optional_decimal(row, "value").unwrap_or(0.0),
+            })
+            .collect(),
+    };
+
+    match terms::render_transfer_term(&term) {
+        Ok(pdf) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                "Content-Disposition",
+                format!("inline; filename=\"termo_transferencia_{}.pdf\"", term.number),
+            ))
+            .body(pdf),
+        Err(e) => {
+            eprintln!("Error rendering transfer term: {}", e);
+            HttpResponse::InternalServerError().json("Error generating transfer term")
+        }
+    }
+}
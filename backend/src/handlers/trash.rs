@@ -0,0 +1,241 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::Utc;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use super::patrimony::auth_middleware;
+use super::depreciation;
+use super::audit::{self, Actor};
+
+#[derive(Deserialize)]
+pub struct TrashQuery {
+    pub department: Option<String>,
+}
+
+// Itens da lixeira da entidade ($1) com o que impede a remoção definitiva
+const TRASH_SELECT: &str =
+    "SELECT p.id, p.plate, p.name, p.department, p.status, p.value, p.deleted_at, p.deleted_by,
+            u.username as deleted_by_name,
+            (CURRENT_DATE - p.deleted_at::date) as days_in_trash,
+            (SELECT COUNT(*) FROM transfers t WHERE t.patrimony_id = p.id) as transfer_count,
+            (p.invoice_file IS NOT NULL OR p.commitment_file IS NOT NULL OR p.denf_se_file IS NOT NULL
+             OR EXISTS (SELECT 1 FROM maintenance_attachments a JOIN maintenance_orders o ON a.order_id = o.id
+                        WHERE o.patrimony_id = p.id)) as has_documents,
+            (EXISTS (SELECT 1 FROM depreciation_entries x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM write_offs x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM custody_assignments x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM location_moves x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM maintenance_orders x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM maintenance_schedules x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM patrimony_meter_readings x WHERE x.patrimony_id = p.id)
+             OR EXISTS (SELECT 1 FROM maintenance_due_items x WHERE x.patrimony_id = p.id)) as has_history
+     FROM patrimonies p
+     LEFT JOIN users u ON p.deleted_by = u.id
+     WHERE p.tenant_id = $1 AND p.deleted_at IS NOT NULL";
+
+fn trash_json(row: &PgRow) -> serde_json::Value {
+    let transfer_count = row.get::<i64, _>("transfer_count");
+    let has_documents = row.get::<bool, _>("has_documents");
+    let has_history = row.get::<bool, _>("has_history");
+
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "plate": row.get::<String, _>("plate"),
+        "name": row.get::<String, _>("name"),
+        "department": row.get::<String, _>("department"),
+        "status": row.get::<String, _>("status"),
+        "value": depreciation::optional_decimal(row, "value"),
+        "deleted_at": row.get::<chrono::DateTime<Utc>, _>("deleted_at"),
+        "deleted_by": row.get::<Option<Uuid>, _>("deleted_by"),
+        "deleted_by_name": row.get::<Option<String>, _>("deleted_by_name"),
+        "days_in_trash": row.get::<i32, _>("days_in_trash"),
+        "transfer_count": transfer_count,
+        "has_documents": has_documents,
+        "has_history": has_history,
+        "can_purge": transfer_count == 0 && !has_documents && !has_history
+    })
+}
+
+pub async fn get_trash(
+    pool: web::Data<PgPool>,
+    query: web::Query<TrashQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(&format!(
+        "{} AND ($2::varchar IS NULL OR p.department = $2) ORDER BY p.deleted_at DESC",
+        TRASH_SELECT
+    ))
+    .bind(user.tenant_id)
+    .bind(&query.department)
+    .map(|row: PgRow| trash_json(&row))
+    .fetch_all(pool.get_ref())
+    .await
+    {
+        Ok(items) => HttpResponse::Ok().json(items),
+        Err(e) => {
+            eprintln!("Error fetching trash: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching trash")
+        }
+    }
+}
+
+pub async fn restore_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error restoring patrimony");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error restoring patrimony");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE patrimonies SET deleted_at = NULL, deleted_by = NULL, updated_at = NOW()
+         WHERE id = $1 AND tenant_id = $2 AND deleted_at IS NOT NULL"
+    )
+    .bind(patrimony_id)
+    .bind(user.tenant_id)
+    .execute(&mut *transaction)
+    .await;
+
+    match result {
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json("Patrimony not found in trash");
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Error restoring patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error restoring patrimony");
+        }
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::RESTORE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error restoring patrimony");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing restore: {}", e);
+        return HttpResponse::InternalServerError().json("Error restoring patrimony");
+    }
+
+    println!("♻️ Patrimônio {} restaurado da lixeira por {}", patrimony_id, user.username);
+    HttpResponse::Ok().json("Patrimony restored")
+}
+
+/// Remoção definitiva, só para itens da lixeira sem histórico (transferências, depreciação, custódia,
+/// movimentações, manutenção) nem documentos
+pub async fn purge_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error purging patrimony");
+        }
+    };
+
+    let item = sqlx::query(&format!("{} AND p.id = $2 FOR UPDATE OF p", TRASH_SELECT))
+        .bind(user.tenant_id)
+        .bind(patrimony_id)
+        .fetch_optional(&mut *transaction)
+        .await;
+
+    match item {
+        Ok(Some(row)) => {
+            if row.get::<i64, _>("transfer_count") > 0 {
+                let _ = transaction.rollback().await;
+                return HttpResponse::Conflict().json("Patrimony has transfer history and cannot be purged");
+            }
+            if row.get::<bool, _>("has_documents") {
+                let _ = transaction.rollback().await;
+                return HttpResponse::Conflict().json("Patrimony has attached documents and cannot be purged");
+            }
+            // Depreciação, baixa, custódia, movimentações e manutenção seriam apagadas em cascata
+            if row.get::<bool, _>("has_history") {
+                let _ = transaction.rollback().await;
+                return HttpResponse::Conflict().json("Patrimony has depreciation, custody, location or maintenance history and cannot be purged");
+            }
+        }
+        Ok(None) => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::NotFound().json("Patrimony not found in trash");
+        }
+        Err(e) => {
+            eprintln!("Error fetching trashed patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error purging patrimony");
+        }
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error purging patrimony");
+        }
+    };
+
+    if let Err(e) = sqlx::query("DELETE FROM patrimonies WHERE id = $1")
+        .bind(patrimony_id)
+        .execute(&mut *transaction)
+        .await
+    {
+        eprintln!("Error purging patrimony: {}", e);
+        return HttpResponse::InternalServerError().json("Error purging patrimony");
+    }
+
+    // O registro de auditoria guarda o último estado do bem removido
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::PURGE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error purging patrimony");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing purge: {}", e);
+        return HttpResponse::InternalServerError().json("Error purging patrimony");
+    }
+
+    println!("🗑️ Patrimônio {} removido definitivamente por {}", patrimony_id, user.username);
+    HttpResponse::Ok().json("Patrimony purged")
+}
@@ -0,0 +1,695 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Postgres, Row, Transaction};
+use sqlx::postgres::PgRow;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::Deserialize;
+use bcrypt::verify;
+use std::env;
+use uuid::Uuid;
+
+use super::patrimony::{auth_middleware, get_user_by_id, LoginResponse, User};
+use super::audit::{self, Actor};
+use super::login_security;
+use super::session;
+
+type HmacSha1 = Hmac<Sha1>;
+
+// RFC 6238 com os parâmetros que os aplicativos autenticadores aceitam por padrão
+const STEP_SECONDS: i64 = 30;
+const DIGITS: u32 = 6;
+const SKEW_STEPS: i64 = 1;              // Tolerância de relógio: um passo para cada lado
+const SECRET_BYTES: usize = 20;
+const DEFAULT_ISSUER: &str = "Patrimonio";
+
+const RECOVERY_CODE_COUNT: usize = 10;
+const CHALLENGE_MINUTES: i64 = 5;
+const CHALLENGE_MAX_ATTEMPTS: i32 = 5;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Deserialize)]
+pub struct TwoFactorLogin {
+    pub challenge_token: String,
+    pub code: String,                   // Código do aplicativo ou código de recuperação
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorCode {
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct DisableTwoFactor {
+    pub password: String,
+    pub code: String,
+}
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            output.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        output.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    output
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').chars() {
+        let value = BASE32_ALPHABET.iter().position(|a| *a as char == c.to_ascii_uppercase())? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push(((buffer >> bits) & 0xff) as u8);
+        }
+    }
+    Some(output)
+}
+
+// HOTP (RFC 4226) com truncamento dinâmico
+fn hotp(secret: &[u8], counter: u64) -> u32 {
+    let mut mac = HmacSha1::new_from_slice(secret).expect("HMAC aceita chave de qualquer tamanho");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([digest[offset] & 0x7f, digest[offset + 1], digest[offset + 2], digest[offset + 3]]);
+    binary % 10_u32.pow(DIGITS)
+}
+
+/// Passo do código que confere, dentro da tolerância e depois do último passo aceito
+fn matching_step(secret: &str, code: &str, last_step: Option<i64>) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let code: u32 = code.parse().ok()?;
+    let secret = base32_decode(secret)?;
+    let current = Utc::now().timestamp() / STEP_SECONDS;
+
+    (current - SKEW_STEPS..=current + SKEW_STEPS)
+        .filter(|step| last_step.is_none_or(|last| *step > last))
+        .find(|step| hotp(&secret, *step as u64) == code)
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// URI otpauth:// lida pelos aplicativos autenticadores (TOTP_ISSUER identifica o sistema)
+fn provisioning_uri(secret: &str, username: &str) -> String {
+    let issuer = percent_encode(&env::var("TOTP_ISSUER").unwrap_or_else(|_| DEFAULT_ISSUER.to_string()));
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        issuer,
+        percent_encode(username),
+        secret,
+        issuer,
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+// QR code em SVG para a tela de ativação, com a margem de 4 módulos exigida pelos leitores
+fn qr_svg(data: &str) -> Result<String, String> {
+    let code = QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| e.to_string())?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let size = modules + 8;
+
+    let mut path = String::new();
+    for row in 0..modules {
+        for column in 0..modules {
+            if colors[row * modules + column] == Color::Dark {
+                path.push_str(&format!("M{} {}h1v1h-1z", column + 4, row + 4));
+            }
+        }
+    }
+
+    Ok(format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {0} {0}\" shape-rendering=\"crispEdges\">\
+         <rect width=\"{0}\" height=\"{0}\" fill=\"#fff\"/><path d=\"{1}\" fill=\"#000\"/></svg>",
+        size, path
+    ))
+}
+
+// Códigos de recuperação são comparados sem hífens, espaços ou maiúsculas
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Troca os códigos de recuperação do usuário; os novos só aparecem nesta resposta
+async fn replace_recovery_codes(transaction: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM two_factor_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **transaction)
+        .await?;
+
+    let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+    for _ in 0..RECOVERY_CODE_COUNT {
+        let raw = session::random_token(5);
+        sqlx::query("INSERT INTO two_factor_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(session::hash_token(&raw))
+            .execute(&mut **transaction)
+            .await?;
+        codes.push(format!("{}-{}", &raw[..5], &raw[5..]));
+    }
+    Ok(codes)
+}
+
+/// Desliga o 2FA e apaga segredo e códigos de recuperação
+pub async fn clear(transaction: &mut Transaction<'_, Postgres>, user_id: Uuid) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE users SET totp_secret = NULL, totp_enabled = FALSE, totp_enabled_at = NULL, totp_last_step = NULL, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(user_id)
+    .execute(&mut **transaction)
+    .await?;
+
+    sqlx::query("DELETE FROM two_factor_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut **transaction)
+        .await
+        .map(|_| ())
+}
+
+/// Confere o segundo fator (código TOTP ou de recuperação, que é consumido).
+/// Devolve o meio usado, ou None se o código não confere
+async fn verify_second_factor(
+    transaction: &mut Transaction<'_, Postgres>,
+    user_id: Uuid,
+    code: &str,
+) -> Result<Option<&'static str>, sqlx::Error> {
+    let (secret, last_step) = sqlx::query("SELECT totp_secret, totp_last_step FROM users WHERE id = $1 AND totp_enabled FOR UPDATE")
+        .bind(user_id)
+        .map(|row: PgRow| (row.get::<Option<String>, _>("totp_secret"), row.get::<Option<i64>, _>("totp_last_step")))
+        .fetch_optional(&mut **transaction)
+        .await?
+        .unwrap_or((None, None));
+    let Some(secret) = secret else {
+        return Ok(None);
+    };
+
+    if let Some(step) = matching_step(&secret, code, last_step) {
+        sqlx::query("UPDATE users SET totp_last_step = $2 WHERE id = $1")
+            .bind(user_id)
+            .bind(step)
+            .execute(&mut **transaction)
+            .await?;
+        return Ok(Some("totp"));
+    }
+
+    let used = sqlx::query(
+        "UPDATE two_factor_recovery_codes SET used_at = NOW()
+         WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+    )
+    .bind(user_id)
+    .bind(session::hash_token(&normalize_recovery_code(code)))
+    .execute(&mut **transaction)
+    .await?;
+
+    Ok((used.rows_affected() > 0).then_some("recovery_code"))
+}
+
+/// Primeira etapa aceita (senha correta): devolve o desafio que o código precisa acompanhar
+pub async fn challenge_response(pool: &PgPool, user: &User) -> HttpResponse {
+    let token = session::random_token(32);
+    let result = sqlx::query("INSERT INTO two_factor_challenges (token_hash, user_id, expires_at) VALUES ($1, $2, $3)")
+        .bind(session::hash_token(&token))
+        .bind(user.id)
+        .bind(Utc::now() + Duration::minutes(CHALLENGE_MINUTES))
+        .execute(pool)
+        .await;
+
+    match result {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "two_factor_required": true,
+            "challenge_token": token,
+            "expires_in": CHALLENGE_MINUTES * 60
+        })),
+        Err(e) => {
+            eprintln!("Error creating two-factor challenge: {}", e);
+            HttpResponse::InternalServerError().json("Error during login")
+        }
+    }
+}
+
+// Rota pública: segunda etapa do login
+pub async fn verify_login(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorLogin>,
+    req: HttpRequest,
+) -> HttpResponse {
+    match login_security::ip_retry_after(pool.get_ref(), &req).await {
+        Ok(0) => {}
+        Ok(seconds) => return login_security::too_many_attempts(seconds),
+        Err(e) => {
+            eprintln!("Error checking login attempts: {}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    };
+
+    let challenge = sqlx::query(
+        "SELECT user_id FROM two_factor_challenges
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+         FOR UPDATE"
+    )
+    .bind(session::hash_token(&body.challenge_token))
+    .map(|row: PgRow| row.get::<Uuid, _>("user_id"))
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let user_id = match challenge {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return HttpResponse::Unauthorized().json("Invalid or expired two-factor challenge"),
+        Err(e) => {
+            eprintln!("Error fetching two-factor challenge: {}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    };
+
+    let user = match get_user_by_id(pool.get_ref(), user_id).await {
+        Ok((_, false)) => return HttpResponse::Forbidden().json("Tenant is inactive"),
+        Ok((user, _)) if !user.active => return HttpResponse::Forbidden().json("User is deactivated"),
+        Ok((user, true)) => user,
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    };
+    if let Some(locked_until) = user.locked_until.filter(|until| *until > Utc::now()) {
+        login_security::record_attempt(pool.get_ref(), &user.username, Some(&user), &req, Some(login_security::LOCKED)).await;
+        return login_security::account_locked(locked_until);
+    }
+
+    let method = match verify_second_factor(&mut transaction, user.id, &body.code).await {
+        Ok(method) => method,
+        Err(e) => {
+            eprintln!("Error verifying two-factor code: {}", e);
+            return HttpResponse::InternalServerError().json("Error during login");
+        }
+    };
+
+    // Código errado: o desafio aceita poucas tentativas e a falha conta para o bloqueio da conta
+    let Some(method) = method else {
+        let result = sqlx::query(
+            "UPDATE two_factor_challenges SET
+                attempts = attempts + 1,
+                used_at = CASE WHEN attempts + 1 >= $2 THEN NOW() ELSE used_at END
+             WHERE token_hash = $1"
+        )
+        .bind(session::hash_token(&body.challenge_token))
+        .bind(CHALLENGE_MAX_ATTEMPTS)
+        .execute(&mut *transaction)
+        .await;
+        if let Err(e) = result {
+            eprintln!("Error updating two-factor challenge: {}", e);
+        }
+        if let Err(e) = transaction.commit().await {
+            eprintln!("Error committing two-factor challenge: {}", e);
+        }
+
+        login_security::record_attempt(pool.get_ref(), &user.username, Some(&user), &req, Some(login_security::INVALID_TWO_FACTOR)).await;
+        return match login_security::register_failure(pool.get_ref(), user.id).await {
+            Ok(Some(locked_until)) => {
+                println!("🔒 Conta '{}' bloqueada por tentativas de login erradas", user.username);
+                login_security::account_locked(locked_until)
+            }
+            Ok(None) => HttpResponse::Unauthorized().json("Invalid two-factor code"),
+            Err(e) => {
+                eprintln!("Error registering login failure: {}", e);
+                HttpResponse::Unauthorized().json("Invalid two-factor code")
+            }
+        };
+    };
+
+    let result = sqlx::query("UPDATE two_factor_challenges SET used_at = NOW() WHERE token_hash = $1")
+        .bind(session::hash_token(&body.challenge_token))
+        .execute(&mut *transaction)
+        .await;
+    if let Err(e) = result {
+        eprintln!("Error updating two-factor challenge: {}", e);
+        return HttpResponse::InternalServerError().json("Error during login");
+    }
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing two-factor login: {}", e);
+        return HttpResponse::InternalServerError().json("Error during login");
+    }
+
+    if method == "recovery_code" {
+        println!("🔐 Código de recuperação usado no login de '{}'", user.username);
+    }
+    if let Err(e) = login_security::register_success(pool.get_ref(), user.id).await {
+        eprintln!("Error resetting login failures: {}", e);
+    }
+    login_security::record_attempt(pool.get_ref(), &user.username, Some(&user), &req, None).await;
+
+    match session::start_session(pool.get_ref(), &user, &req).await {
+        Ok(tokens) => HttpResponse::Ok().json(LoginResponse {
+            token: tokens.token,
+            refresh_token: tokens.refresh_token,
+            expires_in: tokens.expires_in,
+            user,
+        }),
+        Err(response) => response,
+    }
+}
+
+// Gera um novo segredo (ainda desativado) e devolve a URI e o QR code para o aplicativo
+pub async fn setup_two_factor(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if user.two_factor_enabled {
+        return HttpResponse::BadRequest().json("Two-factor authentication is already enabled");
+    }
+
+    let mut secret = vec![0u8; SECRET_BYTES];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut secret);
+    let secret = base32_encode(&secret);
+
+    let result = sqlx::query("UPDATE users SET totp_secret = $2, totp_last_step = NULL WHERE id = $1 AND NOT totp_enabled")
+        .bind(user.id)
+        .bind(&secret)
+        .execute(pool.get_ref())
+        .await;
+    if let Err(e) = result {
+        eprintln!("Error saving two-factor secret: {}", e);
+        return HttpResponse::InternalServerError().json("Error setting up two-factor authentication");
+    }
+
+    let uri = provisioning_uri(&secret, &user.username);
+    let qr = match qr_svg(&uri) {
+        Ok(qr) => qr,
+        Err(e) => {
+            eprintln!("Error generating QR code: {}", e);
+            return HttpResponse::InternalServerError().json("Error setting up two-factor authentication");
+        }
+    };
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "secret": secret,
+        "otpauth_uri": uri,
+        "qr_svg": qr
+    }))
+}
+
+// Ativa o 2FA depois de conferir um código do aplicativo e devolve os códigos de recuperação
+pub async fn enable_two_factor(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorCode>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+        }
+    };
+
+    let pending = sqlx::query("SELECT totp_secret, totp_enabled FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user.id)
+        .map(|row: PgRow| (row.get::<Option<String>, _>("totp_secret"), row.get::<bool, _>("totp_enabled")))
+        .fetch_one(&mut *transaction)
+        .await;
+
+    let secret = match pending {
+        Ok((_, true)) => return HttpResponse::BadRequest().json("Two-factor authentication is already enabled"),
+        Ok((Some(secret), false)) => secret,
+        Ok((None, false)) => return HttpResponse::BadRequest().json("Start the two-factor setup first"),
+        Err(e) => {
+            eprintln!("Error fetching two-factor secret: {}", e);
+            return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+        }
+    };
+    let Some(step) = matching_step(&secret, &body.code, None) else {
+        return HttpResponse::BadRequest().json("Invalid two-factor code");
+    };
+
+    let before = match audit::snapshot(&mut *transaction, audit::USER, user.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET totp_enabled = TRUE, totp_enabled_at = NOW(), totp_last_step = $2, updated_at = NOW() WHERE id = $1"
+    )
+    .bind(user.id)
+    .bind(step)
+    .execute(&mut *transaction)
+    .await;
+
+    let codes = match result {
+        Ok(_) => replace_recovery_codes(&mut transaction, user.id).await,
+        Err(e) => Err(e),
+    };
+    let codes = match codes {
+        Ok(codes) => codes,
+        Err(e) => {
+            eprintln!("Error enabling two-factor authentication: {}", e);
+            return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+        }
+    };
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::ENABLE_TWO_FACTOR, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing two-factor activation: {}", e);
+        return HttpResponse::InternalServerError().json("Error enabling two-factor authentication");
+    }
+
+    println!("🔐 2FA ativado por '{}'", user.username);
+
+    HttpResponse::Ok().json(serde_json::json!({ "recovery_codes": codes }))
+}
+
+// Desativa o 2FA com a senha e um código; não vale para papéis em que ele é obrigatório
+pub async fn disable_two_factor(
+    pool: web::Data<PgPool>,
+    body: web::Json<DisableTwoFactor>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if user.two_factor_required {
+        return HttpResponse::Forbidden().json("Two-factor authentication is mandatory for your role");
+    }
+    if !user.two_factor_enabled {
+        return HttpResponse::BadRequest().json("Two-factor authentication is not enabled");
+    }
+
+    let password_hash = match sqlx::query("SELECT password_hash FROM users WHERE id = $1")
+        .bind(user.id)
+        .map(|row: PgRow| row.get::<String, _>("password_hash"))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(password_hash) => password_hash,
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+        }
+    };
+    if !verify(&body.password, &password_hash).unwrap_or(false) {
+        return HttpResponse::BadRequest().json("Current password is incorrect");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+        }
+    };
+    match verify_second_factor(&mut transaction, user.id, &body.code).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::BadRequest().json("Invalid two-factor code"),
+        Err(e) => {
+            eprintln!("Error verifying two-factor code: {}", e);
+            return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+        }
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::USER, user.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+        }
+    };
+
+    if let Err(e) = clear(&mut transaction, user.id).await {
+        eprintln!("Error disabling two-factor authentication: {}", e);
+        return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, user.id, audit::DISABLE_TWO_FACTOR, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing two-factor deactivation: {}", e);
+        return HttpResponse::InternalServerError().json("Error disabling two-factor authentication");
+    }
+
+    println!("🔓 2FA desativado por '{}'", user.username);
+
+    HttpResponse::Ok().json("Two-factor authentication disabled successfully")
+}
+
+// Gera novos códigos de recuperação (os anteriores deixam de valer)
+pub async fn regenerate_recovery_codes(
+    pool: web::Data<PgPool>,
+    body: web::Json<TwoFactorCode>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if !user.two_factor_enabled {
+        return HttpResponse::BadRequest().json("Two-factor authentication is not enabled");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating recovery codes");
+        }
+    };
+    match verify_second_factor(&mut transaction, user.id, &body.code).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::BadRequest().json("Invalid two-factor code"),
+        Err(e) => {
+            eprintln!("Error verifying two-factor code: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating recovery codes");
+        }
+    }
+
+    let codes = match replace_recovery_codes(&mut transaction, user.id).await {
+        Ok(codes) => codes,
+        Err(e) => {
+            eprintln!("Error generating recovery codes: {}", e);
+            return HttpResponse::InternalServerError().json("Error generating recovery codes");
+        }
+    };
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing recovery codes: {}", e);
+        return HttpResponse::InternalServerError().json("Error generating recovery codes");
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({ "recovery_codes": codes }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn hotp_matches_rfc_4226_vectors() {
+        let expected = [755224, 287082, 359152, 969429, 338314, 254676, 287922, 162583, 399871, 520489];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(hotp(RFC_SECRET, counter as u64), *code, "counter {}", counter);
+        }
+    }
+
+    #[test]
+    fn base32_encodes_rfc_secret() {
+        assert_eq!(base32_encode(RFC_SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+        assert_eq!(base32_decode("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ").unwrap(), RFC_SECRET);
+    }
+
+    #[test]
+    fn base32_round_trips_any_length() {
+        for length in 0..=SECRET_BYTES {
+            let bytes: Vec<u8> = (0..length).map(|i| (i * 37 + 11) as u8).collect();
+            assert_eq!(base32_decode(&base32_encode(&bytes)).unwrap(), bytes, "length {}", length);
+        }
+    }
+
+    #[test]
+    fn base32_decode_accepts_lowercase_and_padding() {
+        assert_eq!(base32_decode("my======").unwrap(), b"f");
+        assert_eq!(base32_decode("mzxw6ytboi").unwrap(), b"foobar");
+        assert!(base32_decode("MZXW1").is_none());
+    }
+
+    #[test]
+    fn matching_step_accepts_current_code_once() {
+        let secret = base32_encode(RFC_SECRET);
+        let step = Utc::now().timestamp() / STEP_SECONDS;
+        let code = format!("{:06}", hotp(RFC_SECRET, step as u64));
+
+        let matched = matching_step(&secret, &code, None).unwrap();
+        assert!((step - SKEW_STEPS..=step + SKEW_STEPS).contains(&matched));
+        assert_eq!(matching_step(&secret, &code, Some(matched)), None);
+        assert_eq!(matching_step(&secret, "12345", None), None);
+    }
+}
@@ -0,0 +1,730 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+use bcrypt::{hash, DEFAULT_COST};
+
+use super::patrimony::{auth_middleware, user_from_row, CreateUser, User, USER_COLUMNS};
+use super::department::validate_department;
+use super::audit::{self, Actor};
+use super::permissions;
+use super::session;
+use super::password_policy::{self, PasswordPolicy};
+use super::two_factor;
+
+// Prazo para aceitar o convite
+const INVITATION_DAYS: i64 = 7;
+
+#[derive(Deserialize)]
+pub struct UpdateUser {
+    pub company_name: Option<String>,
+    pub email: Option<String>,
+    pub department: Option<String>,
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPassword {
+    pub temporary_password: Option<String>,     // Sem ela, uma senha é gerada e devolvida
+}
+
+#[derive(Deserialize)]
+pub struct CreateInvitation {
+    pub email: Option<String>,
+    pub department: String,
+    pub role: Option<String>,
+}
+
+async fn load_user(pool: &PgPool, tenant_id: Uuid, user_id: Uuid) -> Result<User, HttpResponse> {
+    match sqlx::query(&format!("SELECT {} FROM users WHERE id = $1 AND tenant_id = $2", USER_COLUMNS))
+        .bind(user_id)
+        .bind(tenant_id)
+        .map(|row: PgRow| user_from_row(&row))
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(user)) => Ok(user),
+        Ok(None) => Err(HttpResponse::NotFound().json("User not found")),
+        Err(e) => {
+            eprintln!("Error fetching user: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error fetching user"))
+        }
+    }
+}
+
+// Só o superadministrador mexe na conta de outro superadministrador
+fn ensure_manageable(user: &User, target: &User) -> Result<(), HttpResponse> {
+    if target.is_super_admin && !user.is_super_admin {
+        return Err(HttpResponse::Forbidden().json("Super admin access required"));
+    }
+    Ok(())
+}
+
+/// O papel precisa existir e não pode dar permissões que quem atribui não tem
+async fn ensure_assignable_role(pool: &PgPool, user: &User, role: &str) -> Result<(), HttpResponse> {
+    let result = sqlx::query(
+        "SELECT ARRAY(SELECT permission FROM role_permissions WHERE role = r.code)::text[] as permissions
+         FROM roles r WHERE r.code = $1"
+    )
+    .bind(role)
+    .map(|row: PgRow| row.get::<Vec<String>, _>("permissions"))
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(granted)) => {
+            if granted.iter().any(|permission| !user.can(permission)) {
+                return Err(HttpResponse::Forbidden().json(format!("Cannot assign role '{}' with permissions you do not have", role)));
+            }
+            Ok(())
+        }
+        Ok(None) => Err(HttpResponse::BadRequest().json(format!("Unknown role '{}'", role))),
+        Err(e) => {
+            eprintln!("Error fetching role: {}", e);
+            Err(HttpResponse::InternalServerError().json("Error validating role"))
+        }
+    }
+}
+
+pub async fn get_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => HttpResponse::Ok().json(target),
+        Err(response) => response,
+    }
+}
+
+pub async fn create_user(
+    pool: web::Data<PgPool>,
+    user_data: web::Json<CreateUser>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if user_data.username.trim().is_empty() || user_data.password.is_empty() {
+        return HttpResponse::BadRequest().json("Username and password are required");
+    }
+    if let Err(response) = password_policy::validate(&user_data.password, &user_data.username) {
+        return response;
+    }
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, &user_data.department).await {
+        return response;
+    }
+    let role = user_data.role.clone().unwrap_or_else(|| permissions::DEFAULT_ROLE.to_string());
+    if let Err(response) = ensure_assignable_role(pool.get_ref(), &user, &role).await {
+        return response;
+    }
+
+    let password_hash = match hash(&user_data.password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error creating user");
+        }
+    };
+
+    // Senha definida pelo administrador é provisória
+    let result = sqlx::query(&format!(
+        "INSERT INTO users (id, company_name, department, username, password_hash, email, role, tenant_id, must_change_password)
+         VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6, $7, TRUE)
+         RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(&user_data.company_name)
+    .bind(&user_data.department)
+    .bind(user_data.username.trim())
+    .bind(password_hash)
+    .bind(&user_data.email)
+    .bind(&role)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| user_from_row(&row))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let created = match result {
+        Ok(created) => created,
+        Err(e) => {
+            eprintln!("Error creating user: {}", e);
+            if e.to_string().contains("duplicate key") {
+                return HttpResponse::Conflict().json("Username already exists");
+            }
+            return HttpResponse::InternalServerError().json("Error creating user");
+        }
+    };
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, created.id, audit::CREATE, None).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error creating user");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing user: {}", e);
+        return HttpResponse::InternalServerError().json("Error creating user");
+    }
+
+    println!("👤 Usuário '{}' criado por {}", created.username, user.username);
+    HttpResponse::Created().json(created)
+}
+
+pub async fn update_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    user_data: web::Json<UpdateUser>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let target = match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_manageable(&user, &target) {
+        return response;
+    }
+
+    if let Some(department) = &user_data.department {
+        if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, department).await {
+            return response;
+        }
+    }
+    if let Some(role) = &user_data.role {
+        // Trocar o próprio papel poderia tirar de quem gerencia o acesso à gestão de usuários
+        if target.id == user.id && *role != user.role {
+            return HttpResponse::BadRequest().json("Cannot change your own role");
+        }
+        if let Err(response) = ensure_assignable_role(pool.get_ref(), &user, role).await {
+            return response;
+        }
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating user");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, target.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating user");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET
+            company_name = COALESCE($1, company_name),
+            email = COALESCE($2, email),
+            department = COALESCE($3, department),
+            role = COALESCE($4, role),
+            updated_at = NOW()
+         WHERE id = $5"
+    )
+    .bind(&user_data.company_name)
+    .bind(&user_data.email)
+    .bind(&user_data.department)
+    .bind(&user_data.role)
+    .bind(target.id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error updating user: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, target.id, audit::UPDATE, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing user update: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    match load_user(pool.get_ref(), user.tenant_id, target.id).await {
+        Ok(updated) => HttpResponse::Ok().json(updated),
+        Err(response) => response,
+    }
+}
+
+// Ativa ou desativa a conta; desativar derruba todas as sessões
+async fn set_active(pool: web::Data<PgPool>, id: web::Path<Uuid>, req: HttpRequest, active: bool) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let target = match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_manageable(&user, &target) {
+        return response;
+    }
+    if !active && target.id == user.id {
+        return HttpResponse::BadRequest().json("Cannot deactivate your own account");
+    }
+    if target.active == active {
+        let state = if active { "active" } else { "deactivated" };
+        return HttpResponse::BadRequest().json(format!("User is already {}", state));
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating user");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, target.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error updating user");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET active = $2, deactivated_at = CASE WHEN $2 THEN NULL ELSE NOW() END, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(target.id)
+    .bind(active)
+    .execute(&mut *transaction)
+    .await;
+
+    let result = match result {
+        Ok(_) if !active => session::revoke_user_sessions(&mut *transaction, target.id, session::DEACTIVATED).await.map(|_| ()),
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Error updating user status: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    let action = if active { audit::REACTIVATE } else { audit::DEACTIVATE };
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, target.id, action, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing user status: {}", e);
+        return HttpResponse::InternalServerError().json("Error updating user");
+    }
+
+    println!("👤 Usuário '{}' {} por {}", target.username, if active { "reativado" } else { "desativado" }, user.username);
+
+    match load_user(pool.get_ref(), user.tenant_id, target.id).await {
+        Ok(updated) => HttpResponse::Ok().json(updated),
+        Err(response) => response,
+    }
+}
+
+pub async fn deactivate_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    set_active(pool, id, req, false).await
+}
+
+pub async fn reactivate_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    set_active(pool, id, req, true).await
+}
+
+/// Libera a conta bloqueada por tentativas de login erradas e zera o atraso
+pub async fn unlock_user(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let target = match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_manageable(&user, &target) {
+        return response;
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error unlocking user");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, target.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error unlocking user");
+        }
+    };
+
+    let result = sqlx::query(
+        "UPDATE users SET failed_login_count = 0, last_failed_login_at = NULL, locked_until = NULL, updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(target.id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error unlocking user: {}", e);
+        return HttpResponse::InternalServerError().json("Error unlocking user");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, target.id, audit::UNLOCK, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error unlocking user");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing user unlock: {}", e);
+        return HttpResponse::InternalServerError().json("Error unlocking user");
+    }
+
+    println!("🔓 Conta '{}' desbloqueada por {}", target.username, user.username);
+
+    match load_user(pool.get_ref(), user.tenant_id, target.id).await {
+        Ok(updated) => HttpResponse::Ok().json(updated),
+        Err(response) => response,
+    }
+}
+
+/// Desliga o 2FA de quem perdeu o aparelho e os códigos de recuperação; as sessões são encerradas
+pub async fn reset_two_factor(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let target = match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_manageable(&user, &target) {
+        return response;
+    }
+    if !target.two_factor_enabled {
+        return HttpResponse::BadRequest().json("Two-factor authentication is not enabled");
+    }
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting two-factor authentication");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, target.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting two-factor authentication");
+        }
+    };
+
+    let result = match two_factor::clear(&mut transaction, target.id).await {
+        Ok(_) => session::revoke_user_sessions(&mut *transaction, target.id, session::ADMIN_REVOKED).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Error resetting two-factor authentication: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting two-factor authentication");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, target.id, audit::RESET_TWO_FACTOR, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting two-factor authentication");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing two-factor reset: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting two-factor authentication");
+    }
+
+    println!("🔓 2FA de '{}' redefinido por {}", target.username, user.username);
+
+    match load_user(pool.get_ref(), user.tenant_id, target.id).await {
+        Ok(updated) => HttpResponse::Ok().json(updated),
+        Err(response) => response,
+    }
+}
+
+/// Define uma senha temporária, exige a troca no próximo acesso e derruba as sessões
+pub async fn reset_password(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    body: web::Json<ResetPassword>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let target = match load_user(pool.get_ref(), user.tenant_id, id.into_inner()).await {
+        Ok(target) => target,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_manageable(&user, &target) {
+        return response;
+    }
+
+    let generated = body.temporary_password.is_none();
+    let temporary_password = match &body.temporary_password {
+        Some(password) => {
+            if let Err(response) = password_policy::validate(password, &target.username) {
+                return response;
+            }
+            if let Err(response) = password_policy::ensure_not_reused(pool.get_ref(), target.id, password).await {
+                return response;
+            }
+            password.clone()
+        }
+        None => PasswordPolicy::from_env().generate(),
+    };
+    let password_hash = match hash(&temporary_password, DEFAULT_COST) {
+        Ok(hash) => hash,
+        Err(_) => return HttpResponse::InternalServerError().json("Error hashing password"),
+    };
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+    let before = match audit::snapshot(&mut *transaction, audit::USER, target.id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching user for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error resetting password");
+        }
+    };
+
+    let result = match password_policy::replace_password(&mut transaction, target.id, &password_hash, true).await {
+        Ok(_) => session::revoke_user_sessions(&mut *transaction, target.id, session::PASSWORD_RESET).await.map(|_| ()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("Error resetting password: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::USER, target.id, audit::RESET_PASSWORD, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing password reset: {}", e);
+        return HttpResponse::InternalServerError().json("Error resetting password");
+    }
+
+    println!("🔑 Senha do usuário '{}' redefinida por {}", target.username, user.username);
+
+    // A senha gerada só aparece aqui; a informada pelo administrador não é devolvida
+    HttpResponse::Ok().json(serde_json::json!({
+        "user_id": target.id,
+        "must_change_password": true,
+        "temporary_password": if generated { Some(temporary_password) } else { None }
+    }))
+}
+
+const INVITATION_SELECT: &str =
+    "SELECT i.id, i.email, i.department, i.role, i.invited_by, u.username as invited_by_name,
+            i.created_at, i.expires_at, i.accepted_at, i.accepted_user_id, i.revoked_at
+     FROM user_invitations i
+     LEFT JOIN users u ON u.id = i.invited_by";
+
+fn invitation_json(row: &PgRow) -> serde_json::Value {
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "email": row.get::<Option<String>, _>("email"),
+        "department": row.get::<String, _>("department"),
+        "role": row.get::<String, _>("role"),
+        "invited_by": row.get::<Option<Uuid>, _>("invited_by"),
+        "invited_by_name": row.get::<Option<String>, _>("invited_by_name"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at"),
+        "expires_at": row.get::<chrono::DateTime<Utc>, _>("expires_at"),
+        "accepted_at": row.get::<Option<chrono::DateTime<Utc>>, _>("accepted_at"),
+        "accepted_user_id": row.get::<Option<Uuid>, _>("accepted_user_id"),
+        "revoked_at": row.get::<Option<chrono::DateTime<Utc>>, _>("revoked_at")
+    })
+}
+
+pub async fn get_invitations(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(&format!("{} WHERE i.tenant_id = $1 ORDER BY i.created_at DESC", INVITATION_SELECT))
+        .bind(user.tenant_id)
+        .map(|row: PgRow| invitation_json(&row))
+        .fetch_all(pool.get_ref())
+        .await
+    {
+        Ok(invitations) => HttpResponse::Ok().json(invitations),
+        Err(e) => {
+            eprintln!("Error fetching invitations: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching invitations")
+        }
+    }
+}
+
+/// Gera o convite de cadastro; o token só é devolvido nesta resposta
+pub async fn create_invitation(
+    pool: web::Data<PgPool>,
+    invitation: web::Json<CreateInvitation>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    if let Err(response) = validate_department(pool.get_ref(), user.tenant_id, &invitation.department).await {
+        return response;
+    }
+    let role = invitation.role.clone().unwrap_or_else(|| permissions::DEFAULT_ROLE.to_string());
+    if let Err(response) = ensure_assignable_role(pool.get_ref(), &user, &role).await {
+        return response;
+    }
+
+    let token = session::random_token(32);
+    let result = sqlx::query(
+        "INSERT INTO user_invitations (tenant_id, token_hash, email, department, role, invited_by, expires_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id"
+    )
+    .bind(user.tenant_id)
+    .bind(session::hash_token(&token))
+    .bind(&invitation.email)
+    .bind(&invitation.department)
+    .bind(&role)
+    .bind(user.id)
+    .bind(Utc::now() + Duration::days(INVITATION_DAYS))
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(pool.get_ref())
+    .await;
+
+    let invitation_id = match result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating invitation: {}", e);
+            return HttpResponse::InternalServerError().json("Error creating invitation");
+        }
+    };
+
+    println!("✉️ Convite para o departamento '{}' criado por {}", invitation.department, user.username);
+
+    match sqlx::query(&format!("{} WHERE i.id = $1", INVITATION_SELECT))
+        .bind(invitation_id)
+        .map(|row: PgRow| invitation_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(mut created) => {
+            created["token"] = serde_json::json!(token);
+            HttpResponse::Created().json(created)
+        }
+        Err(e) => {
+            eprintln!("Error fetching invitation: {}", e);
+            HttpResponse::InternalServerError().json("Invitation created but error fetching details")
+        }
+    }
+}
+
+pub async fn revoke_invitation(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    match sqlx::query(
+        "UPDATE user_invitations SET revoked_at = NOW()
+         WHERE id = $1 AND tenant_id = $2 AND accepted_at IS NULL AND revoked_at IS NULL"
+    )
+    .bind(id.into_inner())
+    .bind(user.tenant_id)
+    .execute(pool.get_ref())
+    .await
+    {
+        Ok(result) if result.rows_affected() == 0 => HttpResponse::NotFound().json("Pending invitation not found"),
+        Ok(_) => HttpResponse::Ok().json("Invitation revoked successfully"),
+        Err(e) => {
+            eprintln!("Error revoking invitation: {}", e);
+            HttpResponse::InternalServerError().json("Error revoking invitation")
+        }
+    }
+}
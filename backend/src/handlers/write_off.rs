@@ -0,0 +1,455 @@
+use actix_web::{web, HttpResponse, HttpRequest};
+use actix_multipart::Multipart;
+use sqlx::{PgPool, Row};
+use sqlx::postgres::PgRow;
+use sqlx::types::BigDecimal;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+use std::fs;
+use std::path::Path;
+use futures_util::TryStreamExt;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+
+use super::patrimony::{auth_middleware, ensure_visible};
+use super::depreciation::{self, DepreciationPolicy};
+use super::custody;
+use super::maintenance;
+use super::audit::{self, Actor};
+use super::department_grant;
+
+pub const WRITTEN_OFF: &str = "written_off";
+
+// Motivos de baixa aceitos
+pub const WRITE_OFF_REASONS: [&str; 5] = ["obsolescence", "loss", "theft", "donation", "auction"];
+
+#[derive(Deserialize)]
+pub struct CreateWriteOff {
+    pub reason: String,
+    pub act_number: String,
+    pub write_off_date: Option<NaiveDate>,
+    pub notes: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct WriteOffQuery {
+    pub reason: Option<String>,
+    pub department: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+const WRITE_OFF_SELECT: &str =
+    "SELECT w.id, w.patrimony_id, p.plate, p.name as patrimony_name, p.department, w.reason, w.act_number, w.write_off_date,
+            w.notes, w.document_file, w.book_value, w.previous_status, w.approved_by, u.username as approved_by_name, w.created_at
+     FROM write_offs w
+     JOIN patrimonies p ON w.patrimony_id = p.id
+     LEFT JOIN users u ON w.approved_by = u.id";
+
+fn write_off_json(row: &PgRow) -> serde_json::Value {
+    let book_value = row
+        .try_get::<BigDecimal, _>("book_value")
+        .ok()
+        .and_then(|bd| bd.to_string().parse::<f64>().ok())
+        .unwrap_or(0.0);
+
+    serde_json::json!({
+        "id": row.get::<Uuid, _>("id"),
+        "patrimony_id": row.get::<Uuid, _>("patrimony_id"),
+        "plate": row.get::<String, _>("plate"),
+        "patrimony_name": row.get::<String, _>("patrimony_name"),
+        "department": row.get::<String, _>("department"),
+        "reason": row.get::<String, _>("reason"),
+        "act_number": row.get::<String, _>("act_number"),
+        "write_off_date": row.get::<NaiveDate, _>("write_off_date"),
+        "notes": row.get::<Option<String>, _>("notes"),
+        "document_file": row.get::<Option<String>, _>("document_file"),
+        "book_value": book_value,
+        "previous_status": row.get::<String, _>("previous_status"),
+        "approved_by": row.get::<Uuid, _>("approved_by"),
+        "approved_by_name": row.get::<Option<String>, _>("approved_by_name"),
+        "created_at": row.get::<chrono::DateTime<Utc>, _>("created_at")
+    })
+}
+
+// Bens baixados ficam somente leitura: retorna erro se o bem não existir ou já estiver baixado
+pub async fn ensure_not_written_off(pool: &PgPool, patrimony_id: Uuid) -> Result<(), HttpResponse> {
+    match sqlx::query("SELECT status FROM patrimonies WHERE id = $1 AND deleted_at IS NULL")
+        .bind(patrimony_id)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(row)) => {
+            if row.get::<String, _>("status") == WRITTEN_OFF {
+                Err(written_off_response())
+            } else {
+                Ok(())
+            }
+        }
+        Ok(None) => Err(HttpResponse::NotFound().json("Patrimony not found")),
+        Err(e) => {
+            eprintln!("Error checking patrimony status: {:?}", e);
+            Err(HttpResponse::InternalServerError().json("Error checking patrimony status"))
+        }
+    }
+}
+
+pub fn written_off_response() -> HttpResponse {
+    HttpResponse::Conflict().json("Patrimony is written off and read-only")
+}
+
+pub async fn write_off_patrimony(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    write_off: web::Json<CreateWriteOff>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    if !WRITE_OFF_REASONS.contains(&write_off.reason.as_str()) {
+        return HttpResponse::BadRequest().json(format!(
+            "Invalid reason, expected one of: {}",
+            WRITE_OFF_REASONS.join(", ")
+        ));
+    }
+    if write_off.act_number.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Act number is required");
+    }
+
+    let write_off_date = write_off.write_off_date.unwrap_or_else(|| Utc::now().date_naive());
+
+    let mut transaction = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Error starting transaction: {}", e);
+            return HttpResponse::InternalServerError().json("Error processing write-off");
+        }
+    };
+
+    // Bloquear o bem enquanto a baixa é registrada
+    let patrimony = sqlx::query(
+        "SELECT status, value, acquisition_date, depreciation_method, useful_life_months, residual_value
+         FROM patrimonies WHERE id = $1 AND deleted_at IS NULL FOR UPDATE"
+    )
+    .bind(patrimony_id)
+    .fetch_optional(&mut *transaction)
+    .await;
+
+    let row = match patrimony {
+        Ok(Some(row)) => row,
+        Ok(None) => return HttpResponse::NotFound().json("Patrimony not found"),
+        Err(e) => {
+            eprintln!("Error fetching patrimony: {}", e);
+            return HttpResponse::InternalServerError().json("Error processing write-off");
+        }
+    };
+
+    let previous_status: String = row.get("status");
+    if previous_status == WRITTEN_OFF {
+        return HttpResponse::Conflict().json("Patrimony is already written off");
+    }
+
+    let before = match audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await {
+        Ok(before) => before,
+        Err(e) => {
+            eprintln!("Error fetching patrimony for audit: {}", e);
+            return HttpResponse::InternalServerError().json("Error processing write-off");
+        }
+    };
+
+    // Valor contábil na data da baixa
+    let value = row
+        .try_get::<Option<BigDecimal>, _>("value")
+        .ok()
+        .flatten()
+        .and_then(|bd| bd.to_string().parse::<f64>().ok())
+        .unwrap_or(0.0);
+    let book_value = match row.get::<Option<NaiveDate>, _>("acquisition_date") {
+        Some(acquisition_date) => {
+            let policy = DepreciationPolicy::from_row(&row);
+            depreciation::snapshot(value, acquisition_date, &policy, write_off_date).1
+        }
+        None => value,
+    };
+
+    let insert_result = sqlx::query(
+        "INSERT INTO write_offs (patrimony_id, reason, act_number, write_off_date, notes, book_value, previous_status, approved_by)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id"
+    )
+    .bind(patrimony_id)
+    .bind(&write_off.reason)
+    .bind(write_off.act_number.trim())
+    .bind(write_off_date)
+    .bind(&write_off.notes)
+    .bind(book_value)
+    .bind(&previous_status)
+    .bind(user.id)
+    .map(|row: PgRow| row.get::<Uuid, _>("id"))
+    .fetch_one(&mut *transaction)
+    .await;
+
+    let write_off_id = match insert_result {
+        Ok(id) => id,
+        Err(e) => {
+            eprintln!("Error creating write-off record: {}", e);
+            let _ = transaction.rollback().await;
+            return HttpResponse::InternalServerError().json("Error processing write-off");
+        }
+    };
+
+    let update_result = sqlx::query(
+        "UPDATE patrimonies SET status = $1, updated_at = NOW() WHERE id = $2"
+    )
+    .bind(WRITTEN_OFF)
+    .bind(patrimony_id)
+    .execute(&mut *transaction)
+    .await;
+
+    if let Err(e) = update_result {
+        eprintln!("Error updating patrimony status: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error processing write-off");
+    }
+
+    // O bem baixado deixa de estar sob a guarda de um servidor
+    if let Err(e) = custody::release_active_custody(&mut transaction, patrimony_id, user.id).await {
+        eprintln!("Error releasing custody: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error processing write-off");
+    }
+
+    // Ordem de serviço aberta perde o objeto com a baixa
+    if let Err(e) = maintenance::cancel_open_order(&mut transaction, patrimony_id, user.id, "Cancelada pela baixa do bem").await {
+        eprintln!("Error cancelling maintenance order: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error processing write-off");
+    }
+
+    if let Err(e) = audit::record_change(&mut transaction, &Actor::new(&user, &req), audit::PATRIMONY, patrimony_id, audit::WRITE_OFF, before).await {
+        eprintln!("Error recording audit entry: {}", e);
+        let _ = transaction.rollback().await;
+        return HttpResponse::InternalServerError().json("Error processing write-off");
+    }
+
+    if let Err(e) = transaction.commit().await {
+        eprintln!("Error committing transaction: {}", e);
+        return HttpResponse::InternalServerError().json("Error processing write-off");
+    }
+
+    println!("✅ Baixa registrada para o patrimônio {} (ato {})", patrimony_id, write_off.act_number);
+
+    match sqlx::query(&format!("{} WHERE w.id = $1", WRITE_OFF_SELECT))
+        .bind(write_off_id)
+        .map(|row: PgRow| write_off_json(&row))
+        .fetch_one(pool.get_ref())
+        .await
+    {
+        Ok(write_off) => HttpResponse::Created().json(write_off),
+        Err(e) => {
+            eprintln!("Error fetching created write-off: {}", e);
+            HttpResponse::InternalServerError().json("Write-off completed but error fetching details")
+        }
+    }
+}
+
+// Grava a referência do documento da baixa e registra o envio na trilha de auditoria do bem
+async fn store_document_reference(
+    pool: &PgPool,
+    actor: &Actor,
+    patrimony_id: Uuid,
+    write_off_id: Uuid,
+    document_url: &str,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+    let before = audit::snapshot(&mut *transaction, audit::PATRIMONY, patrimony_id).await?;
+
+    sqlx::query("UPDATE write_offs SET document_file = $1 WHERE id = $2")
+        .bind(document_url)
+        .bind(write_off_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    audit::record_change(&mut transaction, actor, audit::PATRIMONY, patrimony_id, audit::UPLOAD, before).await?;
+    transaction.commit().await
+}
+
+pub async fn upload_write_off_document(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    mut payload: Multipart,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let write_off_id = id.into_inner();
+    println!("📤 Upload do documento da baixa: {}", write_off_id);
+
+    let patrimony_id = match sqlx::query(
+        "SELECT w.patrimony_id FROM write_offs w JOIN patrimonies p ON w.patrimony_id = p.id WHERE w.id = $1 AND p.tenant_id = $2"
+    )
+    .bind(write_off_id)
+    .bind(user.tenant_id)
+    .map(|row: PgRow| row.get::<Uuid, _>("patrimony_id"))
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(patrimony_id)) => patrimony_id,
+        Ok(None) => return HttpResponse::NotFound().json("Write-off not found"),
+        Err(e) => {
+            eprintln!("Erro ao verificar baixa: {:?}", e);
+            return HttpResponse::InternalServerError().json("Erro ao verificar baixa");
+        }
+    };
+
+    if let Ok(Some(mut field)) = payload.try_next().await {
+        let filename = field.content_disposition().get_filename().unwrap_or("baixa.pdf").to_string();
+        let is_pdf = Path::new(&filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.eq_ignore_ascii_case("pdf"))
+            .unwrap_or(false);
+        if !is_pdf {
+            return HttpResponse::BadRequest().json("O documento da baixa deve ser um PDF");
+        }
+
+        let docs_dir = "./documents";
+        if !Path::new(docs_dir).exists() {
+            if let Err(e) = fs::create_dir_all(docs_dir) {
+                eprintln!("Erro ao criar diretório de documentos: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao criar diretório de documentos");
+            }
+        }
+
+        let new_filename = format!("write_off_{}.pdf", Uuid::new_v4());
+        let filepath = format!("{}/{}", docs_dir, new_filename);
+
+        let mut file = match File::create(&filepath).await {
+            Ok(f) => f,
+            Err(e) => {
+                eprintln!("Erro ao criar arquivo: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao salvar documento");
+            }
+        };
+
+        let mut total_bytes = 0;
+        while let Ok(Some(chunk)) = field.try_next().await {
+            total_bytes += chunk.len();
+            if let Err(e) = file.write_all(&chunk).await {
+                eprintln!("Erro ao escrever arquivo: {:?}", e);
+                return HttpResponse::InternalServerError().json("Erro ao salvar dados do documento");
+            }
+        }
+
+        let document_url = format!("/documents/{}", new_filename);
+        return match store_document_reference(pool.get_ref(), &Actor::new(&user, &req), patrimony_id, write_off_id, &document_url).await {
+            Ok(_) => {
+                println!("✅ Documento da baixa salvo: {}", document_url);
+                HttpResponse::Ok().json(serde_json::json!({
+                    "message": "Documento enviado com sucesso",
+                    "document_url": document_url,
+                    "file_size": total_bytes
+                }))
+            }
+            Err(e) => {
+                eprintln!("❌ Erro ao atualizar baixa com o documento: {:?}", e);
+                HttpResponse::InternalServerError().json("Erro ao atualizar baixa")
+            }
+        };
+    }
+
+    HttpResponse::BadRequest().json("Nenhum documento fornecido")
+}
+
+pub async fn get_write_offs(
+    pool: web::Data<PgPool>,
+    query: web::Query<WriteOffQuery>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let result = sqlx::query(&format!(
+        "{} WHERE p.tenant_id = $5
+           AND ($1::varchar IS NULL OR w.reason = $1)
+           AND ($2::varchar IS NULL OR p.department = $2)
+           AND ($3::date IS NULL OR w.write_off_date >= $3)
+           AND ($4::date IS NULL OR w.write_off_date <= $4)
+           AND {}
+         ORDER BY w.write_off_date DESC, w.created_at DESC",
+        WRITE_OFF_SELECT,
+        department_grant::filter_sql("p.department", 6)
+    ))
+    .bind(&query.reason)
+    .bind(&query.department)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(user.tenant_id)
+    .bind(department_grant::restriction(&user))
+    .map(|row: PgRow| write_off_json(&row))
+    .fetch_all(pool.get_ref())
+    .await;
+
+    match result {
+        Ok(write_offs) => HttpResponse::Ok().json(write_offs),
+        Err(e) => {
+            eprintln!("Error fetching write-offs: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching write-offs")
+        }
+    }
+}
+
+pub async fn get_patrimony_write_off(
+    pool: web::Data<PgPool>,
+    id: web::Path<Uuid>,
+    req: HttpRequest,
+) -> HttpResponse {
+    // Verificar autenticação
+    let user = match auth_middleware(&req, pool.get_ref()).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::Unauthorized().json("Authentication required"),
+        Err(e) => return e,
+    };
+
+    let patrimony_id = id.into_inner();
+
+    if let Err(response) = ensure_visible(pool.get_ref(), &user, patrimony_id).await {
+        return response;
+    }
+
+    let result = sqlx::query(&format!("{} WHERE w.patrimony_id = $1", WRITE_OFF_SELECT))
+        .bind(patrimony_id)
+        .map(|row: PgRow| write_off_json(&row))
+        .fetch_one(pool.get_ref())
+        .await;
+
+    match result {
+        Ok(write_off) => HttpResponse::Ok().json(write_off),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().json("Write-off not found"),
+        Err(e) => {
+            eprintln!("Error fetching write-off: {}", e);
+            HttpResponse::InternalServerError().json("Error fetching write-off")
+        }
+    }
+}
@@ -0,0 +1,42 @@
+// mail/mod.rs
+// Envio de e-mails (redefinição de senha): o transporte é escolhido por MAIL_TRANSPORT
+pub mod outbox;
+pub mod smtp;
+
+use async_trait::async_trait;
+use std::env;
+use std::sync::Arc;
+
+const DEFAULT_FROM: &str = "Patrimônio <no-reply@localhost>";
+
+/// Mensagem de texto simples
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+/// Remetente das mensagens (MAIL_FROM)
+pub fn sender() -> String {
+    env::var("MAIL_FROM")
+        .ok()
+        .filter(|from| !from.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_FROM.to_string())
+}
+
+/// Transporte configurado: "smtp", "file" (grava .eml em MAIL_OUTBOX_DIR) ou "log" (padrão)
+pub fn from_env() -> Result<Arc<dyn Mailer>, String> {
+    let transport = env::var("MAIL_TRANSPORT").unwrap_or_else(|_| "log".to_string());
+    match transport.trim().to_lowercase().as_str() {
+        "smtp" => Ok(Arc::new(smtp::SmtpMailer::from_env()?)),
+        "file" => Ok(Arc::new(outbox::OutboxMailer::to_dir(outbox::outbox_dir()))),
+        "log" | "" => Ok(Arc::new(outbox::OutboxMailer::log_only())),
+        other => Err(format!("Unknown MAIL_TRANSPORT '{}' (use smtp, file or log)", other)),
+    }
+}
@@ -0,0 +1,55 @@
+// Transporte de desenvolvimento: a mensagem vai para o log e, opcionalmente,
+// para um arquivo .eml na caixa de saída
+use async_trait::async_trait;
+use chrono::Utc;
+use std::env;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::{sender, Email, Mailer};
+
+const DEFAULT_OUTBOX_DIR: &str = "./mail_outbox";
+
+pub fn outbox_dir() -> PathBuf {
+    PathBuf::from(env::var("MAIL_OUTBOX_DIR").unwrap_or_else(|_| DEFAULT_OUTBOX_DIR.to_string()))
+}
+
+pub struct OutboxMailer {
+    dir: Option<PathBuf>,
+}
+
+impl OutboxMailer {
+    pub fn to_dir(dir: PathBuf) -> Self {
+        Self { dir: Some(dir) }
+    }
+
+    pub fn log_only() -> Self {
+        Self { dir: None }
+    }
+}
+
+#[async_trait]
+impl Mailer for OutboxMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let Some(dir) = &self.dir else {
+            println!("📧 E-mail para {} — {}\n{}", email.to, email.subject, email.body);
+            return Ok(());
+        };
+
+        tokio::fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+        let now = Utc::now();
+        let path = dir.join(format!("{}_{}.eml", now.format("%Y%m%dT%H%M%S"), Uuid::new_v4()));
+        let content = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+            sender(),
+            email.to,
+            email.subject,
+            now.to_rfc2822(),
+            email.body
+        );
+        tokio::fs::write(&path, content).await.map_err(|e| e.to_string())?;
+
+        println!("📧 E-mail para {} gravado em {:?}", email.to, path);
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+// Transporte SMTP (ex.: MailHog em localhost:1025 no desenvolvimento)
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::env;
+
+use super::{sender, Email, Mailer};
+
+const DEFAULT_PORT: u16 = 25;
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl SmtpMailer {
+    /// SMTP_HOST, SMTP_PORT, SMTP_TLS (none, starttls ou tls) e, se houver, SMTP_USERNAME/SMTP_PASSWORD
+    pub fn from_env() -> Result<Self, String> {
+        let host = env::var("SMTP_HOST").map_err(|_| "SMTP_HOST is not set".to_string())?;
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port.trim().parse::<u16>().map_err(|_| format!("Invalid SMTP_PORT '{}'", port))?,
+            Err(_) => DEFAULT_PORT,
+        };
+
+        let tls = env::var("SMTP_TLS").unwrap_or_else(|_| "none".to_string());
+        let mut builder = match tls.trim().to_lowercase().as_str() {
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&host),
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host).map_err(|e| e.to_string())?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&host).map_err(|e| e.to_string())?,
+            other => return Err(format!("Invalid SMTP_TLS '{}' (use none, starttls or tls)", other)),
+        }
+        .port(port);
+
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = sender()
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid MAIL_FROM: {}", e))?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let to = email
+            .to
+            .parse::<Mailbox>()
+            .map_err(|e| format!("Invalid recipient '{}': {}", email.to, e))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)
+            .map_err(|e| e.to_string())?;
+
+        self.transport.send(message).await.map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use actix_web::{web, App, HttpServer, HttpResponse};
+use actix_web::middleware::from_fn;
 use actix_cors::Cors;
-use actix_files::Files;
 use sqlx::postgres::{PgPoolOptions, PgConnectOptions};
 use sqlx::{Pool, Postgres};
 use std::time::Duration;
@@ -11,6 +11,8 @@ use dotenv::dotenv; // ✅ ADICIONE ESTA IMPORTACAO
 mod handlers;
 mod models;
 mod database;
+mod documents;
+mod mail;
 
 async fn init_database() -> Result<Pool<Postgres>, sqlx::Error> {
     // ✅ CARREGAR VARIAVEIS DE AMBIENTE
@@ -105,6 +107,34 @@ async fn debug_uploads() -> HttpResponse {
     }))
 }
 
+/// Verificação da cadeia pela linha de comando (código de saída 0 = íntegra)
+async fn verify_audit() -> i32 {
+    let pool = match init_database().await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Fatal error: Failed to initialize database: {}", e);
+            return 2;
+        }
+    };
+
+    match handlers::audit_chain::verify_chain(&pool).await {
+        Ok(report) => {
+            println!("{}", serde_json::to_string_pretty(&report).unwrap_or_default());
+            if report.valid {
+                println!("✅ Trilha de auditoria íntegra ({} entradas)", report.entries_checked);
+                0
+            } else {
+                eprintln!("❌ Trilha de auditoria adulterada");
+                1
+            }
+        }
+        Err(e) => {
+            eprintln!("Error verifying audit chain: {}", e);
+            2
+        }
+    }
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // ✅ CARREGAR VARIAVEIS DE AMBIENTE NO INICIO
@@ -130,6 +160,26 @@ async fn main() -> std::io::Result<()> {
         }
     }
 
+    // ✅ COMANDO DE LINHA: verify-audit percorre a cadeia da auditoria e sai
+    if std::env::args().nth(1).as_deref() == Some("verify-audit") {
+        std::process::exit(verify_audit().await);
+    }
+
+    // ✅ SEGREDO DOS TOKENS É OBRIGATÓRIO (SEM VALOR PADRÃO)
+    if handlers::session::jwt_secret().is_none() {
+        eprintln!("Fatal error: JWT_SECRET is not set");
+        std::process::exit(1);
+    }
+
+    // ✅ TRANSPORTE DE E-MAIL (REDEFINIÇÃO DE SENHA)
+    let mailer = match mail::from_env() {
+        Ok(mailer) => web::Data::from(mailer),
+        Err(e) => {
+            eprintln!("Fatal error: invalid mail configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     let pool = match init_database().await {
         Ok(pool) => pool,
         Err(e) => {
@@ -139,6 +189,12 @@ async fn main() -> std::io::Result<()> {
         }
     };
     
+    // ✅ ROTINA DE MANUTENÇÃO PREVENTIVA EM SEGUNDO PLANO
+    tokio::spawn(handlers::maintenance_schedule::run_scheduler(pool.clone()));
+
+    // ✅ PONTOS DE CONTROLE ASSINADOS DA TRILHA DE AUDITORIA
+    tokio::spawn(handlers::audit_chain::run_checkpoints(pool.clone()));
+
     println!("Server running at http://localhost:8080");
     println!("Database connected successfully");
 
@@ -164,24 +220,21 @@ async fn main() -> std::io::Result<()> {
             .max_age(3600);
         
         App::new()
+            // ✅ PERMISSÕES POR ROTA (registrado antes do CORS para o preflight não passar por aqui)
+            .wrap(from_fn(handlers::permissions::enforce))
             .wrap(cors)
             .app_data(web::Data::new(pool.clone()))
-            // ✅ SERVIR ARQUIVOS ESTÁTICOS PARA IMAGENS E DOCUMENTOS
-            .service(
-                Files::new("/uploads", "./uploads")
-                    .show_files_listing()
-                    .use_last_modified(true),
-            )
-            .service(
-                Files::new("/documents", "./documents")
-                    .show_files_listing()
-                    .use_last_modified(true),
-            )
+            .app_data(mailer.clone())
             // ✅ ROTAS PÚBLICAS (não requerem autenticação)
             .route("/api/register", web::post().to(handlers::register_user_handler))
             .route("/api/login", web::post().to(handlers::login_user_handler))
+            .route("/api/login/two-factor", web::post().to(handlers::verify_two_factor_login_handler))
+            .route("/api/auth/refresh", web::post().to(handlers::refresh_session_handler))
+            .route("/api/password-reset/request", web::post().to(handlers::request_password_reset_handler))
+            .route("/api/password-reset/confirm", web::post().to(handlers::confirm_password_reset_handler))
             .route("/api/health", web::get().to(handlers::health_check))
             .route("/api/departments", web::get().to(handlers::get_departments))
+            .route("/api/departments", web::post().to(handlers::create_department))
             .route("/api/debug/uploads", web::get().to(debug_uploads))
             // ✅ ROTAS PROTEGIDAS (requerem autenticação)
             .service(
@@ -191,15 +244,129 @@ async fn main() -> std::io::Result<()> {
                     .route("/patrimony/{id}", web::get().to(handlers::get_patrimony))
                     .route("/patrimony/{id}", web::put().to(handlers::update_patrimony))
                     .route("/patrimony/{id}", web::delete().to(handlers::delete_patrimony))
+                    .route("/patrimony/{id}/restore", web::post().to(handlers::restore_patrimony))
+                    .route("/patrimony/{id}/purge", web::delete().to(handlers::purge_patrimony))
+                    .route("/patrimonies/trash", web::get().to(handlers::get_trash))
                     .route("/patrimony/{id}/image", web::post().to(handlers::upload_image))
                     .route("/patrimony/{id}/document/{doc_type}", web::post().to(handlers::upload_document_handler))
+                    .route("/uploads/{filename}", web::get().to(handlers::serve_image))
+                    .route("/documents/{filename}", web::get().to(handlers::serve_document))
                     .route("/transfer", web::post().to(handlers::transfer_patrimony))
                     .route("/transfers", web::get().to(handlers::get_transfers))
                     .route("/transfer/{id}", web::get().to(handlers::get_transfer))
+                    .route("/transfer/{id}/approve", web::post().to(handlers::approve_transfer))
+                    .route("/transfer/{id}/reject", web::post().to(handlers::reject_transfer))
+                    .route("/transfer/{id}/receive", web::post().to(handlers::receive_transfer))
+                    .route("/transfers/pending", web::get().to(handlers::get_pending_transfers))
+                    .route("/transfers/batch", web::post().to(handlers::create_transfer_batch))
+                    .route("/transfer/{id}/term.pdf", web::get().to(handlers::get_transfer_term))
                     .route("/stats", web::get().to(handlers::get_stats))
                     .route("/users", web::get().to(handlers::get_users_handler))
                     .route("/debug/images", web::get().to(handlers::debug_images_handler))
                     .route("/patrimonies/department/{department}", web::get().to(handlers::get_patrimonies_by_department))
+                    .route("/patrimony/{id}/depreciation", web::get().to(handlers::get_patrimony_depreciation))
+                    .route("/depreciation/run", web::post().to(handlers::run_depreciation))
+                    .route("/depreciation/entries", web::get().to(handlers::get_depreciation_entries))
+                    .route("/patrimony/{id}/write-off", web::post().to(handlers::write_off_patrimony))
+                    .route("/patrimony/{id}/write-off", web::get().to(handlers::get_patrimony_write_off))
+                    .route("/write-offs", web::get().to(handlers::get_write_offs))
+                    .route("/write-off/{id}/document", web::post().to(handlers::upload_write_off_document))
+                    .route("/inventory/campaigns", web::post().to(handlers::create_campaign))
+                    .route("/inventory/campaigns", web::get().to(handlers::get_campaigns))
+                    .route("/inventory/campaigns/{id}", web::get().to(handlers::get_campaign))
+                    .route("/inventory/campaigns/{id}/sessions", web::post().to(handlers::create_session))
+                    .route("/inventory/campaigns/{id}/close", web::post().to(handlers::close_campaign))
+                    .route("/inventory/campaigns/{id}/report", web::get().to(handlers::get_campaign_report))
+                    .route("/inventory/sessions/{id}/scans", web::post().to(handlers::post_scans))
+                    .route("/inventory/sessions/{id}/close", web::post().to(handlers::close_session))
+                    .route("/labels", web::get().to(handlers::get_labels))
+                    .route("/plate-sequences", web::get().to(handlers::get_plate_sequences))
+                    .route("/plate-sequences", web::post().to(handlers::create_plate_sequence))
+                    .route("/plate-sequences/{id}", web::put().to(handlers::update_plate_sequence))
+                    .route("/plate-sequences/{id}", web::delete().to(handlers::delete_plate_sequence))
+                    .route("/custodians", web::get().to(handlers::get_custodians))
+                    .route("/custodians", web::post().to(handlers::create_custodian))
+                    .route("/custodians/{id}", web::get().to(handlers::get_custodian))
+                    .route("/custodians/{id}", web::put().to(handlers::update_custodian))
+                    .route("/custodians/{id}/term.pdf", web::get().to(handlers::get_responsibility_term))
+                    .route("/patrimony/{id}/custodian", web::post().to(handlers::assign_custodian))
+                    .route("/patrimony/{id}/custodian", web::delete().to(handlers::release_custodian))
+                    .route("/patrimony/{id}/custody-history", web::get().to(handlers::get_custody_history))
+                    .route("/locations", web::get().to(handlers::get_locations))
+                    .route("/locations", web::post().to(handlers::create_location))
+                    .route("/locations/{id}", web::get().to(handlers::get_location))
+                    .route("/locations/{id}", web::put().to(handlers::update_location))
+                    .route("/locations/{id}", web::delete().to(handlers::delete_location))
+                    .route("/patrimony/{id}/move", web::post().to(handlers::move_patrimony))
+                    .route("/patrimony/{id}/moves", web::get().to(handlers::get_patrimony_moves))
+                    .route("/departments/{id}", web::get().to(handlers::get_department))
+                    .route("/departments/{id}", web::put().to(handlers::update_department))
+                    .route("/departments/{id}", web::delete().to(handlers::delete_department))
+                    .route("/departments/{id}/managers", web::post().to(handlers::add_department_manager))
+                    .route("/departments/{id}/managers/{user_id}", web::delete().to(handlers::remove_department_manager))
+                    .route("/categories", web::get().to(handlers::get_categories))
+                    .route("/categories", web::post().to(handlers::create_category))
+                    .route("/categories/{id}", web::get().to(handlers::get_category))
+                    .route("/categories/{id}", web::put().to(handlers::update_category))
+                    .route("/categories/{id}", web::delete().to(handlers::delete_category))
+                    .route("/patrimony/{id}/maintenance", web::post().to(handlers::open_maintenance_order))
+                    .route("/patrimony/{id}/maintenance", web::get().to(handlers::get_patrimony_maintenance))
+                    .route("/maintenance", web::get().to(handlers::get_maintenance_orders))
+                    .route("/maintenance/costs", web::get().to(handlers::get_maintenance_costs))
+                    .route("/maintenance/upcoming", web::get().to(handlers::get_upcoming_maintenance))
+                    .route("/maintenance/schedules", web::get().to(handlers::get_maintenance_schedules))
+                    .route("/maintenance/schedules", web::post().to(handlers::create_maintenance_schedule))
+                    .route("/maintenance/schedules/run", web::post().to(handlers::run_maintenance_schedules))
+                    .route("/maintenance/schedules/{id}", web::get().to(handlers::get_maintenance_schedule))
+                    .route("/maintenance/schedules/{id}", web::put().to(handlers::update_maintenance_schedule))
+                    .route("/maintenance/schedules/{id}", web::delete().to(handlers::delete_maintenance_schedule))
+                    .route("/maintenance/due/{id}/complete", web::post().to(handlers::complete_due_item))
+                    .route("/maintenance/due/{id}/skip", web::post().to(handlers::skip_due_item))
+                    .route("/patrimony/{id}/meter", web::post().to(handlers::create_meter_reading))
+                    .route("/patrimony/{id}/meter", web::get().to(handlers::get_meter_readings))
+                    .route("/maintenance/{id}", web::get().to(handlers::get_maintenance_order))
+                    .route("/maintenance/{id}", web::put().to(handlers::update_maintenance_order))
+                    .route("/maintenance/{id}/close", web::post().to(handlers::close_maintenance_order))
+                    .route("/maintenance/{id}/cancel", web::post().to(handlers::cancel_maintenance_order))
+                    .route("/maintenance/{id}/attachments", web::post().to(handlers::upload_maintenance_attachment))
+                    .route("/audit", web::get().to(handlers::get_audit_log))
+                    .route("/audit/verify", web::get().to(handlers::verify_audit_chain))
+                    .route("/audit/checkpoints", web::get().to(handlers::get_audit_checkpoints))
+                    .route("/audit/checkpoints", web::post().to(handlers::create_audit_checkpoint))
+                    .route("/tenants", web::get().to(handlers::get_tenants))
+                    .route("/tenants", web::post().to(handlers::create_tenant))
+                    .route("/tenants/{id}", web::get().to(handlers::get_tenant))
+                    .route("/tenants/{id}", web::put().to(handlers::update_tenant))
+                    .route("/roles", web::get().to(handlers::get_roles))
+                    .route("/permissions", web::get().to(handlers::get_permissions))
+                    .route("/roles/{code}/permissions", web::put().to(handlers::update_role_permissions))
+                    .route("/department-grants", web::get().to(handlers::get_department_grants))
+                    .route("/department-grants", web::post().to(handlers::create_department_grant))
+                    .route("/department-grants/{id}", web::delete().to(handlers::delete_department_grant))
+                    .route("/logout", web::post().to(handlers::logout))
+                    .route("/users/{id}/sessions", web::get().to(handlers::get_user_sessions))
+                    .route("/users/{id}/revoke-sessions", web::post().to(handlers::revoke_user_sessions))
+                    .route("/users", web::post().to(handlers::create_user))
+                    .route("/users/{id}", web::get().to(handlers::get_user))
+                    .route("/users/{id}", web::put().to(handlers::update_user))
+                    .route("/users/{id}/deactivate", web::post().to(handlers::deactivate_user))
+                    .route("/users/{id}/reactivate", web::post().to(handlers::reactivate_user))
+                    .route("/users/{id}/reset-password", web::post().to(handlers::reset_user_password))
+                    .route("/invitations", web::get().to(handlers::get_invitations))
+                    .route("/invitations", web::post().to(handlers::create_invitation))
+                    .route("/invitations/{id}", web::delete().to(handlers::revoke_invitation))
+                    .route("/me", web::get().to(handlers::get_profile))
+                    .route("/me", web::put().to(handlers::update_profile))
+                    .route("/me/password", web::put().to(handlers::change_password))
+                    .route("/password-policy", web::get().to(handlers::get_password_policy))
+                    .route("/users/{id}/unlock", web::post().to(handlers::unlock_user))
+                    .route("/login-attempts", web::get().to(handlers::get_login_attempts))
+                    .route("/me/two-factor/setup", web::post().to(handlers::setup_two_factor))
+                    .route("/me/two-factor/enable", web::post().to(handlers::enable_two_factor))
+                    .route("/me/two-factor/disable", web::post().to(handlers::disable_two_factor))
+                    .route("/me/two-factor/recovery-codes", web::post().to(handlers::regenerate_recovery_codes))
+                    .route("/users/{id}/reset-two-factor", web::post().to(handlers::reset_user_two_factor))
+                    .route("/roles/{code}/two-factor", web::put().to(handlers::update_role_two_factor))
             )
     })
     .bind("0.0.0.0:8080")?